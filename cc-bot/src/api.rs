@@ -146,6 +146,9 @@ fn validate_message(message: &str) -> Result<(), String> {
 
 use crate::llm::LLMClient;
 use crate::history::ChatMessage;
+use crate::mcp_client::MCPToolDefinition;
+use crate::mcp_manager::{MCPManager, MCPManagerError, MCPServerStatus};
+use crate::mcp_server::BotMCPServer;
 use crate::memory_store::MemoryStore;
 use crate::scheduler::Scheduler;
 use crate::schedule_store::ScheduleStore;
@@ -165,6 +168,10 @@ pub struct ApiState {
     pub base_output_dir: String,
     /// レートリミッター（DoS攻撃防止）
     pub rate_limiter: Arc<Mutex<RateLimiter>>,
+    /// MCPサーバーマネージャー（MCP設定がない場合は None）
+    pub mcp_manager: Option<Arc<MCPManager>>,
+    /// MCPサーバーモード（無効の場合は None）
+    pub mcp_server: Option<BotMCPServer>,
 }
 
 /// ヘルスチェックレスポンス
@@ -246,6 +253,21 @@ fn default_limit() -> usize {
     10
 }
 
+/// MCPツール一覧クエリ
+#[derive(Deserialize)]
+pub struct ListMCPToolsQuery {
+    #[serde(default)]
+    pub server: Option<String>,
+}
+
+/// MCPサーバー操作レスポンス
+#[derive(Serialize)]
+pub struct MCPActionResponse {
+    pub server: String,
+    pub enabled: bool,
+    pub tools: usize,
+}
+
 /// エラーレスポンス
 #[derive(Serialize)]
 struct ErrorResponse {
@@ -302,22 +324,34 @@ pub fn create_router(state: ApiState) -> Router {
             HeaderValue::from_static("max-age=31536000; includeSubDomains"),
         ));
 
+    let mut api_routes = Router::new()
+        // チャット
+        .route("/chat", post(chat))
+        // スケジュール
+        .route("/schedules", get(list_schedules).post(create_schedule))
+        .route("/schedules/{id}", delete(delete_schedule))
+        // メモリ
+        .route("/memories", get(list_memories).post(create_memory))
+        .route("/memories/search", get(search_memories))
+        .route("/memories/{id}", delete(delete_memory))
+        // MCPサーバー管理
+        .route("/mcp", get(list_mcp_servers))
+        .route("/mcp/tools", get(list_mcp_tools))
+        .route("/mcp/{name}/enable", post(enable_mcp_server))
+        .route("/mcp/{name}/disable", post(disable_mcp_server))
+        .route("/mcp/{name}/restart", post(restart_mcp_server));
+    // MCPサーバーモード（Streamable HTTP）
+    if let Some(mcp_server) = state.mcp_server.clone() {
+        api_routes = api_routes.nest_service("/mcp-server", mcp_server.into_service());
+    }
+
     Router::new()
         // ヘルスチェック（認証不要、レートリミットなし）
         .route("/api/health", get(health))
         // 認証が必要なルート
         .nest(
             "/api",
-            Router::new()
-                // チャット
-                .route("/chat", post(chat))
-                // スケジュール
-                .route("/schedules", get(list_schedules).post(create_schedule))
-                .route("/schedules/{id}", delete(delete_schedule))
-                // メモリ
-                .route("/memories", get(list_memories).post(create_memory))
-                .route("/memories/search", get(search_memories))
-                .route("/memories/{id}", delete(delete_memory))
+            api_routes
                 // 認証ミドルウェア
                 .layer(middleware::from_fn(auth_middleware))
                 // レートリミットミドルウェア
@@ -584,6 +618,73 @@ async fn delete_memory(
     }
 }
 
+// ===== MCPサーバー管理 =====
+
+type ApiError = (StatusCode, Json<ErrorResponse>);
+
+/// MCPマネージャーを取得（未設定なら 503）
+fn mcp_manager(state: &ApiState) -> Result<&Arc<MCPManager>, ApiError> {
+    state.mcp_manager.as_ref().ok_or_else(|| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: "MCP is not configured".to_string(),
+            }),
+        )
+    })
+}
+
+/// MCP管理エラーをHTTPステータスに変換
+fn mcp_error(e: MCPManagerError) -> ApiError {
+    let status = match e {
+        MCPManagerError::NotFound(_) => StatusCode::NOT_FOUND,
+        MCPManagerError::Disabled(_) => StatusCode::CONFLICT,
+        MCPManagerError::Failed(_, _) => StatusCode::BAD_GATEWAY,
+    };
+    (status, Json(ErrorResponse { error: e.to_string() }))
+}
+
+async fn list_mcp_servers(
+    State(state): State<Arc<ApiState>>,
+) -> Result<Json<Vec<MCPServerStatus>>, ApiError> {
+    Ok(Json(mcp_manager(&state)?.statuses().await))
+}
+
+async fn list_mcp_tools(
+    State(state): State<Arc<ApiState>>,
+    Query(query): Query<ListMCPToolsQuery>,
+) -> Result<Json<Vec<MCPToolDefinition>>, ApiError> {
+    let manager = mcp_manager(&state)?;
+    manager.tools(query.server.as_deref()).await.map(Json).map_err(mcp_error)
+}
+
+async fn enable_mcp_server(
+    State(state): State<Arc<ApiState>>,
+    Path(name): Path<String>,
+) -> Result<Json<MCPActionResponse>, ApiError> {
+    let tools = mcp_manager(&state)?.enable(&name).await.map_err(mcp_error)?;
+    info!("MCP server {} enabled via API", name);
+    Ok(Json(MCPActionResponse { server: name, enabled: true, tools }))
+}
+
+async fn disable_mcp_server(
+    State(state): State<Arc<ApiState>>,
+    Path(name): Path<String>,
+) -> Result<Json<MCPActionResponse>, ApiError> {
+    mcp_manager(&state)?.disable(&name).await.map_err(mcp_error)?;
+    info!("MCP server {} disabled via API", name);
+    Ok(Json(MCPActionResponse { server: name, enabled: false, tools: 0 }))
+}
+
+async fn restart_mcp_server(
+    State(state): State<Arc<ApiState>>,
+    Path(name): Path<String>,
+) -> Result<Json<MCPActionResponse>, ApiError> {
+    let tools = mcp_manager(&state)?.restart(&name).await.map_err(mcp_error)?;
+    info!("MCP server {} restarted via API", name);
+    Ok(Json(MCPActionResponse { server: name, enabled: true, tools }))
+}
+
 /// APIサーバーを起動
 pub async fn start_server(state: ApiState, port: u16) {
     let app = create_router(state);
@@ -3,7 +3,7 @@
 use crate::schedule_store::ScheduleStore;
 use crate::Handler;
 use serenity::builder::{CreateCommand, CreateCommandOption};
-use serenity::model::application::{CommandInteraction, CommandOptionType};
+use serenity::model::application::{CommandDataOption, CommandDataOptionValue, CommandInteraction, CommandOptionType};
 use serenity::prelude::*;
 use tracing::error;
 
@@ -17,6 +17,23 @@ pub fn register() -> CreateCommand {
         .add_option(
             CreateCommandOption::new(CommandOptionType::SubCommand, "reload", "設定を再読み込み"),
         )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommandGroup, "cache", "Web取得キャッシュ")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::SubCommand, "stats", "キャッシュ統計を表示"),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::SubCommand, "purge", "キャッシュを削除")
+                        .add_sub_option(
+                            CreateCommandOption::new(
+                                CommandOptionType::String,
+                                "target",
+                                "all / expired / URLに含まれる文字列（省略時は expired）",
+                            )
+                            .required(false),
+                        ),
+                ),
+        )
 }
 
 /// /admin コマンドの実行
@@ -45,6 +62,7 @@ pub async fn run(
     match subcommand.name.as_str() {
         "status" => handle_status(handler).await,
         "reload" => handle_reload(handler).await,
+        "cache" => handle_cache_group(handler, subcommand),
         _ => "不明なサブコマンドです。".to_string(),
     }
 }
@@ -59,16 +77,23 @@ async fn handle_status(handler: &Handler) -> String {
 
     // ツール数を取得
     let tm = handler.glm_client.tool_manager();
-    let tool_manager = tm.read().await;
-    let tool_count = tool_manager.list_tools().len();
+    let tool_count = tm.read().await.list_tools().len();
 
-    format!(
+    let mut status = format!(
         "**システム状態**\n\
         - セッション数: {}\n\
         - スケジュール数: {}\n\
         - ツール数: {}",
         session_count, schedule_count, tool_count
-    )
+    );
+
+    // MCPサーバーの状態
+    if let Some(manager) = &handler.mcp_manager {
+        status.push_str("\n\n**MCPサーバー**\n");
+        status.push_str(&super::mcp::format_statuses(&manager.statuses().await));
+    }
+
+    status
 }
 
 /// /admin reload の処理
@@ -94,6 +119,78 @@ async fn handle_reload(handler: &Handler) -> String {
     format!("**設定再読み込み**\n{}", reload_messages.join("\n"))
 }
 
+/// /admin cache グループの処理
+fn handle_cache_group(handler: &Handler, group: &CommandDataOption) -> String {
+    let sub_options = match &group.value {
+        CommandDataOptionValue::SubCommandGroup(options) => options,
+        _ => return "サブコマンドグループの値を取得できませんでした。".to_string(),
+    };
+
+    let subcommand = match sub_options.first() {
+        Some(opt) => opt,
+        None => return "サブコマンドを指定してください。".to_string(),
+    };
+
+    match subcommand.name.as_str() {
+        "stats" => handle_cache_stats(handler),
+        "purge" => {
+            let target = match &subcommand.value {
+                CommandDataOptionValue::SubCommand(options) => options
+                    .iter()
+                    .find(|opt| opt.name == "target")
+                    .and_then(|opt| opt.value.as_str())
+                    .unwrap_or("expired"),
+                _ => "expired",
+            };
+            handle_cache_purge(handler, target)
+        }
+        _ => "不明なキャッシュサブコマンドです。".to_string(),
+    }
+}
+
+/// /admin cache stats の処理
+fn handle_cache_stats(handler: &Handler) -> String {
+    match handler.web_cache.stats() {
+        Ok(stats) => format!(
+            "**Web取得キャッシュ**\n\
+            - エントリ数: {}（期限切れ {}）\n\
+            - 合計サイズ: {:.1} KB\n\
+            - ヒット数: {}\n\
+            - 最古のエントリ: {}",
+            stats.entries,
+            stats.expired,
+            stats.total_bytes as f64 / 1024.0,
+            stats.total_hits,
+            stats
+                .oldest
+                .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+                .unwrap_or_else(|| "なし".to_string())
+        ),
+        Err(e) => {
+            error!("Failed to get web cache stats: {}", e);
+            "キャッシュ統計の取得に失敗しました。".to_string()
+        }
+    }
+}
+
+/// /admin cache purge の処理
+fn handle_cache_purge(handler: &Handler, target: &str) -> String {
+    let target = target.trim();
+    let result = match target {
+        "all" => handler.web_cache.purge_all(),
+        "expired" | "" => handler.web_cache.purge_expired(),
+        pattern => handler.web_cache.purge_matching(pattern),
+    };
+
+    match result {
+        Ok(count) => format!("🗑️ キャッシュを {} 件削除しました（対象: {}）", count, if target.is_empty() { "expired" } else { target }),
+        Err(e) => {
+            error!("Failed to purge web cache: {}", e);
+            "キャッシュの削除に失敗しました。".to_string()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -89,13 +89,8 @@ pub async fn run(ctx: &Context, interaction: &CommandInteraction, handler: &Hand
     };
 
     // ツールコンテキストを作成
-    let tool_context = ToolContext {
-        user_id,
-        user_name,
-        channel_id,
-        base_output_dir: handler.base_output_dir.clone(),
-        custom_output_subdir: None,
-    };
+    let tool_context = ToolContext::new(user_id, user_name, channel_id, handler.base_output_dir.clone())
+        .with_guild_id(interaction.guild_id.map(|id| id.get()));
 
     // GLM APIに問い合わせ
     let response = match handler.glm_client.chat_with_tools(messages, &tool_context).await {
@@ -140,6 +135,9 @@ pub async fn run(ctx: &Context, interaction: &CommandInteraction, handler: &Hand
         }
     }
 
+    // ツールが生成した添付ファイルを送信
+    crate::streaming::send_attachments(&ctx.http, interaction.channel_id, tool_context.take_attachments()).await;
+
     debug!("Response sent successfully");
 }
 
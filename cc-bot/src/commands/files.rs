@@ -0,0 +1,175 @@
+//! /files - ワークスペースファイル履歴Slash Command
+
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandDataOption, CommandDataOptionValue, CommandInteraction, CommandOptionType};
+use serenity::prelude::*;
+
+use std::path::Path;
+
+use crate::file_history::{FileHistory, FileHistoryError};
+use crate::tool::ToolContext;
+use crate::tools::{ApplyPatchTool, FileHistoryTool};
+use crate::Handler;
+
+/// Discordメッセージに収める差分の最大文字数
+const MAX_DIFF_CHARS: usize = 1800;
+
+/// /files コマンドの定義
+pub fn register() -> CreateCommand {
+    CreateCommand::new("files")
+        .description("ファイル履歴の確認・復元")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "history", "バージョン一覧")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "path", "ファイルの相対パス")
+                        .required(true),
+                ),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "diff", "バージョンと現在のファイルの差分")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "path", "ファイルの相対パス")
+                        .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Integer, "version", "バージョンID (省略時は最新)")
+                        .required(false),
+                ),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "undo", "ファイルを復元")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "path", "ファイルの相対パス")
+                        .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Integer, "version", "バージョンID (省略時は直前の状態)")
+                        .required(false),
+                ),
+        )
+}
+
+/// /files コマンドの実行
+pub async fn run(_ctx: &Context, interaction: &CommandInteraction, handler: &Handler) -> String {
+    let user_id = interaction.user.id.get();
+
+    let subcommand = interaction
+        .data
+        .options
+        .first()
+        .and_then(|opt| {
+            if let CommandDataOptionValue::SubCommand(sub_opts) = &opt.value {
+                Some((opt.name.as_str(), sub_opts))
+            } else {
+                None
+            }
+        });
+
+    let Some((name, sub_opts)) = subcommand else {
+        return "不明なサブコマンドです。".to_string();
+    };
+
+    let path = get_string(sub_opts, "path").unwrap_or_default();
+    if path.trim().is_empty() {
+        return "ファイルパスを指定してください。".to_string();
+    }
+    let version = get_version(sub_opts);
+    // 履歴・差分・復元の対象はツールと同じく現在のワークスペース
+    let context = ToolContext::new(
+        user_id,
+        interaction.user.name.clone(),
+        interaction.channel_id.get(),
+        handler.base_output_dir.clone(),
+    )
+    .with_guild_id(interaction.guild_id.map(|id| id.get()));
+    let history = FileHistory::for_context(&context);
+    if name == "history" {
+        return handle_history(&history, &path).await;
+    }
+
+    let file = match ApplyPatchTool::resolve_path(&path, &context) {
+        Ok(file) => file,
+        Err(e) => return format!("`{}` は操作できません: {}", path, e),
+    };
+
+    match name {
+        "diff" => handle_diff(&history, &path, &file, version).await,
+        "undo" => handle_undo(&history, &path, &file, version).await,
+        _ => "不明なサブコマンドです。".to_string(),
+    }
+}
+
+fn get_string(sub_opts: &[CommandDataOption], name: &str) -> Option<String> {
+    sub_opts.iter().find(|opt| opt.name == name).and_then(|opt| {
+        if let CommandDataOptionValue::String(s) = &opt.value {
+            Some(s.clone())
+        } else {
+            None
+        }
+    })
+}
+
+fn get_version(sub_opts: &[CommandDataOption]) -> Option<u64> {
+    sub_opts.iter().find(|opt| opt.name == "version").and_then(|opt| {
+        if let CommandDataOptionValue::Integer(v) = &opt.value {
+            u64::try_from(*v).ok()
+        } else {
+            None
+        }
+    })
+}
+
+/// エラーをユーザー向けメッセージに変換
+fn error_message(path: &str, error: FileHistoryError) -> String {
+    match error {
+        FileHistoryError::NoHistory(_) => format!("`{}` の履歴はありません。", path),
+        FileHistoryError::VersionNotFound(id) => format!("バージョン v{} が見つかりません。", id),
+        other => {
+            tracing::error!("Failed to access file history: {}", other);
+            "履歴の読み込みに失敗しました。".to_string()
+        }
+    }
+}
+
+/// バージョン一覧
+async fn handle_history(history: &FileHistory, path: &str) -> String {
+    match history.list(path).await {
+        Ok(versions) if versions.is_empty() => format!("`{}` の履歴はありません。", path),
+        Ok(versions) => {
+            let mut response = format!("📜 **{}** の履歴（新しい順）\n", path);
+            for version in versions.iter().take(15) {
+                response.push_str(&format!("• {}\n", FileHistoryTool::format_version(version)));
+            }
+            if versions.len() > 15 {
+                response.push_str(&format!("…ほか {} 件", versions.len() - 15));
+            }
+            response
+        }
+        Err(e) => error_message(path, e),
+    }
+}
+
+/// 差分表示
+async fn handle_diff(history: &FileHistory, path: &str, file: &Path, version: Option<u64>) -> String {
+    match history.diff(path, file, version, None).await {
+        Ok(mut diff) => {
+            if diff.chars().count() > MAX_DIFF_CHARS {
+                diff = diff.chars().take(MAX_DIFF_CHARS).collect();
+                diff.push_str("\n...(省略)");
+            }
+            format!("```diff\n{}\n```", diff.replace("```", "ʼʼʼ"))
+        }
+        Err(e) => error_message(path, e),
+    }
+}
+
+/// 復元
+async fn handle_undo(history: &FileHistory, path: &str, file: &Path, version: Option<u64>) -> String {
+    match history.restore(path, file, version).await {
+        Ok(restored) => format!(
+            "✅ `{}` を v{} に戻しました。もう一度 `/files undo` すると元に戻せます。",
+            path, restored.id
+        ),
+        Err(e) => error_message(path, e),
+    }
+}
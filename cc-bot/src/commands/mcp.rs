@@ -0,0 +1,723 @@
+//! /mcp - MCPサーバー連携Slash Command
+//!
+//! `/mcp prompt <server> <name>` でMCPサーバーのプロンプトを取得し、その内容でGLM-4.7に問い合わせます。
+//! サーバー名・プロンプト名は入力時に現在の一覧から補完し（プロンプト名は選択中のサーバーで絞り込む）、
+//! 引数オプションは起動時に取得したプロンプト一覧から生成します。
+//! `list` / `enable` / `disable` / `restart` / `tools` はサーバー管理用で、管理者のみ実行できます。
+//! `approve` / `deny` は `untrusted` サーバーからのサンプリング要求を承認・拒否します（管理者のみ）。
+
+use crate::history::ChatMessage;
+use crate::mcp_client::MCPPromptDefinition;
+use crate::mcp_manager::{MCPManager, MCPServerStatus};
+use crate::mcp_sampling::SamplingRequestInfo;
+use crate::session::SessionKey;
+use crate::tool::ToolContext;
+use rmcp::model::{GetPromptResult, PromptMessageContent, PromptMessageRole, ResourceContents};
+use serenity::builder::{
+    AutocompleteChoice, CreateAutocompleteResponse, CreateCommand, CreateCommandOption, CreateInteractionResponse,
+    CreateInteractionResponseMessage, EditInteractionResponse,
+};
+use serenity::model::application::{
+    CommandDataOption, CommandDataOptionValue, CommandInteraction, CommandOptionType,
+};
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::Handler;
+
+/// 選択肢の最大数（Discordの制限）
+const MAX_CHOICES: usize = 25;
+/// 補完候補の取得を待つ時間（Discordは3秒以内の応答を要求する）
+const AUTOCOMPLETE_TIMEOUT: Duration = Duration::from_secs(2);
+/// 引数オプションの最大数（Discordの25オプション制限から server / name / args を除いた数）
+const MAX_ARGUMENT_OPTIONS: usize = 22;
+/// 固定オプション名（引数オプションと衝突させない）
+const RESERVED_OPTIONS: [&str; 3] = ["server", "name", "args"];
+
+/// /mcp コマンドの定義（prompts は引数オプションの生成に使う）
+pub fn register(prompts: &[MCPPromptDefinition]) -> CreateCommand {
+    let name_option = CreateCommandOption::new(CommandOptionType::String, "name", "プロンプト名")
+        .required(true)
+        .set_autocomplete(true);
+
+    let mut prompt_subcommand =
+        CreateCommandOption::new(CommandOptionType::SubCommand, "prompt", "MCPプロンプトを実行")
+            .add_sub_option(server_option(true))
+            .add_sub_option(name_option);
+    for (option, description) in argument_options(prompts) {
+        prompt_subcommand = prompt_subcommand.add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, option, description).required(false),
+        );
+    }
+    prompt_subcommand = prompt_subcommand.add_sub_option(
+        CreateCommandOption::new(CommandOptionType::String, "args", "その他の引数 (key=value をカンマ区切り)")
+            .required(false),
+    );
+
+    CreateCommand::new("mcp")
+        .description("MCPサーバー連携")
+        .add_option(prompt_subcommand)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            "MCPサーバーの状態を表示（管理者のみ）",
+        ))
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "enable", "MCPサーバーを有効化（管理者のみ）")
+                .add_sub_option(server_option(true)),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "disable", "MCPサーバーを無効化（管理者のみ）")
+                .add_sub_option(server_option(true)),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "restart", "MCPサーバーを再起動（管理者のみ）")
+                .add_sub_option(server_option(true)),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "tools", "MCPツール一覧を表示（管理者のみ）")
+                .add_sub_option(server_option(false)),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "approve", "サンプリング要求を承認（管理者のみ）")
+                .add_sub_option(request_id_option()),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "deny", "サンプリング要求を拒否（管理者のみ）")
+                .add_sub_option(request_id_option()),
+        )
+}
+
+/// サーバー名のオプション（入力時に補完）
+fn server_option(required: bool) -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::String, "server", "MCPサーバー")
+        .required(required)
+        .set_autocomplete(true)
+}
+
+/// 入力中の文字列に一致するサーバー名の候補 (表示名, 値)
+fn server_choices(servers: &[String], input: &str) -> Vec<(String, String)> {
+    let input = input.trim().to_lowercase();
+    servers
+        .iter()
+        .filter(|server| server.to_lowercase().contains(&input))
+        .take(MAX_CHOICES)
+        .map(|server| (server.clone(), server.clone()))
+        .collect()
+}
+
+/// 入力中の文字列に一致するプロンプト名の候補 (表示名, 値)
+///
+/// サーバーが選択されていなければ全サーバーの候補を、サーバー名付きで表示する
+fn prompt_choices(prompts: &[MCPPromptDefinition], server: Option<&str>, input: &str) -> Vec<(String, String)> {
+    let input = input.trim().to_lowercase();
+    prompts
+        .iter()
+        .filter(|p| server.is_none_or(|server| p.server_name == server))
+        .filter(|p| p.name.to_lowercase().contains(&input))
+        .take(MAX_CHOICES)
+        .map(|p| {
+            let label = match server {
+                Some(_) => choice_label(p),
+                None => truncate(&format!("{} / {}", p.server_name, choice_label(p)), 100),
+            };
+            (label, p.name.clone())
+        })
+        .collect()
+}
+
+/// /mcp の入力補完（サーバー名・プロンプト名を現在の一覧から返す）
+pub async fn autocomplete(ctx: &Context, interaction: &CommandInteraction, handler: &Handler) {
+    let Some(focused) = interaction.data.autocomplete() else {
+        return;
+    };
+    let choices = match handler.mcp_manager.as_ref() {
+        None => Vec::new(),
+        Some(manager) => {
+            let client = manager.client();
+            match focused.name {
+                "server" => {
+                    let servers: Vec<String> = client.list_servers().into_iter().map(|s| s.name).collect();
+                    server_choices(&servers, focused.value)
+                }
+                "name" => {
+                    let sub_opts = match interaction.data.options.first().map(|opt| &opt.value) {
+                        Some(CommandDataOptionValue::SubCommand(sub_opts)) => sub_opts.as_slice(),
+                        _ => &[],
+                    };
+                    let server = string_option(sub_opts, "server");
+                    let prompts = match server {
+                        Some(server) => tokio::time::timeout(AUTOCOMPLETE_TIMEOUT, client.list_prompts(server))
+                            .await
+                            .ok()
+                            .and_then(Result::ok)
+                            .unwrap_or_default(),
+                        None => tokio::time::timeout(AUTOCOMPLETE_TIMEOUT, client.list_all_prompts())
+                            .await
+                            .unwrap_or_default(),
+                    };
+                    prompt_choices(&prompts, server, focused.value)
+                }
+                _ => Vec::new(),
+            }
+        }
+    };
+
+    let choices = choices
+        .into_iter()
+        .map(|(label, value)| AutocompleteChoice::new(label, value))
+        .collect();
+    let response = CreateInteractionResponse::Autocomplete(CreateAutocompleteResponse::new().set_choices(choices));
+    if let Err(e) = interaction.create_response(&ctx.http, response).await {
+        warn!("Failed to respond to /mcp autocomplete: {}", e);
+    }
+}
+
+/// サンプリング要求IDのオプション
+fn request_id_option() -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::Integer, "id", "サンプリング要求ID")
+        .min_int_value(1)
+        .required(true)
+}
+
+/// 承認待ちのサンプリング要求を整形
+fn format_pending_requests(requests: &[SamplingRequestInfo]) -> String {
+    requests
+        .iter()
+        .map(|request| {
+            format!(
+                "- #{} `{}` 最大 {} トークン ({})\n  └ {}",
+                request.id,
+                request.server_name,
+                request.max_tokens,
+                request.requested_at.format("%H:%M:%S UTC"),
+                truncate(&request.preview.replace('\n', " "), 100)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// サーバー状態の一覧を整形（/admin status と共用）
+pub fn format_statuses(statuses: &[MCPServerStatus]) -> String {
+    if statuses.is_empty() {
+        return "MCPサーバーは設定されていません。".to_string();
+    }
+    statuses
+        .iter()
+        .map(|status| {
+            let mut line = format!(
+                "- `{}` ({}) {} / ツール {}件 / 再起動 {}回",
+                status.name,
+                status.transport,
+                status.health.state.label(),
+                status.tools,
+                status.health.restarts
+            );
+            if status.health.state == crate::mcp_manager::HealthState::Unhealthy {
+                if let Some(error) = &status.health.last_error {
+                    line.push_str(&format!("\n  └ {}", truncate(error, 150)));
+                }
+                if let Some(next_retry) = status.health.next_retry {
+                    line.push_str(&format!("\n  └ 次回再起動: {}", next_retry.format("%H:%M:%S UTC")));
+                }
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 選択肢の表示名（Discordの100文字制限に収める）
+fn choice_label(prompt: &MCPPromptDefinition) -> String {
+    let label = if prompt.description.is_empty() {
+        prompt.name.clone()
+    } else {
+        format!("{} - {}", prompt.name, prompt.description)
+    };
+    truncate(&label, 100)
+}
+
+/// プロンプト引数名をDiscordのオプション名に変換（小文字英数字・`-`・`_`、32文字以内）
+fn option_name(argument: &str) -> String {
+    let name: String = argument
+        .chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .take(32)
+        .collect();
+    if name.is_empty() { "_".to_string() } else { name }
+}
+
+/// 全プロンプトの引数から重複を除いた引数オプション (オプション名, 説明) を生成
+fn argument_options(prompts: &[MCPPromptDefinition]) -> Vec<(String, String)> {
+    let mut options: Vec<(String, String)> = Vec::new();
+    for argument in prompts.iter().flat_map(|p| &p.arguments) {
+        let name = option_name(&argument.name);
+        if RESERVED_OPTIONS.contains(&name.as_str()) || options.iter().any(|(n, _)| *n == name) {
+            continue;
+        }
+        let description = if argument.description.is_empty() {
+            format!("引数 {}", argument.name)
+        } else {
+            truncate(&argument.description, 100)
+        };
+        options.push((name, description));
+        if options.len() >= MAX_ARGUMENT_OPTIONS {
+            break;
+        }
+    }
+    options
+}
+
+/// 文字数で切り詰め
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max_chars - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// `key=value, key2=value2` 形式の引数をパース
+fn parse_args(input: &str) -> Result<HashMap<String, String>, String> {
+    let mut args = HashMap::new();
+    for pair in input.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("引数の形式が正しくありません: `{}`（key=value で指定してください）", pair))?;
+        args.insert(key.trim().to_string(), value.trim().to_string());
+    }
+    Ok(args)
+}
+
+/// オプションからプロンプト引数を組み立てて検証
+fn collect_arguments(
+    prompt: &MCPPromptDefinition,
+    sub_opts: &[CommandDataOption],
+) -> Result<HashMap<String, String>, String> {
+    let mut arguments = match string_option(sub_opts, "args") {
+        Some(input) => parse_args(input)?,
+        None => HashMap::new(),
+    };
+
+    // args に未定義の引数が含まれていないか確認
+    let unknown: Vec<&str> = arguments
+        .keys()
+        .filter(|key| !prompt.arguments.iter().any(|arg| &arg.name == *key))
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        let known: Vec<&str> = prompt.arguments.iter().map(|arg| arg.name.as_str()).collect();
+        return Err(format!(
+            "プロンプト `{}` に存在しない引数です: {}（指定できる引数: {}）",
+            prompt.name,
+            unknown.join(", "),
+            if known.is_empty() { "なし".to_string() } else { known.join(", ") }
+        ));
+    }
+
+    // 個別オプションの値を優先
+    for argument in &prompt.arguments {
+        if let Some(value) = string_option(sub_opts, &option_name(&argument.name)) {
+            arguments.insert(argument.name.clone(), value.to_string());
+        }
+    }
+
+    let missing = prompt.missing_arguments(&arguments);
+    if !missing.is_empty() {
+        let details: Vec<String> = prompt
+            .arguments
+            .iter()
+            .filter(|arg| missing.contains(&arg.name.as_str()))
+            .map(|arg| {
+                if arg.description.is_empty() {
+                    format!("- `{}`", arg.name)
+                } else {
+                    format!("- `{}`: {}", arg.name, arg.description)
+                }
+            })
+            .collect();
+        return Err(format!("必須の引数が指定されていません:\n{}", details.join("\n")));
+    }
+
+    Ok(arguments)
+}
+
+/// プロンプトのメッセージを会話履歴のメッセージに変換
+fn prompt_to_messages(result: &GetPromptResult) -> Vec<ChatMessage> {
+    result
+        .messages
+        .iter()
+        .map(|message| {
+            let text = match &message.content {
+                PromptMessageContent::Text { text } => text.clone(),
+                PromptMessageContent::Image { .. } => "[画像]".to_string(),
+                PromptMessageContent::Resource { resource } => match &resource.resource {
+                    ResourceContents::TextResourceContents { uri, text, .. } => format!("{}\n{}", uri, text),
+                    ResourceContents::BlobResourceContents { uri, .. } => format!("[バイナリリソース: {}]", uri),
+                },
+                PromptMessageContent::ResourceLink { link } => format!("[リソース: {}]", link.uri),
+            };
+            match message.role {
+                PromptMessageRole::User => ChatMessage::user(text),
+                PromptMessageRole::Assistant => ChatMessage::assistant(text),
+            }
+        })
+        .collect()
+}
+
+/// 文字列オプションを取得
+fn string_option<'a>(sub_opts: &'a [CommandDataOption], name: &str) -> Option<&'a str> {
+    sub_opts.iter().find(|opt| opt.name == name).and_then(|opt| {
+        if let CommandDataOptionValue::String(s) = &opt.value {
+            Some(s.as_str()).filter(|s| !s.trim().is_empty())
+        } else {
+            None
+        }
+    })
+}
+
+/// /mcp コマンドの実行（deferred responseパターン）
+pub async fn run(ctx: &Context, interaction: &CommandInteraction, handler: &Handler) {
+    let subcommand = interaction.data.options.first();
+    let sub_opts = match subcommand.map(|opt| &opt.value) {
+        Some(CommandDataOptionValue::SubCommand(sub_opts)) => sub_opts.as_slice(),
+        _ => &[],
+    };
+    let subcommand = subcommand.map(|opt| opt.name.as_str()).unwrap_or_default();
+
+    // prompt 以外は管理者のみ
+    let user_id = interaction.user.id.get();
+    let early_message = if subcommand != "prompt" && !is_admin(handler, user_id).await {
+        Some("このコマンドは管理者のみ実行できます。")
+    } else {
+        None
+    };
+    let manager = match (early_message, handler.mcp_manager.as_ref()) {
+        (None, Some(manager)) => manager,
+        (message, _) => {
+            let message = message.unwrap_or("MCPサーバーが設定されていません。");
+            let _ = interaction
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content(message)),
+                )
+                .await;
+            return;
+        }
+    };
+
+    info!("Processing /mcp {} from user {}", subcommand, user_id);
+
+    // プロンプト取得・LLM呼び出し・サーバー再起動は3秒を超えうるため先に遅延応答を返す
+    if let Err(e) = interaction
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Defer(CreateInteractionResponseMessage::new()),
+        )
+        .await
+    {
+        error!("Failed to defer response: {}", e);
+        return;
+    }
+
+    let server = string_option(sub_opts, "server");
+    let response = match subcommand {
+        "prompt" => run_prompt(ctx, interaction, handler, manager, sub_opts).await,
+        "list" => {
+            let mut response = format!("**MCPサーバー**\n{}", format_statuses(&manager.statuses().await));
+            if let Some(sampler) = manager.client().sampler() {
+                let pending = sampler.approvals().pending().await;
+                if !pending.is_empty() {
+                    response.push_str(&format!(
+                        "\n\n**承認待ちのサンプリング要求**\n{}",
+                        format_pending_requests(&pending)
+                    ));
+                }
+            }
+            response
+        }
+        "approve" | "deny" => resolve_sampling(manager, subcommand == "approve", sub_opts).await,
+        "enable" => match manager.enable(server.unwrap_or_default()).await {
+            Ok(count) => format!("✅ `{}` を有効化しました（ツール {}件）", server.unwrap_or_default(), count),
+            Err(e) => format!("エラー: {}", e),
+        },
+        "disable" => match manager.disable(server.unwrap_or_default()).await {
+            Ok(()) => format!("✅ `{}` を無効化しました", server.unwrap_or_default()),
+            Err(e) => format!("エラー: {}", e),
+        },
+        "restart" => match manager.restart(server.unwrap_or_default()).await {
+            Ok(count) => format!("✅ `{}` を再起動しました（ツール {}件）", server.unwrap_or_default(), count),
+            Err(e) => format!("エラー: {}", e),
+        },
+        "tools" => match manager.tools(server).await {
+            Ok(tools) if tools.is_empty() => "MCPツールはありません。".to_string(),
+            Ok(tools) => {
+                let lines: Vec<String> = tools
+                    .iter()
+                    .map(|t| format!("- `{}` ({}) {}", t.name, t.server_name, truncate(&t.description, 100)))
+                    .collect();
+                format!("**MCPツール** ({}件)\n{}", tools.len(), lines.join("\n"))
+            }
+            Err(e) => format!("エラー: {}", e),
+        },
+        _ => "不明なサブコマンドです。".to_string(),
+    };
+
+    let parts = crate::streaming::split_message(&response, 2000);
+    if let Err(e) = interaction
+        .edit_response(&ctx.http, EditInteractionResponse::new().content(&parts[0]))
+        .await
+    {
+        error!("Failed to edit response: {}", e);
+        return;
+    }
+    for part in parts.iter().skip(1) {
+        if let Err(e) = interaction.channel_id.say(&ctx.http, part).await {
+            error!("Failed to send additional message: {}", e);
+        }
+    }
+}
+
+/// /mcp approve・deny の処理
+async fn resolve_sampling(manager: &MCPManager, approved: bool, sub_opts: &[CommandDataOption]) -> String {
+    let Some(sampler) = manager.client().sampler() else {
+        return "サンプリングは有効になっていません。".to_string();
+    };
+    let id = sub_opts
+        .iter()
+        .find(|opt| opt.name == "id")
+        .and_then(|opt| opt.value.as_i64())
+        .unwrap_or_default();
+    match sampler.approvals().resolve(id as u64, approved).await {
+        Some(request) => format!(
+            "✅ `{}` のサンプリング要求 #{} を{}しました",
+            request.server_name,
+            request.id,
+            if approved { "承認" } else { "拒否" }
+        ),
+        None => format!("サンプリング要求 #{} は見つかりません（処理済みまたはタイムアウト）。", id),
+    }
+}
+
+/// 管理者チェック（PermissionManagerを使用）
+async fn is_admin(handler: &Handler, user_id: u64) -> bool {
+    let manager = handler.permission_manager.read().await;
+    manager.is_admin(user_id) || manager.is_super_user(user_id)
+}
+
+/// /mcp prompt の処理
+async fn run_prompt(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    handler: &Handler,
+    manager: &MCPManager,
+    sub_opts: &[CommandDataOption],
+) -> String {
+    let client = manager.client();
+    let server = string_option(sub_opts, "server").unwrap_or_default();
+    let name = string_option(sub_opts, "name").unwrap_or_default();
+    let user_id = interaction.user.id.get();
+    let channel_id = interaction.channel_id.get();
+
+    match client.list_prompts(server).await {
+        Err(e) => format!("エラー: {}", e),
+        Ok(prompts) => match prompts.iter().find(|p| p.name == name) {
+            None => format!("サーバー `{}` にプロンプト `{}` はありません。", server, name),
+            Some(prompt) => match collect_arguments(prompt, sub_opts) {
+                Err(message) => message,
+                Ok(arguments) => match client.get_prompt(server, name, arguments).await {
+                    Err(e) => format!("エラー: {}", e),
+                    Ok(result) => {
+                        let tool_context = ToolContext::new(
+                            user_id,
+                            interaction.user.name.clone(),
+                            channel_id,
+                            handler.base_output_dir.clone(),
+                        )
+                        .with_guild_id(interaction.guild_id.map(|id| id.get()));
+                        let response = ask_with_prompt(handler, user_id, channel_id, &result, &tool_context).await;
+                        crate::streaming::send_attachments(
+                            &ctx.http,
+                            interaction.channel_id,
+                            tool_context.take_attachments(),
+                        )
+                        .await;
+                        response
+                    }
+                },
+            },
+        },
+    }
+}
+
+/// プロンプトのメッセージをセッションに追加してGLM-4.7に問い合わせ
+async fn ask_with_prompt(
+    handler: &Handler,
+    user_id: u64,
+    channel_id: u64,
+    result: &GetPromptResult,
+    tool_context: &ToolContext,
+) -> String {
+    let session_key = SessionKey::new(user_id, channel_id);
+    let messages = {
+        let mut mgr = handler.session_manager.lock().await;
+        let session = mgr.get_or_create(session_key.clone());
+        for message in prompt_to_messages(result) {
+            session.history.push(message);
+        }
+        session.history.to_vec()
+    };
+
+    match handler.glm_client.chat_with_tools(messages, tool_context).await {
+        Ok(response) => {
+            let mut mgr = handler.session_manager.lock().await;
+            if let Some(session) = mgr.get_mut(&session_key) {
+                session.history.push(ChatMessage::assistant(&response));
+            }
+            response
+        }
+        Err(e) => {
+            error!("GLM API error: {}", e);
+            format!("エラーが発生しました: {}", e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp_client::MCPPromptArgument;
+
+    fn prompt(name: &str, arguments: &[(&str, bool)]) -> MCPPromptDefinition {
+        MCPPromptDefinition {
+            name: name.to_string(),
+            description: String::new(),
+            arguments: arguments
+                .iter()
+                .map(|(name, required)| MCPPromptArgument {
+                    name: name.to_string(),
+                    description: String::new(),
+                    required: *required,
+                })
+                .collect(),
+            server_name: "notes".to_string(),
+        }
+    }
+
+    fn string_opt(name: &str, value: &str) -> CommandDataOption {
+        serde_json::from_value(serde_json::json!({"name": name, "type": 3, "value": value})).unwrap()
+    }
+
+    #[test]
+    fn test_option_name() {
+        assert_eq!(option_name("topic"), "topic");
+        assert_eq!(option_name("Max Words"), "max_words");
+        assert_eq!(option_name(&"a".repeat(40)).len(), 32);
+    }
+
+    #[test]
+    fn test_argument_options_dedup_and_reserved() {
+        let prompts = vec![
+            prompt("summarize", &[("topic", true), ("name", false)]),
+            prompt("translate", &[("Topic", false), ("language", true)]),
+        ];
+        let names: Vec<String> = argument_options(&prompts).into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, vec!["topic", "language"]);
+    }
+
+    #[test]
+    fn test_autocomplete_choices() {
+        let servers = vec!["notes".to_string(), "github".to_string()];
+        assert_eq!(server_choices(&servers, "GIT"), vec![("github".to_string(), "github".to_string())]);
+        assert_eq!(server_choices(&servers, "").len(), 2);
+
+        // 同名のプロンプトは選択中のサーバーのものだけを候補にする
+        let mut other = prompt("summarize", &[]);
+        other.server_name = "github".to_string();
+        let prompts = vec![prompt("summarize", &[]), prompt("translate", &[]), other];
+        assert_eq!(
+            prompt_choices(&prompts, Some("github"), "sum"),
+            vec![("summarize".to_string(), "summarize".to_string())]
+        );
+        assert_eq!(prompt_choices(&prompts, Some("notes"), "").len(), 2);
+
+        let all = prompt_choices(&prompts, None, "summ");
+        let labels: Vec<&str> = all.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(labels, vec!["notes / summarize", "github / summarize"]);
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = parse_args("topic = rust, lang=ja").unwrap();
+        assert_eq!(args["topic"], "rust");
+        assert_eq!(args["lang"], "ja");
+        assert!(parse_args("").unwrap().is_empty());
+        assert!(parse_args("topic").is_err());
+    }
+
+    #[test]
+    fn test_collect_arguments() {
+        let summarize = prompt("summarize", &[("topic", true), ("Max Words", false)]);
+
+        // 個別オプションと args の併用（個別オプション優先）
+        let opts = vec![string_opt("topic", "MCP"), string_opt("args", "topic=ignored, Max Words=50")];
+        let args = collect_arguments(&summarize, &opts).unwrap();
+        assert_eq!(args["topic"], "MCP");
+        assert_eq!(args["Max Words"], "50");
+
+        // 必須引数の不足
+        let err = collect_arguments(&summarize, &[string_opt("max_words", "10")]).unwrap_err();
+        assert!(err.contains("topic"));
+
+        // 未定義の引数
+        let err = collect_arguments(&summarize, &[string_opt("args", "topic=a, lang=ja")]).unwrap_err();
+        assert!(err.contains("lang"));
+    }
+
+    #[test]
+    fn test_format_statuses() {
+        use crate::mcp_manager::{HealthState, ServerHealth};
+
+        assert!(format_statuses(&[]).contains("設定されていません"));
+
+        let health = ServerHealth {
+            state: HealthState::Unhealthy,
+            consecutive_failures: 2,
+            restarts: 3,
+            last_error: Some("connection refused".to_string()),
+            last_checked: None,
+            next_retry: None,
+        };
+        let status = MCPServerStatus {
+            name: "notes".to_string(),
+            transport: "stdio".to_string(),
+            enabled: true,
+            description: String::new(),
+            tools: 4,
+            health,
+        };
+        let text = format_statuses(&[status]);
+        assert!(text.contains("`notes` (stdio)"));
+        assert!(text.contains("ツール 4件 / 再起動 3回"));
+        assert!(text.contains("connection refused"));
+    }
+
+    #[test]
+    fn test_format_pending_requests() {
+        let request = SamplingRequestInfo {
+            id: 7,
+            server_name: "notes".to_string(),
+            preview: "Summarize\nthis".to_string(),
+            max_tokens: 256,
+            requested_at: chrono::Utc::now(),
+        };
+        let text = format_pending_requests(&[request]);
+        assert!(text.contains("#7 `notes` 最大 256 トークン"));
+        assert!(text.contains("Summarize this"));
+    }
+}
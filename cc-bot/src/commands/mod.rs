@@ -5,32 +5,42 @@
 pub mod admin;
 pub mod ask;
 pub mod clear;
+pub mod files;
+pub mod mcp;
 pub mod memory_cmd;
 pub mod permission;
 pub mod schedule;
 pub mod settings;
+pub mod todo;
 pub mod tools;
 
+use crate::mcp_client::MCPPromptDefinition;
 use serenity::builder::CreateCommand;
 use serenity::model::application::Command;
 
-/// 全てのSlash Commandsを登録
-pub fn register_commands() -> Vec<CreateCommand> {
+/// 全てのSlash Commandsを登録（MCPプロンプトの引数は /mcp prompt のオプションになる）
+pub fn register_commands(mcp_prompts: &[MCPPromptDefinition]) -> Vec<CreateCommand> {
     vec![
         admin::register(),
         ask::register(),
         clear::register(),
+        files::register(),
+        mcp::register(mcp_prompts),
         memory_cmd::register(),
         permission::register(),
         schedule::register(),
         settings::register(),
+        todo::register(),
         tools::register(),
     ]
 }
 
 /// グローバルコマンドとして登録（Discord Developer Portalで設定）
-pub async fn register_global_commands(ctx: &serenity::prelude::Context) {
-    let commands = register_commands();
+pub async fn register_global_commands(
+    ctx: &serenity::prelude::Context,
+    mcp_prompts: &[MCPPromptDefinition],
+) {
+    let commands = register_commands(mcp_prompts);
 
     match Command::set_global_commands(&ctx.http, commands).await {
         Ok(_) => {
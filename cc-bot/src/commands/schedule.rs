@@ -102,7 +102,9 @@ async fn handle_add(
 
     // タスクを作成
     let task = match ScheduledTask::new(cron.to_string(), prompt.to_string(), channel_id) {
-        Ok(t) => t,
+        Ok(t) => t
+            .with_user(command.user.id.get())
+            .with_guild(command.guild_id.map(|id| id.get())),
         Err(e) => return format!("エラー: {}", e),
     };
 
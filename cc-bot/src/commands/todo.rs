@@ -0,0 +1,250 @@
+//! /todo - TODO管理Slash Command
+
+use chrono::Utc;
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{
+    CommandDataOption, CommandDataOptionValue, CommandInteraction, CommandOptionType,
+};
+use serenity::prelude::*;
+
+use crate::todo_store::{NewTodo, StatusFilter, TodoFilter, TodoPriority, TodoStatus};
+use crate::tools::{format_todo, parse_due, ReminderOutcome, TodoReminders};
+use crate::Handler;
+
+/// 一覧で表示する最大件数
+const LIST_LIMIT: usize = 20;
+
+/// /todo コマンドの定義
+pub fn register() -> CreateCommand {
+    CreateCommand::new("todo")
+        .description("TODO管理")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "add", "TODO追加")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "title", "内容")
+                        .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "due", "期限 (例: 2026-10-20 18:00, 2026-10-20, 3h, 2d)")
+                        .required(false),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "priority", "優先度")
+                        .add_string_choice("高", "high")
+                        .add_string_choice("中", "normal")
+                        .add_string_choice("低", "low")
+                        .required(false),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "tag", "タグ (カンマ区切りで複数指定可)")
+                        .required(false),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Boolean, "channel", "このチャンネル限定のTODOにする")
+                        .required(false),
+                ),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "list", "TODO一覧")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "status", "状態 (デフォルト: 未完了)")
+                        .add_string_choice("未完了", "active")
+                        .add_string_choice("完了", "done")
+                        .add_string_choice("すべて", "all")
+                        .required(false),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "tag", "タグでフィルタ")
+                        .required(false),
+                ),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "done", "TODO完了")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Integer, "id", "TODO ID")
+                        .required(true),
+                ),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "delete", "TODO削除")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Integer, "id", "TODO ID")
+                        .required(true),
+                ),
+        )
+}
+
+/// /todo コマンドの実行
+pub async fn run(_ctx: &Context, interaction: &CommandInteraction, handler: &Handler) -> String {
+    let user_id = interaction.user.id.get();
+    let channel_id = interaction.channel_id.get();
+
+    // サブコマンドを取得
+    let subcommand = interaction
+        .data
+        .options
+        .first()
+        .and_then(|opt| {
+            if let CommandDataOptionValue::SubCommand(sub_opts) = &opt.value {
+                Some((opt.name.as_str(), sub_opts))
+            } else {
+                None
+            }
+        });
+
+    let reminders = TodoReminders::new(handler.scheduler.clone(), handler.schedule_store.clone());
+
+    match subcommand {
+        Some(("add", sub_opts)) => handle_add(user_id, channel_id, sub_opts, handler, &reminders).await,
+        Some(("list", sub_opts)) => handle_list(user_id, sub_opts, handler),
+        Some(("done", sub_opts)) => handle_done(user_id, sub_opts, handler, &reminders).await,
+        Some(("delete", sub_opts)) => handle_delete(user_id, sub_opts, handler, &reminders).await,
+        _ => "不明なサブコマンドです。".to_string(),
+    }
+}
+
+/// 文字列オプションを取得
+fn string_option<'a>(sub_opts: &'a [CommandDataOption], name: &str) -> Option<&'a str> {
+    sub_opts.iter().find(|opt| opt.name == name).and_then(|opt| {
+        if let CommandDataOptionValue::String(s) = &opt.value {
+            Some(s.as_str())
+        } else {
+            None
+        }
+    })
+}
+
+/// 整数オプションを取得
+fn integer_option(sub_opts: &[CommandDataOption], name: &str) -> Option<i64> {
+    sub_opts.iter().find(|opt| opt.name == name).and_then(|opt| {
+        if let CommandDataOptionValue::Integer(n) = &opt.value {
+            Some(*n)
+        } else {
+            None
+        }
+    })
+}
+
+/// TODO追加
+async fn handle_add(
+    user_id: u64,
+    channel_id: u64,
+    sub_opts: &[CommandDataOption],
+    handler: &Handler,
+    reminders: &TodoReminders,
+) -> String {
+    let title = string_option(sub_opts, "title").unwrap_or("");
+    if title.trim().is_empty() {
+        return "TODOの内容を入力してください。".to_string();
+    }
+
+    let now = Utc::now();
+    let due_at = match string_option(sub_opts, "due").filter(|v| !v.trim().is_empty()) {
+        Some(value) => match parse_due(value, now) {
+            Some(due) => Some(due),
+            None => return format!("期限の形式が正しくありません: {}", value),
+        },
+        None => None,
+    };
+    let priority = string_option(sub_opts, "priority")
+        .and_then(TodoPriority::parse)
+        .unwrap_or(TodoPriority::Normal);
+    let tags: Vec<String> = string_option(sub_opts, "tag")
+        .map(|s| s.split(',').map(String::from).collect())
+        .unwrap_or_default();
+    let channel_only = sub_opts
+        .iter()
+        .find(|opt| opt.name == "channel")
+        .is_some_and(|opt| matches!(opt.value, CommandDataOptionValue::Boolean(true)));
+
+    let new_todo = NewTodo {
+        user_id,
+        channel_id: channel_only.then_some(channel_id),
+        title: title.to_string(),
+        priority,
+        due_at,
+        tags,
+    };
+
+    match handler.todo_store.add_todo(new_todo) {
+        Ok(todo) => {
+            let mut message = format!("TODOを追加しました:\n{}", format_todo(&todo, now));
+            match reminders.sync(&handler.todo_store, &todo, channel_id).await {
+                ReminderOutcome::Scheduled => message.push_str("\n期限になったらこのチャンネルでお知らせします。"),
+                ReminderOutcome::LimitReached(max) => message.push_str(&format!(
+                    "\nリマインダーが上限（{}件）に達しているため、期限のお知らせは登録しませんでした。",
+                    max
+                )),
+                ReminderOutcome::Skipped => {}
+            }
+            message
+        }
+        Err(e) => format!("エラー: {}", e),
+    }
+}
+
+/// TODO一覧
+fn handle_list(user_id: u64, sub_opts: &[CommandDataOption], handler: &Handler) -> String {
+    let status = match string_option(sub_opts, "status") {
+        Some("done") => StatusFilter::Only(TodoStatus::Done),
+        Some("all") => StatusFilter::All,
+        _ => StatusFilter::Active,
+    };
+    let tag = string_option(sub_opts, "tag").filter(|t| !t.trim().is_empty());
+    let filter = TodoFilter {
+        channel_id: None,
+        status,
+        tag: tag.map(String::from),
+        limit: Some(LIST_LIMIT),
+    };
+
+    match handler.todo_store.list_todos(user_id, &filter) {
+        Ok(todos) if todos.is_empty() => "TODOはありません。".to_string(),
+        Ok(todos) => {
+            let now = Utc::now();
+            let list: Vec<String> = todos.iter().map(|t| format!("- {}", format_todo(t, now))).collect();
+            format!("**あなたのTODO ({}件)**\n{}", todos.len(), list.join("\n"))
+        }
+        Err(e) => format!("エラー: {}", e),
+    }
+}
+
+/// TODO完了
+async fn handle_done(
+    user_id: u64,
+    sub_opts: &[CommandDataOption],
+    handler: &Handler,
+    reminders: &TodoReminders,
+) -> String {
+    let Some(id) = integer_option(sub_opts, "id") else {
+        return "IDを指定してください。".to_string();
+    };
+
+    match handler.todo_store.complete_todo(user_id, id) {
+        Ok(todo) => {
+            reminders.cancel(&handler.todo_store, &todo).await;
+            format!("TODOを完了しました:\n{}", format_todo(&todo, Utc::now()))
+        }
+        Err(e) => format!("エラー: {}", e),
+    }
+}
+
+/// TODO削除
+async fn handle_delete(
+    user_id: u64,
+    sub_opts: &[CommandDataOption],
+    handler: &Handler,
+    reminders: &TodoReminders,
+) -> String {
+    let Some(id) = integer_option(sub_opts, "id") else {
+        return "IDを指定してください。".to_string();
+    };
+
+    match handler.todo_store.delete_todo(user_id, id) {
+        Ok(todo) => {
+            reminders.cancel(&handler.todo_store, &todo).await;
+            format!("TODOを削除しました: #{} {}", todo.id, todo.title)
+        }
+        Err(e) => format!("エラー: {}", e),
+    }
+}
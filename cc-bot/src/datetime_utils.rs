@@ -1,6 +1,6 @@
 //! DateTime操作のヘルパー関数
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveDateTime, TimeZone, Utc};
 use tracing::warn;
 
 /// RFC3339形式の文字列をDateTime<Utc>にパースする
@@ -21,10 +21,40 @@ pub fn parse_rfc3339_or_now(s: &str) -> DateTime<Utc> {
         })
 }
 
+/// 日時をパースする（RFC3339、またはローカル時刻の `YYYY-MM-DD HH:MM`）
+pub fn parse_datetime(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    ["%Y-%m-%d %H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(value, fmt).ok())
+        .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// 相対時間をパースする（`90s`、`30m`、`2h`、`1d`）
+pub fn parse_delay(value: &str) -> Option<ChronoDuration> {
+    let value = value.trim();
+    if value.len() < 2 || !value.is_char_boundary(value.len() - 1) {
+        return None;
+    }
+    let (amount, unit) = value.split_at(value.len() - 1);
+    let amount: i64 = amount.parse().ok().filter(|n| *n > 0)?;
+    match unit {
+        "s" => ChronoDuration::try_seconds(amount),
+        "m" => ChronoDuration::try_minutes(amount),
+        "h" => ChronoDuration::try_hours(amount),
+        "d" => ChronoDuration::try_days(amount),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Datelike;
+    use chrono::{Datelike, Timelike};
 
     #[test]
     fn test_parse_rfc3339_valid() {
@@ -56,4 +86,26 @@ mod tests {
         let now = Utc::now();
         assert_eq!(result.year(), now.year());
     }
+
+    #[test]
+    fn test_parse_datetime() {
+        let utc = parse_datetime("2026-10-20T09:00:00+09:00").unwrap();
+        assert_eq!(utc.hour(), 0);
+
+        let local = parse_datetime("2026-10-20 09:30").unwrap();
+        assert_eq!(local.with_timezone(&Local).hour(), 9);
+        assert_eq!(local.with_timezone(&Local).minute(), 30);
+        assert!(parse_datetime("next tuesday").is_none());
+    }
+
+    #[test]
+    fn test_parse_delay() {
+        assert_eq!(parse_delay("30m"), Some(ChronoDuration::minutes(30)));
+        assert_eq!(parse_delay("2h"), Some(ChronoDuration::hours(2)));
+        assert_eq!(parse_delay("1d"), Some(ChronoDuration::days(1)));
+        assert_eq!(parse_delay("0m"), None);
+        assert_eq!(parse_delay("-5m"), None);
+        assert_eq!(parse_delay("5w"), None);
+        assert_eq!(parse_delay("m"), None);
+    }
 }
@@ -0,0 +1,542 @@
+//! ワークスペースファイルの履歴（スナップショット）管理
+//!
+//! ファイル系ツールが書き込む直前の内容を、ユーザーごとの
+//! コンテンツアドレス型ストア（`{base_output_dir}/.history/{user_id}/`）に保存し、
+//! 一覧・差分・復元を提供します。バージョンはワークスペース（ユーザー出力
+//! ディレクトリ）と相対パスの組で管理するため、日付ごとのワークスペースにある
+//! 同名ファイルの履歴は混ざりません。
+
+use crate::tool::ToolContext;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use similar::TextDiff;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tokio::fs;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+/// パスごとに保持するバージョン数のデフォルト
+pub const DEFAULT_MAX_VERSIONS: usize = 20;
+
+/// ユーザーごとの履歴の合計サイズ上限のデフォルト（100MB）
+pub const DEFAULT_MAX_TOTAL_BYTES: u64 = 100 * 1024 * 1024;
+
+/// 履歴ディレクトリ名（ベース出力ディレクトリ直下）
+pub const HISTORY_DIR_NAME: &str = ".history";
+
+/// インデックスの読み書きを直列化するロック
+static INDEX_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// ファイル履歴エラー
+#[derive(Debug, Error)]
+pub enum FileHistoryError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("No history for path: {0}")]
+    NoHistory(String),
+
+    #[error("Version not found: {0}")]
+    VersionNotFound(u64),
+}
+
+/// 保存されたバージョン
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileVersion {
+    /// ユーザー内で一意なバージョンID
+    pub id: u64,
+    /// バージョンを保存したワークスペース（ユーザー出力ディレクトリ）
+    #[serde(default)]
+    pub workspace: String,
+    /// ワークスペース内の相対パス
+    pub path: String,
+    /// 実ファイルのパス
+    pub file: String,
+    /// 内容のSHA-256（ファイルが存在しなかった場合は None）
+    pub hash: Option<String>,
+    pub size: u64,
+    /// 変更を行ったツール名
+    pub source: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 履歴インデックス（JSON永続化）
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HistoryIndex {
+    next_id: u64,
+    versions: Vec<FileVersion>,
+}
+
+/// ユーザーごとのファイル履歴（1つのワークスペースを対象に操作する）
+pub struct FileHistory {
+    root: PathBuf,
+    workspace: String,
+    max_versions: usize,
+    max_total_bytes: u64,
+}
+
+impl FileHistory {
+    /// ベース出力ディレクトリ・ユーザーID・ワークスペースから作成
+    pub fn new(base_output_dir: &str, user_id: u64, workspace: &str) -> Self {
+        let max_versions = std::env::var("FILE_HISTORY_MAX_VERSIONS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_MAX_VERSIONS);
+        let max_total_bytes = std::env::var("FILE_HISTORY_MAX_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_MAX_TOTAL_BYTES);
+
+        Self {
+            root: Path::new(base_output_dir)
+                .join(HISTORY_DIR_NAME)
+                .join(user_id.to_string()),
+            workspace: workspace.to_string(),
+            max_versions,
+            max_total_bytes,
+        }
+    }
+
+    /// ツールコンテキストから作成（コンテキストの現在のワークスペースが対象）
+    pub fn for_context(context: &ToolContext) -> Self {
+        Self::new(&context.base_output_dir, context.user_id, &context.get_user_output_dir())
+    }
+
+    /// このワークスペースの指定パスのバージョンか
+    fn is_target(&self, version: &FileVersion, path: &str) -> bool {
+        version.workspace == self.workspace && version.path == path
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.root.join("index.json")
+    }
+
+    fn object_path(&self, hash: &str) -> PathBuf {
+        self.root.join("objects").join(hash)
+    }
+
+    /// ワークスペース内の相対パスを正規化
+    fn normalize(path: &str) -> String {
+        path.trim().trim_start_matches("./").to_string()
+    }
+
+    async fn load_index(&self) -> Result<HistoryIndex, FileHistoryError> {
+        let path = self.index_path();
+        if !path.exists() {
+            return Ok(HistoryIndex::default());
+        }
+        let content = fs::read_to_string(&path).await?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    async fn save_index(&self, index: &HistoryIndex) -> Result<(), FileHistoryError> {
+        fs::create_dir_all(&self.root).await?;
+        let content = serde_json::to_string_pretty(index)?;
+        let temp = self.root.join("index.json.tmp");
+        fs::write(&temp, content).await?;
+        fs::rename(&temp, self.index_path()).await?;
+        Ok(())
+    }
+
+    /// 変更前の内容をスナップショットとして保存
+    ///
+    /// 直前のスナップショットと内容が同じ場合は保存せず None を返す。
+    pub async fn snapshot(
+        &self,
+        path: &str,
+        file: &Path,
+        source: &str,
+    ) -> Result<Option<FileVersion>, FileHistoryError> {
+        let path = Self::normalize(path);
+        let content = match fs::read(file).await {
+            Ok(c) => Some(c),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        let size = content.as_ref().map(|c| c.len() as u64).unwrap_or(0);
+        if size > self.max_total_bytes {
+            warn!("{} is larger than the history size limit, not saving a snapshot", path);
+            return Ok(None);
+        }
+        let hash = content
+            .as_ref()
+            .map(|c| format!("{:x}", Sha256::digest(c)));
+
+        let _guard = INDEX_LOCK.lock().await;
+        let mut index = self.load_index().await?;
+
+        let latest = index.versions.iter().rev().find(|v| self.is_target(v, &path));
+        if let Some(latest) = latest {
+            if latest.hash == hash {
+                debug!("Snapshot of {} unchanged, skipping", path);
+                return Ok(None);
+            }
+        }
+
+        if let (Some(hash), Some(content)) = (&hash, &content) {
+            let object = self.object_path(hash);
+            if !object.exists() {
+                fs::create_dir_all(self.root.join("objects")).await?;
+                fs::write(&object, content).await?;
+            }
+        }
+
+        index.next_id += 1;
+        let version = FileVersion {
+            id: index.next_id,
+            workspace: self.workspace.clone(),
+            path: path.clone(),
+            file: file.to_string_lossy().to_string(),
+            hash,
+            size,
+            source: source.to_string(),
+            created_at: Utc::now(),
+        };
+        index.versions.push(version.clone());
+
+        self.prune(&mut index, &path).await;
+        self.save_index(&index).await?;
+
+        debug!("Saved snapshot {} of {}", version.id, path);
+        Ok(Some(version))
+    }
+
+    /// 保持数・合計サイズを超えた古いバージョンと参照されなくなったオブジェクトを削除
+    async fn prune(&self, index: &mut HistoryIndex, path: &str) {
+        let before = index.versions.len();
+
+        let count = index.versions.iter().filter(|v| self.is_target(v, path)).count();
+        let mut excess = count.saturating_sub(self.max_versions);
+        index.versions.retain(|v| {
+            if excess > 0 && self.is_target(v, path) {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+
+        // 合計サイズ（同一内容は1回分）が上限を超えたら、全体で古い順に削除
+        while index.versions.len() > 1 && Self::total_bytes(index) > self.max_total_bytes {
+            index.versions.remove(0);
+        }
+
+        if index.versions.len() == before {
+            return;
+        }
+
+        let referenced: HashSet<&str> = index
+            .versions
+            .iter()
+            .filter_map(|v| v.hash.as_deref())
+            .collect();
+
+        if let Ok(mut entries) = fs::read_dir(self.root.join("objects")).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if !referenced.contains(name.as_str()) {
+                    if let Err(e) = fs::remove_file(entry.path()).await {
+                        warn!("Failed to remove history object {}: {}", name, e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 保存済みオブジェクトの合計サイズ
+    fn total_bytes(index: &HistoryIndex) -> u64 {
+        let mut seen = HashSet::new();
+        index
+            .versions
+            .iter()
+            .filter(|v| v.hash.as_ref().is_some_and(|h| seen.insert(h.as_str())))
+            .map(|v| v.size)
+            .sum()
+    }
+
+    /// パスのバージョン一覧（新しい順）
+    pub async fn list(&self, path: &str) -> Result<Vec<FileVersion>, FileHistoryError> {
+        let path = Self::normalize(path);
+        let _guard = INDEX_LOCK.lock().await;
+        let index = self.load_index().await?;
+        Ok(index
+            .versions
+            .into_iter()
+            .rev()
+            .filter(|v| self.is_target(v, &path))
+            .collect())
+    }
+
+    /// バージョンを取得（ID省略時は最新）
+    pub async fn get(&self, path: &str, version_id: Option<u64>) -> Result<FileVersion, FileHistoryError> {
+        let versions = self.list(path).await?;
+        match version_id {
+            Some(id) => versions
+                .into_iter()
+                .find(|v| v.id == id)
+                .ok_or(FileHistoryError::VersionNotFound(id)),
+            None => versions
+                .into_iter()
+                .next()
+                .ok_or_else(|| FileHistoryError::NoHistory(Self::normalize(path))),
+        }
+    }
+
+    /// バージョンの内容を読み込む（ファイルが存在しなかった版は None）
+    pub async fn read(&self, version: &FileVersion) -> Result<Option<Vec<u8>>, FileHistoryError> {
+        match &version.hash {
+            Some(hash) => Ok(Some(fs::read(self.object_path(hash)).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// バージョンと現在のファイル（または別バージョン）の差分
+    ///
+    /// `file` は呼び出し側が現在のコンテキストで検証した実ファイルのパス。
+    pub async fn diff(
+        &self,
+        path: &str,
+        file: &Path,
+        version_id: Option<u64>,
+        compare_to: Option<u64>,
+    ) -> Result<String, FileHistoryError> {
+        let version = self.get(path, version_id).await?;
+        let old = self.read(&version).await?.unwrap_or_default();
+
+        let (new, new_label) = match compare_to {
+            Some(id) => {
+                let other = self.get(path, Some(id)).await?;
+                (self.read(&other).await?.unwrap_or_default(), format!("v{}", id))
+            }
+            None => {
+                let current = match fs::read(file).await {
+                    Ok(c) => c,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+                    Err(e) => return Err(e.into()),
+                };
+                (current, "current".to_string())
+            }
+        };
+
+        let (Ok(old), Ok(new)) = (std::str::from_utf8(&old), std::str::from_utf8(&new)) else {
+            return Ok(format!(
+                "Binary content differs (v{}: {} bytes, {}: {} bytes)",
+                version.id,
+                old.len(),
+                new_label,
+                new.len()
+            ));
+        };
+
+        let diff = TextDiff::from_lines(old, new)
+            .unified_diff()
+            .context_radius(3)
+            .header(&format!("{} (v{})", version.path, version.id), &format!("{} ({})", version.path, new_label))
+            .to_string();
+
+        if diff.is_empty() {
+            Ok("No differences".to_string())
+        } else {
+            Ok(diff)
+        }
+    }
+
+    /// バージョンを復元（ID省略時は直前の状態に戻す）
+    ///
+    /// 復元前の内容もスナップショットされるため、復元自体を取り消せる。
+    /// 保存済みの `version.file` は信用せず、呼び出し側が現在のコンテキストで
+    /// 検証した `file` に書き戻す。
+    pub async fn restore(
+        &self,
+        path: &str,
+        file: &Path,
+        version_id: Option<u64>,
+    ) -> Result<FileVersion, FileHistoryError> {
+        let version = self.get(path, version_id).await?;
+        let content = self.read(&version).await?;
+
+        self.snapshot(&version.path, file, "restore").await?;
+
+        match content {
+            Some(content) => {
+                if let Some(parent) = file.parent() {
+                    fs::create_dir_all(parent).await?;
+                }
+                fs::write(file, content).await?;
+            }
+            None => {
+                if file.exists() {
+                    fs::remove_file(file).await?;
+                }
+            }
+        }
+
+        info!("Restored {} to version {}", version.path, version.id);
+        Ok(version)
+    }
+}
+
+/// ツールから呼び出すスナップショット（失敗しても書き込みは止めない）
+pub async fn snapshot_before_write(context: &ToolContext, path: &str, file: &Path, source: &str) {
+    if let Err(e) = FileHistory::for_context(context).snapshot(path, file, source).await {
+        warn!("Failed to snapshot {} before write: {}", path, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, FileHistory, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let workspace = dir.path().join("work");
+        let history = FileHistory::new(dir.path().to_str().unwrap(), 1, workspace.to_str().unwrap());
+        let file = workspace.join("a.txt");
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        (dir, history, file)
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_and_list() {
+        let (_dir, history, file) = setup();
+        std::fs::write(&file, "v1").unwrap();
+        history.snapshot("a.txt", &file, "write_file").await.unwrap();
+        std::fs::write(&file, "v2").unwrap();
+        history.snapshot("./a.txt", &file, "edit_file").await.unwrap();
+
+        let versions = history.list("a.txt").await.unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].source, "edit_file");
+        assert_eq!(history.read(&versions[1]).await.unwrap().unwrap(), b"v1");
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_skips_unchanged() {
+        let (_dir, history, file) = setup();
+        std::fs::write(&file, "same").unwrap();
+        assert!(history.snapshot("a.txt", &file, "write_file").await.unwrap().is_some());
+        assert!(history.snapshot("a.txt", &file, "write_file").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_missing_file_and_restore_deletes() {
+        let (_dir, history, file) = setup();
+        history.snapshot("a.txt", &file, "write_file").await.unwrap();
+        std::fs::write(&file, "created").unwrap();
+
+        history.restore("a.txt", &file, None).await.unwrap();
+        assert!(!file.exists());
+    }
+
+    #[tokio::test]
+    async fn test_restore_is_undoable() {
+        let (_dir, history, file) = setup();
+        std::fs::write(&file, "before").unwrap();
+        history.snapshot("a.txt", &file, "write_file").await.unwrap();
+        std::fs::write(&file, "after").unwrap();
+
+        history.restore("a.txt", &file, None).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "before");
+
+        history.restore("a.txt", &file, None).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "after");
+    }
+
+    #[tokio::test]
+    async fn test_diff_against_current() {
+        let (_dir, history, file) = setup();
+        std::fs::write(&file, "hello\n").unwrap();
+        history.snapshot("a.txt", &file, "write_file").await.unwrap();
+        std::fs::write(&file, "world\n").unwrap();
+
+        let diff = history.diff("a.txt", &file, None, None).await.unwrap();
+        assert!(diff.contains("-hello"));
+        assert!(diff.contains("+world"));
+    }
+
+    #[tokio::test]
+    async fn test_retention_prunes_old_versions() {
+        let (_dir, history, file) = setup();
+        let history = FileHistory {
+            max_versions: 2,
+            ..history
+        };
+        for i in 0..4 {
+            std::fs::write(&file, format!("v{}", i)).unwrap();
+            history.snapshot("a.txt", &file, "write_file").await.unwrap();
+        }
+
+        let versions = history.list("a.txt").await.unwrap();
+        assert_eq!(versions.len(), 2);
+        let objects = std::fs::read_dir(history.root.join("objects")).unwrap().count();
+        assert_eq!(objects, 2);
+    }
+
+    #[tokio::test]
+    async fn test_missing_version() {
+        let (_dir, history, _file) = setup();
+        assert!(matches!(
+            history.get("a.txt", None).await,
+            Err(FileHistoryError::NoHistory(_))
+        ));
+        assert!(matches!(
+            history.get("a.txt", Some(9)).await,
+            Err(FileHistoryError::VersionNotFound(9))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_workspaces_have_separate_histories() {
+        let (dir, history, file) = setup();
+        let other_workspace = dir.path().join("2024-01-01");
+        let other = FileHistory::new(dir.path().to_str().unwrap(), 1, other_workspace.to_str().unwrap());
+        let other_file = other_workspace.join("a.txt");
+        std::fs::create_dir_all(&other_workspace).unwrap();
+
+        std::fs::write(&other_file, "old day").unwrap();
+        other.snapshot("a.txt", &other_file, "write_file").await.unwrap();
+        std::fs::write(&file, "old day").unwrap();
+        // 別ワークスペースの同内容と比較してスキップしない
+        assert!(history.snapshot("a.txt", &file, "write_file").await.unwrap().is_some());
+
+        assert_eq!(history.list("a.txt").await.unwrap().len(), 1);
+        assert_eq!(other.list("a.txt").await.unwrap().len(), 1);
+        assert!(matches!(
+            FileHistory::new(dir.path().to_str().unwrap(), 1, "elsewhere").get("a.txt", None).await,
+            Err(FileHistoryError::NoHistory(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_total_size_cap_prunes_oldest() {
+        let (_dir, history, file) = setup();
+        let history = FileHistory {
+            max_total_bytes: 10,
+            ..history
+        };
+        for content in ["aaaa", "bbbb", "cccc"] {
+            std::fs::write(&file, content).unwrap();
+            history.snapshot("a.txt", &file, "write_file").await.unwrap();
+        }
+        let versions = history.list("a.txt").await.unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(history.read(&versions[1]).await.unwrap().unwrap(), b"bbbb");
+        let objects = std::fs::read_dir(history.root.join("objects")).unwrap().count();
+        assert_eq!(objects, 2);
+
+        // 上限を超える内容は保存しない
+        std::fs::write(&file, "x".repeat(11)).unwrap();
+        assert!(history.snapshot("a.txt", &file, "write_file").await.unwrap().is_none());
+    }
+}
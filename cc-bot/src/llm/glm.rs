@@ -13,7 +13,7 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info};
 
-use super::{LLMClient, LLMError};
+use super::{Completion, LLMClient, LLMError};
 
 // 定数
 // Coding Plan用エンドポイント
@@ -25,6 +25,8 @@ struct ChatRequest {
     messages: Vec<ChatMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<ToolDefinition>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -94,6 +96,43 @@ impl GLMClientImpl {
         })
     }
 
+    /// リクエストを送信して最初の選択肢を取得
+    async fn send(&self, request: &ChatRequest) -> Result<ChatChoice, LLMError> {
+        debug!("Request: {}", mask_secrets(&serde_json::to_string(request)?));
+
+        let http_response = self
+            .client
+            .post(GLM_API_URL)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(request)
+            .send()
+            .await?;
+
+        let status = http_response.status();
+        debug!("API status: {}", status);
+
+        if !status.is_success() {
+            let error_text = http_response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unable to read error".to_string());
+            let error_msg = format!("API returned {}: {}", status, error_text);
+            error!("{}", error_msg);
+            return Err(LLMError::ApiError(error_msg));
+        }
+
+        let response_text = http_response.text().await?;
+        debug!("Response: {}", mask_secrets(&response_text));
+
+        let chat_response: ChatResponse = serde_json::from_str(&response_text)?;
+
+        chat_response.choices.into_iter().next().ok_or_else(|| {
+            error!("No response from API");
+            LLMError::NoResponse
+        })
+    }
+
     /// ツール呼び出しを処理
     async fn handle_tool_calls(
         &self,
@@ -151,41 +190,10 @@ impl LLMClient for GLMClientImpl {
             model: self.model.clone(),
             messages: all_messages,
             tools: if tools.is_empty() { None } else { Some(tools) },
+            max_tokens: None,
         };
 
-        debug!("Request: {}", mask_secrets(&serde_json::to_string(&request)?));
-
-        let http_response = self
-            .client
-            .post(GLM_API_URL)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
-
-        let status = http_response.status();
-        debug!("API status: {}", status);
-
-        if !status.is_success() {
-            let error_text = http_response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unable to read error".to_string());
-            let error_msg = format!("API returned {}: {}", status, error_text);
-            error!("{}", error_msg);
-            return Err(LLMError::ApiError(error_msg));
-        }
-
-        let response_text = http_response.text().await?;
-        debug!("Response: {}", mask_secrets(&response_text));
-
-        let chat_response: ChatResponse = serde_json::from_str(&response_text)?;
-
-        let choice = chat_response.choices.into_iter().next().ok_or_else(|| {
-            error!("No response from API");
-            LLMError::NoResponse
-        })?;
+        let choice = self.send(&request).await?;
 
         // ツール呼び出しがある場合
         if let Some(tool_calls) = &choice.message.tool_calls {
@@ -201,6 +209,27 @@ impl LLMClient for GLMClientImpl {
         })
     }
 
+    /// ツールなしの単発補完（出力トークン数を制限）
+    async fn complete(&self, messages: Vec<ChatMessage>, max_tokens: u32) -> Result<Completion, LLMError> {
+        let request = ChatRequest {
+            model: self.model.clone(),
+            messages,
+            tools: None,
+            max_tokens: Some(max_tokens),
+        };
+
+        let choice = self.send(&request).await?;
+        let text = choice.message.content.ok_or_else(|| {
+            error!("No content in response");
+            LLMError::NoResponse
+        })?;
+        Ok(Completion {
+            text,
+            model: self.model.clone(),
+            truncated: choice.finish_reason.as_deref() == Some("length"),
+        })
+    }
+
     /// ツールマネージャーを取得
     fn tool_manager(&self) -> SharedToolManager {
         self.tool_manager.clone()
@@ -221,12 +250,14 @@ mod tests {
                 content: "Hello".to_string(),
             }],
             tools: None,
+            max_tokens: None,
         };
 
         let json = serde_json::to_string(&request).unwrap();
         assert!(json.contains(r#""role":"user""#));
         assert!(json.contains(r#""content":"Hello""#));
         assert!(json.contains(r#""model":"glm-4.7-flash""#));
+        assert!(!json.contains("max_tokens"));
     }
 
     #[test]
@@ -6,7 +6,7 @@ use async_trait::async_trait;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use super::{LLMClient, LLMError};
+use super::{Completion, LLMClient, LLMError};
 
 /// テスト用モックLLMクライアント
 ///
@@ -46,6 +46,16 @@ impl LLMClient for MockLLMClient {
         Ok(self.response.clone())
     }
 
+    async fn complete(&self, _messages: Vec<ChatMessage>, max_tokens: u32) -> Result<Completion, LLMError> {
+        // 1トークン = 1文字として上限を模擬
+        let truncated = self.response.chars().count() > max_tokens as usize;
+        Ok(Completion {
+            text: self.response.chars().take(max_tokens as usize).collect(),
+            model: "mock".to_string(),
+            truncated,
+        })
+    }
+
     fn tool_manager(&self) -> SharedToolManager {
         self.tool_manager.clone()
     }
@@ -38,6 +38,17 @@ pub enum LLMError {
     ToolError(String),
 }
 
+/// 単発補完の結果
+#[derive(Debug, Clone)]
+pub struct Completion {
+    /// 応答テキスト
+    pub text: String,
+    /// 使用したモデル名
+    pub model: String,
+    /// トークン上限で打ち切られたか
+    pub truncated: bool,
+}
+
 /// LLMクライアントtrait
 ///
 /// すべてのLLMプロバイダーが実装する共通インターフェース
@@ -58,6 +69,11 @@ pub trait LLMClient: Send + Sync {
         tool_context: &ToolContext,
     ) -> Result<String, LLMError>;
 
+    /// ツールなし・出力トークン数を制限して補完（MCPサンプリング用）
+    ///
+    /// システムプロンプトは付与せず、`messages` をそのまま送ります。
+    async fn complete(&self, messages: Vec<ChatMessage>, max_tokens: u32) -> Result<Completion, LLMError>;
+
     /// ツールマネージャーを取得
     fn tool_manager(&self) -> SharedToolManager;
 
@@ -2,10 +2,15 @@ mod api;
 mod channel_settings;
 mod commands;
 mod datetime_utils;
+mod file_history;
 mod glm;
 mod llm;
 mod history;
 mod mcp_client;
+mod mcp_manager;
+mod mcp_sampling;
+mod mcp_server;
+mod mcp_sse;
 mod memory;
 mod memory_store;
 mod permission;
@@ -17,12 +22,14 @@ mod schedule_store;
 mod security;
 mod session;
 mod skills;
+mod todo_store;
 mod tool;
 mod tools;
 mod user_roles;
 mod user_settings;
 mod streaming;
 mod validation;
+mod web_cache;
 
 use llm::LLMClient;
 use memory_store::MemoryStore;
@@ -49,6 +56,8 @@ pub struct Handler {
     rate_limiter: Arc<Mutex<rate_limiter::RateLimiter>>,
     permission_manager: Arc<RwLock<permission::PermissionManager>>,
     memory_store: Arc<MemoryStore>,
+    /// TODOストア
+    todo_store: Arc<todo_store::TodoStore>,
     /// ユーザー設定ストア
     pub user_settings_store: Arc<user_settings::UserSettingsStore>,
     /// チャンネル設定ストア
@@ -70,6 +79,10 @@ pub struct Handler {
     pub tool_confirmation_required: bool,
     /// ボットのユーザーID（メンション検出用）
     pub bot_user_id: Option<u64>,
+    /// Web取得キャッシュ
+    pub web_cache: Arc<web_cache::WebCache>,
+    /// MCPクライアント（MCP設定がない場合はNone）
+    pub mcp_manager: Option<Arc<mcp_manager::MCPManager>>,
 }
 
 #[serenity::async_trait]
@@ -128,13 +141,8 @@ impl EventHandler for Handler {
             session.history.to_vec()
         };
 
-        let tool_context = tool::ToolContext {
-            user_id,
-            user_name,
-            channel_id,
-            base_output_dir: self.base_output_dir.clone(),
-            custom_output_subdir: None,
-        };
+        let tool_context = tool::ToolContext::new(user_id, user_name, channel_id, self.base_output_dir.clone())
+            .with_guild_id(msg.guild_id.map(|id| id.get()));
 
         // LLMに問い合わせ
         match self.glm_client.chat_with_tools(messages, &tool_context).await {
@@ -153,6 +161,9 @@ impl EventHandler for Handler {
                         error!("Failed to send reply: {}", e);
                     }
                 }
+
+                // ツールが生成した添付ファイルを送信
+                streaming::send_attachments(&ctx.http, msg.channel_id, tool_context.take_attachments()).await;
             }
             Err(e) => {
                 error!("LLM error in watch mode: {}", e);
@@ -166,14 +177,22 @@ impl EventHandler for Handler {
     async fn ready(&self, ctx: Context, ready: serenity::model::gateway::Ready) {
         info!("{} is connected!", ready.user.name);
 
-        // Slash Commandsを登録
-        commands::register_global_commands(&ctx).await;
+        // Slash Commandsを登録（MCPプロンプトの引数を /mcp prompt のオプションに含める）
+        let mcp_prompts = match &self.mcp_manager {
+            Some(manager) => manager.client().list_all_prompts().await,
+            None => Vec::new(),
+        };
+        commands::register_global_commands(&ctx, &mcp_prompts).await;
         info!("Slash commands registered");
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        if let Interaction::Command(command) = interaction {
-            self.handle_slash_command(&ctx, &command).await;
+        match interaction {
+            Interaction::Command(command) => self.handle_slash_command(&ctx, &command).await,
+            Interaction::Autocomplete(autocomplete) if autocomplete.data.name == "mcp" => {
+                commands::mcp::autocomplete(&ctx, &autocomplete, self).await;
+            }
+            _ => {}
         }
     }
 }
@@ -216,12 +235,18 @@ impl Handler {
             "ask" => {
                 commands::ask::run(ctx, command, self).await;
             }
+            // mcpコマンドもプロンプト取得とLLM呼び出しのため独自に応答する
+            "mcp" => {
+                commands::mcp::run(ctx, command, self).await;
+            }
             _ => {
                 // 他のコマンドは従来通り
                 let response = match command.data.name.as_str() {
                     "admin" => commands::admin::run(ctx, command, self).await,
                     "clear" => commands::clear::run(ctx, command, self).await,
+                    "files" => commands::files::run(ctx, command, self).await,
                     "memory" => commands::memory_cmd::run(ctx, command, self).await,
+                    "todo" => commands::todo::run(ctx, command, self).await,
                     "permission" => commands::permission::run(ctx, command, self).await,
                     "schedule" => commands::schedule::run(ctx, command, self).await,
                     "settings" => commands::settings::run(ctx, command, self).await,
@@ -266,8 +291,18 @@ impl Handler {
     }
 }
 
+fn main() {
+    // data_query の子プロセスとして起動された場合はクエリだけを実行する
+    #[cfg(target_os = "linux")]
+    if env::args().nth(1).as_deref() == Some(tools::DATA_QUERY_WORKER_ARG) {
+        std::process::exit(tools::run_data_query_worker());
+    }
+
+    run_bot();
+}
+
 #[tokio::main]
-async fn main() {
+async fn run_bot() {
     // トレーシング初期化
     tracing_subscriber::fmt()
         .with_env_filter(
@@ -301,11 +336,18 @@ async fn main() {
         }
     };
 
+    // Web取得キャッシュを読み込み
+    let web_cache = Arc::new(web_cache::WebCache::load("data").unwrap_or_else(|e| {
+        error!("Failed to load web cache: {}, using in-memory cache", e);
+        web_cache::WebCache::new().expect("Failed to create web cache")
+    }));
+
     // デフォルトツールを登録
     {
         let tm = glm_client.tool_manager();
         let mut tool_manager = tm.write().await;
         tools::register_default_tools(&mut tool_manager);
+        tools::register_web_tools(&mut tool_manager, web_cache.clone());
         info!("Registered {} tools", tool_manager.list_tools().len());
     }
 
@@ -380,6 +422,7 @@ async fn main() {
     // スケジュールイベントリスナーを開始（startの前にsubscribe）
     let event_http = http.clone();
     let event_glm = glm_client.clone();
+    let event_store = schedule_store.clone();
     let mut event_receiver = scheduler_clone.subscribe();
 
     // スケジューラーを開始
@@ -393,14 +436,38 @@ async fn main() {
                     let task = &event.task;
                     info!("Executing scheduled task: {} in channel {}", task.id, task.channel_id);
 
+                    // 1回だけのタスクはストアから削除
+                    if task.is_one_shot() {
+                        let mut store = event_store.write().await;
+                        store.remove_task(task.id);
+                        if let Err(e) = store.save("data").await {
+                            error!("Failed to save schedule store: {}", e);
+                        }
+                    }
+
+                    // TODOリマインダーはLLMを通さず、登録時のメッセージをそのまま投稿
+                    if task.kind == scheduler::TaskKind::TodoReminder {
+                        let mentions = serenity::builder::CreateAllowedMentions::new()
+                            .users(task.user_id.map(serenity::model::id::UserId::new));
+                        let message = serenity::builder::CreateMessage::new()
+                            .content(&task.prompt)
+                            .allowed_mentions(mentions);
+                        let channel_id = serenity::model::id::ChannelId::new(task.channel_id);
+                        if let Err(e) = channel_id.send_message(&event_http, message).await {
+                            error!("Failed to send todo reminder: {}", e);
+                        }
+                        continue;
+                    }
+
                     // GLMに送信
                     let messages = vec![history::ChatMessage::user(&task.prompt)];
                     let tool_context = tool::ToolContext::new(
-                        0,  // システム実行
+                        task.user_id.unwrap_or(0),  // 作成ユーザー不明ならシステム実行
                         "scheduler".to_string(),
                         task.channel_id,
                         "output".to_string(),  // base_output_dir
-                    );
+                    )
+                    .with_guild_id(task.guild_id);
 
                     match event_glm.chat_with_tools(messages, &tool_context).await {
                         Ok(response) => {
@@ -411,6 +478,7 @@ async fn main() {
                             } else {
                                 info!("Scheduled message sent successfully");
                             }
+                            streaming::send_attachments(&event_http, channel_id, tool_context.take_attachments()).await;
                         }
                         Err(e) => {
                             error!("GLM error in scheduled task: {}", e);
@@ -451,19 +519,65 @@ async fn main() {
         MemoryStore::new().expect("Failed to create memory store")
     }));
 
+    // TODOストアを読み込み
+    let todo_store = Arc::new(todo_store::TodoStore::load("data").unwrap_or_else(|e| {
+        error!("Failed to load todo store: {}, creating new", e);
+        todo_store::TodoStore::new().expect("Failed to create todo store")
+    }));
+
     // メモリツールを登録
-    {
+    let (mcp_tools, schedule_access) = {
         let tm = glm_client.tool_manager();
         let mut tool_manager = tm.write().await;
         tools::register_memory_tools(&mut tool_manager, memory_store.clone());
+        tools::register_discord_tools(&mut tool_manager, http.clone(), permission_manager.clone());
+        let schedule_access = tools::register_schedule_tools(
+            &mut tool_manager,
+            scheduler.clone(),
+            schedule_store.clone(),
+            permission_manager.clone(),
+        );
+        tools::register_todo_tools(
+            &mut tool_manager,
+            todo_store.clone(),
+            scheduler.clone(),
+            schedule_store.clone(),
+        );
+
+        // MCPツールを登録（設定ファイルがあれば、サンプリングの承認依頼は管理者にDM）
+        let sampling_approvals = Arc::new(mcp_sampling::SamplingApprovals::new(Some(Arc::new(
+            mcp_sampling::DiscordApprovalNotifier::new(http.clone(), permission_manager.clone()),
+        ))));
+        let mcp_tools = match tools::register_mcp_tools(
+            &mut tool_manager,
+            "../mcp.json",
+            glm_client.clone(),
+            sampling_approvals,
+        )
+        .await
+        {
+            Ok(registered) => registered,
+            Err(e) => {
+                warn!("Failed to register MCP tools: {}", e);
+                None
+            }
+        };
 
-        // MCPツールを登録（設定ファイルがあれば）
-        if let Err(e) = tools::register_mcp_tools(&mut tool_manager, "../mcp.json").await {
-            warn!("Failed to register MCP tools: {}", e);
+        // HTTP APIツールを登録（設定ファイルがあれば）
+        if let Err(e) = tools::register_http_api_tools(&mut tool_manager, "../http-apis.json") {
+            warn!("Failed to register HTTP API tools: {}", e);
         }
 
         info!("Registered {} tools total", tool_manager.list_tools().len());
-    }
+        (mcp_tools, schedule_access)
+    };
+
+    // MCPサーバーマネージャーとヘルスモニターを起動
+    let mcp_manager = mcp_tools.map(|(client, registered)| {
+        let manager = Arc::new(mcp_manager::MCPManager::new(client, glm_client.tool_manager(), registered));
+        manager.spawn_health_monitor();
+        manager
+    });
 
     // ロール設定を読み込み
     let role_config = Arc::new(RwLock::new(
@@ -524,6 +638,7 @@ async fn main() {
         rate_limiter,
         permission_manager,
         memory_store: memory_store.clone(),
+        todo_store,
         user_settings_store: user_settings_store.clone(),
         channel_settings_store,
         http,
@@ -534,6 +649,8 @@ async fn main() {
         message_watch_mode,
         tool_confirmation_required,
         bot_user_id: None, // Will be set in ready event
+        web_cache,
+        mcp_manager: mcp_manager.clone(),
     };
 
     // APIサーバーを並行起動
@@ -545,6 +662,17 @@ async fn main() {
     // API用レートリミッター
     let api_rate_limiter = Arc::new(Mutex::new(rate_limiter::RateLimiter::new()));
 
+    // MCPサーバーモード（MCP_SERVER_ENABLED=true で /api/mcp-server を公開）
+    let mcp_server = mcp_server::MCPServerSettings::from_env().map(|settings| {
+        mcp_server::BotMCPServer::new(
+            memory_store.clone(),
+            schedule_access,
+            glm_client.tool_manager(),
+            base_output_dir.clone(),
+            settings,
+        )
+    });
+
     let api_state = api::ApiState {
         glm_client,
         session_manager,
@@ -553,6 +681,8 @@ async fn main() {
         memory_store,
         base_output_dir,
         rate_limiter: api_rate_limiter,
+        mcp_manager,
+        mcp_server,
     };
 
     tokio::spawn(async move {
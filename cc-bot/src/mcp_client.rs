@@ -2,39 +2,146 @@
 //!
 //! MCPサーバーとの通信を管理し、動的ツールロードを提供します。
 //! 接続プールによるプロセス再利用でパフォーマンスを最適化します。
+//! リソース・プロンプトの一覧と読み取り結果はキャッシュし、サーバーからの変更通知で無効化します。
+//! `sampling: true` のサーバーからの補完要求は `MCPSampler` 経由でLLMに転送します。
 
 use anyhow::Result;
-use rmcp::model::{CallToolRequestParams, Tool};
-use rmcp::service::{RoleClient, RunningService, ServiceExt};
-use rmcp::transport::{TokioChildProcess, child_process::ConfigureCommandExt};
+use rmcp::model::{
+    CallToolRequestParams, ClientCapabilities, ClientInfo, ClientRequest, CreateMessageRequestMethod,
+    CreateMessageRequestParams, CreateMessageResult, GetPromptRequestParams, GetPromptResult, PingRequest, Prompt,
+    ReadResourceRequestParams, ReadResourceResult, Resource, ResourceUpdatedNotificationParam,
+    SubscribeRequestParams, Tool,
+};
+use rmcp::service::{NotificationContext, Peer, RequestContext, RoleClient, RunningService, ServiceExt};
+use rmcp::{ClientHandler, ErrorData};
+use rmcp::transport::streamable_http_client::StreamableHttpClientTransportConfig;
+use rmcp::transport::{StreamableHttpClientTransport, TokioChildProcess, child_process::ConfigureCommandExt};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tokio::process::Command;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
 
+use crate::llm::LLMClient;
+use crate::mcp_sampling::{MCPSampler, SamplingApprovals};
+use crate::mcp_sse::SseClientTransport;
+
+/// MCPサーバーとの通信方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MCPTransport {
+    /// 子プロセスの標準入出力
+    #[default]
+    Stdio,
+    /// HTTP+SSE（旧仕様）
+    Sse,
+    /// Streamable HTTP
+    StreamableHttp,
+}
+
+impl MCPTransport {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Stdio => "stdio",
+            Self::Sse => "sse",
+            Self::StreamableHttp => "streamable_http",
+        }
+    }
+}
+
 /// MCPサーバー設定
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MCPServerConfig {
     /// サーバー名
     pub name: String,
-    /// 起動コマンド
+    /// 通信方式（デフォルト: stdio）
+    #[serde(default)]
+    pub transport: MCPTransport,
+    /// 起動コマンド（stdio）
+    #[serde(default)]
     pub command: String,
     /// コマンド引数
     #[serde(default)]
     pub args: Vec<String>,
-    /// 環境変数
+    /// 環境変数（stdio）
     #[serde(default)]
     pub env: HashMap<String, String>,
+    /// 接続先URL（sse / streamable_http）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// HTTPヘッダー（sse / streamable_http、値は `${VAR}` で環境変数を参照可能）
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
     /// 有効/無効
     #[serde(default)]
     pub enabled: bool,
     /// 説明
     #[serde(default)]
     pub description: String,
+    /// サンプリング（サーバーからのLLM補完要求）を許可
+    #[serde(default)]
+    pub sampling: bool,
+    /// サンプリングの最大トークン数（0: `settings.sampling_max_tokens` を使用）
+    #[serde(default)]
+    pub sampling_max_tokens: u32,
+    /// サンプリングの1時間あたりのトークン予算（0: `settings.sampling_tokens_per_hour` を使用）
+    #[serde(default)]
+    pub sampling_tokens_per_hour: u32,
+    /// 信頼できないサーバー（サンプリングに管理者の承認が必要）
+    #[serde(default)]
+    pub untrusted: bool,
+}
+
+impl MCPServerConfig {
+    /// 通信方式に必要な設定が揃っているか確認
+    pub fn validate(&self) -> Result<()> {
+        match self.transport {
+            MCPTransport::Stdio => {
+                if self.command.trim().is_empty() {
+                    return Err(anyhow::anyhow!("Server {}: 'command' is required for stdio transport", self.name));
+                }
+            }
+            MCPTransport::Sse | MCPTransport::StreamableHttp => {
+                let url = self.url.as_deref().unwrap_or("").trim();
+                if !(url.starts_with("http://") || url.starts_with("https://")) {
+                    return Err(anyhow::anyhow!(
+                        "Server {}: 'url' (http:// or https://) is required for {} transport",
+                        self.name,
+                        self.transport.as_str()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// ヘッダーを環境変数を展開して構築
+    fn header_map(&self) -> Result<HeaderMap> {
+        let mut map = HeaderMap::new();
+        for (key, value) in &self.headers {
+            let name = HeaderName::from_bytes(key.as_bytes())
+                .map_err(|_| anyhow::anyhow!("Server {}: invalid header name '{}'", self.name, key))?;
+            let value = HeaderValue::from_str(&expand_env_value(value))
+                .map_err(|_| anyhow::anyhow!("Server {}: invalid value for header '{}'", self.name, key))?;
+            map.insert(name, value);
+        }
+        Ok(map)
+    }
+}
+
+/// `${VAR}` 形式の値を環境変数で展開（未設定ならそのまま）
+fn expand_env_value(value: &str) -> String {
+    if value.starts_with("${") && value.ends_with('}') {
+        let var_name = &value[2..value.len() - 1];
+        std::env::var(var_name).unwrap_or_else(|_| value.to_string())
+    } else {
+        value.to_string()
+    }
 }
 
 /// MCP設定全体
@@ -59,11 +166,31 @@ pub struct MCPSettings {
     /// 最大同時ツール数
     #[serde(default = "default_max_concurrent")]
     pub max_concurrent_tools: usize,
+    /// ヘルスチェック間隔（秒、0で無効）
+    #[serde(default = "default_health_check_interval")]
+    pub health_check_interval_seconds: u64,
+    /// 再起動バックオフの上限（秒）
+    #[serde(default = "default_max_restart_backoff")]
+    pub max_restart_backoff_seconds: u64,
+    /// サンプリングの最大トークン数（サーバーごとの指定がない場合）
+    #[serde(default = "default_sampling_max_tokens")]
+    pub sampling_max_tokens: u32,
+    /// サンプリングの1時間あたりのトークン予算（サーバーごと、0で無制限）
+    #[serde(default = "default_sampling_tokens_per_hour")]
+    pub sampling_tokens_per_hour: u32,
+    /// サンプリング承認の待ち時間（秒）
+    #[serde(default = "default_sampling_approval_timeout")]
+    pub sampling_approval_timeout_seconds: u64,
 }
 
 fn default_connection_timeout() -> u64 { 30 }
 fn default_tool_timeout() -> u64 { 60 }
 fn default_max_concurrent() -> usize { 5 }
+fn default_health_check_interval() -> u64 { 30 }
+fn default_max_restart_backoff() -> u64 { 300 }
+fn default_sampling_max_tokens() -> u32 { 1024 }
+fn default_sampling_tokens_per_hour() -> u32 { 20000 }
+fn default_sampling_approval_timeout() -> u64 { 300 }
 
 impl Default for MCPSettings {
     fn default() -> Self {
@@ -71,15 +198,52 @@ impl Default for MCPSettings {
             connection_timeout_seconds: default_connection_timeout(),
             tool_execution_timeout_seconds: default_tool_timeout(),
             max_concurrent_tools: default_max_concurrent(),
+            health_check_interval_seconds: default_health_check_interval(),
+            max_restart_backoff_seconds: default_max_restart_backoff(),
+            sampling_max_tokens: default_sampling_max_tokens(),
+            sampling_tokens_per_hour: default_sampling_tokens_per_hour(),
+            sampling_approval_timeout_seconds: default_sampling_approval_timeout(),
         }
     }
 }
 
+/// MCPツールの登録名の接頭辞
+pub const MCP_TOOL_PREFIX: &str = "mcp__";
+/// LLMプロバイダーが受け付けるツール名の最大長
+const MAX_TOOL_NAME_LEN: usize = 64;
+/// 長すぎる名前を切り詰めるときに付けるハッシュの桁数
+const TOOL_NAME_HASH_LEN: usize = 8;
+
+/// MCPツールの登録名 `mcp__<server>__<tool>` を生成
+///
+/// 英数字・`_`・`-` 以外の文字は `_` に置換します。64文字を超える場合は切り詰め、
+/// 元のサーバー名・ツール名のハッシュを末尾に付けて区別できるようにします。
+pub fn namespaced_tool_name(server_name: &str, tool_name: &str) -> String {
+    let sanitize = |name: &str| -> String {
+        name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+            .collect()
+    };
+    let name = format!("{}{}__{}", MCP_TOOL_PREFIX, sanitize(server_name), sanitize(tool_name));
+    if name.len() <= MAX_TOOL_NAME_LEN {
+        return name;
+    }
+
+    let digest = format!("{:x}", Sha256::digest(format!("{}\0{}", server_name, tool_name)));
+    format!(
+        "{}_{}",
+        &name[..MAX_TOOL_NAME_LEN - TOOL_NAME_HASH_LEN - 1],
+        &digest[..TOOL_NAME_HASH_LEN]
+    )
+}
+
 /// MCPツール定義
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MCPToolDefinition {
-    /// ツール名
+    /// 登録名（`mcp__<server>__<tool>`）
     pub name: String,
+    /// サーバー上のツール名
+    pub tool_name: String,
     /// ツールの説明
     pub description: String,
     /// 入力スキーマ
@@ -88,28 +252,238 @@ pub struct MCPToolDefinition {
     pub server_name: String,
 }
 
+/// MCPリソース定義
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MCPResourceDefinition {
+    /// リソースURI
+    pub uri: String,
+    /// リソース名
+    pub name: String,
+    /// リソースの説明
+    pub description: String,
+    /// MIMEタイプ
+    pub mime_type: Option<String>,
+    /// 提供元サーバー名
+    pub server_name: String,
+}
+
+/// MCPプロンプト引数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MCPPromptArgument {
+    /// 引数名
+    pub name: String,
+    /// 引数の説明
+    pub description: String,
+    /// 必須かどうか
+    pub required: bool,
+}
+
+/// MCPプロンプト定義
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MCPPromptDefinition {
+    /// プロンプト名
+    pub name: String,
+    /// プロンプトの説明
+    pub description: String,
+    /// 引数
+    pub arguments: Vec<MCPPromptArgument>,
+    /// 提供元サーバー名
+    pub server_name: String,
+}
+
+impl MCPPromptDefinition {
+    /// 未指定の必須引数名を取得
+    pub fn missing_arguments(&self, arguments: &HashMap<String, String>) -> Vec<&str> {
+        self.arguments
+            .iter()
+            .filter(|arg| arg.required)
+            .filter(|arg| arguments.get(&arg.name).is_none_or(|v| v.trim().is_empty()))
+            .map(|arg| arg.name.as_str())
+            .collect()
+    }
+}
+
+impl From<(&str, Resource)> for MCPResourceDefinition {
+    fn from((server_name, resource): (&str, Resource)) -> Self {
+        let raw = resource.raw;
+        Self {
+            uri: raw.uri,
+            name: raw.title.unwrap_or(raw.name),
+            description: raw.description.unwrap_or_default(),
+            mime_type: raw.mime_type,
+            server_name: server_name.to_string(),
+        }
+    }
+}
+
+impl From<(&str, Prompt)> for MCPPromptDefinition {
+    fn from((server_name, prompt): (&str, Prompt)) -> Self {
+        Self {
+            name: prompt.name,
+            description: prompt.description.unwrap_or_default(),
+            arguments: prompt
+                .arguments
+                .unwrap_or_default()
+                .into_iter()
+                .map(|arg| MCPPromptArgument {
+                    name: arg.name,
+                    description: arg.description.unwrap_or_default(),
+                    required: arg.required.unwrap_or(false),
+                })
+                .collect(),
+            server_name: server_name.to_string(),
+        }
+    }
+}
+
+/// リソース・プロンプトのキャッシュ
+///
+/// サーバーからの変更通知、または再接続（購読が切れるため）で無効化します。
+#[derive(Default)]
+struct MCPCache {
+    /// サーバー名 -> リソース一覧
+    resources: RwLock<HashMap<String, Vec<Resource>>>,
+    /// (サーバー名, URI) -> 読み取り結果（購読中のリソースのみ）
+    contents: RwLock<HashMap<(String, String), ReadResourceResult>>,
+    /// 購読済みの (サーバー名, URI)
+    subscriptions: RwLock<HashSet<(String, String)>>,
+    /// サーバー名 -> プロンプト一覧
+    prompts: RwLock<HashMap<String, Vec<Prompt>>>,
+}
+
+impl MCPCache {
+    /// `notifications/resources/updated`: 該当リソースの内容を破棄
+    async fn resource_updated(&self, server_name: &str, uri: &str) {
+        let key = (server_name.to_string(), uri.to_string());
+        if self.contents.write().await.remove(&key).is_some() {
+            debug!("Invalidated cached resource {} from {}", uri, server_name);
+        }
+    }
+
+    /// `notifications/resources/list_changed`: リソース一覧と内容を破棄
+    async fn resource_list_changed(&self, server_name: &str) {
+        self.resources.write().await.remove(server_name);
+        self.contents.write().await.retain(|(server, _), _| server != server_name);
+        debug!("Invalidated resource list of {}", server_name);
+    }
+
+    /// `notifications/prompts/list_changed`: プロンプト一覧を破棄
+    async fn prompt_list_changed(&self, server_name: &str) {
+        self.prompts.write().await.remove(server_name);
+        debug!("Invalidated prompt list of {}", server_name);
+    }
+
+    /// サーバー単位で全キャッシュを破棄
+    async fn invalidate_server(&self, server_name: &str) {
+        self.resource_list_changed(server_name).await;
+        self.prompt_list_changed(server_name).await;
+        self.subscriptions.write().await.retain(|(server, _)| server != server_name);
+    }
+}
+
+/// サーバーからの通知を受け取り、キャッシュを無効化するハンドラー
+///
+/// サンプリングが許可されたサーバーには `sampling` 機能を通知し、補完要求に応答します。
+#[derive(Clone)]
+struct MCPClientHandler {
+    server: MCPServerConfig,
+    cache: Arc<MCPCache>,
+    sampler: Option<Arc<MCPSampler>>,
+}
+
+impl ClientHandler for MCPClientHandler {
+    fn get_info(&self) -> ClientInfo {
+        let capabilities = if self.sampler.is_some() {
+            ClientCapabilities::builder().enable_sampling().build()
+        } else {
+            ClientCapabilities::default()
+        };
+        ClientInfo {
+            capabilities,
+            client_info: rmcp::model::Implementation {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    async fn create_message(
+        &self,
+        params: CreateMessageRequestParams,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<CreateMessageResult, ErrorData> {
+        match &self.sampler {
+            Some(sampler) => sampler.create_message(&self.server, params).await,
+            None => Err(ErrorData::method_not_found::<CreateMessageRequestMethod>()),
+        }
+    }
+
+    async fn on_resource_updated(
+        &self,
+        params: ResourceUpdatedNotificationParam,
+        _context: NotificationContext<RoleClient>,
+    ) {
+        self.cache.resource_updated(&self.server.name, &params.uri).await;
+    }
+
+    async fn on_resource_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        self.cache.resource_list_changed(&self.server.name).await;
+    }
+
+    async fn on_prompt_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        self.cache.prompt_list_changed(&self.server.name).await;
+    }
+}
+
 /// サーバー接続情報
 struct ServerConnection {
     /// サービス
-    service: RunningService<RoleClient, ()>,
+    service: RunningService<RoleClient, MCPClientHandler>,
     /// 最終使用時刻
     last_used: Instant,
 }
 
 impl ServerConnection {
     /// 新しい接続を作成
-    async fn new(server: &MCPServerConfig) -> Result<Self> {
+    async fn new(server: &MCPServerConfig, handler: MCPClientHandler) -> Result<Self> {
+        server.validate()?;
+
+        let service = match server.transport {
+            MCPTransport::Stdio => Self::connect_stdio(server, handler).await?,
+            MCPTransport::Sse => {
+                let url = server.url.as_deref().unwrap_or_default();
+                let transport = SseClientTransport::connect(url, server.header_map()?).await?;
+                handler.serve(transport).await?
+            }
+            MCPTransport::StreamableHttp => {
+                let url = server.url.as_deref().unwrap_or_default();
+                let headers: HashMap<HeaderName, HeaderValue> = server
+                    .header_map()?
+                    .into_iter()
+                    .filter_map(|(name, value)| name.map(|name| (name, value)))
+                    .collect();
+                let config = StreamableHttpClientTransportConfig::with_uri(url).custom_headers(headers);
+                handler.serve(StreamableHttpClientTransport::from_config(config)).await?
+            }
+        };
+        debug!("Connected to MCP server: {} ({})", server.name, server.transport.as_str());
+
+        Ok(Self {
+            service,
+            last_used: Instant::now(),
+        })
+    }
+
+    /// 子プロセスとしてサーバーを起動して接続
+    async fn connect_stdio(
+        server: &MCPServerConfig,
+        handler: MCPClientHandler,
+    ) -> Result<RunningService<RoleClient, MCPClientHandler>> {
         // 環境変数を展開
         let expanded_env: HashMap<String, String> = server.env.iter()
-            .map(|(k, v)| {
-                let expanded = if v.starts_with("${") && v.ends_with("}") {
-                    let var_name = &v[2..v.len()-1];
-                    std::env::var(var_name).unwrap_or_else(|_| v.clone())
-                } else {
-                    v.clone()
-                };
-                (k.clone(), expanded)
-            })
+            .map(|(k, v)| (k.clone(), expand_env_value(v)))
             .collect();
 
         // 子プロセスとしてサーバーを起動
@@ -125,13 +499,7 @@ impl ServerConnection {
         )?;
 
         // サービスを開始
-        let service = ().serve(transport).await?;
-        debug!("Connected to MCP server: {}", server.name);
-
-        Ok(Self {
-            service,
-            last_used: Instant::now(),
-        })
+        Ok(handler.serve(transport).await?)
     }
 }
 
@@ -144,6 +512,10 @@ pub struct MCPConnectionPool {
     connections: RwLock<HashMap<String, ServerConnection>>,
     /// アイドルタイムアウト（秒）
     idle_timeout_seconds: u64,
+    /// リソース・プロンプトのキャッシュ
+    cache: Arc<MCPCache>,
+    /// サンプリング要求の処理（未設定ならサンプリング無効）
+    sampler: std::sync::RwLock<Option<Arc<MCPSampler>>>,
 }
 
 impl MCPConnectionPool {
@@ -152,11 +524,23 @@ impl MCPConnectionPool {
         Self {
             connections: RwLock::new(HashMap::new()),
             idle_timeout_seconds,
+            cache: Arc::new(MCPCache::default()),
+            sampler: std::sync::RwLock::new(None),
         }
     }
 
-    /// 接続を使用してツール一覧を取得
-    pub async fn list_tools(&self, server: &MCPServerConfig) -> Result<Vec<Tool>> {
+    /// サンプリング要求の処理を設定（以降の新規接続に適用）
+    pub fn set_sampler(&self, sampler: Arc<MCPSampler>) {
+        *self.sampler.write().unwrap_or_else(|e| e.into_inner()) = Some(sampler);
+    }
+
+    /// サンプリング要求の処理を取得
+    pub fn sampler(&self) -> Option<Arc<MCPSampler>> {
+        self.sampler.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 接続を取得（未接続またはアイドルタイムアウト時は新規接続）
+    async fn peer(&self, server: &MCPServerConfig) -> Result<Peer<RoleClient>> {
         let server_name = &server.name;
 
         // 既存の接続を確認
@@ -167,7 +551,7 @@ impl MCPConnectionPool {
                 if elapsed < self.idle_timeout_seconds {
                     debug!("Reusing existing connection to {} (idle: {}s)", server_name, elapsed);
                     conn.last_used = Instant::now();
-                    return self.do_list_tools(&conn.service).await;
+                    return Ok(conn.service.peer().clone());
                 } else {
                     info!("Connection to {} timed out (idle: {}s), reconnecting", server_name, elapsed);
                     connections.remove(server_name);
@@ -175,16 +559,28 @@ impl MCPConnectionPool {
             }
         }
 
-        // 新規接続を作成
+        // 新規接続を作成（購読は接続ごとなのでキャッシュも破棄）
         debug!("Creating new connection to {}", server_name);
-        let conn = ServerConnection::new(server).await?;
-        let tools = self.do_list_tools(&conn.service).await?;
+        self.cache.invalidate_server(server_name).await;
+        let handler = MCPClientHandler {
+            server: server.clone(),
+            cache: self.cache.clone(),
+            sampler: if server.sampling { self.sampler() } else { None },
+        };
+        let conn = ServerConnection::new(server, handler).await?;
+        let peer = conn.service.peer().clone();
 
         // 接続をプールに保存
         let mut connections = self.connections.write().await;
         connections.insert(server_name.clone(), conn);
 
-        Ok(tools)
+        Ok(peer)
+    }
+
+    /// 接続を使用してツール一覧を取得
+    pub async fn list_tools(&self, server: &MCPServerConfig) -> Result<Vec<Tool>> {
+        let peer = self.peer(server).await?;
+        self.do_list_tools(&peer).await
     }
 
     /// 接続を使用してツールを実行
@@ -194,50 +590,118 @@ impl MCPConnectionPool {
         tool_name: &str,
         arguments: Option<serde_json::Map<String, serde_json::Value>>,
     ) -> Result<rmcp::model::CallToolResult> {
-        let server_name = &server.name;
+        let peer = self.peer(server).await?;
+        self.do_call_tool(&peer, tool_name, arguments).await
+    }
 
-        // 既存の接続を確認
-        {
-            let mut connections = self.connections.write().await;
-            if let Some(conn) = connections.get_mut(server_name) {
-                let elapsed = conn.last_used.elapsed().as_secs();
-                if elapsed < self.idle_timeout_seconds {
-                    debug!("Reusing existing connection to {} (idle: {}s)", server_name, elapsed);
-                    conn.last_used = Instant::now();
-                    return self.do_call_tool(&conn.service, tool_name, arguments).await;
-                } else {
-                    info!("Connection to {} timed out (idle: {}s), reconnecting", server_name, elapsed);
-                    connections.remove(server_name);
-                }
+    /// 接続を使用してリソース一覧を取得（キャッシュ優先）
+    pub async fn list_resources(&self, server: &MCPServerConfig) -> Result<Vec<Resource>> {
+        let peer = self.peer(server).await?;
+        if let Some(resources) = self.cache.resources.read().await.get(&server.name) {
+            return Ok(resources.clone());
+        }
+
+        // resources 非対応のサーバーは空扱い
+        let supported = peer.peer_info().is_some_and(|info| info.capabilities.resources.is_some());
+        let resources = if supported { peer.list_all_resources().await? } else { Vec::new() };
+
+        self.cache.resources.write().await.insert(server.name.clone(), resources.clone());
+        Ok(resources)
+    }
+
+    /// 接続を使用してリソースを読み取り
+    ///
+    /// サーバーが購読に対応していれば購読し、`notifications/resources/updated` を
+    /// 受け取るまで読み取り結果をキャッシュします。
+    pub async fn read_resource(&self, server: &MCPServerConfig, uri: &str) -> Result<ReadResourceResult> {
+        let peer = self.peer(server).await?;
+        let key = (server.name.clone(), uri.to_string());
+        if let Some(result) = self.cache.contents.read().await.get(&key) {
+            debug!("Using cached resource {} from {}", uri, server.name);
+            return Ok(result.clone());
+        }
+
+        let result = peer
+            .read_resource(ReadResourceRequestParams {
+                meta: None,
+                uri: uri.to_string(),
+            })
+            .await?;
+
+        let can_subscribe = peer.peer_info().is_some_and(|info| {
+            info.capabilities
+                .resources
+                .as_ref()
+                .is_some_and(|resources| resources.subscribe == Some(true))
+        });
+        if can_subscribe {
+            let subscribed = self.cache.subscriptions.read().await.contains(&key);
+            let subscribed = subscribed
+                || match peer.subscribe(SubscribeRequestParams { meta: None, uri: uri.to_string() }).await {
+                    Ok(()) => {
+                        self.cache.subscriptions.write().await.insert(key.clone());
+                        true
+                    }
+                    Err(e) => {
+                        warn!("Failed to subscribe to {} on {}: {}", uri, server.name, e);
+                        false
+                    }
+                };
+            if subscribed {
+                self.cache.contents.write().await.insert(key, result.clone());
             }
         }
 
-        // 新規接続を作成
-        debug!("Creating new connection to {}", server_name);
-        let conn = ServerConnection::new(server).await?;
-        let result = self.do_call_tool(&conn.service, tool_name, arguments).await?;
+        Ok(result)
+    }
 
-        // 接続をプールに保存
-        let mut connections = self.connections.write().await;
-        connections.insert(server_name.clone(), conn);
+    /// 接続を使用してプロンプト一覧を取得（キャッシュ優先）
+    pub async fn list_prompts(&self, server: &MCPServerConfig) -> Result<Vec<Prompt>> {
+        let peer = self.peer(server).await?;
+        if let Some(prompts) = self.cache.prompts.read().await.get(&server.name) {
+            return Ok(prompts.clone());
+        }
+
+        // prompts 非対応のサーバーは空扱い
+        let supported = peer.peer_info().is_some_and(|info| info.capabilities.prompts.is_some());
+        let prompts = if supported { peer.list_all_prompts().await? } else { Vec::new() };
+
+        self.cache.prompts.write().await.insert(server.name.clone(), prompts.clone());
+        Ok(prompts)
+    }
 
+    /// 接続を使用してプロンプトを取得
+    pub async fn get_prompt(
+        &self,
+        server: &MCPServerConfig,
+        name: &str,
+        arguments: Option<serde_json::Map<String, serde_json::Value>>,
+    ) -> Result<GetPromptResult> {
+        let peer = self.peer(server).await?;
+        let result = peer
+            .get_prompt(GetPromptRequestParams {
+                meta: None,
+                name: name.to_string(),
+                arguments,
+            })
+            .await?;
         Ok(result)
     }
 
     /// ツール一覧取得の実装
-    async fn do_list_tools(&self, service: &RunningService<RoleClient, ()>) -> Result<Vec<Tool>> {
-        let result = service.list_tools(Default::default()).await?;
+    async fn do_list_tools(&self, peer: &Peer<RoleClient>) -> Result<Vec<Tool>> {
+        let result = peer.list_tools(Default::default()).await?;
         Ok(result.tools)
     }
 
     /// ツール実行の実装
     async fn do_call_tool(
         &self,
-        service: &RunningService<RoleClient, ()>,
+        peer: &Peer<RoleClient>,
         tool_name: &str,
         arguments: Option<serde_json::Map<String, serde_json::Value>>,
     ) -> Result<rmcp::model::CallToolResult> {
-        let result = service.call_tool(CallToolRequestParams {
+        let result = peer.call_tool(CallToolRequestParams {
             name: tool_name.to_string().into(),
             arguments,
             meta: None,
@@ -246,6 +710,40 @@ impl MCPConnectionPool {
         Ok(result)
     }
 
+    /// 既存の接続に ping を送る（接続がなければ None、最終使用時刻は更新しない）
+    pub async fn ping(&self, server_name: &str, timeout: Duration) -> Option<Result<()>> {
+        let peer = {
+            let connections = self.connections.read().await;
+            let conn = connections.get(server_name)?;
+            if conn.service.is_closed() || conn.service.is_transport_closed() {
+                return Some(Err(anyhow::anyhow!("Connection to {} is closed", server_name)));
+            }
+            conn.service.peer().clone()
+        };
+
+        let request = ClientRequest::PingRequest(PingRequest::default());
+        let result = match tokio::time::timeout(timeout, peer.send_request(request)).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(anyhow::anyhow!("Ping to {} failed: {}", server_name, e)),
+            Err(_) => Err(anyhow::anyhow!("Ping to {} timed out after {}s", server_name, timeout.as_secs())),
+        };
+        Some(result)
+    }
+
+    /// 指定サーバーの接続をクローズ
+    pub async fn close(&self, server_name: &str) -> bool {
+        let removed = self.connections.write().await.remove(server_name).is_some();
+        if removed {
+            info!("Closed MCP connection to {}", server_name);
+        }
+        removed
+    }
+
+    /// 指定サーバーに接続中かどうか
+    pub async fn is_connected(&self, server_name: &str) -> bool {
+        self.connections.read().await.contains_key(server_name)
+    }
+
     /// アイドル接続をクリーンアップ
     pub async fn cleanup_idle_connections(&self) {
         let mut connections = self.connections.write().await;
@@ -283,7 +781,8 @@ impl MCPConnectionPool {
 
 /// MCPクライアント
 pub struct MCPClient {
-    config: MCPConfig,
+    /// 設定（`/mcp enable` などで実行中に変更されるため同期ロックで保持）
+    config: std::sync::RwLock<MCPConfig>,
     config_path: PathBuf,
     tools: Arc<RwLock<Vec<MCPToolDefinition>>>,
     pool: MCPConnectionPool,
@@ -293,7 +792,7 @@ impl MCPClient {
     /// 新しいMCPクライアントを作成
     pub fn new() -> Self {
         Self {
-            config: MCPConfig::default(),
+            config: std::sync::RwLock::new(MCPConfig::default()),
             config_path: PathBuf::from("mcp-servers.json"),
             tools: Arc::new(RwLock::new(Vec::new())),
             pool: MCPConnectionPool::new(300), // デフォルト5分アイドルタイムアウト
@@ -316,32 +815,62 @@ impl MCPClient {
         let idle_timeout = config.settings.connection_timeout_seconds * 10; // 接続タイムアウトの10倍
 
         Ok(Self {
-            config,
+            config: std::sync::RwLock::new(config),
             config_path: path.to_path_buf(),
             tools: Arc::new(RwLock::new(Vec::new())),
             pool: MCPConnectionPool::new(idle_timeout),
         })
     }
 
+    /// サンプリングを有効化（`sampling: true` のサーバーの補完要求を `llm` で処理）
+    ///
+    /// ハンドラーは接続時に作成されるため、ツール一覧の取得より前に呼び出してください。
+    pub fn enable_sampling(&self, llm: Arc<dyn LLMClient>, approvals: Arc<SamplingApprovals>) {
+        let settings = self.config_read().settings.clone();
+        self.pool.set_sampler(Arc::new(MCPSampler::new(
+            llm,
+            approvals,
+            settings.sampling_max_tokens,
+            settings.sampling_tokens_per_hour,
+            Duration::from_secs(settings.sampling_approval_timeout_seconds),
+        )));
+    }
+
+    /// サンプリング要求の処理を取得（無効なら None）
+    pub fn sampler(&self) -> Option<Arc<MCPSampler>> {
+        self.pool.sampler()
+    }
+
+    /// 設定の読み取りロックを取得（ロック汚染時もそのまま続行）
+    fn config_read(&self) -> std::sync::RwLockReadGuard<'_, MCPConfig> {
+        self.config.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 設定の書き込みロックを取得（ロック汚染時もそのまま続行）
+    fn config_write(&self) -> std::sync::RwLockWriteGuard<'_, MCPConfig> {
+        self.config.write().unwrap_or_else(|e| e.into_inner())
+    }
+
     /// 設定をファイルに保存
     pub fn save(&self) -> Result<()> {
-        let content = serde_json::to_string_pretty(&self.config)?;
+        let content = serde_json::to_string_pretty(&*self.config_read())?;
         std::fs::write(&self.config_path, content)?;
         info!("Saved MCP config to {:?}", self.config_path);
         Ok(())
     }
 
     /// サーバー設定を追加
-    pub fn add_server(&mut self, server: MCPServerConfig) {
+    pub fn add_server(&self, server: MCPServerConfig) {
         info!("Adding MCP server: {}", server.name);
-        self.config.servers.push(server);
+        self.config_write().servers.push(server);
     }
 
     /// サーバー設定を削除
-    pub fn remove_server(&mut self, name: &str) -> bool {
-        if let Some(pos) = self.config.servers.iter().position(|s| s.name == name) {
+    pub fn remove_server(&self, name: &str) -> bool {
+        let mut config = self.config_write();
+        if let Some(pos) = config.servers.iter().position(|s| s.name == name) {
             info!("Removing MCP server: {}", name);
-            self.config.servers.remove(pos);
+            config.servers.remove(pos);
             true
         } else {
             false
@@ -349,8 +878,8 @@ impl MCPClient {
     }
 
     /// サーバーを有効/無効化
-    pub fn set_server_enabled(&mut self, name: &str, enabled: bool) -> bool {
-        if let Some(server) = self.config.servers.iter_mut().find(|s| s.name == name) {
+    pub fn set_server_enabled(&self, name: &str, enabled: bool) -> bool {
+        if let Some(server) = self.config_write().servers.iter_mut().find(|s| s.name == name) {
             server.enabled = enabled;
             info!("Set MCP server {} enabled: {}", name, enabled);
             true
@@ -360,13 +889,18 @@ impl MCPClient {
     }
 
     /// サーバー一覧を取得
-    pub fn list_servers(&self) -> &[MCPServerConfig] {
-        &self.config.servers
+    pub fn list_servers(&self) -> Vec<MCPServerConfig> {
+        self.config_read().servers.clone()
     }
 
     /// 有効なサーバー一覧を取得
-    pub fn list_enabled_servers(&self) -> Vec<&MCPServerConfig> {
-        self.config.servers.iter().filter(|s| s.enabled).collect()
+    pub fn list_enabled_servers(&self) -> Vec<MCPServerConfig> {
+        self.config_read().servers.iter().filter(|s| s.enabled).cloned().collect()
+    }
+
+    /// サーバー設定を名前で取得
+    pub fn get_server(&self, name: &str) -> Option<MCPServerConfig> {
+        self.config_read().servers.iter().find(|s| s.name == name).cloned()
     }
 
     /// 全サーバーからツール一覧を取得
@@ -376,10 +910,8 @@ impl MCPClient {
 
     /// 指定サーバーに接続してツール一覧を取得（接続プールを使用）
     pub async fn refresh_tools_from_server(&self, server_name: &str) -> Result<Vec<MCPToolDefinition>> {
-        let server = self.config.servers.iter()
-            .find(|s| s.name == server_name)
-            .ok_or_else(|| anyhow::anyhow!("Server not found: {}", server_name))?
-            .clone();
+        let server = self.get_server(server_name)
+            .ok_or_else(|| anyhow::anyhow!("Server not found: {}", server_name))?;
 
         if !server.enabled {
             warn!("Server {} is disabled, skipping", server_name);
@@ -392,16 +924,27 @@ impl MCPClient {
         let raw_tools = self.pool.list_tools(&server).await?;
         debug!("Received {} tools from {}", raw_tools.len(), server_name);
 
-        let tools: Vec<MCPToolDefinition> = raw_tools.into_iter().map(|tool| {
+        let mut tools: Vec<MCPToolDefinition> = Vec::new();
+        for tool in raw_tools {
+            // サニタイズ・切り詰めで同じ登録名になったツールは先勝ち
+            let name = namespaced_tool_name(server_name, &tool.name);
+            if let Some(existing) = tools.iter().find(|t| t.name == name) {
+                warn!(
+                    "Skipping MCP tool {} on server {}: registered name {} collides with tool {}",
+                    tool.name, server_name, name, existing.tool_name
+                );
+                continue;
+            }
             // input_schemaをValueに変換
             let schema_value = serde_json::to_value(&*tool.input_schema).unwrap_or(serde_json::json!({}));
-            MCPToolDefinition {
-                name: format!("mcp_{}_{}", server_name, tool.name),
+            tools.push(MCPToolDefinition {
+                name,
+                tool_name: tool.name.to_string(),
                 description: tool.description.unwrap_or_default().to_string(),
                 input_schema: schema_value,
                 server_name: server_name.to_string(),
-            }
-        }).collect();
+            });
+        }
 
         // キャッシュを更新
         let mut cached_tools = self.tools.write().await;
@@ -415,11 +958,40 @@ impl MCPClient {
         Ok(tools)
     }
 
+    /// 指定サーバーのツール一覧（キャッシュ）を取得
+    pub async fn list_server_tools(&self, server_name: &str) -> Vec<MCPToolDefinition> {
+        self.tools.read().await.iter().filter(|t| t.server_name == server_name).cloned().collect()
+    }
+
+    /// 指定サーバーの接続を閉じ、ツールキャッシュから外す
+    pub async fn close_server(&self, server_name: &str) {
+        self.pool.close(server_name).await;
+        self.tools.write().await.retain(|t| t.server_name != server_name);
+    }
+
+    /// 指定サーバーに再接続してツール一覧を更新
+    pub async fn restart_server(&self, server_name: &str) -> Result<Vec<MCPToolDefinition>> {
+        info!("Restarting MCP server: {}", server_name);
+        self.pool.close(server_name).await;
+        self.refresh_tools_from_server(server_name).await
+    }
+
+    /// 指定サーバーの死活確認（接続がなければ None）
+    pub async fn ping_server(&self, server_name: &str) -> Option<Result<()>> {
+        let timeout = Duration::from_secs(self.config_read().settings.connection_timeout_seconds);
+        self.pool.ping(server_name, timeout).await
+    }
+
+    /// 指定サーバーに接続中かどうか
+    pub async fn is_connected(&self, server_name: &str) -> bool {
+        self.pool.is_connected(server_name).await
+    }
+
     /// 全有効サーバーからツールを更新
     pub async fn refresh_all_tools(&self) -> Result<usize> {
-        let enabled_servers: Vec<_> = self.config.servers.iter()
-            .filter(|s| s.enabled)
-            .map(|s| s.name.clone())
+        let enabled_servers: Vec<_> = self.list_enabled_servers()
+            .into_iter()
+            .map(|s| s.name)
             .collect();
 
         let mut total_tools = 0;
@@ -439,49 +1011,98 @@ impl MCPClient {
     }
 
     /// ツールを実行（接続プールを使用）
+    ///
+    /// `tool_name` はサーバー上のツール名（登録名ではない）
     pub async fn execute_tool(
         &self,
+        server_name: &str,
         tool_name: &str,
         arguments: Option<serde_json::Map<String, serde_json::Value>>,
     ) -> Result<serde_json::Value> {
-        // ツール名からサーバー名を抽出 (mcp_<server>_<tool> 形式)
-        let parts: Vec<&str> = tool_name.splitn(3, '_').collect();
-        if parts.len() < 3 || parts[0] != "mcp" {
-            return Err(anyhow::anyhow!("Invalid MCP tool name format: {}", tool_name));
-        }
+        let server = self.enabled_server(server_name)?;
+
+        debug!("Executing tool {} on server {} (pool)", tool_name, server_name);
+
+        // 接続プールを使用してツールを実行
+        let result = self.pool.call_tool(&server, tool_name, arguments).await?;
+
+        // 結果をJSONに変換
+        let value = serde_json::to_value(&result)?;
+        debug!("Tool result: {:?}", value);
 
-        let server_name = parts[1];
-        let actual_tool_name = parts[2];
+        Ok(value)
+    }
 
-        let server = self.config.servers.iter()
-            .find(|s| s.name == server_name)
-            .ok_or_else(|| anyhow::anyhow!("Server not found: {}", server_name))?
-            .clone();
+    /// 有効なサーバー設定を名前で取得
+    fn enabled_server(&self, server_name: &str) -> Result<MCPServerConfig> {
+        let server = self.get_server(server_name)
+            .ok_or_else(|| anyhow::anyhow!("Server not found: {}", server_name))?;
 
         if !server.enabled {
             return Err(anyhow::anyhow!("Server {} is disabled", server_name));
         }
+        Ok(server)
+    }
 
-        debug!("Executing tool {} on server {} (pool)", actual_tool_name, server_name);
+    /// 指定サーバーのリソース一覧を取得
+    pub async fn list_resources(&self, server_name: &str) -> Result<Vec<MCPResourceDefinition>> {
+        let server = self.enabled_server(server_name)?;
+        let resources = self.pool.list_resources(&server).await?;
+        Ok(resources.into_iter().map(|r| (server_name, r).into()).collect())
+    }
 
-        // 接続プールを使用してツールを実行
-        let result = self.pool.call_tool(&server, actual_tool_name, arguments).await?;
+    /// 指定サーバーのリソースを読み取り
+    pub async fn read_resource(&self, server_name: &str, uri: &str) -> Result<ReadResourceResult> {
+        let server = self.enabled_server(server_name)?;
+        debug!("Reading resource {} from server {} (pool)", uri, server_name);
+        self.pool.read_resource(&server, uri).await
+    }
 
-        // 結果をJSONに変換
-        let value = serde_json::to_value(&result)?;
-        debug!("Tool result: {:?}", value);
+    /// 指定サーバーのプロンプト一覧を取得
+    pub async fn list_prompts(&self, server_name: &str) -> Result<Vec<MCPPromptDefinition>> {
+        let server = self.enabled_server(server_name)?;
+        let prompts = self.pool.list_prompts(&server).await?;
+        Ok(prompts.into_iter().map(|p| (server_name, p).into()).collect())
+    }
 
-        Ok(value)
+    /// 全有効サーバーのプロンプト一覧を取得（取得に失敗したサーバーはスキップ）
+    pub async fn list_all_prompts(&self) -> Vec<MCPPromptDefinition> {
+        let mut all_prompts = Vec::new();
+        for server in self.list_enabled_servers() {
+            match self.list_prompts(&server.name).await {
+                Ok(prompts) => all_prompts.extend(prompts),
+                Err(e) => error!("Failed to list prompts from {}: {}", server.name, e),
+            }
+        }
+        all_prompts
+    }
+
+    /// プロンプトを取得（引数を埋め込んだメッセージ列）
+    pub async fn get_prompt(
+        &self,
+        server_name: &str,
+        prompt_name: &str,
+        arguments: HashMap<String, String>,
+    ) -> Result<GetPromptResult> {
+        let server = self.enabled_server(server_name)?;
+        let arguments = (!arguments.is_empty()).then(|| {
+            arguments
+                .into_iter()
+                .map(|(k, v)| (k, serde_json::Value::String(v)))
+                .collect()
+        });
+        debug!("Getting prompt {} from server {} (pool)", prompt_name, server_name);
+        self.pool.get_prompt(&server, prompt_name, arguments).await
     }
 
     /// 設定を取得
-    pub fn config(&self) -> &MCPConfig {
-        &self.config
+    pub fn config(&self) -> MCPConfig {
+        self.config_read().clone()
     }
 
     /// 設定を取得（mutable）
     pub fn config_mut(&mut self) -> &mut MCPConfig {
-        &mut self.config
+        self.config.get_mut().unwrap_or_else(|e| e.into_inner())
     }
 
     /// アイドル接続をクリーンアップ
@@ -530,6 +1151,7 @@ mod tests {
                 env: HashMap::new(),
                 enabled: true,
                 description: "Test server".to_string(),
+                ..Default::default()
             }],
             settings: MCPSettings::default(),
         };
@@ -551,7 +1173,7 @@ mod tests {
 
     #[test]
     fn test_mcp_client_add_remove_server() {
-        let mut client = MCPClient::new();
+        let client = MCPClient::new();
 
         client.add_server(MCPServerConfig {
             name: "server1".to_string(),
@@ -560,6 +1182,7 @@ mod tests {
             env: HashMap::new(),
             enabled: true,
             description: String::new(),
+            ..Default::default()
         });
 
         assert_eq!(client.list_servers().len(), 1);
@@ -571,6 +1194,7 @@ mod tests {
             env: HashMap::new(),
             enabled: false,
             description: String::new(),
+            ..Default::default()
         });
 
         assert_eq!(client.list_servers().len(), 2);
@@ -583,7 +1207,7 @@ mod tests {
 
     #[test]
     fn test_mcp_client_set_enabled() {
-        let mut client = MCPClient::new();
+        let client = MCPClient::new();
 
         client.add_server(MCPServerConfig {
             name: "test".to_string(),
@@ -592,6 +1216,7 @@ mod tests {
             env: HashMap::new(),
             enabled: true,
             description: String::new(),
+            ..Default::default()
         });
 
         assert!(client.set_server_enabled("test", false));
@@ -615,6 +1240,7 @@ mod tests {
             env: HashMap::new(),
             enabled: true,
             description: "Saved server".to_string(),
+            ..Default::default()
         });
 
         client.save().unwrap();
@@ -628,20 +1254,511 @@ mod tests {
     #[test]
     fn test_mcp_tool_definition() {
         let tool = MCPToolDefinition {
-            name: "mcp_git_status".to_string(),
+            name: "mcp__git__status".to_string(),
+            tool_name: "status".to_string(),
             description: "Get git status".to_string(),
             input_schema: serde_json::json!({"type": "object"}),
             server_name: "git".to_string(),
         };
 
-        assert!(tool.name.starts_with("mcp_"));
+        assert!(tool.name.starts_with(MCP_TOOL_PREFIX));
         assert_eq!(tool.server_name, "git");
     }
 
+    #[test]
+    fn test_namespaced_tool_name() {
+        assert_eq!(namespaced_tool_name("git", "status"), "mcp__git__status");
+        assert_eq!(namespaced_tool_name("my server", "files.read"), "mcp__my_server__files_read");
+        assert_eq!(namespaced_tool_name("exa", "web-search"), "mcp__exa__web-search");
+
+        // 長い名前は64文字に切り詰め、元の名前のハッシュで区別する
+        let long_a = namespaced_tool_name("server", &format!("{}a", "x".repeat(80)));
+        let long_b = namespaced_tool_name("server", &format!("{}b", "x".repeat(80)));
+        assert_eq!(long_a.len(), MAX_TOOL_NAME_LEN);
+        assert_ne!(long_a, long_b);
+        assert!(long_a.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'));
+    }
+
     #[tokio::test]
     async fn test_mcp_client_list_tools() {
         let client = MCPClient::new();
         let tools = client.list_all_tools().await;
         assert!(tools.is_empty());
     }
+
+    #[test]
+    fn test_mcp_server_config_transport() {
+        let config: MCPConfig = serde_json::from_str(r#"{
+            "servers": [
+                {"name": "local", "command": "npx", "enabled": true},
+                {"name": "remote", "transport": "streamable_http", "url": "https://mcp.example.com/mcp",
+                 "headers": {"Authorization": "Bearer ${MCP_TEST_TOKEN}"}, "enabled": true},
+                {"name": "legacy", "transport": "sse", "enabled": true}
+            ]
+        }"#).unwrap();
+
+        assert_eq!(config.servers[0].transport, MCPTransport::Stdio);
+        assert!(config.servers[0].validate().is_ok());
+        assert_eq!(config.servers[1].transport, MCPTransport::StreamableHttp);
+        assert!(config.servers[1].validate().is_ok());
+        assert_eq!(config.servers[2].transport, MCPTransport::Sse);
+        assert!(config.servers[2].validate().is_err());
+
+        // stdio の設定は従来どおり url / headers を出力しない
+        let json = serde_json::to_value(&config.servers[0]).unwrap();
+        assert!(json.get("url").is_none());
+        assert!(json.get("headers").is_none());
+    }
+
+    #[test]
+    fn test_prompt_missing_arguments() {
+        let prompt: MCPPromptDefinition = ("notes", rmcp::model::Prompt::new(
+            "summarize",
+            Some("Summarize a topic"),
+            Some(vec![
+                rmcp::model::PromptArgument { name: "topic".to_string(), title: None, description: None, required: Some(true) },
+                rmcp::model::PromptArgument { name: "style".to_string(), title: None, description: None, required: None },
+            ]),
+        )).into();
+
+        assert_eq!(prompt.server_name, "notes");
+        assert_eq!(prompt.missing_arguments(&HashMap::new()), vec!["topic"]);
+        let args = HashMap::from([("topic".to_string(), " ".to_string())]);
+        assert_eq!(prompt.missing_arguments(&args), vec!["topic"]);
+        let args = HashMap::from([("topic".to_string(), "MCP".to_string())]);
+        assert!(prompt.missing_arguments(&args).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cache_invalidation() {
+        let cache = MCPCache::default();
+        let key = |server: &str, uri: &str| (server.to_string(), uri.to_string());
+        for (server, uri) in [("a", "note://1"), ("a", "note://2"), ("b", "note://1")] {
+            cache.contents.write().await.insert(key(server, uri), ReadResourceResult { contents: vec![] });
+        }
+        cache.resources.write().await.insert("a".to_string(), vec![]);
+        cache.prompts.write().await.insert("a".to_string(), vec![]);
+
+        // 更新通知は該当リソースのみ破棄
+        cache.resource_updated("a", "note://1").await;
+        assert!(!cache.contents.read().await.contains_key(&key("a", "note://1")));
+        assert!(cache.contents.read().await.contains_key(&key("a", "note://2")));
+        assert!(cache.contents.read().await.contains_key(&key("b", "note://1")));
+
+        // 一覧変更通知はサーバーのリソースをすべて破棄
+        cache.resource_list_changed("a").await;
+        assert!(!cache.resources.read().await.contains_key("a"));
+        assert_eq!(cache.contents.read().await.len(), 1);
+        assert!(cache.prompts.read().await.contains_key("a"));
+
+        cache.prompt_list_changed("a").await;
+        assert!(cache.prompts.read().await.is_empty());
+    }
+
+    // ============================================
+    // トランスポートの結合テスト（ローカルの rmcp サーバー）
+    // ============================================
+
+    mod transports {
+        use super::*;
+        use axum::extract::State;
+        use axum::http::{HeaderMap as AxumHeaderMap, StatusCode};
+        use axum::response::sse::{Event, Sse};
+        use futures::Stream;
+        use rmcp::model::{
+            AnnotateAble, CallToolResult, Content, ListPromptsResult, ListResourcesResult, ListToolsResult,
+            PaginatedRequestParams, PromptArgument, PromptMessage, PromptMessageRole, RawResource, ResourceContents,
+            ServerCapabilities, ServerInfo,
+        };
+        use rmcp::service::RequestContext;
+        use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
+        use rmcp::transport::streamable_http_server::{StreamableHttpServerConfig, StreamableHttpService};
+        use rmcp::{ErrorData, RoleServer, ServerHandler};
+        use std::convert::Infallible;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+        /// echo ツールだけを持つテスト用サーバー（受け取った x-api-key ヘッダーも返す）
+        #[derive(Clone)]
+        struct EchoServer;
+
+        impl ServerHandler for EchoServer {
+            fn get_info(&self) -> ServerInfo {
+                ServerInfo {
+                    capabilities: ServerCapabilities::builder().enable_tools().build(),
+                    ..Default::default()
+                }
+            }
+
+            async fn list_tools(
+                &self,
+                _request: Option<PaginatedRequestParams>,
+                _context: RequestContext<RoleServer>,
+            ) -> Result<ListToolsResult, ErrorData> {
+                let schema = serde_json::json!({"type": "object", "properties": {"text": {"type": "string"}}});
+                let schema = Arc::new(schema.as_object().cloned().unwrap_or_default());
+                Ok(ListToolsResult::with_all_items(vec![Tool::new("echo", "Echo text", schema)]))
+            }
+
+            async fn call_tool(
+                &self,
+                request: CallToolRequestParams,
+                context: RequestContext<RoleServer>,
+            ) -> Result<CallToolResult, ErrorData> {
+                let text = request
+                    .arguments
+                    .and_then(|args| args.get("text").and_then(|v| v.as_str()).map(String::from))
+                    .unwrap_or_default();
+                let api_key = context
+                    .extensions
+                    .get::<axum::http::request::Parts>()
+                    .and_then(|parts| parts.headers.get("x-api-key"))
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("-")
+                    .to_string();
+                Ok(CallToolResult::success(vec![Content::text(format!("{} ({})", text, api_key))]))
+            }
+        }
+
+        /// リソースとプロンプトを持つテスト用サーバー（本文に読み取り回数を含める）
+        #[derive(Clone, Default)]
+        struct NotesServer {
+            reads: Arc<AtomicUsize>,
+            /// 購読したクライアント（更新通知の送信用）
+            subscriber: Arc<tokio::sync::Mutex<Option<rmcp::Peer<RoleServer>>>>,
+        }
+
+        impl ServerHandler for NotesServer {
+            fn get_info(&self) -> ServerInfo {
+                ServerInfo {
+                    capabilities: ServerCapabilities::builder()
+                        .enable_prompts()
+                        .enable_resources()
+                        .enable_resources_subscribe()
+                        .build(),
+                    ..Default::default()
+                }
+            }
+
+            async fn list_resources(
+                &self,
+                _request: Option<PaginatedRequestParams>,
+                _context: RequestContext<RoleServer>,
+            ) -> Result<ListResourcesResult, ErrorData> {
+                let resource = RawResource::new("note://counter", "counter").no_annotation();
+                Ok(ListResourcesResult::with_all_items(vec![resource]))
+            }
+
+            async fn read_resource(
+                &self,
+                request: ReadResourceRequestParams,
+                _context: RequestContext<RoleServer>,
+            ) -> Result<ReadResourceResult, ErrorData> {
+                let reads = self.reads.fetch_add(1, Ordering::SeqCst) + 1;
+                Ok(ReadResourceResult {
+                    contents: vec![ResourceContents::text(format!("read {}", reads), request.uri)],
+                })
+            }
+
+            async fn subscribe(
+                &self,
+                _request: SubscribeRequestParams,
+                context: RequestContext<RoleServer>,
+            ) -> Result<(), ErrorData> {
+                *self.subscriber.lock().await = Some(context.peer);
+                Ok(())
+            }
+
+            async fn list_prompts(
+                &self,
+                _request: Option<PaginatedRequestParams>,
+                _context: RequestContext<RoleServer>,
+            ) -> Result<ListPromptsResult, ErrorData> {
+                let topic = PromptArgument {
+                    name: "topic".to_string(),
+                    title: None,
+                    description: Some("Topic to summarize".to_string()),
+                    required: Some(true),
+                };
+                Ok(ListPromptsResult::with_all_items(vec![Prompt::new(
+                    "summarize",
+                    Some("Summarize a topic"),
+                    Some(vec![topic]),
+                )]))
+            }
+
+            async fn get_prompt(
+                &self,
+                request: GetPromptRequestParams,
+                _context: RequestContext<RoleServer>,
+            ) -> Result<GetPromptResult, ErrorData> {
+                let topic = request
+                    .arguments
+                    .and_then(|args| args.get("topic").and_then(|v| v.as_str()).map(String::from))
+                    .ok_or_else(|| ErrorData::invalid_params("topic is required", None))?;
+                Ok(GetPromptResult {
+                    description: None,
+                    messages: vec![PromptMessage::new_text(PromptMessageRole::User, format!("Summarize {}", topic))],
+                })
+            }
+        }
+
+        async fn serve(router: axum::Router) -> std::net::SocketAddr {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move {
+                let _ = axum::serve(listener, router).await;
+            });
+            addr
+        }
+
+        /// Streamable HTTP サーバーを起動
+        async fn spawn_streamable_http_server() -> String {
+            let service: StreamableHttpService<EchoServer, LocalSessionManager> = StreamableHttpService::new(
+                || Ok(EchoServer),
+                Default::default(),
+                StreamableHttpServerConfig::default(),
+            );
+            let addr = serve(axum::Router::new().nest_service("/mcp", service)).await;
+            format!("http://{}/mcp", addr)
+        }
+
+        /// HTTP+SSE サーバーを起動（rmcp サーバーを標準入出力形式で中継する）
+        async fn spawn_sse_server() -> String {
+            type Writer = tokio::io::WriteHalf<tokio::io::DuplexStream>;
+            type Shared = Arc<tokio::sync::Mutex<Option<Writer>>>;
+
+            async fn sse(
+                State(writer): State<Shared>,
+                headers: AxumHeaderMap,
+            ) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+                if headers.get("x-api-key").and_then(|v| v.to_str().ok()) != Some("secret") {
+                    return Err(StatusCode::UNAUTHORIZED);
+                }
+                let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+                tokio::spawn(async move {
+                    if let Ok(running) = EchoServer.serve(server_io).await {
+                        let _ = running.waiting().await;
+                    }
+                });
+                let (reader, write_half) = tokio::io::split(client_io);
+                *writer.lock().await = Some(write_half);
+
+                let endpoint = futures::stream::once(async {
+                    Ok(Event::default().event("endpoint").data("/message?sessionId=test"))
+                });
+                let lines = tokio::io::BufReader::new(reader).lines();
+                let messages = futures::stream::unfold(lines, |mut lines| async move {
+                    let line = lines.next_line().await.ok().flatten()?;
+                    Some((Ok(Event::default().event("message").data(line)), lines))
+                });
+                Ok(Sse::new(futures::StreamExt::chain(endpoint, messages)))
+            }
+
+            async fn message(State(writer): State<Shared>, body: String) -> StatusCode {
+                let mut writer = writer.lock().await;
+                let Some(writer) = writer.as_mut() else {
+                    return StatusCode::NOT_FOUND;
+                };
+                let _ = writer.write_all(format!("{}\n", body).as_bytes()).await;
+                StatusCode::ACCEPTED
+            }
+
+            let router = axum::Router::new()
+                .route("/sse", axum::routing::get(sse))
+                .route("/message", axum::routing::post(message))
+                .with_state(Shared::default());
+            let addr = serve(router).await;
+            format!("http://{}/sse", addr)
+        }
+
+        fn remote_client(transport: MCPTransport, url: String) -> MCPClient {
+            let client = MCPClient::new();
+            client.add_server(MCPServerConfig {
+                name: "remote".to_string(),
+                transport,
+                url: Some(url),
+                headers: HashMap::from([("x-api-key".to_string(), "secret".to_string())]),
+                enabled: true,
+                ..Default::default()
+            });
+            client
+        }
+
+        async fn echo(client: &MCPClient) -> String {
+            let args = serde_json::json!({"text": "hello"}).as_object().cloned();
+            let result = client.execute_tool("remote", "echo", args).await.unwrap();
+            result["content"][0]["text"].as_str().unwrap_or_default().to_string()
+        }
+
+        #[tokio::test]
+        async fn test_streamable_http_transport() {
+            let url = spawn_streamable_http_server().await;
+            let client = remote_client(MCPTransport::StreamableHttp, url);
+
+            assert_eq!(client.refresh_all_tools().await.unwrap(), 1);
+            assert_eq!(client.list_all_tools().await[0].name, "mcp__remote__echo");
+            assert_eq!(echo(&client).await, "hello (secret)");
+            assert_eq!(client.connection_count().await, 1);
+        }
+
+        fn resource_text(result: &ReadResourceResult) -> String {
+            match &result.contents[0] {
+                ResourceContents::TextResourceContents { text, .. } => text.clone(),
+                _ => String::new(),
+            }
+        }
+
+        #[tokio::test]
+        async fn test_resources_and_prompts() {
+            let notes = NotesServer::default();
+            let factory = notes.clone();
+            let service: StreamableHttpService<NotesServer, LocalSessionManager> = StreamableHttpService::new(
+                move || Ok(factory.clone()),
+                Default::default(),
+                StreamableHttpServerConfig::default(),
+            );
+            let addr = serve(axum::Router::new().nest_service("/mcp", service)).await;
+            let client = remote_client(MCPTransport::StreamableHttp, format!("http://{}/mcp", addr));
+
+            let resources = client.list_resources("remote").await.unwrap();
+            assert_eq!(resources.len(), 1);
+            assert_eq!(resources[0].uri, "note://counter");
+
+            // 購読中は読み取り結果をキャッシュする
+            let first = client.read_resource("remote", "note://counter").await.unwrap();
+            let second = client.read_resource("remote", "note://counter").await.unwrap();
+            assert_eq!(resource_text(&first), "read 1");
+            assert_eq!(resource_text(&second), "read 1");
+
+            // 更新通知でキャッシュが破棄される
+            let subscriber = notes.subscriber.lock().await.clone().expect("client should subscribe");
+            subscriber
+                .notify_resource_updated(ResourceUpdatedNotificationParam { uri: "note://counter".to_string() })
+                .await
+                .unwrap();
+            let mut text = String::new();
+            for _ in 0..50 {
+                text = resource_text(&client.read_resource("remote", "note://counter").await.unwrap());
+                if text != "read 1" {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            assert_eq!(text, "read 2");
+
+            let prompts = client.list_all_prompts().await;
+            assert_eq!(prompts.len(), 1);
+            assert_eq!(prompts[0].name, "summarize");
+            assert!(prompts[0].arguments[0].required);
+
+            let args = HashMap::from([("topic".to_string(), "MCP".to_string())]);
+            let result = client.get_prompt("remote", "summarize", args).await.unwrap();
+            assert_eq!(result.messages[0].content, rmcp::model::PromptMessageContent::text("Summarize MCP"));
+            assert!(client.get_prompt("remote", "summarize", HashMap::new()).await.is_err());
+        }
+
+        #[tokio::test]
+        async fn test_sse_transport() {
+            let url = spawn_sse_server().await;
+            let client = remote_client(MCPTransport::Sse, url.clone());
+
+            assert_eq!(client.refresh_all_tools().await.unwrap(), 1);
+            assert_eq!(echo(&client).await, "hello (-)");
+
+            // ヘッダーが足りなければ接続できない
+            let mut client = remote_client(MCPTransport::Sse, url);
+            client.config_mut().servers[0].headers.clear();
+            assert!(client.refresh_tools_from_server("remote").await.is_err());
+        }
+
+        /// ツール実行時にクライアントへサンプリングを要求するテスト用サーバー
+        #[derive(Clone)]
+        struct SamplingServer;
+
+        impl ServerHandler for SamplingServer {
+            fn get_info(&self) -> ServerInfo {
+                ServerInfo {
+                    capabilities: ServerCapabilities::builder().enable_tools().build(),
+                    ..Default::default()
+                }
+            }
+
+            async fn list_tools(
+                &self,
+                _request: Option<PaginatedRequestParams>,
+                _context: RequestContext<RoleServer>,
+            ) -> Result<ListToolsResult, ErrorData> {
+                let schema = Arc::new(serde_json::Map::new());
+                Ok(ListToolsResult::with_all_items(vec![Tool::new("ask", "Ask the client model", schema)]))
+            }
+
+            async fn call_tool(
+                &self,
+                _request: CallToolRequestParams,
+                context: RequestContext<RoleServer>,
+            ) -> Result<CallToolResult, ErrorData> {
+                let params = CreateMessageRequestParams {
+                    meta: None,
+                    task: None,
+                    messages: vec![rmcp::model::SamplingMessage::user_text("Hello")],
+                    model_preferences: None,
+                    system_prompt: None,
+                    include_context: None,
+                    temperature: None,
+                    max_tokens: 100,
+                    stop_sequences: None,
+                    metadata: None,
+                    tools: None,
+                    tool_choice: None,
+                };
+                let text = match context.peer.create_message(params).await {
+                    Ok(result) => format!(
+                        "{} ({})",
+                        result
+                            .message
+                            .content
+                            .first()
+                            .and_then(|c| c.as_text())
+                            .map(|t| t.text.as_str())
+                            .unwrap_or_default(),
+                        result.stop_reason.unwrap_or_default()
+                    ),
+                    Err(e) => format!("error: {}", e),
+                };
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+        }
+
+        #[tokio::test]
+        async fn test_sampling() {
+            let service: StreamableHttpService<SamplingServer, LocalSessionManager> = StreamableHttpService::new(
+                || Ok(SamplingServer),
+                Default::default(),
+                StreamableHttpServerConfig::default(),
+            );
+            let addr = serve(axum::Router::new().nest_service("/mcp", service)).await;
+            let url = format!("http://{}/mcp", addr);
+            let ask = |client: MCPClient| async move {
+                let result = client.execute_tool("remote", "ask", None).await.unwrap();
+                result["content"][0]["text"].as_str().unwrap_or_default().to_string()
+            };
+            let sampling_client = |sampling: bool| {
+                let mut client = remote_client(MCPTransport::StreamableHttp, url.clone());
+                client.config_mut().servers[0].sampling = sampling;
+                client.config_mut().servers[0].sampling_max_tokens = 5;
+                client.enable_sampling(
+                    Arc::new(crate::llm::MockLLMClient::new("answer from the bot")),
+                    Arc::new(SamplingApprovals::new(None)),
+                );
+                client
+            };
+
+            // サーバーごとの上限でトークン数を制限
+            assert_eq!(ask(sampling_client(true)).await, "answe (maxTokens)");
+            // sampling が無効なサーバーの要求は拒否
+            assert!(ask(sampling_client(false)).await.starts_with("error:"));
+        }
+    }
 }
@@ -0,0 +1,499 @@
+//! MCPサーバーの実行時管理とヘルスモニター
+//!
+//! `/mcp` コマンドと `/api/mcp` から共通で使う有効化・無効化・再起動の処理を提供します。
+//! ヘルスモニターは接続中のサーバーに定期的に ping を送り、応答しないサーバー（子プロセスの
+//! クラッシュなど）をバックオフ付きで再起動します。
+
+use crate::mcp_client::{MCPClient, MCPToolDefinition};
+use crate::tool::SharedToolManager;
+use crate::tools::{MCPReadResourceTool, MCPToolAdapter};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// リソース読み取りツール名
+const READ_RESOURCE_TOOL: &str = "mcp_read_resource";
+
+/// MCP管理操作のエラー
+#[derive(Debug, Error)]
+pub enum MCPManagerError {
+    #[error("MCP server not found: {0}")]
+    NotFound(String),
+    #[error("MCP server {0} is disabled")]
+    Disabled(String),
+    #[error("MCP server {0} failed: {1}")]
+    Failed(String, String),
+}
+
+/// サーバーの稼働状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    /// 接続中で ping に応答
+    Healthy,
+    /// 未接続（アイドルタイムアウト後など、次の利用時に接続）
+    Idle,
+    /// 接続失敗または ping 失敗（再起動待ち）
+    Unhealthy,
+    /// 無効化されている
+    Disabled,
+}
+
+impl HealthState {
+    /// 表示用ラベル
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Healthy => "🟢 正常",
+            Self::Idle => "⚪ 待機",
+            Self::Unhealthy => "🔴 異常",
+            Self::Disabled => "⚫ 無効",
+        }
+    }
+}
+
+/// サーバーごとのヘルス情報
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerHealth {
+    pub state: HealthState,
+    /// 連続失敗回数
+    pub consecutive_failures: u32,
+    /// 再起動回数（起動後の累計）
+    pub restarts: u32,
+    pub last_error: Option<String>,
+    pub last_checked: Option<DateTime<Utc>>,
+    /// 次の再起動予定時刻（バックオフ中のみ）
+    pub next_retry: Option<DateTime<Utc>>,
+}
+
+impl ServerHealth {
+    fn new(state: HealthState) -> Self {
+        Self {
+            state,
+            consecutive_failures: 0,
+            restarts: 0,
+            last_error: None,
+            last_checked: None,
+            next_retry: None,
+        }
+    }
+}
+
+/// サーバーの状態（一覧表示・API用）
+#[derive(Debug, Clone, Serialize)]
+pub struct MCPServerStatus {
+    pub name: String,
+    pub transport: String,
+    pub enabled: bool,
+    pub description: String,
+    /// 登録中のツール数
+    pub tools: usize,
+    #[serde(flatten)]
+    pub health: ServerHealth,
+}
+
+/// 連続失敗回数に応じた再起動までの待ち時間（base × 2^(n-1)、上限 max）
+fn restart_backoff(failures: u32, base: Duration, max: Duration) -> Duration {
+    let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+    base.saturating_mul(factor).min(max)
+}
+
+/// MCPサーバーマネージャー
+pub struct MCPManager {
+    client: Arc<MCPClient>,
+    tool_manager: SharedToolManager,
+    health: RwLock<HashMap<String, ServerHealth>>,
+    /// ツールマネージャーに登録したMCPツール（ツール名 → サーバー名、衝突で登録できなかったものは含まない）
+    registered: RwLock<HashMap<String, String>>,
+}
+
+impl MCPManager {
+    /// `registered` は起動時に登録できたMCPツール
+    pub fn new(
+        client: Arc<MCPClient>,
+        tool_manager: SharedToolManager,
+        registered: Vec<MCPToolDefinition>,
+    ) -> Self {
+        Self {
+            client,
+            tool_manager,
+            health: RwLock::new(HashMap::new()),
+            registered: RwLock::new(registered.into_iter().map(|t| (t.name, t.server_name)).collect()),
+        }
+    }
+
+    /// MCPクライアントを取得
+    pub fn client(&self) -> &Arc<MCPClient> {
+        &self.client
+    }
+
+    /// 全サーバーの状態を取得
+    pub async fn statuses(&self) -> Vec<MCPServerStatus> {
+        let health = self.health.read().await.clone();
+        let mut statuses = Vec::new();
+        for server in self.client.list_servers() {
+            let health = match health.get(&server.name) {
+                Some(h) => h.clone(),
+                None if !server.enabled => ServerHealth::new(HealthState::Disabled),
+                None if self.client.is_connected(&server.name).await => ServerHealth::new(HealthState::Healthy),
+                None => ServerHealth::new(HealthState::Idle),
+            };
+            statuses.push(MCPServerStatus {
+                tools: self.client.list_server_tools(&server.name).await.len(),
+                name: server.name,
+                transport: server.transport.as_str().to_string(),
+                enabled: server.enabled,
+                description: server.description,
+                health,
+            });
+        }
+        statuses
+    }
+
+    /// ツール一覧を取得（サーバー指定時はそのサーバーのみ）
+    pub async fn tools(&self, server_name: Option<&str>) -> Result<Vec<MCPToolDefinition>, MCPManagerError> {
+        match server_name {
+            Some(name) => {
+                self.server(name)?;
+                Ok(self.client.list_server_tools(name).await)
+            }
+            None => Ok(self.client.list_all_tools().await),
+        }
+    }
+
+    /// サーバーを有効化して接続し、ツールを登録
+    pub async fn enable(&self, server_name: &str) -> Result<usize, MCPManagerError> {
+        self.server(server_name)?;
+        self.client.set_server_enabled(server_name, true);
+        self.save_config();
+        self.restart_inner(server_name, false).await
+    }
+
+    /// サーバーを無効化し、接続を閉じてツールの登録を解除
+    pub async fn disable(&self, server_name: &str) -> Result<(), MCPManagerError> {
+        self.server(server_name)?;
+        self.client.set_server_enabled(server_name, false);
+        self.save_config();
+
+        let old_tools = self.client.list_server_tools(server_name).await;
+        self.client.close_server(server_name).await;
+        self.sync_tools(&old_tools, &[]).await;
+
+        let mut health = self.health.write().await;
+        let entry = health
+            .entry(server_name.to_string())
+            .or_insert_with(|| ServerHealth::new(HealthState::Disabled));
+        entry.state = HealthState::Disabled;
+        entry.consecutive_failures = 0;
+        entry.next_retry = None;
+        info!("Disabled MCP server {}", server_name);
+        Ok(())
+    }
+
+    /// サーバーに再接続（stdio は子プロセスを起動し直す）してツールを更新
+    pub async fn restart(&self, server_name: &str) -> Result<usize, MCPManagerError> {
+        let server = self.server(server_name)?;
+        if !server.enabled {
+            return Err(MCPManagerError::Disabled(server_name.to_string()));
+        }
+        self.restart_inner(server_name, true).await
+    }
+
+    /// 再接続の共通処理（結果をヘルス情報に反映）
+    async fn restart_inner(&self, server_name: &str, count_restart: bool) -> Result<usize, MCPManagerError> {
+        let old_tools = self.client.list_server_tools(server_name).await;
+        let result = self.client.restart_server(server_name).await;
+        let now = Utc::now();
+
+        let mut health = self.health.write().await;
+        let entry = health
+            .entry(server_name.to_string())
+            .or_insert_with(|| ServerHealth::new(HealthState::Idle));
+        entry.last_checked = Some(now);
+
+        match result {
+            Ok(new_tools) => {
+                entry.state = HealthState::Healthy;
+                entry.consecutive_failures = 0;
+                entry.last_error = None;
+                entry.next_retry = None;
+                if count_restart {
+                    entry.restarts += 1;
+                }
+                drop(health);
+                self.sync_tools(&old_tools, &new_tools).await;
+                info!("MCP server {} is up with {} tools", server_name, new_tools.len());
+                Ok(new_tools.len())
+            }
+            Err(e) => {
+                let (base, max) = self.backoff_settings();
+                entry.state = HealthState::Unhealthy;
+                entry.consecutive_failures += 1;
+                entry.last_error = Some(e.to_string());
+                let wait = restart_backoff(entry.consecutive_failures, base, max);
+                entry.next_retry = chrono::Duration::from_std(wait).ok().map(|d| now + d);
+                warn!(
+                    "Failed to start MCP server {} ({} consecutive failures, retry in {}s): {}",
+                    server_name,
+                    entry.consecutive_failures,
+                    wait.as_secs(),
+                    e
+                );
+                Err(MCPManagerError::Failed(server_name.to_string(), e.to_string()))
+            }
+        }
+    }
+
+    /// 全サーバーのヘルスチェックを1回実行
+    pub async fn check_health(&self) {
+        self.client.cleanup_idle_connections().await;
+
+        for server in self.client.list_servers() {
+            let now = Utc::now();
+            let previous = self.health.read().await.get(&server.name).cloned();
+
+            if !server.enabled {
+                let mut health = self.health.write().await;
+                let entry = health
+                    .entry(server.name.clone())
+                    .or_insert_with(|| ServerHealth::new(HealthState::Disabled));
+                entry.state = HealthState::Disabled;
+                entry.next_retry = None;
+                continue;
+            }
+
+            let needs_restart = match self.client.ping_server(&server.name).await {
+                Some(Ok(())) => {
+                    let mut health = self.health.write().await;
+                    let entry = health
+                        .entry(server.name.clone())
+                        .or_insert_with(|| ServerHealth::new(HealthState::Healthy));
+                    entry.state = HealthState::Healthy;
+                    entry.consecutive_failures = 0;
+                    entry.last_checked = Some(now);
+                    entry.next_retry = None;
+                    false
+                }
+                Some(Err(e)) => {
+                    warn!("MCP server {} is not responding: {}", server.name, e);
+                    let mut health = self.health.write().await;
+                    let entry = health
+                        .entry(server.name.clone())
+                        .or_insert_with(|| ServerHealth::new(HealthState::Unhealthy));
+                    entry.state = HealthState::Unhealthy;
+                    entry.last_error = Some(e.to_string());
+                    entry.last_checked = Some(now);
+                    true
+                }
+                // 未接続: 起動時から一度も接続できていない、または再起動待ちなら接続を試みる
+                None => match &previous {
+                    None => true,
+                    Some(h) if h.state == HealthState::Unhealthy => true,
+                    Some(_) => {
+                        let mut health = self.health.write().await;
+                        if let Some(entry) = health.get_mut(&server.name) {
+                            entry.state = HealthState::Idle;
+                            entry.last_checked = Some(now);
+                        }
+                        false
+                    }
+                },
+            };
+
+            let due = previous
+                .as_ref()
+                .and_then(|h| h.next_retry)
+                .is_none_or(|next_retry| next_retry <= now);
+            if needs_restart && due {
+                info!("Restarting MCP server {} (health check)", server.name);
+                let count_restart = previous.is_some();
+                let _ = self.restart_inner(&server.name, count_restart).await;
+            }
+        }
+    }
+
+    /// ヘルスモニターをバックグラウンドで起動（間隔0なら起動しない）
+    pub fn spawn_health_monitor(self: &Arc<Self>) -> Option<JoinHandle<()>> {
+        let interval_seconds = self.client.config().settings.health_check_interval_seconds;
+        if interval_seconds == 0 {
+            info!("MCP health monitor is disabled");
+            return None;
+        }
+
+        let manager = Arc::clone(self);
+        info!("Starting MCP health monitor (interval: {}s)", interval_seconds);
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(interval_seconds));
+            // 起動直後は接続済みなので最初の tick は捨てる
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                manager.check_health().await;
+            }
+        }))
+    }
+
+    /// サーバー設定を取得（存在しなければ NotFound）
+    fn server(&self, server_name: &str) -> Result<crate::mcp_client::MCPServerConfig, MCPManagerError> {
+        self.client
+            .get_server(server_name)
+            .ok_or_else(|| MCPManagerError::NotFound(server_name.to_string()))
+    }
+
+    /// バックオフの基準間隔と上限
+    fn backoff_settings(&self) -> (Duration, Duration) {
+        let settings = self.client.config().settings;
+        let base = Duration::from_secs(settings.health_check_interval_seconds.max(1));
+        let max = Duration::from_secs(settings.max_restart_backoff_seconds.max(1));
+        (base, max)
+    }
+
+    /// 設定ファイルに保存（失敗してもメモリ上の変更は維持）
+    fn save_config(&self) {
+        if let Err(e) = self.client.save() {
+            error!("Failed to save MCP config: {}", e);
+        }
+    }
+
+    /// ツールマネージャーの登録を新しいツール一覧に合わせる
+    ///
+    /// 解除するのは自分が登録したツールだけで、衝突相手の既存ツールには触れません。
+    async fn sync_tools(&self, old_tools: &[MCPToolDefinition], new_tools: &[MCPToolDefinition]) {
+        let mut tool_manager = self.tool_manager.write().await;
+        let mut registered = self.registered.write().await;
+        for tool in old_tools {
+            if registered.get(&tool.name) == Some(&tool.server_name) {
+                registered.remove(&tool.name);
+                tool_manager.unregister(&tool.name);
+            }
+        }
+        for tool in new_tools {
+            if tool_manager.register(MCPToolAdapter::new(self.client.clone(), tool.clone())) {
+                registered.insert(tool.name.clone(), tool.server_name.clone());
+            } else {
+                warn!(
+                    "MCP tool {} from server {} was not registered: name collides with an existing tool",
+                    tool.name, tool.server_name
+                );
+            }
+        }
+
+        // リソース読み取りツールは有効なサーバーがある間だけ登録
+        let has_enabled = !self.client.list_enabled_servers().is_empty();
+        if has_enabled && tool_manager.get(READ_RESOURCE_TOOL).is_none() {
+            tool_manager.register(MCPReadResourceTool::new(self.client.clone()));
+        } else if !has_enabled {
+            tool_manager.unregister(READ_RESOURCE_TOOL);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp_client::{MCPConfig, MCPServerConfig};
+    use crate::tool::ToolManager;
+    use tempfile::TempDir;
+
+    /// 一時ディレクトリの設定ファイルから読み込んだマネージャーを作成（保存先もそのファイル）
+    fn manager_with(servers: Vec<MCPServerConfig>) -> (MCPManager, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("mcp.json");
+        let config = MCPConfig {
+            servers,
+            ..Default::default()
+        };
+        std::fs::write(&config_path, serde_json::to_string(&config).unwrap()).unwrap();
+        let client = MCPClient::load(&config_path).unwrap();
+        let manager = MCPManager::new(Arc::new(client), Arc::new(RwLock::new(ToolManager::new())), Vec::new());
+        (manager, temp_dir)
+    }
+
+    fn stdio_server(name: &str, command: &str, enabled: bool) -> MCPServerConfig {
+        MCPServerConfig {
+            name: name.to_string(),
+            command: command.to_string(),
+            enabled,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_restart_backoff() {
+        let base = Duration::from_secs(30);
+        let max = Duration::from_secs(300);
+        assert_eq!(restart_backoff(1, base, max), Duration::from_secs(30));
+        assert_eq!(restart_backoff(2, base, max), Duration::from_secs(60));
+        assert_eq!(restart_backoff(3, base, max), Duration::from_secs(120));
+        assert_eq!(restart_backoff(5, base, max), Duration::from_secs(300));
+        assert_eq!(restart_backoff(100, base, max), Duration::from_secs(300));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_server() {
+        let (manager, _temp_dir) = manager_with(vec![]);
+        assert!(matches!(manager.enable("missing").await, Err(MCPManagerError::NotFound(_))));
+        assert!(matches!(manager.restart("missing").await, Err(MCPManagerError::NotFound(_))));
+        assert!(matches!(manager.tools(Some("missing")).await, Err(MCPManagerError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_disable_and_restart_disabled() {
+        let (manager, temp_dir) = manager_with(vec![stdio_server("local", "true", true)]);
+        manager.disable("local").await.unwrap();
+
+        assert!(!manager.client().list_servers()[0].enabled);
+        let saved = MCPClient::load(temp_dir.path().join("mcp.json")).unwrap();
+        assert!(!saved.list_servers()[0].enabled);
+        assert!(matches!(manager.restart("local").await, Err(MCPManagerError::Disabled(_))));
+        let statuses = manager.statuses().await;
+        assert_eq!(statuses[0].health.state, HealthState::Disabled);
+    }
+
+    #[tokio::test]
+    async fn test_health_check_backoff_on_failure() {
+        let (manager, _temp_dir) = manager_with(vec![stdio_server("broken", "/nonexistent/mcp-server", true)]);
+
+        // 一度も接続できていないサーバーは接続を試み、失敗したらバックオフ
+        manager.check_health().await;
+        let status = manager.statuses().await.remove(0);
+        assert_eq!(status.health.state, HealthState::Unhealthy);
+        assert_eq!(status.health.consecutive_failures, 1);
+        assert!(status.health.last_error.is_some());
+        let next_retry = status.health.next_retry.expect("retry should be scheduled");
+        assert!(next_retry > Utc::now());
+
+        // バックオフ中は再試行しない
+        manager.check_health().await;
+        let status = manager.statuses().await.remove(0);
+        assert_eq!(status.health.consecutive_failures, 1);
+        assert_eq!(status.health.next_retry, Some(next_retry));
+    }
+
+    #[tokio::test]
+    async fn test_sync_tools_keeps_colliding_tool() {
+        let (manager, _temp_dir) = manager_with(vec![]);
+        let definition = |server: &str| MCPToolDefinition {
+            name: "mcp__a__search".to_string(),
+            tool_name: "search".to_string(),
+            description: format!("search on {}", server),
+            input_schema: serde_json::json!({"type": "object"}),
+            server_name: server.to_string(),
+        };
+
+        // 先に登録したサーバー a のツールが残り、b のツールは拒否される
+        manager.sync_tools(&[], &[definition("a")]).await;
+        manager.sync_tools(&[], &[definition("b")]).await;
+        // b を外しても a のツールは解除されない
+        manager.sync_tools(&[definition("b")], &[]).await;
+
+        let tool_manager = manager.tool_manager.read().await;
+        let tool = tool_manager.get("mcp__a__search").expect("tool from server a should remain");
+        assert_eq!(tool.description(), "search on a");
+    }
+}
@@ -0,0 +1,701 @@
+//! MCPサーバーモード
+//!
+//! Claude Desktop などの外部エージェントから、ボットのメモリ・スケジュール・ツールを
+//! MCP（Streamable HTTP）で利用できるようにします。HTTP APIの `/api/mcp-server` に
+//! マウントされるため、認証とレートリミットはHTTP APIと共通です。
+
+use crate::memory_store::{Memory, MemoryStore, NewMemory};
+use crate::tool::{SharedToolManager, Tool as _, ToolContext, ToolError, ToolResult};
+use crate::tools::{ScheduleAccess, ScheduleCreateTool, ScheduleListTool};
+use rmcp::model::{
+    AnnotateAble, CallToolRequestParams, CallToolResult, Content, Implementation, ListResourcesResult, ListToolsResult,
+    PaginatedRequestParams, RawResource, ReadResourceRequestParams, ReadResourceResult, ResourceContents,
+    ServerCapabilities, ServerInfo, Tool,
+};
+use rmcp::service::RequestContext;
+use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
+use rmcp::transport::streamable_http_server::{StreamableHttpServerConfig, StreamableHttpService};
+use rmcp::{ErrorData, RoleServer, ServerHandler};
+use serde_json::{json, Map, Value as JsonValue};
+use std::env;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// メモリリソースのURI接頭辞
+const MEMORY_URI_PREFIX: &str = "memory://";
+/// memory_search の既定件数
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+/// ツールの実行コンテキストに使うユーザー名
+const MCP_USER_NAME: &str = "mcp";
+
+/// MCPサーバー自身が提供するツール名（ToolManager のツールより優先）
+const BUILTIN_TOOLS: [&str; 4] = ["memory_search", "memory_add", "schedule_list", "schedule_create"];
+
+/// MCPサーバーの設定（環境変数から読み込み）
+#[derive(Debug, Clone, Default)]
+pub struct MCPServerSettings {
+    /// 公開する ToolManager のツール名
+    pub tools: Vec<String>,
+    /// メモリ操作・ツール実行に使うユーザーID（HTTP APIと同じく既定は0）
+    pub user_id: u64,
+    /// schedule_create で投稿先にできるチャンネルID（空なら作成不可）
+    pub schedule_channels: Vec<u64>,
+}
+
+impl MCPServerSettings {
+    /// `MCP_SERVER_ENABLED` が有効なら設定を読み込む（無効なら None）
+    pub fn from_env() -> Option<Self> {
+        let enabled = env::var("MCP_SERVER_ENABLED")
+            .map(|v| v.to_lowercase() == "true" || v == "1")
+            .unwrap_or(false);
+        if !enabled {
+            return None;
+        }
+
+        let tools = env::var("MCP_SERVER_TOOLS")
+            .map(|v| parse_tool_list(&v))
+            .unwrap_or_default();
+        let user_id = env::var("MCP_SERVER_USER_ID")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let schedule_channels = env::var("MCP_SERVER_SCHEDULE_CHANNELS")
+            .map(|v| parse_channel_list(&v))
+            .unwrap_or_default();
+        Some(Self { tools, user_id, schedule_channels })
+    }
+}
+
+/// カンマ区切りのチャンネルIDをパース（数値でないものは除外）
+fn parse_channel_list(input: &str) -> Vec<u64> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .filter_map(|id| match id.parse() {
+            Ok(id) => Some(id),
+            Err(_) => {
+                warn!("MCP_SERVER_SCHEDULE_CHANNELS: invalid channel ID {}, ignoring", id);
+                None
+            }
+        })
+        .collect()
+}
+
+/// カンマ区切りのツール名をパース（MCPサーバー自身のツールと重複するものは除外）
+fn parse_tool_list(input: &str) -> Vec<String> {
+    let mut tools: Vec<String> = Vec::new();
+    for name in input.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        if BUILTIN_TOOLS.contains(&name) {
+            warn!("MCP_SERVER_TOOLS: {} is provided by the MCP server itself, ignoring", name);
+        } else if !tools.iter().any(|t| t == name) {
+            tools.push(name.to_string());
+        }
+    }
+    tools
+}
+
+/// ボットをMCPサーバーとして公開するハンドラー
+#[derive(Clone)]
+pub struct BotMCPServer {
+    memory_store: Arc<MemoryStore>,
+    schedule_access: Arc<ScheduleAccess>,
+    tool_manager: SharedToolManager,
+    base_output_dir: String,
+    settings: Arc<MCPServerSettings>,
+}
+
+impl BotMCPServer {
+    /// schedule_access はスケジュールツールと共有する（権限・上限・保存を共通化）
+    pub fn new(
+        memory_store: Arc<MemoryStore>,
+        schedule_access: Arc<ScheduleAccess>,
+        tool_manager: SharedToolManager,
+        base_output_dir: String,
+        settings: MCPServerSettings,
+    ) -> Self {
+        Self {
+            memory_store,
+            schedule_access,
+            tool_manager,
+            base_output_dir,
+            settings: Arc::new(settings),
+        }
+    }
+
+    /// axum にマウントする Streamable HTTP サービスを作成
+    pub fn into_service(self) -> StreamableHttpService<Self, LocalSessionManager> {
+        info!(
+            "MCP server mode enabled ({} built-in tools, {} shared tools)",
+            BUILTIN_TOOLS.len(),
+            self.settings.tools.len()
+        );
+        StreamableHttpService::new(
+            move || Ok(self.clone()),
+            Default::default(),
+            StreamableHttpServerConfig::default(),
+        )
+    }
+
+    /// MCPサーバー自身のツール定義
+    fn builtin_tools(&self) -> Vec<Tool> {
+        // スケジュール作成の引数はスケジュールツールに投稿先チャンネルを加えたもの
+        let mut create_schema = ScheduleCreateTool::new(self.schedule_access.clone()).parameters_schema();
+        create_schema["properties"]["channel_id"] = json!({
+            "type": "string",
+            "description": "Discord channel ID to post to (must be allowed by the bot configuration)"
+        });
+        create_schema["required"] = json!(["prompt", "channel_id"]);
+
+        vec![
+            tool(
+                "memory_search",
+                "Search the bot's long-term memories by keyword.",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {"type": "string", "description": "Keyword to search for"},
+                        "limit": {"type": "integer", "description": "Maximum number of results (default: 10)"}
+                    },
+                    "required": ["query"]
+                }),
+            ),
+            tool(
+                "memory_add",
+                "Save a new long-term memory for the bot.",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "content": {"type": "string", "description": "Content to remember"},
+                        "category": {"type": "string", "description": "Category (default: general)"},
+                        "tags": {"type": "array", "items": {"type": "string"}, "description": "Tags"}
+                    },
+                    "required": ["content"]
+                }),
+            ),
+            tool(
+                "schedule_list",
+                "List the scheduled tasks owned by the MCP server user.",
+                json!({"type": "object", "properties": {}}),
+            ),
+            tool(
+                "schedule_create",
+                "Schedule a prompt to be run later on behalf of the MCP server user. Specify exactly one of: \
+                 run_at (one-shot at a time), delay (one-shot after a delay) or cron (recurring, evaluated in UTC). \
+                 When it fires, the prompt is sent to the bot and the answer is posted to the Discord channel.",
+                create_schema,
+            ),
+        ]
+    }
+
+    /// MCPサーバー自身のツールを実行（該当しなければ None）
+    async fn call_builtin(&self, name: &str, args: &Map<String, JsonValue>) -> Option<Result<String, String>> {
+        let result = match name {
+            "memory_search" => self.memory_search(args),
+            "memory_add" => self.memory_add(args),
+            "schedule_list" => self.schedule_list().await,
+            "schedule_create" => self.schedule_create(args).await,
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    fn memory_search(&self, args: &Map<String, JsonValue>) -> Result<String, String> {
+        let query = string_arg(args, "query").ok_or("query is required")?;
+        let limit = args
+            .get("limit")
+            .and_then(JsonValue::as_u64)
+            .map(|l| l as usize)
+            .unwrap_or(DEFAULT_SEARCH_LIMIT);
+        let memories = self
+            .memory_store
+            .search_memories(self.settings.user_id, query)
+            .map_err(|e| e.to_string())?;
+        if memories.is_empty() {
+            return Ok(format!("No memories found for '{}'", query));
+        }
+        let lines: Vec<String> = memories.iter().take(limit).map(format_memory).collect();
+        Ok(lines.join("\n"))
+    }
+
+    fn memory_add(&self, args: &Map<String, JsonValue>) -> Result<String, String> {
+        let content = string_arg(args, "content").ok_or("content is required")?;
+        let tags = args.get("tags").and_then(JsonValue::as_array).map(|tags| {
+            tags.iter()
+                .filter_map(JsonValue::as_str)
+                .map(String::from)
+                .collect()
+        });
+        let memory = self
+            .memory_store
+            .add_memory(NewMemory {
+                user_id: self.settings.user_id,
+                content: content.to_string(),
+                category: string_arg(args, "category").map(String::from),
+                tags,
+                ..Default::default()
+            })
+            .map_err(|e| e.to_string())?;
+        Ok(format!("Saved memory #{} ({}{})", memory.id, MEMORY_URI_PREFIX, memory.id))
+    }
+
+    async fn schedule_list(&self) -> Result<String, String> {
+        // MCP クライアントの代理ユーザーのスケジュールのみ（Todo リマインダーは除く）
+        let context = self.tool_context(0);
+        tool_output(ScheduleListTool::new(self.schedule_access.clone()).execute(json!({}), &context).await)
+    }
+
+    async fn schedule_create(&self, args: &Map<String, JsonValue>) -> Result<String, String> {
+        // Discord のIDは JSON の数値精度を超えうるため文字列でも受け付ける
+        let channel_id = match args.get("channel_id") {
+            Some(JsonValue::String(s)) => s.trim().parse::<u64>().ok(),
+            Some(v) => v.as_u64(),
+            None => None,
+        }
+        .ok_or("channel_id must be a Discord channel ID")?;
+        if !self.settings.schedule_channels.contains(&channel_id) {
+            return Err(format!(
+                "Channel {} is not allowed for MCP schedules (see MCP_SERVER_SCHEDULE_CHANNELS)",
+                channel_id
+            ));
+        }
+
+        // 検証・権限・上限・保存はスケジュールツールと共通
+        let mut params = args.clone();
+        params.remove("channel_id");
+        let context = self.tool_context(channel_id);
+        tool_output(
+            ScheduleCreateTool::new(self.schedule_access.clone())
+                .execute(JsonValue::Object(params), &context)
+                .await,
+        )
+    }
+
+    /// MCPサーバーのユーザーとして実行するツールコンテキスト
+    fn tool_context(&self, channel_id: u64) -> ToolContext {
+        ToolContext::new(
+            self.settings.user_id,
+            MCP_USER_NAME.to_string(),
+            channel_id,
+            self.base_output_dir.clone(),
+        )
+    }
+
+    /// ToolManager の公開ツールを実行
+    async fn call_shared_tool(&self, name: &str, args: JsonValue) -> Result<CallToolResult, ErrorData> {
+        if !self.settings.tools.iter().any(|t| t == name) {
+            return Err(ErrorData::invalid_params(format!("Unknown tool: {}", name), None));
+        }
+        let tool = self
+            .tool_manager
+            .read()
+            .await
+            .get(name)
+            .ok_or_else(|| ErrorData::invalid_params(format!("Unknown tool: {}", name), None))?;
+
+        let context = self.tool_context(0);
+        info!("MCP server: executing tool {}", name);
+        Ok(match tool_output(tool.execute(args, &context).await) {
+            Ok(text) => CallToolResult::success(vec![Content::text(text)]),
+            Err(message) => CallToolResult::error(vec![Content::text(message)]),
+        })
+    }
+
+    /// URI からメモリを取得
+    fn find_memory(&self, uri: &str) -> Result<Memory, ErrorData> {
+        let not_found = || ErrorData::resource_not_found(format!("Resource not found: {}", uri), None);
+        let id = uri
+            .strip_prefix(MEMORY_URI_PREFIX)
+            .and_then(|id| id.parse::<i64>().ok())
+            .ok_or_else(not_found)?;
+        self.memory_store
+            .get_all_memories(self.settings.user_id)
+            .map_err(|e| ErrorData::internal_error(e.to_string(), None))?
+            .into_iter()
+            .find(|m| m.id == id)
+            .ok_or_else(not_found)
+    }
+}
+
+impl ServerHandler for BotMCPServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder().enable_tools().enable_resources().build(),
+            server_info: Implementation {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                ..Default::default()
+            },
+            instructions: Some(
+                "Access the Discord bot's long-term memories (also available as memory:// resources), \
+                 scheduled tasks and selected tools."
+                    .to_string(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        let mut tools = self.builtin_tools();
+        let tool_manager = self.tool_manager.read().await;
+        for name in &self.settings.tools {
+            match tool_manager.get(name) {
+                Some(shared) => tools.push(tool(name, shared.description(), shared.parameters_schema())),
+                None => warn!("MCP_SERVER_TOOLS: tool {} is not registered", name),
+            }
+        }
+        Ok(ListToolsResult::with_all_items(tools))
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let args = request.arguments.unwrap_or_default();
+        if let Some(result) = self.call_builtin(&request.name, &args).await {
+            return Ok(match result {
+                Ok(text) => CallToolResult::success(vec![Content::text(text)]),
+                Err(message) => CallToolResult::error(vec![Content::text(message)]),
+            });
+        }
+        self.call_shared_tool(&request.name, JsonValue::Object(args)).await
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        let memories = self
+            .memory_store
+            .get_all_memories(self.settings.user_id)
+            .map_err(|e| ErrorData::internal_error(e.to_string(), None))?;
+        let resources = memories
+            .iter()
+            .map(|m| {
+                let mut resource = RawResource::new(format!("{}{}", MEMORY_URI_PREFIX, m.id), memory_title(m));
+                resource.description = Some(format!("category: {}", m.category));
+                resource.mime_type = Some("text/plain".to_string());
+                resource.no_annotation()
+            })
+            .collect();
+        Ok(ListResourcesResult::with_all_items(resources))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        let memory = self.find_memory(&request.uri)?;
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::text(memory.content, request.uri)],
+        })
+    }
+}
+
+/// ツール定義を作成
+fn tool(name: &str, description: &str, schema: JsonValue) -> Tool {
+    let schema = schema.as_object().cloned().unwrap_or_default();
+    Tool::new(name.to_string(), description.to_string(), Arc::new(schema))
+}
+
+/// ツールの実行結果を本文とエラーに振り分け
+fn tool_output(result: Result<ToolResult, ToolError>) -> Result<String, String> {
+    match result {
+        Ok(result) if result.is_error => Err(result.output),
+        Ok(result) => Ok(result.output),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// 文字列引数を取得（空文字は None）
+fn string_arg<'a>(args: &'a Map<String, JsonValue>, name: &str) -> Option<&'a str> {
+    args.get(name)
+        .and_then(JsonValue::as_str)
+        .filter(|s| !s.trim().is_empty())
+}
+
+/// メモリを1行に整形
+fn format_memory(memory: &Memory) -> String {
+    let mut line = format!("- #{} [{}] {}", memory.id, memory.category, memory.content);
+    if !memory.tags.is_empty() {
+        line.push_str(&format!(" (tags: {})", memory.tags.join(", ")));
+    }
+    line
+}
+
+/// リソース名（内容の先頭50文字）
+fn memory_title(memory: &Memory) -> String {
+    let first_line = memory.content.lines().next().unwrap_or_default();
+    if first_line.chars().count() > 50 {
+        format!("{}…", first_line.chars().take(50).collect::<String>())
+    } else {
+        first_line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::permission::PermissionManager;
+    use crate::schedule_store::ScheduleStore;
+    use crate::scheduler::Scheduler;
+    use crate::tool::ToolManager;
+    use tokio::sync::RwLock;
+
+    fn server(tools: &[&str]) -> BotMCPServer {
+        let dir = std::env::temp_dir().join("cc-bot-test");
+        BotMCPServer::new(
+            Arc::new(MemoryStore::new().unwrap()),
+            schedule_access(&dir.to_string_lossy(), 10),
+            Arc::new(RwLock::new(ToolManager::new())),
+            "/tmp/cc-bot-test".to_string(),
+            MCPServerSettings {
+                tools: tools.iter().map(|t| t.to_string()).collect(),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// チャンネル1だけにスケジュールを作成できる、ユーザーあたり2件までのサーバー
+    fn server_for(tools: &[&str], user_id: u64, dir: &tempfile::TempDir) -> BotMCPServer {
+        BotMCPServer::new(
+            Arc::new(MemoryStore::new().unwrap()),
+            schedule_access(&dir.path().to_string_lossy(), 2),
+            Arc::new(RwLock::new(ToolManager::new())),
+            "/tmp/cc-bot-test".to_string(),
+            MCPServerSettings {
+                tools: tools.iter().map(|t| t.to_string()).collect(),
+                user_id,
+                schedule_channels: vec![1],
+            },
+        )
+    }
+
+    fn schedule_access(data_dir: &str, max_per_user: usize) -> Arc<ScheduleAccess> {
+        Arc::new(
+            ScheduleAccess::new(
+                Arc::new(Scheduler::new()),
+                Arc::new(RwLock::new(ScheduleStore::new())),
+                Arc::new(RwLock::new(PermissionManager::new())),
+            )
+            .with_data_dir(data_dir)
+            .with_max_per_user(max_per_user),
+        )
+    }
+
+    fn args(value: JsonValue) -> Map<String, JsonValue> {
+        value.as_object().cloned().unwrap()
+    }
+
+    #[test]
+    fn test_parse_tool_list() {
+        assert_eq!(
+            parse_tool_list("web_fetch, memory_add ,web_fetch,,read_file"),
+            vec!["web_fetch", "read_file"]
+        );
+        assert!(parse_tool_list("").is_empty());
+        assert_eq!(parse_channel_list("1, 22,abc,,333"), vec![1, 22, 333]);
+    }
+
+    #[tokio::test]
+    async fn test_memory_tools_and_resources() {
+        let server = server(&[]);
+        let saved = server
+            .call_builtin("memory_add", &args(json!({"content": "Rust is fun\nsecond line", "tags": ["lang"]})))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(saved.contains("memory://"));
+
+        let found = server
+            .call_builtin("memory_search", &args(json!({"query": "Rust"})))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(found.contains("Rust is fun"));
+        assert!(found.contains("tags: lang"));
+
+        let memory = server.memory_store.get_all_memories(0).unwrap().remove(0);
+        assert_eq!(memory_title(&memory), "Rust is fun");
+        let uri = format!("{}{}", MEMORY_URI_PREFIX, memory.id);
+        assert_eq!(server.find_memory(&uri).unwrap().content, memory.content);
+        assert!(server.find_memory("memory://999999").is_err());
+        assert!(server.find_memory("file:///etc/passwd").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_schedule_create_validation() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let server = server_for(&[], 42, &dir);
+        let err = server
+            .call_builtin("schedule_create", &args(json!({"cron": "invalid", "prompt": "hi", "channel_id": "1"})))
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(err.contains("cron"), "{}", err);
+
+        let err = server
+            .call_builtin("schedule_create", &args(json!({"cron": "0 0 9 * * *", "prompt": "hi"})))
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(err.contains("channel_id"));
+
+        // 許可されていないチャンネルには作成できない
+        let err = server
+            .call_builtin("schedule_create", &args(json!({"cron": "0 0 9 * * *", "prompt": "hi", "channel_id": "2"})))
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(err.contains("not allowed"));
+
+        // プロンプトの長さはスケジュールツールと同じ上限
+        let long = "x".repeat(2001);
+        let err = server
+            .call_builtin("schedule_create", &args(json!({"delay": "1h", "prompt": long, "channel_id": "1"})))
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(err.contains("too long"));
+        assert_eq!(server.schedule_list().await.unwrap(), "You have no schedules.");
+
+        // ユーザー不明（MCP_SERVER_USER_ID 未設定）では作成できない
+        let server = server_for(&[], 0, &dir);
+        let err = server
+            .call_builtin("schedule_create", &args(json!({"delay": "1h", "prompt": "hi", "channel_id": "1"})))
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(err.contains("on behalf of a user"));
+    }
+
+    #[tokio::test]
+    async fn test_schedule_create_uses_schedule_tool() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let server = server_for(&[], 42, &dir);
+        for params in [
+            json!({"cron": "0 9 * * *", "prompt": "daily report", "channel_id": "1"}),
+            json!({"delay": "2h", "prompt": "one-shot", "channel_id": 1}),
+        ] {
+            let created = server.call_builtin("schedule_create", &args(params)).await.unwrap().unwrap();
+            assert!(created.starts_with("Scheduled"), "{}", created);
+        }
+        let stored = ScheduleStore::load(&dir.path().to_string_lossy()).await.unwrap();
+        assert_eq!(stored.tasks.len(), 2);
+        assert!(stored.tasks.iter().all(|t| t.user_id == Some(42) && t.channel_id == 1));
+
+        // ユーザーごとの上限もスケジュールツールと共通
+        let err = server
+            .call_builtin("schedule_create", &args(json!({"delay": "1h", "prompt": "third", "channel_id": "1"})))
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(err.contains("limit"));
+
+        let list = server.schedule_list().await.unwrap();
+        assert!(list.contains("cron `0 0 9 * * *`") && list.contains("daily report"));
+        assert!(list.contains("once") && list.contains("one-shot"));
+    }
+
+    #[tokio::test]
+    async fn test_shared_tool_must_be_listed() {
+        let server = server(&["web_fetch"]);
+        assert!(server.call_builtin("bash", &Map::new()).await.is_none());
+        assert!(server.call_shared_tool("bash", json!({})).await.is_err());
+        // 公開設定にあっても未登録なら実行できない
+        assert!(server.call_shared_tool("web_fetch", json!({})).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_streamable_http_roundtrip() {
+        use crate::mcp_client::{MCPClient, MCPServerConfig, MCPTransport};
+
+        let server = server(&[]);
+        let memory_store = server.memory_store.clone();
+        let router = axum::Router::new().nest_service("/mcp-server", server.into_service());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, router).await;
+        });
+
+        let client = MCPClient::new();
+        client.add_server(MCPServerConfig {
+            name: "bot".to_string(),
+            transport: MCPTransport::StreamableHttp,
+            url: Some(format!("http://{}/mcp-server", addr)),
+            enabled: true,
+            ..Default::default()
+        });
+
+        let tools = client.refresh_tools_from_server("bot").await.unwrap();
+        let names: Vec<&str> = tools.iter().map(|t| t.tool_name.as_str()).collect();
+        assert_eq!(names, BUILTIN_TOOLS);
+
+        let args = json!({"content": "remember me"}).as_object().cloned();
+        client.execute_tool("bot", "memory_add", args).await.unwrap();
+        assert_eq!(memory_store.get_all_memories(0).unwrap().len(), 1);
+
+        let resources = client.list_resources("bot").await.unwrap();
+        assert_eq!(resources.len(), 1);
+        let result = client.read_resource("bot", &resources[0].uri).await.unwrap();
+        match &result.contents[0] {
+            ResourceContents::TextResourceContents { text, .. } => assert_eq!(text, "remember me"),
+            other => panic!("unexpected contents: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_api_mount_requires_api_key() {
+        use crate::api::{create_router, ApiState};
+        use crate::llm::MockLLMClient;
+        use crate::rate_limiter::RateLimiter;
+        use crate::session::SessionManager;
+        use tokio::sync::Mutex;
+
+        std::env::set_var("API_KEY", "mcp-server-test-key");
+        std::env::set_var("ALLOWED_ORIGINS", "http://localhost");
+        let server = server(&[]);
+        let state = ApiState {
+            glm_client: Arc::new(MockLLMClient::new("ok")),
+            session_manager: Arc::new(Mutex::new(SessionManager::default())),
+            scheduler: Arc::new(Scheduler::new()),
+            schedule_store: Arc::new(RwLock::new(ScheduleStore::new())),
+            memory_store: server.memory_store.clone(),
+            base_output_dir: "/tmp/cc-bot-test".to_string(),
+            rate_limiter: Arc::new(Mutex::new(RateLimiter::new())),
+            mcp_manager: None,
+            mcp_server: Some(server),
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/api/mcp-server", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, create_router(state)).await;
+        });
+
+        let client = reqwest::Client::new();
+        let initialize = json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {
+            "protocolVersion": "2025-03-26", "capabilities": {}, "clientInfo": {"name": "test", "version": "0"}}});
+        let send = |key: Option<&str>| {
+            let mut request = client
+                .post(&url)
+                .header("Accept", "application/json, text/event-stream")
+                .json(&initialize);
+            if let Some(key) = key {
+                request = request.bearer_auth(key);
+            }
+            request.send()
+        };
+
+        assert_eq!(send(None).await.unwrap().status(), reqwest::StatusCode::UNAUTHORIZED);
+        assert_eq!(send(Some("wrong")).await.unwrap().status(), reqwest::StatusCode::UNAUTHORIZED);
+        assert!(send(Some("mcp-server-test-key")).await.unwrap().status().is_success());
+    }
+}
@@ -0,0 +1,180 @@
+//! MCP HTTP+SSE クライアントトランスポート
+//!
+//! 旧仕様（2024-11-05）の HTTP+SSE トランスポートを実装します。
+//! GET で SSE ストリームを開き、最初の `endpoint` イベントで通知された URL に
+//! JSON-RPC メッセージを POST し、応答は SSE の `message` イベントとして受け取ります。
+//! rmcp 0.16 には SSE クライアントが含まれないため、`Transport` を直接実装しています。
+
+use futures::StreamExt;
+use reqwest::header::{HeaderMap, ACCEPT};
+use reqwest::Url;
+use rmcp::service::{RoleClient, RxJsonRpcMessage, TxJsonRpcMessage};
+use rmcp::transport::Transport;
+use sse_stream::SseStream;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// `endpoint` イベントを待つ最大時間
+const ENDPOINT_TIMEOUT: Duration = Duration::from_secs(30);
+/// 受信メッセージのバッファ数
+const CHANNEL_CAPACITY: usize = 64;
+
+/// SSE トランスポートのエラー
+#[derive(Debug, Error)]
+pub enum SseTransportError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+    #[error("SSE stream error: {0}")]
+    Stream(String),
+    #[error("Server did not send an endpoint event")]
+    MissingEndpoint,
+}
+
+/// HTTP+SSE クライアントトランスポート
+pub struct SseClientTransport {
+    client: reqwest::Client,
+    /// メッセージの送信先（`endpoint` イベントで通知された URL）
+    endpoint: Url,
+    headers: HeaderMap,
+    receiver: mpsc::Receiver<RxJsonRpcMessage<RoleClient>>,
+    reader: JoinHandle<()>,
+}
+
+impl SseClientTransport {
+    /// SSE ストリームに接続し、`endpoint` イベントを受け取るまで待つ
+    pub async fn connect(url: &str, headers: HeaderMap) -> Result<Self, SseTransportError> {
+        let sse_url = Url::parse(url).map_err(|e| SseTransportError::InvalidUrl(format!("{}: {}", url, e)))?;
+        let client = reqwest::Client::new();
+
+        let response = client
+            .get(sse_url.clone())
+            .headers(headers.clone())
+            .header(ACCEPT, "text/event-stream")
+            .send()
+            .await?
+            .error_for_status()?;
+        let mut events = SseStream::from_bytes_stream(response.bytes_stream());
+
+        // 最初の endpoint イベントで POST 先を受け取る
+        let endpoint = tokio::time::timeout(ENDPOINT_TIMEOUT, async {
+            while let Some(event) = events.next().await {
+                let event = event.map_err(|e| SseTransportError::Stream(format!("{:?}", e)))?;
+                if event.event.as_deref() == Some("endpoint") {
+                    return Ok(event.data.unwrap_or_default());
+                }
+            }
+            Err(SseTransportError::MissingEndpoint)
+        })
+        .await
+        .map_err(|_| SseTransportError::MissingEndpoint)??;
+        let endpoint = Self::resolve_endpoint(&sse_url, endpoint.trim())?;
+        debug!("MCP SSE endpoint: {}", endpoint);
+
+        let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+        let reader = tokio::spawn(async move {
+            while let Some(event) = events.next().await {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => {
+                        warn!("MCP SSE stream error: {:?}", e);
+                        break;
+                    }
+                };
+                // イベント名なし、または message のみを JSON-RPC メッセージとして扱う
+                if !matches!(event.event.as_deref(), None | Some("message")) {
+                    continue;
+                }
+                let Some(data) = event.data else { continue };
+                match serde_json::from_str::<RxJsonRpcMessage<RoleClient>>(&data) {
+                    Ok(message) => {
+                        if sender.send(message).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => warn!("Ignoring invalid MCP SSE message: {}", e),
+                }
+            }
+            debug!("MCP SSE stream closed");
+        });
+
+        Ok(Self {
+            client,
+            endpoint,
+            headers,
+            receiver,
+            reader,
+        })
+    }
+
+    /// endpoint を SSE の URL 基準で解決（別オリジンへの送信は拒否）
+    fn resolve_endpoint(sse_url: &Url, endpoint: &str) -> Result<Url, SseTransportError> {
+        let resolved = sse_url
+            .join(endpoint)
+            .map_err(|e| SseTransportError::InvalidUrl(format!("{}: {}", endpoint, e)))?;
+        if resolved.origin() != sse_url.origin() {
+            return Err(SseTransportError::InvalidUrl(format!(
+                "endpoint {} is not on the same origin as {}",
+                resolved, sse_url
+            )));
+        }
+        Ok(resolved)
+    }
+}
+
+impl Transport<RoleClient> for SseClientTransport {
+    type Error = SseTransportError;
+
+    fn send(
+        &mut self,
+        item: TxJsonRpcMessage<RoleClient>,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send + 'static {
+        let request = self
+            .client
+            .post(self.endpoint.clone())
+            .headers(self.headers.clone())
+            .json(&item);
+        async move {
+            request.send().await?.error_for_status()?;
+            Ok(())
+        }
+    }
+
+    fn receive(&mut self) -> impl std::future::Future<Output = Option<RxJsonRpcMessage<RoleClient>>> + Send {
+        self.receiver.recv()
+    }
+
+    async fn close(&mut self) -> Result<(), Self::Error> {
+        self.reader.abort();
+        Ok(())
+    }
+}
+
+impl Drop for SseClientTransport {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_endpoint() {
+        let base = Url::parse("http://localhost:8080/sse").unwrap();
+        assert_eq!(
+            SseClientTransport::resolve_endpoint(&base, "/message?sessionId=abc").unwrap().as_str(),
+            "http://localhost:8080/message?sessionId=abc"
+        );
+        assert_eq!(
+            SseClientTransport::resolve_endpoint(&base, "http://localhost:8080/messages").unwrap().as_str(),
+            "http://localhost:8080/messages"
+        );
+        assert!(SseClientTransport::resolve_endpoint(&base, "http://evil.example/collect").is_err());
+    }
+}
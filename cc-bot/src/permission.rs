@@ -15,6 +15,8 @@ pub enum Permission {
     FileWrite,
     /// スケジュール管理権限
     Schedule,
+    /// Discordへの書き込み権限（スレッド作成・リアクション・ピン留め）
+    DiscordWrite,
     /// 管理者権限
     Admin,
     /// スーパーユーザー権限（全権限を持ち、全チェックで最優先）
@@ -28,6 +30,7 @@ impl Permission {
             "fileread" | "file_read" => Some(Permission::FileRead),
             "filewrite" | "file_write" => Some(Permission::FileWrite),
             "schedule" => Some(Permission::Schedule),
+            "discordwrite" | "discord_write" => Some(Permission::DiscordWrite),
             "admin" => Some(Permission::Admin),
             "superuser" | "super_user" | "super-user" => Some(Permission::SuperUser),
             _ => None,
@@ -40,6 +43,7 @@ impl Permission {
             Permission::FileRead => "FileRead",
             Permission::FileWrite => "FileWrite",
             Permission::Schedule => "Schedule",
+            Permission::DiscordWrite => "DiscordWrite",
             Permission::Admin => "Admin",
             Permission::SuperUser => "SuperUser",
         }
@@ -275,6 +279,7 @@ impl PermissionManager {
             all_perms.insert(Permission::FileRead);
             all_perms.insert(Permission::FileWrite);
             all_perms.insert(Permission::Schedule);
+            all_perms.insert(Permission::DiscordWrite);
             all_perms.insert(Permission::Admin);
             all_perms.insert(Permission::SuperUser);
             return all_perms;
@@ -422,6 +427,11 @@ impl PermissionManager {
     pub fn get_admins(&self) -> &HashSet<u64> {
         &self.store.admins
     }
+
+    /// スーパーユーザー一覧を取得
+    pub fn get_super_users(&self) -> &HashSet<u64> {
+        &self.store.super_users
+    }
 }
 
 impl Default for PermissionManager {
@@ -448,6 +458,7 @@ mod tests {
         assert!(perms.contains(&Permission::FileRead));
         assert!(perms.contains(&Permission::FileWrite));
         assert!(perms.contains(&Permission::Schedule));
+        assert!(!perms.contains(&Permission::DiscordWrite));
         assert!(!perms.contains(&Permission::Admin));
     }
 
@@ -12,6 +12,17 @@ use uuid::Uuid;
 /// スケジュールID
 pub type ScheduleId = Uuid;
 
+/// タスクの種類
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskKind {
+    /// ユーザー・管理者が登録したスケジュール
+    #[default]
+    Schedule,
+    /// TODO の期限リマインダー（TODO ツールが管理する）
+    TodoReminder,
+}
+
 /// スケジュールされたタスク
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScheduledTask {
@@ -21,6 +32,18 @@ pub struct ScheduledTask {
     pub channel_id: u64,
     pub created_at: DateTime<Utc>,
     pub enabled: bool,
+    /// 1回だけ実行する場合の実行時刻（None なら cron 式で繰り返し）
+    #[serde(default)]
+    pub run_at: Option<DateTime<Utc>>,
+    /// 作成したユーザー（APIなどユーザー不明の場合は None）
+    #[serde(default)]
+    pub user_id: Option<u64>,
+    /// 作成元のサーバーID（DM・API・MCPサーバーから作成した場合は None）
+    #[serde(default)]
+    pub guild_id: Option<u64>,
+    /// タスクの種類
+    #[serde(default)]
+    pub kind: TaskKind,
 }
 
 impl ScheduledTask {
@@ -39,14 +62,78 @@ impl ScheduledTask {
             channel_id,
             created_at: Utc::now(),
             enabled: true,
+            run_at: None,
+            user_id: None,
+            guild_id: None,
+            kind: TaskKind::Schedule,
+        })
+    }
+
+    /// 指定時刻に1回だけ実行するタスクを作成
+    pub fn once(run_at: DateTime<Utc>, prompt: String, channel_id: u64) -> Result<Self, SchedulerError> {
+        if run_at <= Utc::now() {
+            return Err(SchedulerError::InvalidRunAt(format!("{} is in the past", run_at.to_rfc3339())));
+        }
+
+        Ok(Self {
+            id: Uuid::new_v4(),
+            cron_expression: String::new(),
+            prompt,
+            channel_id,
+            created_at: Utc::now(),
+            enabled: true,
+            run_at: Some(run_at),
+            user_id: None,
+            guild_id: None,
+            kind: TaskKind::Schedule,
         })
     }
 
+    /// 作成ユーザーを設定
+    pub fn with_user(mut self, user_id: u64) -> Self {
+        self.user_id = Some(user_id);
+        self
+    }
+
+    /// 作成元のサーバーを設定
+    pub fn with_guild(mut self, guild_id: Option<u64>) -> Self {
+        self.guild_id = guild_id;
+        self
+    }
+
+    /// タスクの種類を設定
+    pub fn with_kind(mut self, kind: TaskKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// 1回だけ実行するタスクか
+    pub fn is_one_shot(&self) -> bool {
+        self.run_at.is_some()
+    }
+
     /// 次回実行時刻を取得
     pub fn next_run(&self) -> Option<DateTime<Utc>> {
+        if let Some(run_at) = self.run_at {
+            return Some(run_at);
+        }
         let schedule = self.cron_expression.parse::<Schedule>().ok()?;
         schedule.upcoming(Utc).next()
     }
+
+    /// 次のチェックまで（`window` 秒以内）に実行すべきか
+    ///
+    /// 1回だけのタスクは、停止中などで実行時刻を過ぎていても実行する
+    fn is_due(&self, now: DateTime<Utc>, window: i64) -> bool {
+        match self.next_run() {
+            Some(next_run) if self.is_one_shot() => (next_run - now).num_seconds() <= window,
+            Some(next_run) => {
+                let diff = (next_run - now).num_seconds();
+                diff <= window && diff > 0
+            }
+            None => false,
+        }
+    }
 }
 
 /// スケジューラーエラー
@@ -55,6 +142,9 @@ pub enum SchedulerError {
     #[error("Invalid cron expression: {0}")]
     InvalidCronExpression(String),
 
+    #[error("Invalid run time: {0}")]
+    InvalidRunAt(String),
+
     #[error("Schedule not found: {0}")]
     NotFound(String),
 
@@ -146,31 +236,35 @@ impl Scheduler {
             loop {
                 interval.tick().await;
 
-                let tasks = self.tasks.lock().await;
+                let mut tasks = self.tasks.lock().await;
                 let now = Utc::now();
+                let mut finished = Vec::new();
 
                 for task in tasks.iter() {
                     if !task.enabled {
                         continue;
                     }
 
-                    if let Some(next_run) = task.next_run() {
-                        let diff = (next_run - now).num_seconds();
+                    // 次回実行時刻が60秒以内なら実行
+                    if task.is_due(now, 60) {
+                        info!("Triggering scheduled task: {}", task.id);
 
-                        // 次回実行時刻が60秒以内なら実行
-                        if diff <= 60 && diff > 0 {
-                            info!("Triggering scheduled task: {}", task.id);
+                        let event = ScheduleEvent {
+                            task: task.clone(),
+                        };
 
-                            let event = ScheduleEvent {
-                                task: task.clone(),
-                            };
+                        if let Err(e) = self.event_sender.send(event) {
+                            error!("Failed to send schedule event: {}", e);
+                        }
 
-                            if let Err(e) = self.event_sender.send(event) {
-                                error!("Failed to send schedule event: {}", e);
-                            }
+                        if task.is_one_shot() {
+                            finished.push(task.id);
                         }
                     }
                 }
+
+                // 1回だけのタスクは実行後に削除（ストアからはイベント受信側で削除）
+                tasks.retain(|t| !finished.contains(&t.id));
             }
         });
     }
@@ -211,6 +305,42 @@ mod tests {
         assert!(task.is_err());
     }
 
+    #[test]
+    fn test_one_shot_task() {
+        let run_at = Utc::now() + chrono::Duration::minutes(30);
+        let task = ScheduledTask::once(run_at, "Remind".to_string(), 12345)
+            .unwrap()
+            .with_user(42);
+
+        assert!(task.is_one_shot());
+        assert_eq!(task.user_id, Some(42));
+        assert_eq!(task.next_run(), Some(run_at));
+        assert!(!task.is_due(Utc::now(), 60));
+        assert!(task.is_due(run_at - chrono::Duration::seconds(30), 60));
+        // 実行時刻を過ぎていても実行対象
+        assert!(task.is_due(run_at + chrono::Duration::minutes(5), 60));
+
+        let past = ScheduledTask::once(Utc::now() - chrono::Duration::minutes(1), "x".to_string(), 1);
+        assert!(matches!(past, Err(SchedulerError::InvalidRunAt(_))));
+    }
+
+    #[test]
+    fn test_task_deserializes_without_new_fields() {
+        let json = r#"{
+            "id": "6f1c2a4e-7d1b-4c55-9a43-1f6c2b9e0d11",
+            "cron_expression": "0 9 * * * *",
+            "prompt": "Hello",
+            "channel_id": 12345,
+            "created_at": "2026-01-01T00:00:00Z",
+            "enabled": true
+        }"#;
+        let task: ScheduledTask = serde_json::from_str(json).unwrap();
+        assert!(!task.is_one_shot());
+        assert_eq!(task.user_id, None);
+        assert_eq!(task.guild_id, None);
+        assert_eq!(task.kind, TaskKind::Schedule);
+    }
+
     #[tokio::test]
     async fn test_scheduler_add_remove() {
         let scheduler = Scheduler::new();
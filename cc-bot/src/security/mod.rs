@@ -1,7 +1,9 @@
 //! セキュリティ関連モジュール
 //!
-//! ログマスキングなどのセキュリティ機能を提供する。
+//! ログマスキングやWebアクセスポリシーなどのセキュリティ機能を提供する。
 
 mod logging;
+mod web_policy;
 
 pub use logging::{mask_api_key, mask_discord_token, mask_secrets, SecretMasker};
+pub use web_policy::{GuardedResolver, WebPolicy, WebPolicyError};
@@ -0,0 +1,396 @@
+//! Webアクセスポリシー（SSRF対策）
+//!
+//! このモジュールは`data/web_policy.json`からスキーム・ポート・ドメインの
+//! 許可/拒否設定を読み込み、ツールが取得するURLを検証します。
+//! 接続先IPアドレスはDNS解決後に検査し、ループバック・プライベート・
+//! リンクローカル・マルチキャストなど内部ネットワーク宛てのアクセスを拒否します。
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::{debug, info, warn};
+
+/// Webポリシーのエラー
+#[derive(Debug, Error)]
+pub enum WebPolicyError {
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("URL scheme '{0}' is not allowed")]
+    SchemeNotAllowed(String),
+
+    #[error("Port {0} is not allowed")]
+    PortNotAllowed(u16),
+
+    #[error("Domain '{0}' is blocked by the web policy")]
+    DomainDenied(String),
+
+    #[error("Domain '{0}' is not in the allowed domain list")]
+    DomainNotAllowed(String),
+
+    #[error("Access to internal address {1} ({0}) is not allowed")]
+    BlockedAddress(String, IpAddr),
+
+    #[error("Failed to resolve host '{0}'")]
+    ResolveFailed(String),
+
+    #[error("Config error: {0}")]
+    Config(String),
+}
+
+/// ドメインの許可/拒否リスト
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DomainRules {
+    /// 許可するドメイン（空なら全ドメインを許可）
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// 拒否するドメイン（許可より優先）
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+/// Webアクセスポリシー
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebPolicy {
+    /// 許可するURLスキーム
+    pub allowed_schemes: Vec<String>,
+    /// 許可するポート
+    pub allowed_ports: Vec<u16>,
+    /// 全サーバー共通のドメインルール
+    pub domains: DomainRules,
+    /// サーバーID -> サーバー固有のドメインルール
+    pub guilds: HashMap<u64, DomainRules>,
+    /// サーバー外（DM・API・MCPサーバー）からの実行に適用するドメインルール
+    pub no_guild: DomainRules,
+}
+
+impl Default for WebPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_schemes: vec!["http".to_string(), "https".to_string()],
+            allowed_ports: vec![80, 443, 8080, 8443],
+            domains: DomainRules::default(),
+            guilds: HashMap::new(),
+            no_guild: DomainRules::default(),
+        }
+    }
+}
+
+impl WebPolicy {
+    /// ファイルパスを生成
+    fn get_file_path(base_dir: &str) -> PathBuf {
+        Path::new(base_dir).join("web_policy.json")
+    }
+
+    /// JSONファイルから読み込み
+    ///
+    /// ファイルが存在しない場合はデフォルト値を使用
+    pub fn load(base_dir: &str) -> Result<Self, WebPolicyError> {
+        let path = Self::get_file_path(base_dir);
+        debug!("Loading web policy from {:?}", path);
+
+        if !path.exists() {
+            info!("Web policy file not found at {:?}, using defaults", path);
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| WebPolicyError::Config(format!("Failed to read file: {}", e)))?;
+
+        let policy: Self = serde_json::from_str(&content).map_err(|e| {
+            // パース失敗は設定ミスの可能性が高いためwarn
+            warn!("Failed to parse web policy file at {:?}: {}", path, e);
+            WebPolicyError::Config("Invalid configuration format".to_string())
+        })?;
+
+        info!(
+            "Loaded web policy ({} global allow, {} global deny, {} guild overrides)",
+            policy.domains.allow.len(),
+            policy.domains.deny.len(),
+            policy.guilds.len()
+        );
+        Ok(policy)
+    }
+
+    /// JSONファイルから読み込み（失敗時はデフォルト値）
+    pub fn load_or_default(base_dir: &str) -> Self {
+        Self::load(base_dir).unwrap_or_else(|e| {
+            warn!("Failed to load web policy, using defaults: {}", e);
+            Self::default()
+        })
+    }
+
+    /// URLのスキーム・ポート・ドメインを検証
+    ///
+    /// DNS解決は行わない（[`WebPolicy::check_url`] を参照）
+    pub fn check_static(&self, url: &Url, guild_id: Option<u64>) -> Result<(), WebPolicyError> {
+        let scheme = url.scheme();
+        if !self.allowed_schemes.iter().any(|s| s.eq_ignore_ascii_case(scheme)) {
+            return Err(WebPolicyError::SchemeNotAllowed(scheme.to_string()));
+        }
+
+        let port = url
+            .port_or_known_default()
+            .ok_or_else(|| WebPolicyError::InvalidUrl("missing port".to_string()))?;
+        if !self.allowed_ports.contains(&port) {
+            return Err(WebPolicyError::PortNotAllowed(port));
+        }
+
+        let host = url
+            .host_str()
+            .ok_or_else(|| WebPolicyError::InvalidUrl("missing host".to_string()))?;
+        let host = host.trim_start_matches('[').trim_end_matches(']').to_ascii_lowercase();
+
+        // IPアドレス直指定はここで検査（リゾルバーを経由しないため）
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            if is_blocked_ip(ip) {
+                return Err(WebPolicyError::BlockedAddress(host.clone(), ip));
+            }
+        }
+
+        self.check_domain(&host, guild_id)
+    }
+
+    /// URLを検証し、ホストのDNS解決結果がすべて外部アドレスであることを確認
+    pub async fn check_url(&self, url: &Url, guild_id: Option<u64>) -> Result<(), WebPolicyError> {
+        self.check_static(url, guild_id)?;
+
+        let host = url.host_str().unwrap_or_default();
+        if host.starts_with('[') || host.parse::<IpAddr>().is_ok() {
+            return Ok(());
+        }
+        resolve_external(host).await.map(|_| ())
+    }
+
+    /// ドメインの許可/拒否を判定（拒否が優先、許可リストはすべて満たす必要がある）
+    ///
+    /// サーバー固有のルールはそのサーバーでの実行にだけ適用し、
+    /// サーバー外の実行には代わりに `no_guild` のルールを適用する
+    fn check_domain(&self, host: &str, guild_id: Option<u64>) -> Result<(), WebPolicyError> {
+        let guild_rules = match guild_id {
+            Some(id) => self.guilds.get(&id),
+            None => Some(&self.no_guild),
+        };
+
+        for rules in std::iter::once(&self.domains).chain(guild_rules) {
+            if rules.deny.iter().any(|pattern| domain_matches(host, pattern)) {
+                return Err(WebPolicyError::DomainDenied(host.to_string()));
+            }
+        }
+        for rules in std::iter::once(&self.domains).chain(guild_rules) {
+            if !rules.allow.is_empty() && !rules.allow.iter().any(|pattern| domain_matches(host, pattern)) {
+                return Err(WebPolicyError::DomainNotAllowed(host.to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// ドメインがパターンに一致するか（サブドメインも一致、`*.` 接頭辞は省略可）
+fn domain_matches(host: &str, pattern: &str) -> bool {
+    let pattern = pattern.trim().trim_start_matches("*.").trim_end_matches('.').to_ascii_lowercase();
+    if pattern.is_empty() {
+        return false;
+    }
+    let host = host.trim_end_matches('.');
+    host == pattern || host.ends_with(&format!(".{}", pattern))
+}
+
+/// 内部ネットワーク宛てなど、アクセスを禁止するIPアドレスか判定
+pub fn is_blocked_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_blocked_ipv4(v4),
+        IpAddr::V6(v6) => {
+            // IPv4射影・NAT64アドレスは埋め込まれたIPv4で判定
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_blocked_ipv4(v4);
+            }
+            let segments = v6.segments();
+            if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                let [a, b] = segments[6].to_be_bytes();
+                let [c, d] = segments[7].to_be_bytes();
+                return is_blocked_ipv4(Ipv4Addr::new(a, b, c, d));
+            }
+            is_blocked_ipv6(v6)
+        }
+    }
+}
+
+fn is_blocked_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, _, _] = ip.octets();
+    ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_multicast()
+        || ip.is_broadcast()
+        || ip.is_unspecified()
+        || ip.is_documentation()
+        || a == 0
+        // キャリアグレードNAT (100.64.0.0/10)
+        || (a == 100 && (64..128).contains(&b))
+        // IETFプロトコル割り当て (192.0.0.0/24)
+        || (a == 192 && b == 0 && ip.octets()[2] == 0)
+        // ベンチマーク用 (198.18.0.0/15)
+        || (a == 198 && (b == 18 || b == 19))
+        // 予約済み (240.0.0.0/4)
+        || a >= 240
+}
+
+fn is_blocked_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // ユニークローカル (fc00::/7)
+        || (first & 0xfe00) == 0xfc00
+        // リンクローカル (fe80::/10)
+        || (first & 0xffc0) == 0xfe80
+        // サイトローカル（廃止済み, fec0::/10）
+        || (first & 0xffc0) == 0xfec0
+        // ドキュメント用 (2001:db8::/32)
+        || (first == 0x2001 && ip.segments()[1] == 0x0db8)
+}
+
+/// ホストをDNS解決し、内部アドレスが含まれていれば拒否
+async fn resolve_external(host: &str) -> Result<Vec<SocketAddr>, WebPolicyError> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, 0))
+        .await
+        .map_err(|_| WebPolicyError::ResolveFailed(host.to_string()))?
+        .collect();
+
+    if addrs.is_empty() {
+        return Err(WebPolicyError::ResolveFailed(host.to_string()));
+    }
+    // 1つでも内部アドレスがあれば拒否（DNSラウンドロビンによる回避を防ぐ）
+    if let Some(blocked) = addrs.iter().find(|addr| is_blocked_ip(addr.ip())) {
+        return Err(WebPolicyError::BlockedAddress(host.to_string(), blocked.ip()));
+    }
+    Ok(addrs)
+}
+
+/// 内部アドレスを返さないDNSリゾルバー
+///
+/// 検証後にDNSの応答が変わる（DNSリバインディング）場合でも、
+/// 実際の接続先を検証済みのアドレスに限定する
+#[derive(Debug, Clone, Default)]
+pub struct GuardedResolver;
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = resolve_external(name.as_str()).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_blocked_ips() {
+        for ip in [
+            "127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254",
+            "224.0.0.1", "0.0.0.0", "100.64.0.1", "::1", "fd00::1", "fe80::1",
+            "ff02::1", "::ffff:127.0.0.1", "64:ff9b::a9fe:a9fe",
+        ] {
+            assert!(is_blocked_ip(ip.parse().unwrap()), "{} should be blocked", ip);
+        }
+        for ip in ["8.8.8.8", "1.1.1.1", "2606:4700:4700::1111"] {
+            assert!(!is_blocked_ip(ip.parse().unwrap()), "{} should be allowed", ip);
+        }
+    }
+
+    #[test]
+    fn test_scheme_and_port() {
+        let policy = WebPolicy::default();
+        assert!(policy.check_static(&url("https://example.com/"), None).is_ok());
+        assert!(matches!(
+            policy.check_static(&url("ftp://example.com/"), None),
+            Err(WebPolicyError::SchemeNotAllowed(_))
+        ));
+        assert!(matches!(
+            policy.check_static(&url("http://example.com:22/"), None),
+            Err(WebPolicyError::PortNotAllowed(22))
+        ));
+    }
+
+    #[test]
+    fn test_ip_literals_blocked() {
+        let policy = WebPolicy::default();
+        for u in [
+            "http://127.0.0.1:8080/api/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://[::1]/",
+            "http://0x7f000001/",
+        ] {
+            assert!(
+                matches!(policy.check_static(&url(u), None), Err(WebPolicyError::BlockedAddress(..))),
+                "{} should be blocked",
+                u
+            );
+        }
+    }
+
+    #[test]
+    fn test_domain_rules_per_guild() {
+        let json = r#"{
+            "domains": { "deny": ["evil.example"] },
+            "guilds": { "42": { "allow": ["*.docs.rs", "rust-lang.org"] } }
+        }"#;
+        let policy: WebPolicy = serde_json::from_str(json).unwrap();
+
+        assert!(matches!(
+            policy.check_static(&url("https://api.evil.example/"), None),
+            Err(WebPolicyError::DomainDenied(_))
+        ));
+        assert!(policy.check_static(&url("https://github.com/"), None).is_ok());
+        assert!(policy.check_static(&url("https://github.com/"), Some(7)).is_ok());
+        assert!(matches!(
+            policy.check_static(&url("https://github.com/"), Some(42)),
+            Err(WebPolicyError::DomainNotAllowed(_))
+        ));
+        assert!(policy.check_static(&url("https://www.rust-lang.org/"), Some(42)).is_ok());
+        assert!(policy.check_static(&url("https://serde.docs.rs/"), Some(42)).is_ok());
+        // 部分一致はしない
+        assert!(policy.check_static(&url("https://notrust-lang.org/"), Some(42)).is_err());
+    }
+
+    #[test]
+    fn test_domain_rules_without_guild() {
+        let json = r#"{
+            "guilds": { "42": { "deny": ["github.com"] } },
+            "no_guild": { "allow": ["docs.rs"] }
+        }"#;
+        let policy: WebPolicy = serde_json::from_str(json).unwrap();
+
+        // サーバー固有のルールはサーバー外の実行には適用しない
+        assert!(policy.check_static(&url("https://docs.rs/"), None).is_ok());
+        assert!(matches!(
+            policy.check_static(&url("https://github.com/"), None),
+            Err(WebPolicyError::DomainNotAllowed(_))
+        ));
+        // no_guild はサーバー内の実行には適用しない
+        assert!(policy.check_static(&url("https://example.com/"), Some(7)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_check_url_resolves_localhost() {
+        let policy = WebPolicy::default();
+        let result = policy.check_url(&url("http://localhost:8080/"), None).await;
+        assert!(matches!(
+            result,
+            Err(WebPolicyError::BlockedAddress(..)) | Err(WebPolicyError::ResolveFailed(_))
+        ));
+    }
+}
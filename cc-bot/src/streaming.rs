@@ -2,12 +2,14 @@
 //!
 //! DiscordでのLLM応答ストリーミング表示とツール実行進捗表示を提供
 
+use crate::tool::{ToolAttachment, MAX_UPLOAD_BYTES};
+use serenity::builder::{CreateAttachment, CreateMessage};
 use serenity::http::Http;
 use serenity::model::channel::Message;
 use serenity::model::id::ChannelId;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 /// Discordメッセージの最大文字数
 const MAX_MESSAGE_LENGTH: usize = 2000;
@@ -249,6 +251,66 @@ pub fn split_message(content: &str, max_length: usize) -> Vec<String> {
     messages
 }
 
+/// 添付ファイルを1メッセージのアップロード上限に収まるよう分割
+fn batch_attachments(attachments: Vec<ToolAttachment>) -> Vec<Vec<ToolAttachment>> {
+    let mut batches: Vec<Vec<ToolAttachment>> = Vec::new();
+    let mut current = Vec::new();
+    let mut current_bytes = 0;
+    for attachment in attachments {
+        let size = attachment.data.len();
+        if !current.is_empty() && current_bytes + size > MAX_UPLOAD_BYTES {
+            batches.push(std::mem::take(&mut current));
+            current_bytes = 0;
+        }
+        current_bytes += size;
+        current.push(attachment);
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+    batches
+}
+
+/// ツールが生成した添付ファイルをチャンネルに送信
+///
+/// アップロード上限を超える場合は複数メッセージに分けて送信し、
+/// 失敗したファイルはチャンネルに通知する。
+pub async fn send_attachments(http: &Http, channel_id: ChannelId, attachments: Vec<ToolAttachment>) {
+    if attachments.is_empty() {
+        return;
+    }
+
+    let mut failed = Vec::new();
+    for batch in batch_attachments(attachments) {
+        let names: Vec<String> = batch.iter().map(|a| a.filename.clone()).collect();
+        let files: Vec<CreateAttachment> = batch
+            .into_iter()
+            .map(|a| CreateAttachment::bytes(a.data, a.filename))
+            .collect();
+
+        match channel_id
+            .send_message(http, CreateMessage::new().add_files(files))
+            .await
+        {
+            Ok(_) => debug!("Sent {} tool attachments", names.len()),
+            Err(e) => {
+                error!("Failed to send tool attachments: {}", e);
+                failed.extend(names);
+            }
+        }
+    }
+
+    if !failed.is_empty() {
+        let notice = format!(
+            "⚠️ 添付ファイルを送信できませんでした: {}",
+            failed.join(", ")
+        );
+        if let Err(e) = channel_id.say(http, notice).await {
+            warn!("Failed to report attachment failure: {}", e);
+        }
+    }
+}
+
 /// ツール実行のユーザー確認が必要かどうかを判定
 pub fn requires_confirmation(tool_name: &str, confirmation_enabled: bool) -> bool {
     if !confirmation_enabled {
@@ -285,6 +347,27 @@ pub fn build_confirmation_message(tool_name: &str, params: &serde_json::Value) -
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_batch_attachments_respects_upload_limit() {
+        let attachment = |name: &str, size: usize| ToolAttachment {
+            filename: name.to_string(),
+            data: vec![0; size],
+        };
+        let half = MAX_UPLOAD_BYTES / 2;
+        let batches = batch_attachments(vec![
+            attachment("a", half),
+            attachment("b", half),
+            attachment("c", 1),
+            attachment("d", MAX_UPLOAD_BYTES),
+        ]);
+
+        let names: Vec<Vec<&str>> = batches
+            .iter()
+            .map(|b| b.iter().map(|a| a.filename.as_str()).collect())
+            .collect();
+        assert_eq!(names, vec![vec!["a", "b"], vec!["c"], vec!["d"]]);
+    }
+
     #[test]
     fn test_progress_status_display() {
         let status = ProgressStatus::ToolStarting {
@@ -0,0 +1,731 @@
+use crate::datetime_utils::parse_rfc3339_or_now;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use thiserror::Error;
+use tracing::{debug, error, info};
+
+/// TODO ID
+pub type TodoId = i64;
+
+/// タイトルの最大文字数
+const MAX_TITLE_CHARS: usize = 200;
+/// 1件あたりの最大タグ数
+const MAX_TAGS: usize = 10;
+/// ユーザーあたりの最大TODO数
+const MAX_TODOS_PER_USER: usize = 500;
+
+/// TODOエラー
+#[derive(Debug, Error)]
+pub enum TodoError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+
+    #[error("Todo not found: {0}")]
+    NotFound(TodoId),
+
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+}
+
+/// TODOの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoStatus {
+    Open,
+    InProgress,
+    Done,
+}
+
+impl TodoStatus {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "open" | "todo" => Some(Self::Open),
+            "in_progress" | "in-progress" | "doing" => Some(Self::InProgress),
+            "done" | "completed" | "complete" => Some(Self::Done),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::InProgress => "in_progress",
+            Self::Done => "done",
+        }
+    }
+}
+
+/// TODOの優先度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoPriority {
+    Low,
+    Normal,
+    High,
+}
+
+impl TodoPriority {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "low" => Some(Self::Low),
+            "normal" | "medium" => Some(Self::Normal),
+            "high" | "urgent" => Some(Self::High),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::High => "high",
+        }
+    }
+
+    /// DB保存用の値（並び替えに使用）
+    fn rank(&self) -> i64 {
+        match self {
+            Self::Low => 1,
+            Self::Normal => 2,
+            Self::High => 3,
+        }
+    }
+
+    fn from_rank(rank: i64) -> Self {
+        match rank {
+            i64::MIN..=1 => Self::Low,
+            2 => Self::Normal,
+            _ => Self::High,
+        }
+    }
+}
+
+/// TODOエントリ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Todo {
+    pub id: TodoId,
+    pub user_id: u64,
+    /// チャンネル限定のTODOの場合のチャンネルID（None なら個人のTODO）
+    pub channel_id: Option<u64>,
+    pub title: String,
+    pub status: TodoStatus,
+    pub priority: TodoPriority,
+    pub due_at: Option<DateTime<Utc>>,
+    pub tags: Vec<String>,
+    /// 期限リマインダーのスケジュールID
+    pub reminder_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl Todo {
+    /// 期限切れか（未完了で期限を過ぎている）
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        self.status != TodoStatus::Done && self.due_at.is_some_and(|due| due < now)
+    }
+}
+
+/// 新規TODO作成用
+#[derive(Debug, Clone)]
+pub struct NewTodo {
+    pub user_id: u64,
+    pub channel_id: Option<u64>,
+    pub title: String,
+    pub priority: TodoPriority,
+    pub due_at: Option<DateTime<Utc>>,
+    pub tags: Vec<String>,
+}
+
+/// TODO更新内容（None の項目は変更しない）
+#[derive(Debug, Clone, Default)]
+pub struct TodoUpdate {
+    pub title: Option<String>,
+    pub status: Option<TodoStatus>,
+    pub priority: Option<TodoPriority>,
+    /// `Some(None)` で期限を解除
+    pub due_at: Option<Option<DateTime<Utc>>>,
+    pub tags: Option<Vec<String>>,
+}
+
+/// 一覧取得時の状態フィルタ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StatusFilter {
+    /// 未完了（open / in_progress）
+    #[default]
+    Active,
+    Only(TodoStatus),
+    All,
+}
+
+/// 一覧取得条件
+#[derive(Debug, Clone, Default)]
+pub struct TodoFilter {
+    /// 指定時はそのチャンネル限定のTODOのみ
+    pub channel_id: Option<u64>,
+    pub status: StatusFilter,
+    pub tag: Option<String>,
+    pub limit: Option<usize>,
+}
+
+const TODO_COLUMNS: &str =
+    "id, user_id, channel_id, title, status, priority, due_at, tags, reminder_id, created_at, updated_at, completed_at";
+
+/// 日時をDB保存用の文字列に変換（文字列比較で並び替えられる形式）
+fn to_db_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// 行をTODOに変換
+fn row_to_todo(row: &Row<'_>) -> rusqlite::Result<Todo> {
+    Ok(Todo {
+        id: row.get(0)?,
+        user_id: row.get::<_, i64>(1)? as u64,
+        channel_id: row.get::<_, Option<i64>>(2)?.map(|id| id as u64),
+        title: row.get(3)?,
+        status: TodoStatus::parse(&row.get::<_, String>(4)?).unwrap_or(TodoStatus::Open),
+        priority: TodoPriority::from_rank(row.get(5)?),
+        due_at: row.get::<_, Option<String>>(6)?.map(|s| parse_rfc3339_or_now(&s)),
+        tags: serde_json::from_str(&row.get::<_, String>(7)?).unwrap_or_default(),
+        reminder_id: row.get(8)?,
+        created_at: parse_rfc3339_or_now(&row.get::<_, String>(9)?),
+        updated_at: parse_rfc3339_or_now(&row.get::<_, String>(10)?),
+        completed_at: row.get::<_, Option<String>>(11)?.map(|s| parse_rfc3339_or_now(&s)),
+    })
+}
+
+/// タイトルを検証
+fn validate_title(title: &str) -> Result<String, TodoError> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err(TodoError::InvalidInput("Title cannot be empty".to_string()));
+    }
+    if title.chars().count() > MAX_TITLE_CHARS {
+        return Err(TodoError::InvalidInput(format!(
+            "Title is too long (max {} characters)",
+            MAX_TITLE_CHARS
+        )));
+    }
+    Ok(title.to_string())
+}
+
+/// タグを正規化（空白除去・重複除去）
+fn normalize_tags(tags: Vec<String>) -> Result<Vec<String>, TodoError> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().trim_start_matches('#').to_lowercase();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    if normalized.len() > MAX_TAGS {
+        return Err(TodoError::InvalidInput(format!("Too many tags (max {})", MAX_TAGS)));
+    }
+    Ok(normalized)
+}
+
+/// TODOストア（SQLite永続化）
+pub struct TodoStore {
+    conn: Mutex<Connection>,
+}
+
+impl TodoStore {
+    /// Mutexロックを取得するヘルパー
+    fn lock_conn(&self) -> Result<std::sync::MutexGuard<'_, Connection>, TodoError> {
+        self.conn
+            .lock()
+            .map_err(|e| TodoError::DatabaseError(format!("Failed to lock connection: {}", e)))
+    }
+
+    /// 新しいTodoStoreを作成（インメモリ）
+    pub fn new() -> Result<Self, TodoError> {
+        let conn = Connection::open_in_memory().map_err(|e| {
+            error!("Failed to create in-memory DB: {}", e);
+            TodoError::DatabaseError("Failed to create database".to_string())
+        })?;
+
+        let store = Self {
+            conn: Mutex::new(conn),
+        };
+        store.initialize()?;
+        Ok(store)
+    }
+
+    /// ファイルパスから読み込み
+    pub fn load(base_dir: &str) -> Result<Self, TodoError> {
+        let path = Self::get_file_path(base_dir);
+        debug!("Loading todo store from {:?}", path);
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                error!("Failed to create directory: {}", e);
+                TodoError::DatabaseError("Failed to initialize storage".to_string())
+            })?;
+        }
+
+        let conn = Connection::open(&path).map_err(|e| {
+            error!("Failed to open database at {:?}: {}", path, e);
+            TodoError::DatabaseError("Failed to open database".to_string())
+        })?;
+
+        let store = Self {
+            conn: Mutex::new(conn),
+        };
+        store.initialize()?;
+        info!("Todo store loaded successfully");
+        Ok(store)
+    }
+
+    /// ファイルパスを生成
+    fn get_file_path(base_dir: &str) -> PathBuf {
+        Path::new(base_dir).join("todos.db")
+    }
+
+    /// データベースを初期化
+    fn initialize(&self) -> Result<(), TodoError> {
+        let conn = self.lock_conn()?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS todos (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                channel_id INTEGER,
+                title TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'open',
+                priority INTEGER NOT NULL DEFAULT 2,
+                due_at TEXT,
+                tags TEXT NOT NULL DEFAULT '[]',
+                reminder_id TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                completed_at TEXT
+            )",
+            [],
+        )
+        .map_err(|e| TodoError::DatabaseError(format!("Failed to create table: {}", e)))?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_todos_user_status ON todos(user_id, status)",
+            [],
+        )
+        .map_err(|e| TodoError::DatabaseError(format!("Failed to create index: {}", e)))?;
+
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_todos_due_at ON todos(due_at)", [])
+            .map_err(|e| TodoError::DatabaseError(format!("Failed to create index: {}", e)))?;
+
+        debug!("Todo store initialized");
+        Ok(())
+    }
+
+    /// TODOを追加
+    pub fn add_todo(&self, new_todo: NewTodo) -> Result<Todo, TodoError> {
+        let title = validate_title(&new_todo.title)?;
+        let tags = normalize_tags(new_todo.tags)?;
+
+        if self.count_todos(new_todo.user_id)? >= MAX_TODOS_PER_USER {
+            return Err(TodoError::InvalidInput(format!(
+                "Too many todos (max {}). Delete completed ones first.",
+                MAX_TODOS_PER_USER
+            )));
+        }
+
+        let now = Utc::now();
+        let tags_json = serde_json::to_string(&tags).unwrap_or_else(|_| "[]".to_string());
+
+        let conn = self.lock_conn()?;
+        conn.execute(
+            "INSERT INTO todos (user_id, channel_id, title, status, priority, due_at, tags, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                new_todo.user_id as i64,
+                new_todo.channel_id.map(|id| id as i64),
+                title,
+                TodoStatus::Open.as_str(),
+                new_todo.priority.rank(),
+                new_todo.due_at.map(to_db_time),
+                tags_json,
+                to_db_time(now),
+                to_db_time(now),
+            ],
+        )
+        .map_err(|e| TodoError::DatabaseError(format!("Failed to insert todo: {}", e)))?;
+
+        let id = conn.last_insert_rowid();
+        debug!("Added todo {} for user {}", id, new_todo.user_id);
+
+        Ok(Todo {
+            id,
+            user_id: new_todo.user_id,
+            channel_id: new_todo.channel_id,
+            title,
+            status: TodoStatus::Open,
+            priority: new_todo.priority,
+            due_at: new_todo.due_at,
+            tags,
+            reminder_id: None,
+            created_at: now,
+            updated_at: now,
+            completed_at: None,
+        })
+    }
+
+    /// TODOを取得（ユーザー確認付き、テスト用）
+    #[cfg(test)]
+    pub fn get_todo(&self, user_id: u64, id: TodoId) -> Result<Todo, TodoError> {
+        let conn = self.lock_conn()?;
+        Self::get_owned(&conn, user_id, id)
+    }
+
+    /// 本人のTODOを取得
+    fn get_owned(conn: &Connection, user_id: u64, id: TodoId) -> Result<Todo, TodoError> {
+        let todo = conn
+            .query_row(
+                &format!("SELECT {} FROM todos WHERE id = ?1", TODO_COLUMNS),
+                params![id],
+                row_to_todo,
+            )
+            .optional()
+            .map_err(|e| TodoError::DatabaseError(format!("Failed to query todo: {}", e)))?
+            .ok_or(TodoError::NotFound(id))?;
+
+        if todo.user_id != user_id {
+            return Err(TodoError::PermissionDenied(
+                "Cannot access another user's todo".to_string(),
+            ));
+        }
+        Ok(todo)
+    }
+
+    /// TODO一覧を取得（期限が近い順、期限なしは最後）
+    pub fn list_todos(&self, user_id: u64, filter: &TodoFilter) -> Result<Vec<Todo>, TodoError> {
+        let mut sql = format!("SELECT {} FROM todos WHERE user_id = ?", TODO_COLUMNS);
+        let mut values: Vec<SqlValue> = vec![SqlValue::Integer(user_id as i64)];
+
+        if let Some(channel_id) = filter.channel_id {
+            sql.push_str(" AND channel_id = ?");
+            values.push(SqlValue::Integer(channel_id as i64));
+        }
+        match filter.status {
+            StatusFilter::Active => {
+                sql.push_str(" AND status != ?");
+                values.push(SqlValue::Text(TodoStatus::Done.as_str().to_string()));
+            }
+            StatusFilter::Only(status) => {
+                sql.push_str(" AND status = ?");
+                values.push(SqlValue::Text(status.as_str().to_string()));
+            }
+            StatusFilter::All => {}
+        }
+        if let Some(tag) = &filter.tag {
+            sql.push_str(" AND EXISTS (SELECT 1 FROM json_each(todos.tags) WHERE json_each.value = ?)");
+            values.push(SqlValue::Text(tag.trim().trim_start_matches('#').to_lowercase()));
+        }
+        sql.push_str(" ORDER BY (due_at IS NULL), due_at, priority DESC, id LIMIT ?");
+        values.push(SqlValue::Integer(filter.limit.unwrap_or(MAX_TODOS_PER_USER) as i64));
+
+        let conn = self.lock_conn()?;
+        let mut stmt = conn
+            .prepare(&sql)
+            .map_err(|e| TodoError::DatabaseError(format!("Failed to prepare statement: {}", e)))?;
+
+        let todos = stmt
+            .query_map(params_from_iter(values), row_to_todo)
+            .map_err(|e| TodoError::DatabaseError(format!("Failed to query todos: {}", e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| TodoError::DatabaseError(format!("Failed to collect todos: {}", e)))?;
+
+        Ok(todos)
+    }
+
+    /// TODOを更新（ユーザー確認付き）
+    pub fn update_todo(&self, user_id: u64, id: TodoId, update: TodoUpdate) -> Result<Todo, TodoError> {
+        let conn = self.lock_conn()?;
+        let mut todo = Self::get_owned(&conn, user_id, id)?;
+        let now = Utc::now();
+
+        if let Some(title) = update.title {
+            todo.title = validate_title(&title)?;
+        }
+        if let Some(tags) = update.tags {
+            todo.tags = normalize_tags(tags)?;
+        }
+        if let Some(priority) = update.priority {
+            todo.priority = priority;
+        }
+        if let Some(due_at) = update.due_at {
+            todo.due_at = due_at;
+        }
+        if let Some(status) = update.status {
+            if status != todo.status {
+                todo.completed_at = (status == TodoStatus::Done).then_some(now);
+            }
+            todo.status = status;
+        }
+        todo.updated_at = now;
+
+        conn.execute(
+            "UPDATE todos SET title = ?1, status = ?2, priority = ?3, due_at = ?4, tags = ?5,
+                 updated_at = ?6, completed_at = ?7
+             WHERE id = ?8",
+            params![
+                todo.title,
+                todo.status.as_str(),
+                todo.priority.rank(),
+                todo.due_at.map(to_db_time),
+                serde_json::to_string(&todo.tags).unwrap_or_else(|_| "[]".to_string()),
+                to_db_time(todo.updated_at),
+                todo.completed_at.map(to_db_time),
+                id,
+            ],
+        )
+        .map_err(|e| TodoError::DatabaseError(format!("Failed to update todo: {}", e)))?;
+
+        Ok(todo)
+    }
+
+    /// TODOを完了にする
+    pub fn complete_todo(&self, user_id: u64, id: TodoId) -> Result<Todo, TodoError> {
+        self.update_todo(
+            user_id,
+            id,
+            TodoUpdate {
+                status: Some(TodoStatus::Done),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// TODOを削除（ユーザー確認付き）
+    pub fn delete_todo(&self, user_id: u64, id: TodoId) -> Result<Todo, TodoError> {
+        let conn = self.lock_conn()?;
+        let todo = Self::get_owned(&conn, user_id, id)?;
+
+        conn.execute("DELETE FROM todos WHERE id = ?1", params![id])
+            .map_err(|e| TodoError::DatabaseError(format!("Failed to delete todo: {}", e)))?;
+
+        Ok(todo)
+    }
+
+    /// リマインダーのスケジュールIDを設定
+    pub fn set_reminder(&self, id: TodoId, reminder_id: Option<&str>) -> Result<(), TodoError> {
+        let conn = self.lock_conn()?;
+        let affected = conn
+            .execute(
+                "UPDATE todos SET reminder_id = ?1 WHERE id = ?2",
+                params![reminder_id, id],
+            )
+            .map_err(|e| TodoError::DatabaseError(format!("Failed to update reminder: {}", e)))?;
+
+        if affected == 0 {
+            return Err(TodoError::NotFound(id));
+        }
+        Ok(())
+    }
+
+    /// ユーザーのTODO数を取得
+    pub fn count_todos(&self, user_id: u64) -> Result<usize, TodoError> {
+        let conn = self.lock_conn()?;
+
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM todos WHERE user_id = ?1",
+                params![user_id as i64],
+                |row| row.get(0),
+            )
+            .map_err(|e| TodoError::DatabaseError(format!("Failed to count todos: {}", e)))?;
+
+        Ok(count as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn new_todo(user_id: u64, title: &str) -> NewTodo {
+        NewTodo {
+            user_id,
+            channel_id: None,
+            title: title.to_string(),
+            priority: TodoPriority::Normal,
+            due_at: None,
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_add_and_get_todo() {
+        let store = TodoStore::new().unwrap();
+
+        let todo = store
+            .add_todo(NewTodo {
+                tags: vec!["#Work".to_string(), "work".to_string(), " ".to_string()],
+                ..new_todo(1, "  Write report  ")
+            })
+            .unwrap();
+        assert_eq!(todo.title, "Write report");
+        assert_eq!(todo.tags, vec!["work"]);
+        assert_eq!(todo.status, TodoStatus::Open);
+
+        let fetched = store.get_todo(1, todo.id).unwrap();
+        assert_eq!(fetched.title, "Write report");
+        assert_eq!(fetched.tags, vec!["work"]);
+
+        // 他のユーザーは参照できない
+        assert!(matches!(store.get_todo(2, todo.id), Err(TodoError::PermissionDenied(_))));
+        assert!(matches!(store.add_todo(new_todo(1, "   ")), Err(TodoError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_list_order_and_filters() {
+        let store = TodoStore::new().unwrap();
+        let now = Utc::now();
+
+        let later = store
+            .add_todo(NewTodo {
+                due_at: Some(now + Duration::days(2)),
+                ..new_todo(1, "later")
+            })
+            .unwrap();
+        let no_due = store
+            .add_todo(NewTodo {
+                priority: TodoPriority::High,
+                tags: vec!["home".to_string()],
+                ..new_todo(1, "no due")
+            })
+            .unwrap();
+        let soon = store
+            .add_todo(NewTodo {
+                due_at: Some(now + Duration::hours(1)),
+                channel_id: Some(99),
+                ..new_todo(1, "soon")
+            })
+            .unwrap();
+        store.add_todo(new_todo(2, "other user")).unwrap();
+
+        let ids: Vec<TodoId> = store
+            .list_todos(1, &TodoFilter::default())
+            .unwrap()
+            .iter()
+            .map(|t| t.id)
+            .collect();
+        assert_eq!(ids, vec![soon.id, later.id, no_due.id]);
+
+        let channel = TodoFilter {
+            channel_id: Some(99),
+            ..Default::default()
+        };
+        assert_eq!(store.list_todos(1, &channel).unwrap().len(), 1);
+
+        let tagged = TodoFilter {
+            tag: Some("#Home".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(store.list_todos(1, &tagged).unwrap()[0].id, no_due.id);
+
+        store.complete_todo(1, later.id).unwrap();
+        assert_eq!(store.list_todos(1, &TodoFilter::default()).unwrap().len(), 2);
+        let done = TodoFilter {
+            status: StatusFilter::Only(TodoStatus::Done),
+            ..Default::default()
+        };
+        assert_eq!(store.list_todos(1, &done).unwrap()[0].id, later.id);
+        let all = TodoFilter {
+            status: StatusFilter::All,
+            ..Default::default()
+        };
+        assert_eq!(store.list_todos(1, &all).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_update_and_complete() {
+        let store = TodoStore::new().unwrap();
+        let todo = store
+            .add_todo(NewTodo {
+                due_at: Some(Utc::now() + Duration::days(1)),
+                ..new_todo(1, "draft")
+            })
+            .unwrap();
+
+        let updated = store
+            .update_todo(
+                1,
+                todo.id,
+                TodoUpdate {
+                    title: Some("final".to_string()),
+                    status: Some(TodoStatus::InProgress),
+                    priority: Some(TodoPriority::High),
+                    due_at: Some(None),
+                    tags: Some(vec!["a".to_string()]),
+                },
+            )
+            .unwrap();
+        assert_eq!(updated.title, "final");
+        assert_eq!(updated.status, TodoStatus::InProgress);
+        assert_eq!(updated.priority, TodoPriority::High);
+        assert!(updated.due_at.is_none());
+
+        let completed = store.complete_todo(1, todo.id).unwrap();
+        assert_eq!(completed.status, TodoStatus::Done);
+        assert!(completed.completed_at.is_some());
+        assert_eq!(store.get_todo(1, todo.id).unwrap().status, TodoStatus::Done);
+
+        // 他のユーザーは更新できない
+        let result = store.complete_todo(2, todo.id);
+        assert!(matches!(result, Err(TodoError::PermissionDenied(_))));
+    }
+
+    #[test]
+    fn test_reminder_and_delete() {
+        let store = TodoStore::new().unwrap();
+        let todo = store.add_todo(new_todo(1, "call")).unwrap();
+
+        store.set_reminder(todo.id, Some("abc")).unwrap();
+        assert_eq!(store.get_todo(1, todo.id).unwrap().reminder_id.as_deref(), Some("abc"));
+        store.set_reminder(todo.id, None).unwrap();
+        assert!(store.get_todo(1, todo.id).unwrap().reminder_id.is_none());
+
+        assert!(matches!(store.delete_todo(2, todo.id), Err(TodoError::PermissionDenied(_))));
+        store.delete_todo(1, todo.id).unwrap();
+        assert!(matches!(store.get_todo(1, todo.id), Err(TodoError::NotFound(_))));
+        assert!(matches!(store.set_reminder(todo.id, None), Err(TodoError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_is_overdue() {
+        let store = TodoStore::new().unwrap();
+        let now = Utc::now();
+        let todo = store
+            .add_todo(NewTodo {
+                due_at: Some(now - Duration::hours(1)),
+                ..new_todo(1, "late")
+            })
+            .unwrap();
+        assert!(todo.is_overdue(now));
+
+        let done = store.complete_todo(1, todo.id).unwrap();
+        assert!(!done.is_overdue(now));
+    }
+
+    #[test]
+    fn test_persistence() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let base_dir = dir.path().to_string_lossy().to_string();
+        {
+            let store = TodoStore::load(&base_dir).unwrap();
+            store.add_todo(new_todo(1, "persisted")).unwrap();
+        }
+        let store = TodoStore::load(&base_dir).unwrap();
+        let todos = store.list_todos(1, &TodoFilter::default()).unwrap();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].title, "persisted");
+    }
+}
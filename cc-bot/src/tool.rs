@@ -6,7 +6,21 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::RwLock;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
+
+/// 1回の応答に添付できるファイル数の上限（Discordの制限）
+pub const MAX_ATTACHMENTS: usize = 10;
+/// 1メッセージでアップロードできる合計サイズ（Discordの制限）
+pub const MAX_UPLOAD_BYTES: usize = 8 * 1024 * 1024;
+/// 1回の応答に添付できる合計サイズの上限（複数メッセージに分けて送信する）
+pub const MAX_ATTACHMENT_TOTAL_BYTES: usize = 3 * MAX_UPLOAD_BYTES;
+
+/// ツールが生成した添付ファイル（応答と一緒にDiscordへ送信）
+#[derive(Debug, Clone)]
+pub struct ToolAttachment {
+    pub filename: String,
+    pub data: Vec<u8>,
+}
 
 /// ツール実行コンテキスト
 #[derive(Debug, Clone)]
@@ -14,9 +28,13 @@ pub struct ToolContext {
     pub user_id: u64,
     pub user_name: String,
     pub channel_id: u64,
+    /// 実行元のサーバーID（DM・スケジューラー・APIでは None）
+    pub guild_id: Option<u64>,
     pub base_output_dir: String,
     /// カスタム出力サブディレクトリ（ユーザー設定から取得）
     pub custom_output_subdir: Option<String>,
+    /// ツールが追加した添付ファイル（クローン間で共有）
+    pub attachments: Arc<std::sync::Mutex<Vec<ToolAttachment>>>,
 }
 
 impl ToolContext {
@@ -25,17 +43,47 @@ impl ToolContext {
             user_id,
             user_name,
             channel_id,
+            guild_id: None,
             base_output_dir,
             custom_output_subdir: None,
+            attachments: Arc::new(std::sync::Mutex::new(Vec::new())),
         }
     }
 
+    /// 添付ファイルを追加（件数・サイズの上限を超える場合は false）
+    pub fn add_attachment(&self, filename: impl Into<String>, data: Vec<u8>) -> bool {
+        let mut attachments = self.attachments.lock().unwrap();
+        if attachments.len() >= MAX_ATTACHMENTS || data.len() > MAX_UPLOAD_BYTES {
+            return false;
+        }
+        let total: usize = attachments.iter().map(|a| a.data.len()).sum();
+        if total + data.len() > MAX_ATTACHMENT_TOTAL_BYTES {
+            return false;
+        }
+        attachments.push(ToolAttachment {
+            filename: filename.into(),
+            data,
+        });
+        true
+    }
+
+    /// 追加された添付ファイルを取り出す
+    pub fn take_attachments(&self) -> Vec<ToolAttachment> {
+        std::mem::take(&mut *self.attachments.lock().unwrap())
+    }
+
     /// カスタムサブディレクトリを指定して作成
     pub fn with_custom_subdir(mut self, subdir: impl Into<String>) -> Self {
         self.custom_output_subdir = Some(subdir.into());
         self
     }
 
+    /// 実行元のサーバーIDを設定
+    pub fn with_guild_id(mut self, guild_id: Option<u64>) -> Self {
+        self.guild_id = guild_id;
+        self
+    }
+
     /// ユーザー設定から出力先を設定して作成
     pub fn with_user_settings(mut self, output_subdir: Option<&str>) -> Self {
         self.custom_output_subdir = output_subdir.map(|s| s.to_string());
@@ -60,6 +108,12 @@ impl ToolContext {
                 .replace('<', "_")
                 .replace('>', "_")
                 .replace('|', "_");
+            // 隠しディレクトリ（.history など内部用）と衝突させない
+            let safe_subdir = if safe_subdir.starts_with('.') {
+                format!("_{}", safe_subdir)
+            } else {
+                safe_subdir
+            };
             return format!("{}/{}", self.base_output_dir, safe_subdir);
         }
 
@@ -183,11 +237,25 @@ impl ToolManager {
         }
     }
 
-    /// ツールを登録
-    pub fn register<T: Tool + 'static>(&mut self, tool: T) {
+    /// ツールを登録（同名のツールが登録済みなら上書きせずに拒否し、false を返す）
+    pub fn register<T: Tool + 'static>(&mut self, tool: T) -> bool {
         let name = tool.name().to_string();
+        if self.tools.contains_key(&name) {
+            warn!("Refusing to register tool {}: a tool with the same name is already registered", name);
+            return false;
+        }
         info!("Registering tool: {}", name);
         self.tools.insert(name, Arc::new(tool));
+        true
+    }
+
+    /// ツールの登録を解除
+    pub fn unregister(&mut self, name: &str) -> bool {
+        let removed = self.tools.remove(name).is_some();
+        if removed {
+            info!("Unregistered tool: {}", name);
+        }
+        removed
     }
 
     /// ツールを取得
@@ -319,6 +387,14 @@ mod tests {
         assert!(manager.get("unknown").is_none());
     }
 
+    #[test]
+    fn test_tool_manager_register_collision() {
+        let mut manager = ToolManager::new();
+        assert!(manager.register(MockTool));
+        assert!(!manager.register(MockTool));
+        assert_eq!(manager.list_tools().len(), 1);
+    }
+
     #[test]
     fn test_tool_manager_list() {
         let mut manager = ToolManager::new();
@@ -329,6 +405,16 @@ mod tests {
         assert!(tools.contains(&"mock_tool"));
     }
 
+    #[test]
+    fn test_tool_manager_unregister() {
+        let mut manager = ToolManager::new();
+        manager.register(MockTool);
+
+        assert!(manager.unregister("mock_tool"));
+        assert!(manager.get("mock_tool").is_none());
+        assert!(!manager.unregister("mock_tool"));
+    }
+
     #[tokio::test]
     async fn test_tool_execute() {
         let mut manager = ToolManager::new();
@@ -380,6 +466,46 @@ mod tests {
         assert!(!output_dir.contains(":bad"));
     }
 
+    #[test]
+    fn test_tool_context_attachments() {
+        let ctx = ToolContext::new(123, "test_user".to_string(), 456, "output".to_string());
+        let clone = ctx.clone();
+        assert!(clone.add_attachment("a.png", vec![1, 2, 3]));
+
+        let attachments = ctx.take_attachments();
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(attachments[0].filename, "a.png");
+        assert!(ctx.take_attachments().is_empty());
+
+        for i in 0..MAX_ATTACHMENTS {
+            assert!(ctx.add_attachment(format!("{}.png", i), Vec::new()));
+        }
+        assert!(!ctx.add_attachment("overflow.png", Vec::new()));
+    }
+
+    #[test]
+    fn test_tool_context_attachment_byte_budget() {
+        let ctx = ToolContext::new(123, "test_user".to_string(), 456, "output".to_string());
+        assert!(!ctx.add_attachment("huge.bin", vec![0; MAX_UPLOAD_BYTES + 1]));
+
+        for i in 0..MAX_ATTACHMENT_TOTAL_BYTES / MAX_UPLOAD_BYTES {
+            assert!(ctx.add_attachment(format!("{}.bin", i), vec![0; MAX_UPLOAD_BYTES]));
+        }
+        assert!(!ctx.add_attachment("over.bin", vec![0; 1]));
+        assert_eq!(ctx.take_attachments().len(), 3);
+    }
+
+    #[test]
+    fn test_tool_context_custom_subdir_hidden_dir() {
+        let ctx = ToolContext::new(123, "test_user".to_string(), 456, "output".to_string())
+            .with_custom_subdir(".history");
+        assert_eq!(ctx.get_user_output_dir(), "output/_.history");
+
+        let ctx = ToolContext::new(123, "test_user".to_string(), 456, "output".to_string())
+            .with_custom_subdir("..");
+        assert_eq!(ctx.get_user_output_dir(), "output/_..");
+    }
+
     #[test]
     fn test_tool_context_with_user_settings_some() {
         let ctx = ToolContext::new(123, "test_user".to_string(), 456, "output".to_string())
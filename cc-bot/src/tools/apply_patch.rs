@@ -0,0 +1,896 @@
+//! パッチ適用ツール
+//!
+//! unified diff または編集リストを受け取り、全ハンクを検証してから
+//! 複数ファイルへまとめて（アトミックに）書き込みます。
+
+use super::edit::EditTool;
+use crate::file_history;
+use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
+use crate::validation::PathValidator;
+use async_trait::async_trait;
+use serde_json::{json, Value as JsonValue};
+use similar::{ChangeTag, TextDiff};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// プレビューに含める差分の最大文字数
+const MAX_PREVIEW_CHARS: usize = 10000;
+
+/// 1ハンク分の変更
+#[derive(Debug, Clone, PartialEq)]
+struct Hunk {
+    /// 元ファイルでの開始行（1始まり、`@@ @@` のみの場合は None）
+    old_start: Option<usize>,
+    old_lines: Vec<String>,
+    new_lines: Vec<String>,
+}
+
+/// 1ファイル分のパッチ
+#[derive(Debug, Clone, PartialEq)]
+struct FilePatch {
+    /// 変更前のパス（新規作成時は None）
+    old_path: Option<String>,
+    /// 変更後のパス（削除時は None）
+    new_path: Option<String>,
+    hunks: Vec<Hunk>,
+    /// 変更後の末尾に改行がない
+    no_newline_at_end: bool,
+}
+
+/// diff ヘッダーのパスを取り出す（`a/` `b/` 接頭辞とタイムスタンプを除去）
+fn parse_header_path(rest: &str) -> Option<String> {
+    let path = rest.split('\t').next().unwrap_or("").trim();
+    if path == "/dev/null" || path.is_empty() {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(path.to_string())
+}
+
+/// `@@ -l,c +l,c @@` から元ファイルの開始行を取り出す
+fn parse_hunk_header(line: &str) -> Option<usize> {
+    let old = line.trim_start_matches('@').split_whitespace().next()?;
+    let old = old.strip_prefix('-')?;
+    old.split(',').next()?.parse().ok()
+}
+
+/// unified diff をパースする
+///
+/// LLM が生成する diff は行数が合っていないことが多いため、
+/// ハンクの行数は信用せず次のヘッダーまでを1ハンクとして扱う。
+fn parse_unified_diff(patch: &str) -> Result<Vec<FilePatch>, String> {
+    let lines: Vec<&str> = patch.lines().collect();
+    let mut patches: Vec<FilePatch> = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+
+        if let Some(rest) = line.strip_prefix("--- ") {
+            let next = lines.get(i + 1).copied().unwrap_or("");
+            let new_rest = next.strip_prefix("+++ ").ok_or_else(|| {
+                format!("Line {}: expected '+++' header after '---'", i + 2)
+            })?;
+            patches.push(FilePatch {
+                old_path: parse_header_path(rest),
+                new_path: parse_header_path(new_rest),
+                hunks: Vec::new(),
+                no_newline_at_end: false,
+            });
+            i += 2;
+            continue;
+        }
+
+        if line.starts_with("@@") {
+            let file = patches
+                .last_mut()
+                .ok_or_else(|| format!("Line {}: hunk without file header", i + 1))?;
+
+            let mut hunk = Hunk {
+                old_start: parse_hunk_header(line),
+                old_lines: Vec::new(),
+                new_lines: Vec::new(),
+            };
+            // 本物の空行（先頭スペースなし）の数。末尾の区切り空行を除くために使う
+            let mut trailing_blank = 0;
+            let mut last_was_add = false;
+            i += 1;
+
+            while i < lines.len() {
+                let body = lines[i];
+                if body.starts_with("@@")
+                    || body.starts_with("diff ")
+                    || (body.starts_with("--- ")
+                        && lines.get(i + 1).is_some_and(|n| n.starts_with("+++ ")))
+                {
+                    break;
+                }
+
+                if let Some(text) = body.strip_prefix('+') {
+                    hunk.new_lines.push(text.to_string());
+                    trailing_blank = 0;
+                    last_was_add = true;
+                } else if let Some(text) = body.strip_prefix('-') {
+                    hunk.old_lines.push(text.to_string());
+                    trailing_blank = 0;
+                    last_was_add = false;
+                } else if body.starts_with('\\') {
+                    if last_was_add {
+                        file.no_newline_at_end = true;
+                    }
+                } else {
+                    let text = body.strip_prefix(' ').unwrap_or(body);
+                    hunk.old_lines.push(text.to_string());
+                    hunk.new_lines.push(text.to_string());
+                    trailing_blank = if body.is_empty() { trailing_blank + 1 } else { 0 };
+                    last_was_add = false;
+                }
+                i += 1;
+            }
+
+            for _ in 0..trailing_blank {
+                hunk.old_lines.pop();
+                hunk.new_lines.pop();
+            }
+
+            file.hunks.push(hunk);
+            continue;
+        }
+
+        // diff --git / index / new file mode などのメタ行は読み飛ばす
+        i += 1;
+    }
+
+    if patches.is_empty() {
+        return Err("No file headers ('--- a/...' / '+++ b/...') found in patch".to_string());
+    }
+
+    Ok(patches)
+}
+
+/// 比較方法（厳密 → 行末空白無視 → 前後空白無視の順に試す）
+const MATCHERS: [fn(&str, &str) -> bool; 3] = [
+    |a, b| a == b,
+    |a, b| a.trim_end() == b.trim_end(),
+    |a, b| a.trim() == b.trim(),
+];
+
+/// `needle` が一致する位置を `expected` に近い順に探す
+fn find_block(lines: &[String], needle: &[String], expected: usize, min: usize) -> Option<usize> {
+    if needle.len() > lines.len() {
+        return None;
+    }
+    let max = lines.len() - needle.len();
+    if min > max {
+        return None;
+    }
+    let expected = expected.clamp(min, max);
+
+    for matcher in MATCHERS {
+        let matches_at = |pos: usize| {
+            needle
+                .iter()
+                .zip(&lines[pos..pos + needle.len()])
+                .all(|(n, l)| matcher(n, l))
+        };
+
+        for distance in 0..=(max - min) {
+            if expected >= min + distance && matches_at(expected - distance) {
+                return Some(expected - distance);
+            }
+            if distance > 0 && expected + distance <= max && matches_at(expected + distance) {
+                return Some(expected + distance);
+            }
+        }
+    }
+
+    None
+}
+
+/// `needle` が一致する位置をすべて探す（重ならない範囲のみ）
+///
+/// 最初に一致が見つかった比較方法の結果だけを返す。
+fn find_all_blocks(lines: &[String], needle: &[String]) -> Vec<usize> {
+    if needle.is_empty() || needle.len() > lines.len() {
+        return Vec::new();
+    }
+    let max = lines.len() - needle.len();
+
+    for matcher in MATCHERS {
+        let mut positions = Vec::new();
+        let mut pos = 0;
+        while pos <= max {
+            if needle.iter().zip(&lines[pos..pos + needle.len()]).all(|(n, l)| matcher(n, l)) {
+                positions.push(pos);
+                pos += needle.len();
+            } else {
+                pos += 1;
+            }
+        }
+        if !positions.is_empty() {
+            return positions;
+        }
+    }
+
+    Vec::new()
+}
+
+/// 行単位のテキスト（改行コードと末尾改行の有無を保持）
+struct TextLines {
+    lines: Vec<String>,
+    crlf: bool,
+    trailing_newline: bool,
+}
+
+impl TextLines {
+    fn parse(content: &str) -> Self {
+        Self {
+            lines: content.lines().map(String::from).collect(),
+            crlf: content.contains("\r\n"),
+            trailing_newline: content.is_empty() || content.ends_with('\n'),
+        }
+    }
+
+    fn render(&self) -> String {
+        if self.lines.is_empty() {
+            return String::new();
+        }
+        let newline = if self.crlf { "\r\n" } else { "\n" };
+        let mut text = self.lines.join(newline);
+        if self.trailing_newline {
+            text.push_str(newline);
+        }
+        text
+    }
+}
+
+/// ハンクを順に適用する
+fn apply_hunks(content: &str, hunks: &[Hunk], no_newline_at_end: bool) -> Result<String, String> {
+    let mut text = TextLines::parse(content);
+    if content.is_empty() {
+        text.trailing_newline = !no_newline_at_end;
+    } else if no_newline_at_end {
+        text.trailing_newline = false;
+    }
+
+    let mut cursor = 0;
+    let mut offset: isize = 0;
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        let pos = if hunk.old_lines.is_empty() {
+            // 挿入のみ: `-l,0` は l 行目の後ろに挿入
+            let start = hunk.old_start.unwrap_or(text.lines.len());
+            (start as isize + offset).clamp(cursor as isize, text.lines.len() as isize) as usize
+        } else {
+            let expected = hunk
+                .old_start
+                .map(|s| (s.saturating_sub(1) as isize + offset).max(0) as usize)
+                .unwrap_or(cursor);
+            find_block(&text.lines, &hunk.old_lines, expected, cursor).ok_or_else(|| {
+                format!(
+                    "Hunk {} does not match the file. Expected lines:\n{}",
+                    index + 1,
+                    hunk.old_lines.join("\n")
+                )
+            })?
+        };
+
+        // 元ファイルでの位置（0始まり）。挿入のみのハンクは l 行目の直後
+        let old_start = match hunk.old_start {
+            Some(s) if hunk.old_lines.is_empty() => s,
+            Some(s) => s.saturating_sub(1),
+            None => pos,
+        };
+        text.lines
+            .splice(pos..pos + hunk.old_lines.len(), hunk.new_lines.iter().cloned());
+        cursor = pos + hunk.new_lines.len();
+        offset = (pos + hunk.new_lines.len()) as isize
+            - (old_start + hunk.old_lines.len()) as isize;
+    }
+
+    Ok(text.render())
+}
+
+/// 文字列置換を適用する（完全一致しない場合は行単位で空白を緩めて探す）
+fn apply_edit(content: &str, old: &str, new: &str, replace_all: bool) -> Result<String, String> {
+    let count = content.matches(old).count();
+    if count == 1 || (count > 1 && replace_all) {
+        return Ok(content.replace(old, new));
+    }
+    if count > 1 {
+        return Err(format!(
+            "Text found {} times. Set replace_all=true or include more context:\n{}",
+            count, old
+        ));
+    }
+
+    let mut text = TextLines::parse(content);
+    let needle: Vec<String> = old.lines().map(String::from).collect();
+    if needle.is_empty() {
+        return Err("old_string cannot be empty for an existing file".to_string());
+    }
+    let positions = find_all_blocks(&text.lines, &needle);
+    if positions.is_empty() {
+        return Err(format!("Text not found in file:\n{}", old));
+    }
+    // 空白を緩めた一致でも、曖昧な場合は完全一致と同じく拒否する
+    if positions.len() > 1 && !replace_all {
+        return Err(format!(
+            "Text found {} times (ignoring whitespace). Set replace_all=true or include more context:\n{}",
+            positions.len(),
+            old
+        ));
+    }
+    // 後ろから置換して前方の位置をずらさない
+    for pos in positions.into_iter().rev() {
+        text.lines
+            .splice(pos..pos + needle.len(), new.lines().map(String::from));
+    }
+    Ok(text.render())
+}
+
+/// 適用予定のファイル
+#[derive(Debug)]
+struct PlannedFile {
+    full_path: PathBuf,
+    original: Option<String>,
+    updated: Option<String>,
+}
+
+impl PlannedFile {
+    fn is_changed(&self) -> bool {
+        self.original != self.updated
+    }
+}
+
+/// パッチ適用ツール
+pub struct ApplyPatchTool;
+
+impl ApplyPatchTool {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// パスを検証し、ユーザーディレクトリ内の実パスに変換
+    pub(crate) fn resolve_path(path: &str, context: &ToolContext) -> Result<PathBuf, ToolError> {
+        let path = path.trim().trim_start_matches("./");
+        if path.is_empty() {
+            return Err(ToolError::InvalidParams("File path cannot be empty".to_string()));
+        }
+
+        EditTool::validate_path(path)?;
+
+        let base = context.get_user_output_dir();
+        let full_path = PathValidator::new(&base)
+            .validate_path(path)
+            .map_err(|e| ToolError::PermissionDenied(e.to_string()))?;
+
+        // ユーザーディレクトリ以下の各階層でシンボリックリンクを拒否
+        let mut current = PathBuf::from(&base);
+        for component in Path::new(path).components() {
+            current.push(component);
+            if EditTool::is_symlink(&current) {
+                return Err(ToolError::PermissionDenied(
+                    "Symbolic links are not allowed for security reasons".to_string(),
+                ));
+            }
+        }
+
+        Ok(full_path)
+    }
+
+    /// 計画にファイルを読み込む（初回のみ）
+    async fn load<'a>(
+        plan: &'a mut BTreeMap<String, PlannedFile>,
+        path: &str,
+        context: &ToolContext,
+    ) -> Result<&'a mut PlannedFile, ToolError> {
+        let key = path.trim().trim_start_matches("./").to_string();
+        if !plan.contains_key(&key) {
+            let full_path = Self::resolve_path(&key, context)?;
+            let original = if full_path.is_file() {
+                match fs::read_to_string(&full_path).await {
+                    Ok(content) => Some(content),
+                    Err(e) => {
+                        warn!("Failed to read file {:?}: {}", full_path, e);
+                        return Err(ToolError::ExecutionFailed(format!(
+                            "Failed to read file: {}",
+                            key
+                        )));
+                    }
+                }
+            } else {
+                None
+            };
+            plan.insert(
+                key.clone(),
+                PlannedFile {
+                    full_path,
+                    updated: original.clone(),
+                    original,
+                },
+            );
+        }
+        Ok(plan.get_mut(&key).expect("entry inserted above"))
+    }
+
+    /// unified diff から変更計画を作成
+    async fn plan_patch(
+        patch: &str,
+        context: &ToolContext,
+    ) -> Result<Result<BTreeMap<String, PlannedFile>, String>, ToolError> {
+        let file_patches = match parse_unified_diff(patch) {
+            Ok(p) => p,
+            Err(e) => return Ok(Err(format!("Invalid patch: {}", e))),
+        };
+
+        let mut plan = BTreeMap::new();
+        for file_patch in file_patches {
+            let source = file_patch
+                .old_path
+                .clone()
+                .or_else(|| file_patch.new_path.clone())
+                .ok_or_else(|| ToolError::InvalidParams("Patch has no file path".to_string()))?;
+
+            let entry = Self::load(&mut plan, &source, context).await?;
+            let current = match (&file_patch.old_path, &entry.updated) {
+                (Some(_), Some(content)) => content.clone(),
+                (Some(path), None) => return Ok(Err(format!("File not found: {}", path))),
+                (None, Some(_)) => return Ok(Err(format!("File already exists: {}", source))),
+                (None, None) => String::new(),
+            };
+
+            let result = if file_patch.hunks.is_empty() {
+                current
+            } else {
+                match apply_hunks(&current, &file_patch.hunks, file_patch.no_newline_at_end) {
+                    Ok(content) => content,
+                    Err(e) => return Ok(Err(format!("{}: {}", source, e))),
+                }
+            };
+
+            match &file_patch.new_path {
+                None => {
+                    if !file_patch.hunks.is_empty() && !result.is_empty() {
+                        return Ok(Err(format!(
+                            "{}: deletion patch does not remove all content",
+                            source
+                        )));
+                    }
+                    entry.updated = None;
+                }
+                Some(target) if *target != source => {
+                    // リネーム
+                    entry.updated = None;
+                    let target_entry = Self::load(&mut plan, target, context).await?;
+                    if target_entry.updated.is_some() {
+                        return Ok(Err(format!("File already exists: {}", target)));
+                    }
+                    target_entry.updated = Some(result);
+                }
+                Some(_) => entry.updated = Some(result),
+            }
+        }
+
+        Ok(Ok(plan))
+    }
+
+    /// 編集リストから変更計画を作成
+    async fn plan_edits(
+        edits: &[JsonValue],
+        context: &ToolContext,
+    ) -> Result<Result<BTreeMap<String, PlannedFile>, String>, ToolError> {
+        let mut plan = BTreeMap::new();
+
+        for (index, edit) in edits.iter().enumerate() {
+            let path = edit["path"].as_str().ok_or_else(|| {
+                ToolError::InvalidParams(format!("Edit {}: missing 'path'", index + 1))
+            })?;
+            let old_string = edit["old_string"].as_str().ok_or_else(|| {
+                ToolError::InvalidParams(format!("Edit {}: missing 'old_string'", index + 1))
+            })?;
+            let new_string = edit["new_string"].as_str().ok_or_else(|| {
+                ToolError::InvalidParams(format!("Edit {}: missing 'new_string'", index + 1))
+            })?;
+            let replace_all = edit["replace_all"].as_bool().unwrap_or(false);
+
+            let entry = Self::load(&mut plan, path, context).await?;
+            let updated = match &entry.updated {
+                // 存在しないファイルに old_string 空で新規作成
+                None if old_string.is_empty() => new_string.to_string(),
+                None => return Ok(Err(format!("Edit {}: file not found: {}", index + 1, path))),
+                Some(content) => match apply_edit(content, old_string, new_string, replace_all) {
+                    Ok(c) => c,
+                    Err(e) => return Ok(Err(format!("Edit {} ({}): {}", index + 1, path, e))),
+                },
+            };
+            entry.updated = Some(updated);
+        }
+
+        Ok(Ok(plan))
+    }
+
+    /// 一時ファイル経由で全ファイルを書き込み、失敗時は元に戻す
+    async fn commit(plan: &BTreeMap<String, PlannedFile>) -> Result<(), String> {
+        let changed: Vec<(&String, &PlannedFile)> =
+            plan.iter().filter(|(_, f)| f.is_changed()).collect();
+
+        // 1. 一時ファイルへ書き込み
+        let mut temps: Vec<(PathBuf, &PlannedFile)> = Vec::new();
+        for (path, file) in &changed {
+            let Some(content) = &file.updated else { continue };
+            let parent = file.full_path.parent().unwrap_or(Path::new("."));
+            let result = async {
+                fs::create_dir_all(parent).await?;
+                let name = file
+                    .full_path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let temp = parent.join(format!(".{}.{}.tmp", name, Uuid::new_v4().simple()));
+                fs::write(&temp, content).await?;
+                Ok::<PathBuf, std::io::Error>(temp)
+            }
+            .await;
+
+            match result {
+                Ok(temp) => temps.push((temp, file)),
+                Err(e) => {
+                    for (temp, _) in &temps {
+                        let _ = fs::remove_file(temp).await;
+                    }
+                    return Err(format!("Failed to write {}: {}", path, e));
+                }
+            }
+        }
+
+        // 2. リネームと削除
+        let mut done: Vec<&PlannedFile> = Vec::new();
+        let mut failure = None;
+        for (index, (temp, file)) in temps.iter().enumerate() {
+            if let Err(e) = fs::rename(temp, &file.full_path).await {
+                for (rest, _) in &temps[index..] {
+                    let _ = fs::remove_file(rest).await;
+                }
+                failure = Some(format!("Failed to replace {:?}: {}", file.full_path, e));
+                break;
+            }
+            done.push(file);
+        }
+        if failure.is_none() {
+            for (_, file) in changed.iter().filter(|(_, f)| f.updated.is_none()) {
+                if let Err(e) = fs::remove_file(&file.full_path).await {
+                    failure = Some(format!("Failed to delete {:?}: {}", file.full_path, e));
+                    break;
+                }
+                done.push(file);
+            }
+        }
+
+        // 3. 失敗したら適用済みのファイルを元に戻す
+        if let Some(error) = failure {
+            for file in done {
+                let restored = match &file.original {
+                    Some(content) => fs::write(&file.full_path, content).await,
+                    None => fs::remove_file(&file.full_path).await,
+                };
+                if let Err(e) = restored {
+                    warn!("Failed to roll back {:?}: {}", file.full_path, e);
+                }
+            }
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
+    /// 変更概要と差分プレビューを作成
+    fn summarize(plan: &BTreeMap<String, PlannedFile>, with_diff: bool) -> String {
+        let mut summary = Vec::new();
+        let mut diffs = String::new();
+
+        for (path, file) in plan.iter().filter(|(_, f)| f.is_changed()) {
+            let old = file.original.as_deref().unwrap_or("");
+            let new = file.updated.as_deref().unwrap_or("");
+            let diff = TextDiff::from_lines(old, new);
+
+            let (mut added, mut removed) = (0, 0);
+            for change in diff.iter_all_changes() {
+                match change.tag() {
+                    ChangeTag::Insert => added += 1,
+                    ChangeTag::Delete => removed += 1,
+                    ChangeTag::Equal => {}
+                }
+            }
+
+            let kind = match (&file.original, &file.updated) {
+                (None, _) => "A",
+                (_, None) => "D",
+                _ => "M",
+            };
+            summary.push(format!("  {} {} (+{} -{})", kind, path, added, removed));
+
+            if with_diff {
+                let old_header = if file.original.is_some() { format!("a/{}", path) } else { "/dev/null".to_string() };
+                let new_header = if file.updated.is_some() { format!("b/{}", path) } else { "/dev/null".to_string() };
+                diffs.push_str(
+                    &diff
+                        .unified_diff()
+                        .context_radius(3)
+                        .header(&old_header, &new_header)
+                        .to_string(),
+                );
+            }
+        }
+
+        let mut output = summary.join("\n");
+        if with_diff && !diffs.is_empty() {
+            if diffs.chars().count() > MAX_PREVIEW_CHARS {
+                diffs = diffs.chars().take(MAX_PREVIEW_CHARS).collect();
+                diffs.push_str("\n...(preview truncated)");
+            }
+            output.push_str("\n\n```diff\n");
+            output.push_str(&diffs);
+            if !diffs.ends_with('\n') {
+                output.push('\n');
+            }
+            output.push_str("```");
+        }
+        output
+    }
+}
+
+#[async_trait]
+impl Tool for ApplyPatchTool {
+    fn name(&self) -> &str {
+        "apply_patch"
+    }
+
+    fn description(&self) -> &str {
+        "Apply a unified diff or a list of edits to one or more files. Every hunk is validated before anything is written, and all files are updated together or not at all. Hunks are located near their line numbers and tolerate whitespace differences. Use dry_run=true to preview the resulting diff."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "patch": {
+                    "type": "string",
+                    "description": "Unified diff with '--- a/path' / '+++ b/path' headers and '@@' hunks. Use /dev/null to create or delete files."
+                },
+                "edits": {
+                    "type": "array",
+                    "description": "List of string replacements, applied in order (alternative to patch)",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "path": { "type": "string", "description": "Relative file path" },
+                            "old_string": { "type": "string", "description": "Text to replace (empty to create a new file)" },
+                            "new_string": { "type": "string", "description": "Replacement text" },
+                            "replace_all": { "type": "boolean", "description": "Replace all occurrences (default: false)" }
+                        },
+                        "required": ["path", "old_string", "new_string"]
+                    }
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Validate and preview the diff without writing (default: false)"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let dry_run = params["dry_run"].as_bool().unwrap_or(false);
+
+        let plan = match (params["patch"].as_str(), params["edits"].as_array()) {
+            (Some(_), Some(_)) => {
+                return Err(ToolError::InvalidParams(
+                    "Specify either 'patch' or 'edits', not both".to_string(),
+                ))
+            }
+            (Some(patch), None) => Self::plan_patch(patch, context).await?,
+            (None, Some(edits)) => Self::plan_edits(edits, context).await?,
+            (None, None) => {
+                return Err(ToolError::InvalidParams(
+                    "Missing 'patch' or 'edits' parameter".to_string(),
+                ))
+            }
+        };
+
+        let plan = match plan {
+            Ok(plan) => plan,
+            Err(e) => {
+                debug!("Patch validation failed: {}", e);
+                return Ok(ToolResult::error(format!("Patch not applied: {}", e)));
+            }
+        };
+
+        if !plan.values().any(PlannedFile::is_changed) {
+            return Ok(ToolResult::success("Patch produces no changes".to_string()));
+        }
+
+        if dry_run {
+            return Ok(ToolResult::success(format!(
+                "Dry run (no files were changed):\n{}",
+                Self::summarize(&plan, true)
+            )));
+        }
+
+        // 変更前の内容を履歴に保存
+        for (path, file) in plan.iter().filter(|(_, f)| f.is_changed()) {
+            file_history::snapshot_before_write(context, path, &file.full_path, self.name()).await;
+        }
+
+        if let Err(e) = Self::commit(&plan).await {
+            warn!("Failed to apply patch: {}", e);
+            return Err(ToolError::ExecutionFailed(
+                "Failed to write patched files. No changes were kept.".to_string(),
+            ));
+        }
+
+        info!(
+            "Applied patch to {} file(s) for user {}",
+            plan.values().filter(|f| f.is_changed()).count(),
+            context.user_id
+        );
+        Ok(ToolResult::success(format!(
+            "Patch applied:\n{}",
+            Self::summarize(&plan, false)
+        )))
+    }
+}
+
+impl Default for ApplyPatchTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::test_workspace;
+
+    fn write(ctx: &ToolContext, path: &str, content: &str) {
+        std::fs::write(format!("{}/{}", ctx.get_user_output_dir(), path), content).unwrap();
+    }
+
+    fn read(ctx: &ToolContext, path: &str) -> String {
+        std::fs::read_to_string(format!("{}/{}", ctx.get_user_output_dir(), path)).unwrap()
+    }
+
+    #[test]
+    fn test_parse_unified_diff() {
+        let patch = "diff --git a/a.txt b/a.txt\n--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n one\n-two\n+TWO\n";
+        let files = parse_unified_diff(patch).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].old_path.as_deref(), Some("a.txt"));
+        assert_eq!(files[0].hunks[0].old_start, Some(1));
+        assert_eq!(files[0].hunks[0].old_lines, vec!["one", "two"]);
+        assert_eq!(files[0].hunks[0].new_lines, vec!["one", "TWO"]);
+    }
+
+    #[test]
+    fn test_apply_hunks_with_offset_and_whitespace() {
+        let content = "header\nextra\nfn main() {\n    println!(\"hi\");  \n}\n";
+        let hunk = Hunk {
+            old_start: Some(1),
+            old_lines: vec!["fn main() {".into(), "  println!(\"hi\");".into(), "}".into()],
+            new_lines: vec!["fn main() {".into(), "    println!(\"bye\");".into(), "}".into()],
+        };
+        let result = apply_hunks(content, &[hunk], false).unwrap();
+        assert_eq!(result, "header\nextra\nfn main() {\n    println!(\"bye\");\n}\n");
+    }
+
+    #[test]
+    fn test_apply_edit_requires_unique_match() {
+        assert!(apply_edit("a a", "a", "b", false).is_err());
+        assert_eq!(apply_edit("a a", "a", "b", true).unwrap(), "b b");
+        assert_eq!(apply_edit("x\n  y\n", "x\ny", "z", false).unwrap(), "z\n");
+
+        // 空白を無視した一致が複数ある場合も拒否する
+        let content = "a \nb\na\t\nb\n";
+        let err = apply_edit(content, "a\nb", "c", false).unwrap_err();
+        assert!(err.contains("found 2 times (ignoring whitespace)"), "{}", err);
+        assert_eq!(apply_edit(content, "a\nb", "c", true).unwrap(), "c\nc\n");
+    }
+
+    #[tokio::test]
+    async fn test_apply_multi_file_patch() {
+        let (_dir, ctx, _root) = test_workspace();
+        write(&ctx, "a.txt", "one\ntwo\nthree\n");
+        write(&ctx, "old.txt", "bye\n");
+
+        let patch = "--- a/a.txt\n+++ b/a.txt\n@@ -2,1 +2,1 @@\n-two\n+2\n--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,1 @@\n+hello\n--- a/old.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-bye\n";
+        let result = ApplyPatchTool::new()
+            .execute(json!({ "patch": patch }), &ctx)
+            .await
+            .unwrap();
+
+        assert!(!result.is_error, "{}", result.output);
+        assert_eq!(read(&ctx, "a.txt"), "one\n2\nthree\n");
+        assert_eq!(read(&ctx, "new.txt"), "hello\n");
+        assert!(!Path::new(&format!("{}/old.txt", ctx.get_user_output_dir())).exists());
+    }
+
+    #[tokio::test]
+    async fn test_failed_hunk_writes_nothing() {
+        let (_dir, ctx, _root) = test_workspace();
+        write(&ctx, "a.txt", "one\n");
+        write(&ctx, "b.txt", "two\n");
+
+        let patch = "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-one\n+ONE\n--- a/b.txt\n+++ b/b.txt\n@@ -1 +1 @@\n-missing\n+TWO\n";
+        let result = ApplyPatchTool::new()
+            .execute(json!({ "patch": patch }), &ctx)
+            .await
+            .unwrap();
+
+        assert!(result.is_error);
+        assert_eq!(read(&ctx, "a.txt"), "one\n");
+        assert_eq!(read(&ctx, "b.txt"), "two\n");
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_with_edits() {
+        let (_dir, ctx, _root) = test_workspace();
+        write(&ctx, "a.txt", "hello world\n");
+
+        let result = ApplyPatchTool::new()
+            .execute(
+                json!({
+                    "edits": [{ "path": "a.txt", "old_string": "world", "new_string": "there" }],
+                    "dry_run": true
+                }),
+                &ctx,
+            )
+            .await
+            .unwrap();
+
+        assert!(!result.is_error);
+        assert!(result.output.contains("+hello there"));
+        assert_eq!(read(&ctx, "a.txt"), "hello world\n");
+    }
+
+    #[tokio::test]
+    async fn test_path_traversal_rejected() {
+        let (_dir, ctx, _root) = test_workspace();
+        let result = ApplyPatchTool::new()
+            .execute(
+                json!({
+                    "edits": [{ "path": "../escape.txt", "old_string": "", "new_string": "x" }]
+                }),
+                &ctx,
+            )
+            .await;
+
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlink_rejected() {
+        let (dir, ctx, _root) = test_workspace();
+        let outside = dir.path().join("outside.txt");
+        std::fs::write(&outside, "secret\n").unwrap();
+        std::os::unix::fs::symlink(&outside, format!("{}/link.txt", ctx.get_user_output_dir())).unwrap();
+
+        let result = ApplyPatchTool::new()
+            .execute(
+                json!({
+                    "edits": [{ "path": "link.txt", "old_string": "secret", "new_string": "x" }]
+                }),
+                &ctx,
+            )
+            .await;
+
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+        assert_eq!(std::fs::read_to_string(outside).unwrap(), "secret\n");
+    }
+}
@@ -0,0 +1,1010 @@
+//! アーカイブツール
+//!
+//! ユーザーの作業ディレクトリ内で zip / tar.gz の一覧表示・作成・展開を行います。
+//! 展開時はすべてのエントリを書き込み前に検査し、作業ディレクトリ外への書き込み（zip-slip）、
+//! 合計サイズ・エントリ数・圧縮率の上限超過（zip bomb）、シンボリックリンク・ハードリンクを拒否します。
+//! `overwrite=true` で既存ファイルを置き換える場合は、書き込み前の内容をファイル履歴に保存します。
+
+use crate::file_history;
+use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
+use crate::tools::{ApplyPatchTool, EditTool};
+use crate::validation::PathValidator;
+use async_trait::async_trait;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::{json, Value as JsonValue};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use tar::EntryType;
+use tracing::{info, warn};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// 展開・作成できる合計サイズのデフォルト上限（100MB）
+const DEFAULT_MAX_TOTAL_BYTES: u64 = 100 * 1024 * 1024;
+/// エントリ数のデフォルト上限
+const DEFAULT_MAX_ENTRIES: usize = 2000;
+/// 圧縮率（展開後 / 圧縮後）のデフォルト上限
+const DEFAULT_MAX_RATIO: u64 = 100;
+/// 圧縮率をチェックする最小サイズ（小さなファイルは高圧縮でも問題にしない）
+const RATIO_CHECK_MIN_BYTES: u64 = 1024 * 1024;
+/// Discordに添付するアーカイブの最大サイズ
+const MAX_ATTACHMENT_BYTES: u64 = 8 * 1024 * 1024;
+/// list で返す最大エントリ数
+const MAX_LIST_ENTRIES: usize = 500;
+
+/// アーカイブ形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveFormat {
+    Zip,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// 明示指定または拡張子から形式を判定
+    fn detect(path: &str, explicit: Option<&str>) -> Result<Self, ToolError> {
+        match explicit.map(|f| f.trim().to_lowercase()).as_deref() {
+            Some("zip") => return Ok(Self::Zip),
+            Some("tar.gz") | Some("tgz") => return Ok(Self::TarGz),
+            Some("") | None => {}
+            Some(other) => {
+                return Err(ToolError::InvalidParams(format!(
+                    "Unsupported format '{}' (use zip or tar.gz)",
+                    other
+                )))
+            }
+        }
+
+        let lower = path.to_lowercase();
+        if lower.ends_with(".zip") {
+            Ok(Self::Zip)
+        } else if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+            Ok(Self::TarGz)
+        } else {
+            Err(ToolError::InvalidParams(
+                "Cannot detect archive format from extension; specify 'format' (zip or tar.gz)".to_string(),
+            ))
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::TarGz => "tar.gz",
+        }
+    }
+
+    /// 拡張子を除いたパス（展開先のデフォルト）
+    fn strip_extension(path: &str) -> &str {
+        let lower = path.to_lowercase();
+        for ext in [".tar.gz", ".tgz", ".zip"] {
+            if lower.ends_with(ext) {
+                return &path[..path.len() - ext.len()];
+            }
+        }
+        path
+    }
+}
+
+/// 展開・作成時の上限
+#[derive(Debug, Clone, Copy)]
+pub struct ArchiveLimits {
+    /// 合計サイズの上限（バイト）
+    pub max_total_bytes: u64,
+    /// エントリ数の上限
+    pub max_entries: usize,
+    /// 圧縮率の上限
+    pub max_ratio: u64,
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        Self {
+            max_total_bytes: DEFAULT_MAX_TOTAL_BYTES,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            max_ratio: DEFAULT_MAX_RATIO,
+        }
+    }
+}
+
+impl ArchiveLimits {
+    /// 圧縮率が上限を超えているか
+    fn exceeds_ratio(&self, uncompressed: u64, compressed: u64) -> bool {
+        uncompressed > RATIO_CHECK_MIN_BYTES && uncompressed > compressed.max(1).saturating_mul(self.max_ratio)
+    }
+}
+
+/// 展開前に検査済みのエントリ
+#[derive(Debug)]
+enum PlannedEntry {
+    Dir(PathBuf),
+    File { path: PathBuf, size: u64 },
+}
+
+/// 展開結果
+#[derive(Debug, Default)]
+struct ExtractSummary {
+    files: usize,
+    dirs: usize,
+    bytes: u64,
+}
+
+/// アーカイブツール
+pub struct ArchiveTool {
+    limits: ArchiveLimits,
+}
+
+impl ArchiveTool {
+    pub fn new() -> Self {
+        Self::with_limits(ArchiveLimits::default())
+    }
+
+    pub fn with_limits(limits: ArchiveLimits) -> Self {
+        Self { limits }
+    }
+
+    /// 環境変数 `ARCHIVE_MAX_TOTAL_BYTES` / `ARCHIVE_MAX_ENTRIES` / `ARCHIVE_MAX_RATIO` から作成
+    pub fn from_env() -> Self {
+        let defaults = ArchiveLimits::default();
+        let env = |key: &str| std::env::var(key).ok().and_then(|v| v.trim().parse::<u64>().ok()).filter(|v| *v > 0);
+        Self::with_limits(ArchiveLimits {
+            max_total_bytes: env("ARCHIVE_MAX_TOTAL_BYTES").unwrap_or(defaults.max_total_bytes),
+            max_entries: env("ARCHIVE_MAX_ENTRIES").map(|v| v as usize).unwrap_or(defaults.max_entries),
+            max_ratio: env("ARCHIVE_MAX_RATIO").unwrap_or(defaults.max_ratio),
+        })
+    }
+
+    fn required_str<'a>(params: &'a JsonValue, key: &str) -> Result<&'a str, ToolError> {
+        params[key]
+            .as_str()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .ok_or_else(|| ToolError::InvalidParams(format!("Missing '{}' parameter", key)))
+    }
+
+    /// アクションを実行（ブロッキング）
+    fn run(&self, action: &str, params: &JsonValue, context: &ToolContext) -> Result<JsonValue, ToolError> {
+        let path = Self::required_str(params, "path")?;
+        let format = ArchiveFormat::detect(path, params["format"].as_str())?;
+        let archive_path = ApplyPatchTool::resolve_path(path, context)?;
+
+        match action {
+            "list" => self.list(path, &archive_path, format),
+            "extract" => {
+                let dest = params["dest"]
+                    .as_str()
+                    .map(str::trim)
+                    .filter(|d| !d.is_empty())
+                    .unwrap_or_else(|| ArchiveFormat::strip_extension(path));
+                let overwrite = params["overwrite"].as_bool().unwrap_or(false);
+                self.extract(path, &archive_path, format, dest, overwrite, context)
+            }
+            "create" => {
+                let sources = match &params["sources"] {
+                    JsonValue::String(s) => vec![s.clone()],
+                    JsonValue::Array(items) => items.iter().filter_map(|v| v.as_str().map(str::to_string)).collect(),
+                    _ => Vec::new(),
+                };
+                if sources.is_empty() {
+                    return Err(ToolError::InvalidParams("Missing 'sources' parameter".to_string()));
+                }
+                let overwrite = params["overwrite"].as_bool().unwrap_or(false);
+                let attach = params["attach"].as_bool().unwrap_or(false);
+                self.create(path, &archive_path, format, &sources, overwrite, attach, context)
+            }
+            other => Err(ToolError::InvalidParams(format!(
+                "Unknown action '{}' (use list, create or extract)",
+                other
+            ))),
+        }
+    }
+
+    // ============================================
+    // list
+    // ============================================
+
+    fn list(&self, path: &str, archive_path: &Path, format: ArchiveFormat) -> Result<JsonValue, ToolError> {
+        let mut entries = Vec::new();
+        let mut total_entries = 0usize;
+        let mut total_size = 0u64;
+
+        match format {
+            ArchiveFormat::Zip => {
+                let mut archive = open_zip(archive_path)?;
+                for i in 0..archive.len() {
+                    let file = archive.by_index_raw(i).map_err(zip_error)?;
+                    total_entries += 1;
+                    total_size = total_size.saturating_add(file.size());
+                    if entries.len() < MAX_LIST_ENTRIES {
+                        let kind = if file.is_symlink() {
+                            "symlink"
+                        } else if file.is_dir() {
+                            "dir"
+                        } else {
+                            "file"
+                        };
+                        entries.push(json!({
+                            "name": file.name(),
+                            "type": kind,
+                            "size": file.size(),
+                            "compressed_size": file.compressed_size(),
+                        }));
+                    }
+                }
+            }
+            ArchiveFormat::TarGz => {
+                let mut archive = open_tar(archive_path)?;
+                for entry in archive.entries().map_err(io_error)? {
+                    let entry = entry.map_err(io_error)?;
+                    let header = entry.header();
+                    if header.entry_type() == EntryType::XGlobalHeader {
+                        continue;
+                    }
+                    total_entries += 1;
+                    // 一覧表示だけで巨大なアーカイブを最後まで展開しないよう、上限で打ち切る
+                    if total_entries > self.limits.max_entries {
+                        break;
+                    }
+                    let size = header.size().unwrap_or(0);
+                    total_size = total_size.saturating_add(size);
+                    if entries.len() < MAX_LIST_ENTRIES {
+                        entries.push(json!({
+                            "name": String::from_utf8_lossy(&entry.path_bytes()),
+                            "type": tar_kind(header.entry_type()),
+                            "size": size,
+                        }));
+                    }
+                }
+            }
+        }
+
+        Ok(json!({
+            "archive": path,
+            "format": format.as_str(),
+            "total_entries": total_entries,
+            "total_size": total_size,
+            "truncated": total_entries > entries.len(),
+            "entries": entries,
+        }))
+    }
+
+    // ============================================
+    // extract
+    // ============================================
+
+    fn extract(
+        &self,
+        path: &str,
+        archive_path: &Path,
+        format: ArchiveFormat,
+        dest: &str,
+        overwrite: bool,
+        context: &ToolContext,
+    ) -> Result<JsonValue, ToolError> {
+        if !archive_path.is_file() {
+            return Err(ToolError::InvalidParams(format!("Archive not found: {}", path)));
+        }
+        let dest_path = ApplyPatchTool::resolve_path(dest, context)?;
+        let compressed_size = fs::metadata(archive_path).map_err(io_error)?.len();
+
+        // 書き込み前に全エントリを検査する
+        let plan = match format {
+            ArchiveFormat::Zip => self.plan_zip(archive_path, &dest_path)?,
+            ArchiveFormat::TarGz => self.plan_tar(archive_path, &dest_path)?,
+        };
+        let total: u64 = plan
+            .iter()
+            .map(|e| match e {
+                PlannedEntry::File { size, .. } => *size,
+                PlannedEntry::Dir(_) => 0,
+            })
+            .sum();
+        if self.limits.exceeds_ratio(total, compressed_size) {
+            return Err(ToolError::PermissionDenied(format!(
+                "Archive compression ratio exceeds {}:1 (possible zip bomb)",
+                self.limits.max_ratio
+            )));
+        }
+        for entry in &plan {
+            let target = match entry {
+                PlannedEntry::File { path, .. } => path,
+                PlannedEntry::Dir(path) => path,
+            };
+            reject_symlink_components(&dest_path, target)?;
+            if !overwrite && matches!(entry, PlannedEntry::File { .. }) && target.exists() {
+                return Err(ToolError::InvalidParams(format!(
+                    "File already exists: {} (set overwrite=true to replace)",
+                    target.strip_prefix(&dest_path).unwrap_or(target).display()
+                )));
+            }
+        }
+
+        if overwrite {
+            let dest = Path::new(dest.trim().trim_start_matches("./"));
+            let existing: Vec<(String, &Path)> = plan
+                .iter()
+                .filter_map(|entry| match entry {
+                    PlannedEntry::File { path, .. } => {
+                        let relative = dest.join(path.strip_prefix(&dest_path).ok()?);
+                        Some((relative.to_string_lossy().to_string(), path.as_path()))
+                    }
+                    PlannedEntry::Dir(_) => None,
+                })
+                .collect();
+            snapshot_existing(context, &existing);
+        }
+
+        fs::create_dir_all(&dest_path).map_err(io_error)?;
+        let summary = match format {
+            ArchiveFormat::Zip => self.write_zip(archive_path, plan)?,
+            ArchiveFormat::TarGz => self.write_tar(archive_path, plan)?,
+        };
+
+        info!(
+            "Extracted {} ({} files, {} bytes) to {:?} for user {}",
+            path, summary.files, summary.bytes, dest_path, context.user_id
+        );
+
+        Ok(json!({
+            "archive": path,
+            "dest": dest.trim().trim_start_matches("./"),
+            "files": summary.files,
+            "dirs": summary.dirs,
+            "bytes": summary.bytes,
+        }))
+    }
+
+    /// エントリ数・合計サイズの上限を確認
+    fn check_totals(&self, entries: usize, total: u64) -> Result<(), ToolError> {
+        if entries > self.limits.max_entries {
+            return Err(ToolError::PermissionDenied(format!(
+                "Archive has too many entries (limit: {})",
+                self.limits.max_entries
+            )));
+        }
+        if total > self.limits.max_total_bytes {
+            return Err(ToolError::PermissionDenied(format!(
+                "Archive expands beyond the size limit of {} bytes",
+                self.limits.max_total_bytes
+            )));
+        }
+        Ok(())
+    }
+
+    fn plan_zip(&self, archive_path: &Path, dest: &Path) -> Result<Vec<PlannedEntry>, ToolError> {
+        let mut archive = open_zip(archive_path)?;
+        let mut plan = Vec::with_capacity(archive.len());
+        let mut total = 0u64;
+
+        for i in 0..archive.len() {
+            let file = archive.by_index_raw(i).map_err(zip_error)?;
+            let name = file.name().to_string();
+            if file.is_symlink() {
+                return Err(ToolError::PermissionDenied(format!(
+                    "Symbolic link entries are not allowed: {}",
+                    name
+                )));
+            }
+            let target = entry_path(&name, dest)?;
+            if file.is_dir() {
+                plan.push(PlannedEntry::Dir(target));
+            } else {
+                if self.limits.exceeds_ratio(file.size(), file.compressed_size()) {
+                    return Err(ToolError::PermissionDenied(format!(
+                        "Entry '{}' exceeds the compression ratio limit of {}:1 (possible zip bomb)",
+                        name, self.limits.max_ratio
+                    )));
+                }
+                total = total.saturating_add(file.size());
+                plan.push(PlannedEntry::File {
+                    path: target,
+                    size: file.size(),
+                });
+            }
+            self.check_totals(plan.len(), total)?;
+        }
+
+        Ok(plan)
+    }
+
+    fn plan_tar(&self, archive_path: &Path, dest: &Path) -> Result<Vec<PlannedEntry>, ToolError> {
+        let mut archive = open_tar(archive_path)?;
+        let mut plan = Vec::new();
+        let mut total = 0u64;
+
+        for entry in archive.entries().map_err(io_error)? {
+            let entry = entry.map_err(io_error)?;
+            let name = String::from_utf8_lossy(&entry.path_bytes()).to_string();
+            let entry_type = entry.header().entry_type();
+            match entry_type {
+                EntryType::XGlobalHeader => continue,
+                EntryType::Directory => plan.push(PlannedEntry::Dir(entry_path(&name, dest)?)),
+                EntryType::Regular | EntryType::Continuous => {
+                    let size = entry.header().size().map_err(io_error)?;
+                    total = total.saturating_add(size);
+                    plan.push(PlannedEntry::File {
+                        path: entry_path(&name, dest)?,
+                        size,
+                    });
+                }
+                EntryType::Symlink | EntryType::Link => {
+                    return Err(ToolError::PermissionDenied(format!(
+                        "Link entries are not allowed: {}",
+                        name
+                    )))
+                }
+                other => {
+                    return Err(ToolError::PermissionDenied(format!(
+                        "Unsupported entry type {:?}: {}",
+                        other, name
+                    )))
+                }
+            }
+            self.check_totals(plan.len(), total)?;
+        }
+
+        Ok(plan)
+    }
+
+    fn write_zip(&self, archive_path: &Path, plan: Vec<PlannedEntry>) -> Result<ExtractSummary, ToolError> {
+        let mut archive = open_zip(archive_path)?;
+        let mut summary = ExtractSummary::default();
+
+        for (i, entry) in plan.into_iter().enumerate() {
+            match entry {
+                PlannedEntry::Dir(path) => {
+                    fs::create_dir_all(&path).map_err(io_error)?;
+                    summary.dirs += 1;
+                }
+                PlannedEntry::File { path, size } => {
+                    let mut file = archive.by_index(i).map_err(zip_error)?;
+                    summary.bytes += write_entry(&mut file, &path, size)?;
+                    summary.files += 1;
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    fn write_tar(&self, archive_path: &Path, plan: Vec<PlannedEntry>) -> Result<ExtractSummary, ToolError> {
+        let mut archive = open_tar(archive_path)?;
+        let mut summary = ExtractSummary::default();
+        let mut plan = plan.into_iter();
+
+        for entry in archive.entries().map_err(io_error)? {
+            let mut entry = entry.map_err(io_error)?;
+            if entry.header().entry_type() == EntryType::XGlobalHeader {
+                continue;
+            }
+            // 検査時と同じ順序で読み出す（検査後に差し替えられた場合は中止）
+            match plan.next() {
+                Some(PlannedEntry::Dir(path)) => {
+                    fs::create_dir_all(&path).map_err(io_error)?;
+                    summary.dirs += 1;
+                }
+                Some(PlannedEntry::File { path, size }) => {
+                    summary.bytes += write_entry(&mut entry, &path, size)?;
+                    summary.files += 1;
+                }
+                None => {
+                    return Err(ToolError::ExecutionFailed(
+                        "Archive changed during extraction".to_string(),
+                    ))
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    // ============================================
+    // create
+    // ============================================
+
+    #[allow(clippy::too_many_arguments)]
+    fn create(
+        &self,
+        path: &str,
+        archive_path: &Path,
+        format: ArchiveFormat,
+        sources: &[String],
+        overwrite: bool,
+        attach: bool,
+        context: &ToolContext,
+    ) -> Result<JsonValue, ToolError> {
+        if archive_path.exists() && !overwrite {
+            return Err(ToolError::InvalidParams(format!(
+                "File already exists: {} (set overwrite=true to replace)",
+                path
+            )));
+        }
+
+        let base = PathBuf::from(context.get_user_output_dir());
+        let mut entries = Vec::new();
+        let mut total = 0u64;
+        let mut skipped = Vec::new();
+        for source in sources {
+            let full_path = ApplyPatchTool::resolve_path(source, context)?;
+            if !full_path.exists() {
+                return Err(ToolError::InvalidParams(format!("Path not found: {}", source)));
+            }
+            self.collect(&base, &full_path, archive_path, &mut entries, &mut total, &mut skipped)?;
+        }
+        if entries.iter().all(|(_, _, is_dir)| *is_dir) {
+            return Err(ToolError::InvalidParams("No files to archive".to_string()));
+        }
+
+        snapshot_existing(context, &[(path.to_string(), archive_path)]);
+        if let Some(parent) = archive_path.parent() {
+            fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        let file_count = entries.iter().filter(|(_, _, is_dir)| !is_dir).count();
+        let result = match format {
+            ArchiveFormat::Zip => write_zip_archive(archive_path, &entries),
+            ArchiveFormat::TarGz => write_tar_archive(archive_path, &entries),
+        };
+        if let Err(e) = result {
+            let _ = fs::remove_file(archive_path);
+            return Err(e);
+        }
+
+        let size = fs::metadata(archive_path).map_err(io_error)?.len();
+        let attached = attach && {
+            let filename = archive_path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| format!("archive.{}", format.as_str()));
+            if size > MAX_ATTACHMENT_BYTES {
+                warn!("Archive {} is too large to attach ({} bytes)", path, size);
+                false
+            } else {
+                let data = fs::read(archive_path).map_err(io_error)?;
+                context.add_attachment(filename, data)
+            }
+        };
+
+        info!(
+            "Created {} ({} files, {} bytes) for user {}",
+            path, file_count, size, context.user_id
+        );
+
+        Ok(json!({
+            "archive": path.trim().trim_start_matches("./"),
+            "format": format.as_str(),
+            "files": file_count,
+            "input_bytes": total,
+            "size": size,
+            "attached": attached,
+            "skipped_symlinks": skipped,
+        }))
+    }
+
+    /// ファイル・ディレクトリを再帰的に収集（シンボリックリンクは辿らずスキップ）
+    fn collect(
+        &self,
+        base: &Path,
+        path: &Path,
+        archive_path: &Path,
+        entries: &mut Vec<(String, PathBuf, bool)>,
+        total: &mut u64,
+        skipped: &mut Vec<String>,
+    ) -> Result<(), ToolError> {
+        let relative = path.strip_prefix(base).unwrap_or(path);
+        let name = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let metadata = fs::symlink_metadata(path).map_err(io_error)?;
+        if metadata.file_type().is_symlink() {
+            skipped.push(name);
+            return Ok(());
+        }
+        // 作成中のアーカイブ自身は含めない
+        if path == archive_path {
+            return Ok(());
+        }
+
+        if metadata.is_dir() {
+            if !name.is_empty() && !entries.iter().any(|(n, _, _)| *n == name) {
+                entries.push((name, path.to_path_buf(), true));
+            }
+            let mut children: Vec<PathBuf> = fs::read_dir(path)
+                .map_err(io_error)?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .collect();
+            children.sort();
+            for child in children {
+                self.collect(base, &child, archive_path, entries, total, skipped)?;
+            }
+        } else if metadata.is_file() {
+            if entries.iter().any(|(n, _, _)| *n == name) {
+                return Ok(());
+            }
+            *total = total.saturating_add(metadata.len());
+            entries.push((name, path.to_path_buf(), false));
+        }
+
+        self.check_totals(entries.len(), *total)
+    }
+}
+
+impl Default for ArchiveTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// エントリ名を検証し、展開先の実パスに変換（zip-slip 対策）
+fn entry_path(name: &str, dest: &Path) -> Result<PathBuf, ToolError> {
+    let trimmed = name.trim_start_matches("./").trim_end_matches('/');
+    if trimmed.is_empty() || trimmed.contains('\\') || trimmed.contains('\0') {
+        return Err(ToolError::PermissionDenied(format!("Unsafe entry name: {:?}", name)));
+    }
+    PathValidator::new(dest)
+        .validate_path(trimmed)
+        .map_err(|e| ToolError::PermissionDenied(format!("Unsafe entry '{}': {}", name, e)))
+}
+
+/// 展開先以下の既存パスにシンボリックリンクが含まれていないか確認
+fn reject_symlink_components(dest: &Path, target: &Path) -> Result<(), ToolError> {
+    let relative = target.strip_prefix(dest).unwrap_or(target);
+    let mut current = dest.to_path_buf();
+    for component in relative.components() {
+        current.push(component);
+        if EditTool::is_symlink(&current) {
+            return Err(ToolError::PermissionDenied(
+                "Symbolic links are not allowed for security reasons".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// エントリをファイルに書き出す（宣言サイズを超えるデータは zip bomb として中止）
+fn write_entry(reader: &mut impl Read, path: &Path, size: u64) -> Result<u64, ToolError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(io_error)?;
+    }
+    let mut out = BufWriter::new(File::create(path).map_err(io_error)?);
+    let written = io::copy(&mut reader.take(size.saturating_add(1)), &mut out).map_err(io_error)?;
+    out.flush().map_err(io_error)?;
+    if written > size {
+        drop(out);
+        let _ = fs::remove_file(path);
+        return Err(ToolError::PermissionDenied(format!(
+            "Entry {} is larger than its declared size (possible zip bomb)",
+            path.display()
+        )));
+    }
+    Ok(written)
+}
+
+fn write_zip_archive(archive_path: &Path, entries: &[(String, PathBuf, bool)]) -> Result<(), ToolError> {
+    let mut writer = ZipWriter::new(BufWriter::new(File::create(archive_path).map_err(io_error)?));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, path, is_dir) in entries {
+        if *is_dir {
+            writer.add_directory(format!("{}/", name), options).map_err(zip_error)?;
+        } else {
+            writer.start_file(name, options).map_err(zip_error)?;
+            let mut file = File::open(path).map_err(io_error)?;
+            io::copy(&mut file, &mut writer).map_err(io_error)?;
+        }
+    }
+    writer.finish().map_err(zip_error)?.flush().map_err(io_error)
+}
+
+fn write_tar_archive(archive_path: &Path, entries: &[(String, PathBuf, bool)]) -> Result<(), ToolError> {
+    let encoder = GzEncoder::new(BufWriter::new(File::create(archive_path).map_err(io_error)?), Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    builder.follow_symlinks(false);
+    for (name, path, is_dir) in entries {
+        if *is_dir {
+            builder.append_dir(name, path).map_err(io_error)?;
+        } else {
+            builder.append_path_with_name(path, name).map_err(io_error)?;
+        }
+    }
+    builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .and_then(|mut out| out.flush())
+        .map_err(io_error)
+}
+
+/// 上書きする既存ファイルを履歴に保存（ブロッキングスレッドから呼び出す）
+fn snapshot_existing(context: &ToolContext, files: &[(String, &Path)]) {
+    let Ok(handle) = tokio::runtime::Handle::try_current() else {
+        warn!("No runtime available, skipping file history snapshots");
+        return;
+    };
+    for (path, file) in files.iter().filter(|(_, file)| file.is_file()) {
+        handle.block_on(file_history::snapshot_before_write(context, path, file, "archive"));
+    }
+}
+
+fn open_zip(path: &Path) -> Result<ZipArchive<BufReader<File>>, ToolError> {
+    let file = File::open(path).map_err(io_error)?;
+    ZipArchive::new(BufReader::new(file)).map_err(zip_error)
+}
+
+fn open_tar(path: &Path) -> Result<tar::Archive<GzDecoder<BufReader<File>>>, ToolError> {
+    let file = File::open(path).map_err(io_error)?;
+    Ok(tar::Archive::new(GzDecoder::new(BufReader::new(file))))
+}
+
+fn tar_kind(entry_type: EntryType) -> &'static str {
+    match entry_type {
+        EntryType::Directory => "dir",
+        EntryType::Symlink => "symlink",
+        EntryType::Link => "hardlink",
+        EntryType::Regular | EntryType::Continuous => "file",
+        _ => "other",
+    }
+}
+
+fn io_error(e: io::Error) -> ToolError {
+    ToolError::ExecutionFailed(e.to_string())
+}
+
+fn zip_error(e: zip::result::ZipError) -> ToolError {
+    ToolError::ExecutionFailed(e.to_string())
+}
+
+#[async_trait]
+impl Tool for ArchiveTool {
+    fn name(&self) -> &str {
+        "archive"
+    }
+
+    fn description(&self) -> &str {
+        "List, create or extract zip / tar.gz archives inside your workspace. \
+         'create' packs files and directories (symlinks are skipped) and can attach the archive to the reply with attach=true. \
+         'extract' unpacks into 'dest' (default: archive name without extension) and refuses entries that escape the destination, \
+         symlink/hardlink entries, and archives that exceed the size, entry count or compression ratio limits."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["list", "create", "extract"],
+                    "description": "Operation to perform"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Archive path relative to your workspace (e.g. 'backup.zip', 'logs.tar.gz')"
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["zip", "tar.gz"],
+                    "description": "Archive format (default: detected from the extension)"
+                },
+                "sources": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "create: files or directories to include (relative to your workspace)"
+                },
+                "dest": {
+                    "type": "string",
+                    "description": "extract: destination directory relative to your workspace"
+                },
+                "overwrite": {
+                    "type": "boolean",
+                    "description": "Replace existing files (default: false)"
+                },
+                "attach": {
+                    "type": "boolean",
+                    "description": "create: attach the archive to the reply (max 8MB, default: false)"
+                }
+            },
+            "required": ["action", "path"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let action = Self::required_str(&params, "action")?.to_lowercase();
+
+        info!("archive {} for user {}", action, context.user_id);
+
+        let tool = Self::with_limits(self.limits);
+        let context = context.clone();
+        let result = tokio::task::spawn_blocking(move || tool.run(&action, &params, &context))
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("archive task failed: {}", e)))?;
+
+        match result {
+            Ok(value) => Ok(ToolResult::success(serde_json::to_string_pretty(&value)?)),
+            Err(ToolError::ExecutionFailed(message)) => Ok(ToolResult::error(format!("archive error: {}", message))),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::test_workspace;
+
+    fn setup() -> (tempfile::TempDir, ToolContext, PathBuf) {
+        let (dir, ctx, root) = test_workspace();
+        fs::create_dir_all(root.join("docs/sub")).unwrap();
+        fs::write(root.join("docs/a.txt"), "hello").unwrap();
+        fs::write(root.join("docs/sub/b.txt"), "world").unwrap();
+        (dir, ctx, root)
+    }
+
+    async fn run(tool: &ArchiveTool, ctx: &ToolContext, params: JsonValue) -> Result<JsonValue, ToolError> {
+        let result = tool.execute(params, ctx).await?;
+        assert!(!result.is_error, "{}", result.output);
+        Ok(serde_json::from_str(&result.output).unwrap())
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(ArchiveFormat::detect("a.zip", None).unwrap(), ArchiveFormat::Zip);
+        assert_eq!(ArchiveFormat::detect("a.TAR.GZ", None).unwrap(), ArchiveFormat::TarGz);
+        assert_eq!(ArchiveFormat::detect("a.bin", Some("tgz")).unwrap(), ArchiveFormat::TarGz);
+        assert!(ArchiveFormat::detect("a.rar", None).is_err());
+        assert!(ArchiveFormat::detect("a.zip", Some("7z")).is_err());
+        assert_eq!(ArchiveFormat::strip_extension("out/logs.tar.gz"), "out/logs");
+    }
+
+    #[test]
+    fn test_entry_path_rejects_zip_slip() {
+        let dest = Path::new("/tmp/work/out");
+        assert_eq!(entry_path("./dir/a.txt", dest).unwrap(), dest.join("dir/a.txt"));
+        assert!(entry_path("../evil.txt", dest).is_err());
+        assert!(entry_path("dir/../../evil.txt", dest).is_err());
+        assert!(entry_path("/etc/passwd", dest).is_err());
+        assert!(entry_path("..\\evil.txt", dest).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_zip_roundtrip_with_attachment() {
+        let (_dir, ctx, root) = setup();
+        let tool = ArchiveTool::new();
+
+        let created = run(
+            &tool,
+            &ctx,
+            json!({"action": "create", "path": "out/docs.zip", "sources": ["docs"], "attach": true}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(created["files"], 2);
+        assert_eq!(created["attached"], true);
+        let attachments = ctx.take_attachments();
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(attachments[0].filename, "docs.zip");
+
+        let listed = run(&tool, &ctx, json!({"action": "list", "path": "out/docs.zip"})).await.unwrap();
+        let names: Vec<&str> = listed["entries"].as_array().unwrap().iter().map(|e| e["name"].as_str().unwrap()).collect();
+        assert!(names.contains(&"docs/a.txt"));
+        assert!(names.contains(&"docs/sub/b.txt"));
+
+        let extracted = run(&tool, &ctx, json!({"action": "extract", "path": "out/docs.zip"})).await.unwrap();
+        assert_eq!(extracted["files"], 2);
+        assert_eq!(fs::read_to_string(root.join("out/docs/docs/sub/b.txt")).unwrap(), "world");
+
+        // 既存ファイルは overwrite=true でなければ上書きしない
+        let result = tool
+            .execute(json!({"action": "extract", "path": "out/docs.zip"}), &ctx)
+            .await;
+        assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+    }
+
+    #[tokio::test]
+    async fn test_overwrite_snapshots_existing_files() {
+        let (_dir, ctx, root) = setup();
+        let tool = ArchiveTool::new();
+        let history = file_history::FileHistory::for_context(&ctx);
+
+        run(&tool, &ctx, json!({"action": "create", "path": "docs.zip", "sources": ["docs"]}))
+            .await
+            .unwrap();
+        run(&tool, &ctx, json!({"action": "extract", "path": "docs.zip", "dest": "out"}))
+            .await
+            .unwrap();
+        fs::write(root.join("out/docs/a.txt"), "edited").unwrap();
+
+        run(&tool, &ctx, json!({"action": "extract", "path": "docs.zip", "dest": "out", "overwrite": true}))
+            .await
+            .unwrap();
+        assert_eq!(fs::read_to_string(root.join("out/docs/a.txt")).unwrap(), "hello");
+        let version = history.get("out/docs/a.txt", None).await.unwrap();
+        assert_eq!(version.source, "archive");
+        assert_eq!(history.read(&version).await.unwrap().unwrap(), b"edited");
+
+        run(&tool, &ctx, json!({"action": "create", "path": "docs.zip", "sources": ["docs/a.txt"], "overwrite": true}))
+            .await
+            .unwrap();
+        assert_eq!(history.list("docs.zip").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tar_gz_roundtrip() {
+        let (_dir, ctx, root) = setup();
+        let tool = ArchiveTool::new();
+
+        run(&tool, &ctx, json!({"action": "create", "path": "docs.tgz", "sources": ["docs/a.txt", "docs/sub"]}))
+            .await
+            .unwrap();
+        assert!(ctx.take_attachments().is_empty());
+
+        let extracted = run(&tool, &ctx, json!({"action": "extract", "path": "docs.tgz", "dest": "restored"}))
+            .await
+            .unwrap();
+        assert_eq!(extracted["files"], 2);
+        assert_eq!(fs::read_to_string(root.join("restored/docs/a.txt")).unwrap(), "hello");
+    }
+
+    #[tokio::test]
+    async fn test_extract_rejects_zip_slip_and_symlinks() {
+        let (dir, ctx, root) = setup();
+        let tool = ArchiveTool::new();
+        let options = SimpleFileOptions::default();
+
+        let mut writer = ZipWriter::new(File::create(root.join("slip.zip")).unwrap());
+        writer.start_file("ok.txt", options).unwrap();
+        writer.write_all(b"ok").unwrap();
+        writer.start_file("../../evil.txt", options).unwrap();
+        writer.write_all(b"evil").unwrap();
+        writer.finish().unwrap();
+
+        let result = tool.execute(json!({"action": "extract", "path": "slip.zip"}), &ctx).await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+        // 検査で拒否された場合は何も書き込まない
+        assert!(!root.join("slip").exists());
+        assert!(!dir.path().join("evil.txt").exists());
+
+        let mut writer = ZipWriter::new(File::create(root.join("link.zip")).unwrap());
+        writer.add_symlink("passwd", "/etc/passwd", options).unwrap();
+        writer.finish().unwrap();
+        let result = tool.execute(json!({"action": "extract", "path": "link.zip"}), &ctx).await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+
+        let encoder = GzEncoder::new(File::create(root.join("link.tar.gz")).unwrap(), Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(EntryType::Symlink);
+        header.set_size(0);
+        builder.append_link(&mut header, "passwd", "/etc/passwd").unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+        let result = tool.execute(json!({"action": "extract", "path": "link.tar.gz"}), &ctx).await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+        assert!(!root.join("link/passwd").exists());
+    }
+
+    #[tokio::test]
+    async fn test_extract_rejects_zip_bomb() {
+        let (_dir, ctx, root) = setup();
+        let mut writer = ZipWriter::new(File::create(root.join("bomb.zip")).unwrap());
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        writer.start_file("zeros.bin", options).unwrap();
+        writer.write_all(&vec![0u8; 4 * 1024 * 1024]).unwrap();
+        writer.finish().unwrap();
+
+        // 圧縮率の上限
+        let tool = ArchiveTool::new();
+        let result = tool.execute(json!({"action": "extract", "path": "bomb.zip"}), &ctx).await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(msg)) if msg.contains("ratio")));
+
+        // 合計サイズの上限
+        let tool = ArchiveTool::with_limits(ArchiveLimits {
+            max_total_bytes: 1024 * 1024,
+            max_ratio: u64::MAX,
+            ..ArchiveLimits::default()
+        });
+        let result = tool.execute(json!({"action": "extract", "path": "bomb.zip"}), &ctx).await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(msg)) if msg.contains("size limit")));
+        assert!(!root.join("bomb/zeros.bin").exists());
+    }
+}
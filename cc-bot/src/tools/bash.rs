@@ -1,17 +1,26 @@
+use super::shell_session::{build_shell_command, ShellSessionManager};
+use crate::session::SessionKey;
 use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
 use async_trait::async_trait;
 use serde_json::{json, Value as JsonValue};
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::process::Command as TokioCommand;
 use tokio::time::timeout;
 use tracing::{debug, warn};
 
 /// Bashツール（クロスプラットフォームシェルコマンド実行）
-pub struct BashTool;
+pub struct BashTool {
+    sessions: Arc<ShellSessionManager>,
+}
 
 impl BashTool {
     pub fn new() -> Self {
-        Self
+        Self::with_sessions(Arc::new(ShellSessionManager::default()))
+    }
+
+    /// 永続シェル・ジョブ管理を共有して作成
+    pub fn with_sessions(sessions: Arc<ShellSessionManager>) -> Self {
+        Self { sessions }
     }
 
     /// 危険なコマンドをチェック
@@ -69,25 +78,9 @@ impl BashTool {
     ) -> Result<(String, String, bool), String> {
         let timeout_duration = Duration::from_secs(timeout_secs);
 
-        #[cfg(windows)]
-        let result = {
-            let cmd = TokioCommand::new("powershell")
-                .args(["-Command", command])
-                .current_dir(working_dir)
-                .output();
-
-            timeout(timeout_duration, cmd).await
-        };
-
-        #[cfg(not(windows))]
-        let result = {
-            let cmd = TokioCommand::new("sh")
-                .args(["-c", command])
-                .current_dir(working_dir)
-                .output();
-
-            timeout(timeout_duration, cmd).await
-        };
+        let mut cmd = build_shell_command(command, working_dir);
+        cmd.kill_on_drop(true);
+        let result = timeout(timeout_duration, cmd.output()).await;
 
         match result {
             Ok(Ok(output)) => {
@@ -95,7 +88,7 @@ impl BashTool {
                 let stderr = String::from_utf8_lossy(&output.stderr).to_string();
                 Ok((stdout, stderr, output.status.success()))
             }
-            Ok(Err(e)) => Err("Failed to execute command. Please check the command syntax.".to_string()),
+            Ok(Err(_)) => Err("Failed to execute command. Please check the command syntax.".to_string()),
             Err(_) => Err(format!("Command timed out after {} seconds", timeout_secs)),
         }
     }
@@ -108,7 +101,7 @@ impl Tool for BashTool {
     }
 
     fn description(&self) -> &str {
-        "Execute shell commands. Uses PowerShell on Windows and sh on Unix. Commands are executed in a sandboxed environment with safety restrictions."
+        "Execute shell commands. Uses PowerShell on Windows and sh on Unix. Commands are executed in a sandboxed environment with safety restrictions. Set persistent=true to keep cwd and environment variables across calls, or background=true to start a long-running job and check it later with bash_jobs."
     }
 
     fn parameters_schema(&self) -> JsonValue {
@@ -122,6 +115,14 @@ impl Tool for BashTool {
                 "timeout": {
                     "type": "integer",
                     "description": "Timeout in seconds (default: 30, max: 60)"
+                },
+                "persistent": {
+                    "type": "boolean",
+                    "description": "Run in a persistent shell that keeps cwd and environment between calls (default: false, Unix only)"
+                },
+                "background": {
+                    "type": "boolean",
+                    "description": "Start the command as a background job and return its job id (default: false)"
                 }
             },
             "required": ["command"]
@@ -134,6 +135,14 @@ impl Tool for BashTool {
         })?;
 
         let timeout_secs = params["timeout"].as_u64().unwrap_or(30).min(60);
+        let persistent = params["persistent"].as_bool().unwrap_or(false);
+        let background = params["background"].as_bool().unwrap_or(false);
+
+        if persistent && background {
+            return Err(ToolError::InvalidParams(
+                "'persistent' and 'background' cannot be used together".to_string(),
+            ));
+        }
 
         debug!("Executing command: {} (timeout: {}s)", command, timeout_secs);
 
@@ -162,8 +171,44 @@ impl Tool for BashTool {
             })?;
         }
 
+        // バックグラウンドジョブとして開始
+        if background {
+            return match self
+                .sessions
+                .spawn_job(context.user_id, command, &working_dir)
+                .await
+            {
+                Ok(job_id) => Ok(ToolResult::success(format!(
+                    "Started background job: {}\nUse the bash_jobs tool to poll or kill it.",
+                    job_id
+                ))),
+                Err(e) => {
+                    warn!("Failed to start background job: {}", e);
+                    Ok(ToolResult::error(e))
+                }
+            };
+        }
+
         // コマンド実行（非同期、タイムアウト付き）
-        match Self::execute_command_async(command, timeout_secs, &working_dir).await {
+        let result = if persistent {
+            if cfg!(windows) {
+                return Err(ToolError::InvalidParams(
+                    "Persistent shell sessions are not supported on Windows".to_string(),
+                ));
+            }
+            let key = SessionKey::new(context.user_id, context.channel_id);
+            self.sessions
+                .run_persistent(key, command, timeout_secs, &working_dir)
+                .await
+                .map(|output| {
+                    let success = output.success();
+                    (output.stdout, output.stderr, success)
+                })
+        } else {
+            Self::execute_command_async(command, timeout_secs, &working_dir).await
+        };
+
+        match result {
             Ok((stdout, stderr, success)) => {
                 if success {
                     let output = if stdout.trim().is_empty() {
@@ -378,4 +423,74 @@ mod tests {
         assert!(result.is_err());
         assert!(matches!(result, Err(ToolError::ExecutionFailed(_))));
     }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_bash_persistent_keeps_environment() {
+        let tool = BashTool::new();
+        let ctx = create_test_context();
+
+        tool.execute(
+            json!({
+                "command": "export CC_BOT_TEST_VAR=kept",
+                "persistent": true
+            }),
+            &ctx,
+        )
+        .await
+        .unwrap();
+
+        let result = tool
+            .execute(
+                json!({
+                    "command": "echo $CC_BOT_TEST_VAR",
+                    "persistent": true
+                }),
+                &ctx,
+            )
+            .await
+            .unwrap();
+
+        assert!(!result.is_error);
+        assert!(result.output.contains("kept"));
+    }
+
+    #[tokio::test]
+    async fn test_bash_background_returns_job_id() {
+        let tool = BashTool::new();
+        let ctx = create_test_context();
+
+        let result = tool
+            .execute(
+                json!({
+                    "command": "echo background",
+                    "background": true
+                }),
+                &ctx,
+            )
+            .await
+            .unwrap();
+
+        assert!(!result.is_error);
+        assert!(result.output.contains("Started background job"));
+    }
+
+    #[tokio::test]
+    async fn test_bash_persistent_and_background_conflict() {
+        let tool = BashTool::new();
+        let ctx = create_test_context();
+
+        let result = tool
+            .execute(
+                json!({
+                    "command": "echo hello",
+                    "persistent": true,
+                    "background": true
+                }),
+                &ctx,
+            )
+            .await;
+
+        assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+    }
 }
@@ -0,0 +1,189 @@
+use super::shell_session::{JobSnapshot, ShellSessionManager};
+use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
+use async_trait::async_trait;
+use serde_json::{json, Value as JsonValue};
+use std::sync::Arc;
+use tracing::debug;
+
+/// ポーリング時に返す出力の最大文字数（末尾を返す）
+const MAX_POLL_OUTPUT_CHARS: usize = 4000;
+
+/// バックグラウンドジョブ管理ツール（bash の background モード用）
+pub struct BashJobsTool {
+    sessions: Arc<ShellSessionManager>,
+}
+
+impl BashJobsTool {
+    pub fn new(sessions: Arc<ShellSessionManager>) -> Self {
+        Self { sessions }
+    }
+
+    /// 出力の末尾を切り出す
+    fn tail(text: &str, max_chars: usize) -> String {
+        let count = text.chars().count();
+        if count <= max_chars {
+            text.to_string()
+        } else {
+            let tail: String = text.chars().skip(count - max_chars).collect();
+            format!("...(truncated)\n{}", tail)
+        }
+    }
+
+    fn format_snapshot(job: &JobSnapshot) -> String {
+        let mut output = format!(
+            "Job {} [{}]\nCommand: {}\nStarted: {}",
+            job.id,
+            job.status,
+            job.command,
+            job.started_at.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+
+        if !job.stdout.is_empty() {
+            output.push_str(&format!(
+                "\n\nstdout:\n{}",
+                Self::tail(&job.stdout, MAX_POLL_OUTPUT_CHARS)
+            ));
+        }
+        if !job.stderr.is_empty() {
+            output.push_str(&format!(
+                "\n\nstderr:\n{}",
+                Self::tail(&job.stderr, MAX_POLL_OUTPUT_CHARS)
+            ));
+        }
+
+        output
+    }
+}
+
+#[async_trait]
+impl Tool for BashJobsTool {
+    fn name(&self) -> &str {
+        "bash_jobs"
+    }
+
+    fn description(&self) -> &str {
+        "Manage background jobs started by the bash tool with background=true. Actions: list (all your jobs), poll (status and output of a job), kill (stop a running job)."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["list", "poll", "kill"],
+                    "description": "Action to perform"
+                },
+                "job_id": {
+                    "type": "string",
+                    "description": "Job id returned by the bash tool (required for poll and kill)"
+                }
+            },
+            "required": ["action"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let action = params["action"].as_str().ok_or_else(|| {
+            ToolError::InvalidParams("Missing 'action' parameter".to_string())
+        })?;
+
+        debug!("bash_jobs action '{}' for user {}", action, context.user_id);
+
+        match action {
+            "list" => {
+                let jobs = self.sessions.list_jobs(context.user_id).await;
+                if jobs.is_empty() {
+                    return Ok(ToolResult::success("No background jobs".to_string()));
+                }
+                let lines: Vec<String> = jobs
+                    .iter()
+                    .map(|job| format!("{} [{}] {}", job.id, job.status, job.command))
+                    .collect();
+                Ok(ToolResult::success(lines.join("\n")))
+            }
+            "poll" | "kill" => {
+                let job_id = params["job_id"].as_str().ok_or_else(|| {
+                    ToolError::InvalidParams("Missing 'job_id' parameter".to_string())
+                })?;
+
+                if action == "kill" {
+                    return match self.sessions.kill_job(context.user_id, job_id).await {
+                        Ok(()) => Ok(ToolResult::success(format!("Killed job: {}", job_id))),
+                        Err(e) => Ok(ToolResult::error(e)),
+                    };
+                }
+
+                match self.sessions.poll_job(context.user_id, job_id).await {
+                    Some(job) => Ok(ToolResult::success(Self::format_snapshot(&job))),
+                    None => Ok(ToolResult::error(format!("Job not found: {}", job_id))),
+                }
+            }
+            other => Err(ToolError::InvalidParams(format!(
+                "Unknown action: {} (expected list, poll or kill)",
+                other
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_context() -> ToolContext {
+        ToolContext::new(123, "test_user".to_string(), 456, "output".to_string())
+    }
+
+    #[test]
+    fn test_tail_truncates() {
+        assert_eq!(BashJobsTool::tail("abc", 10), "abc");
+        assert_eq!(BashJobsTool::tail("abcdef", 3), "...(truncated)\ndef");
+    }
+
+    #[tokio::test]
+    async fn test_list_empty() {
+        let tool = BashJobsTool::new(Arc::new(ShellSessionManager::default()));
+        let result = tool
+            .execute(json!({"action": "list"}), &create_test_context())
+            .await
+            .unwrap();
+        assert_eq!(result.output, "No background jobs");
+    }
+
+    #[tokio::test]
+    async fn test_poll_unknown_job() {
+        let tool = BashJobsTool::new(Arc::new(ShellSessionManager::default()));
+        let result = tool
+            .execute(json!({"action": "poll", "job_id": "missing"}), &create_test_context())
+            .await
+            .unwrap();
+        assert!(result.is_error);
+    }
+
+    #[tokio::test]
+    async fn test_poll_requires_job_id() {
+        let tool = BashJobsTool::new(Arc::new(ShellSessionManager::default()));
+        let result = tool
+            .execute(json!({"action": "poll"}), &create_test_context())
+            .await;
+        assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+    }
+
+    #[tokio::test]
+    async fn test_list_shows_spawned_job() {
+        let sessions = Arc::new(ShellSessionManager::default());
+        let tool = BashJobsTool::new(sessions.clone());
+        let dir = tempfile::tempdir().unwrap();
+        let id = sessions
+            .spawn_job(123, "echo hi", dir.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        let result = tool
+            .execute(json!({"action": "list"}), &create_test_context())
+            .await
+            .unwrap();
+        assert!(result.output.contains(&id));
+    }
+}
@@ -0,0 +1,1095 @@
+//! 構造化データクエリツール
+//!
+//! 作業ディレクトリ内の JSON / JSON Lines / YAML / TOML / CSV ファイルに対して
+//! jq 式（jaq）を実行し、必要な部分だけを返します。CSV は条件による絞り込みと
+//! グループ集計を行ったうえで、行オブジェクトの配列として jq 式に渡します。
+//! 大きなファイルを `read_file` で丸ごと読み込まずに済ませるためのツールです。
+//! jaq には評価を中断する仕組みがないため、Linux ではクエリを CPU 時間・メモリを制限した
+//! 子プロセス（ボット自身の実行ファイルを隠しサブコマンドで起動）で実行し、時間切れの場合は
+//! プロセスごと強制終了します。
+
+use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
+use crate::tools::{ApplyPatchTool, SqlQueryTool};
+use async_trait::async_trait;
+use jaq_core::load::{Arena, File as JaqFile, Loader};
+use jaq_core::{Compiler, Ctx, RcIter};
+use jaq_json::Val;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value as JsonValue};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// 読み込むファイルの最大サイズ
+const MAX_FILE_BYTES: u64 = 50 * 1024 * 1024;
+/// 結果に含める最大文字数
+const MAX_OUTPUT_CHARS: usize = 15000;
+/// 返す結果のデフォルト件数
+const DEFAULT_MAX_RESULTS: usize = 100;
+/// 返す結果の最大件数
+const MAX_RESULTS: usize = 1000;
+/// クエリの実行時間上限
+const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+/// クエリを実行する子プロセスが追加で使えるメモリ
+#[cfg(target_os = "linux")]
+const QUERY_MEMORY_LIMIT: u64 = 512 * 1024 * 1024;
+
+/// 使用を禁止する組み込み関数（環境変数の読み取り、プロセス終了、標準エラー出力）
+const BLOCKED_FILTERS: &[&str] = &["env", "halt", "halt_error", "debug", "stderr"];
+
+/// ファイル形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum DataFormat {
+    Json,
+    JsonLines,
+    Yaml,
+    Toml,
+    Csv,
+}
+
+impl DataFormat {
+    /// 明示指定または拡張子から形式を判定
+    fn detect(path: &str, explicit: Option<&str>) -> Result<Self, ToolError> {
+        let name = match explicit.map(|f| f.trim().to_lowercase()) {
+            Some(f) if !f.is_empty() => f,
+            _ => Path::new(path)
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default(),
+        };
+        match name.as_str() {
+            "json" => Ok(Self::Json),
+            "jsonl" | "ndjson" => Ok(Self::JsonLines),
+            "yaml" | "yml" => Ok(Self::Yaml),
+            "toml" => Ok(Self::Toml),
+            "csv" | "tsv" => Ok(Self::Csv),
+            other => Err(ToolError::InvalidParams(format!(
+                "Unsupported format '{}' (use json, jsonl, yaml, toml, csv or tsv)",
+                other
+            ))),
+        }
+    }
+}
+
+/// CSV の比較演算子
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Contains,
+}
+
+/// CSV の絞り込み条件（例: `amount >= 100`, `status == done`, `name contains 田中`）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Condition {
+    column: String,
+    op: CompareOp,
+    value: String,
+}
+
+impl Condition {
+    fn parse(input: &str) -> Result<Self, String> {
+        let (column, op, value) = if let Some((column, value)) = input.split_once(" contains ") {
+            (column, CompareOp::Contains, value)
+        } else {
+            const OPS: &[(&str, CompareOp)] = &[
+                ("==", CompareOp::Eq),
+                ("!=", CompareOp::Ne),
+                (">=", CompareOp::Ge),
+                ("<=", CompareOp::Le),
+                (">", CompareOp::Gt),
+                ("<", CompareOp::Lt),
+                ("=", CompareOp::Eq),
+            ];
+            input
+                .char_indices()
+                .find_map(|(i, _)| {
+                    OPS.iter()
+                        .find(|(token, _)| input[i..].starts_with(token))
+                        .map(|(token, op)| (&input[..i], *op, &input[i + token.len()..]))
+                })
+                .ok_or_else(|| format!("Invalid condition '{}' (e.g. \"amount >= 100\")", input))?
+        };
+
+        let column = column.trim();
+        if column.is_empty() {
+            return Err(format!("Invalid condition '{}': missing column", input));
+        }
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+            .unwrap_or(value);
+
+        Ok(Self {
+            column: column.to_string(),
+            op,
+            value: value.to_string(),
+        })
+    }
+
+    fn matches(&self, cell: &str) -> bool {
+        if self.op == CompareOp::Contains {
+            return cell.to_lowercase().contains(&self.value.to_lowercase());
+        }
+        let ordering = match (cell.trim().parse::<f64>(), self.value.parse::<f64>()) {
+            (Ok(a), Ok(b)) => a.partial_cmp(&b),
+            _ => Some(cell.cmp(self.value.as_str())),
+        };
+        match (self.op, ordering) {
+            (CompareOp::Eq, Some(o)) => o == Ordering::Equal,
+            (CompareOp::Ne, Some(o)) => o != Ordering::Equal,
+            (CompareOp::Gt, Some(o)) => o == Ordering::Greater,
+            (CompareOp::Ge, Some(o)) => o != Ordering::Less,
+            (CompareOp::Lt, Some(o)) => o == Ordering::Less,
+            (CompareOp::Le, Some(o)) => o != Ordering::Greater,
+            _ => false,
+        }
+    }
+}
+
+/// CSV の集計関数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum AggregateFn {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+/// CSV の集計指定（例: `count`, `sum:amount`）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Aggregate {
+    function: AggregateFn,
+    column: Option<String>,
+}
+
+impl Aggregate {
+    fn parse(input: &str) -> Result<Self, String> {
+        let (name, column) = match input.split_once(':') {
+            Some((name, column)) => (name.trim().to_lowercase(), Some(column.trim().to_string())),
+            None => (input.trim().to_lowercase(), None),
+        };
+        let function = match name.as_str() {
+            "count" => AggregateFn::Count,
+            "sum" => AggregateFn::Sum,
+            "avg" => AggregateFn::Avg,
+            "min" => AggregateFn::Min,
+            "max" => AggregateFn::Max,
+            _ => return Err(format!("Unknown aggregate '{}' (use count, sum, avg, min or max)", input)),
+        };
+        if function != AggregateFn::Count && column.as_deref().is_none_or(str::is_empty) {
+            return Err(format!("Aggregate '{}' needs a column (e.g. \"{}:amount\")", input, name));
+        }
+        Ok(Self { function, column })
+    }
+
+    /// 結果のキー名（例: `sum_amount`）
+    fn key(&self) -> String {
+        let name = match self.function {
+            AggregateFn::Count => "count",
+            AggregateFn::Sum => "sum",
+            AggregateFn::Avg => "avg",
+            AggregateFn::Min => "min",
+            AggregateFn::Max => "max",
+        };
+        match &self.column {
+            Some(column) => format!("{}_{}", name, column),
+            None => name.to_string(),
+        }
+    }
+
+    fn compute(&self, rows: &[&Vec<String>], index: Option<usize>) -> JsonValue {
+        let cells = || rows.iter().filter_map(move |row| index.and_then(|i| row.get(i))).map(|c| c.trim());
+        let numbers = || cells().filter_map(|c| c.parse::<f64>().ok());
+        match self.function {
+            AggregateFn::Count => match index {
+                Some(_) => json!(cells().filter(|c| !c.is_empty()).count()),
+                None => json!(rows.len()),
+            },
+            AggregateFn::Sum => number_value(numbers().sum()),
+            AggregateFn::Avg => {
+                let (sum, count) = numbers().fold((0.0, 0usize), |(s, n), v| (s + v, n + 1));
+                if count == 0 {
+                    JsonValue::Null
+                } else {
+                    number_value(sum / count as f64)
+                }
+            }
+            AggregateFn::Min | AggregateFn::Max => {
+                let pick_max = self.function == AggregateFn::Max;
+                let values: Vec<&str> = cells().filter(|c| !c.is_empty()).collect();
+                // すべて数値なら数値として比較し、そうでなければ文字列として比較する
+                if !values.is_empty() && values.iter().all(|c| c.parse::<f64>().is_ok()) {
+                    let mut nums = values.iter().filter_map(|c| c.parse::<f64>().ok());
+                    let first = nums.next().unwrap_or_default();
+                    let result = nums.fold(first, |acc, v| if pick_max { acc.max(v) } else { acc.min(v) });
+                    number_value(result)
+                } else {
+                    let result = if pick_max { values.iter().max() } else { values.iter().min() };
+                    result.map_or(JsonValue::Null, |v| json!(v))
+                }
+            }
+        }
+    }
+}
+
+/// CSV の絞り込み・集計の指定
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CsvOptions {
+    conditions: Vec<Condition>,
+    columns: Vec<String>,
+    group_by: Option<String>,
+    aggregates: Vec<Aggregate>,
+}
+
+impl CsvOptions {
+    fn from_params(params: &JsonValue) -> Result<Self, ToolError> {
+        let invalid = ToolError::InvalidParams;
+        let conditions = string_list(params, "where")?
+            .iter()
+            .map(|c| Condition::parse(c))
+            .collect::<Result<_, _>>()
+            .map_err(invalid)?;
+        let mut aggregates: Vec<Aggregate> = string_list(params, "aggregate")?
+            .iter()
+            .map(|a| Aggregate::parse(a))
+            .collect::<Result<_, _>>()
+            .map_err(invalid)?;
+        let group_by = params["group_by"]
+            .as_str()
+            .map(str::trim)
+            .filter(|g| !g.is_empty())
+            .map(str::to_string);
+        if group_by.is_some() && aggregates.is_empty() {
+            aggregates.push(Aggregate {
+                function: AggregateFn::Count,
+                column: None,
+            });
+        }
+
+        Ok(Self {
+            conditions,
+            columns: string_list(params, "columns")?,
+            group_by,
+            aggregates,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.conditions.is_empty() && self.columns.is_empty() && self.group_by.is_none() && self.aggregates.is_empty()
+    }
+
+    /// 絞り込み・集計を適用し、行オブジェクトの配列に変換
+    fn apply(&self, headers: &[String], rows: &[Vec<String>]) -> Result<JsonValue, String> {
+        let column_index = |name: &str| {
+            headers
+                .iter()
+                .position(|h| h == name)
+                .ok_or_else(|| format!("Unknown column '{}' (columns: {})", name, headers.join(", ")))
+        };
+
+        let conditions = self
+            .conditions
+            .iter()
+            .map(|c| column_index(&c.column).map(|i| (i, c)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let filtered: Vec<&Vec<String>> = rows
+            .iter()
+            .filter(|row| {
+                conditions
+                    .iter()
+                    .all(|(i, c)| c.matches(row.get(*i).map(String::as_str).unwrap_or("")))
+            })
+            .collect();
+
+        if self.aggregates.is_empty() {
+            let selected: Vec<usize> = if self.columns.is_empty() {
+                (0..headers.len()).collect()
+            } else {
+                self.columns.iter().map(|c| column_index(c)).collect::<Result<_, _>>()?
+            };
+            let records = filtered
+                .iter()
+                .map(|row| {
+                    let object: Map<String, JsonValue> = selected
+                        .iter()
+                        .map(|&i| (headers[i].clone(), cell_value(row.get(i).map(String::as_str).unwrap_or(""))))
+                        .collect();
+                    JsonValue::Object(object)
+                })
+                .collect();
+            return Ok(JsonValue::Array(records));
+        }
+
+        let aggregates = self
+            .aggregates
+            .iter()
+            .map(|a| a.column.as_deref().map(column_index).transpose().map(|i| (a, i)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // グループは最初に出現した順に並べる
+        let mut groups: Vec<(String, Vec<&Vec<String>>)> = Vec::new();
+        match &self.group_by {
+            Some(group_by) => {
+                let group_index = column_index(group_by)?;
+                let mut positions: HashMap<String, usize> = HashMap::new();
+                for row in filtered {
+                    let key = row.get(group_index).cloned().unwrap_or_default();
+                    let position = *positions.entry(key.clone()).or_insert_with(|| {
+                        groups.push((key, Vec::new()));
+                        groups.len() - 1
+                    });
+                    groups[position].1.push(row);
+                }
+            }
+            None => groups.push((String::new(), filtered)),
+        }
+
+        let records = groups
+            .into_iter()
+            .map(|(key, rows)| {
+                let mut object = Map::new();
+                if let Some(group_by) = &self.group_by {
+                    object.insert(group_by.clone(), cell_value(&key));
+                }
+                for (aggregate, index) in &aggregates {
+                    object.insert(aggregate.key(), aggregate.compute(&rows, *index));
+                }
+                JsonValue::Object(object)
+            })
+            .collect();
+        Ok(JsonValue::Array(records))
+    }
+}
+
+/// CSV のセルを JSON 値に変換（空文字は null、数値は数値として扱う）
+fn cell_value(cell: &str) -> JsonValue {
+    let trimmed = cell.trim();
+    if trimmed.is_empty() {
+        JsonValue::Null
+    } else if let Ok(n) = trimmed.parse::<i64>() {
+        json!(n)
+    } else if let Some(n) = trimmed.parse::<f64>().ok().and_then(serde_json::Number::from_f64) {
+        JsonValue::Number(n)
+    } else {
+        JsonValue::String(cell.to_string())
+    }
+}
+
+/// 集計結果の数値（整数になる場合は整数で返す）
+fn number_value(value: f64) -> JsonValue {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        json!(value as i64)
+    } else {
+        serde_json::Number::from_f64(value).map_or(JsonValue::Null, JsonValue::Number)
+    }
+}
+
+/// パラメータの文字列配列（または単一文字列）を取得
+fn string_list(params: &JsonValue, key: &str) -> Result<Vec<String>, ToolError> {
+    match &params[key] {
+        JsonValue::Null => Ok(Vec::new()),
+        JsonValue::String(s) => Ok(vec![s.clone()]),
+        JsonValue::Array(items) => Ok(items.iter().filter_map(|v| v.as_str().map(str::to_string)).collect()),
+        _ => Err(ToolError::InvalidParams(format!("'{}' must be a string or an array", key))),
+    }
+}
+
+/// TOML の値を JSON に変換（日時は文字列にする）
+fn toml_to_json(value: toml::Value) -> JsonValue {
+    match value {
+        toml::Value::String(s) => JsonValue::String(s),
+        toml::Value::Integer(i) => json!(i),
+        toml::Value::Float(f) => serde_json::Number::from_f64(f).map_or(JsonValue::Null, JsonValue::Number),
+        toml::Value::Boolean(b) => JsonValue::Bool(b),
+        toml::Value::Datetime(d) => JsonValue::String(d.to_string()),
+        toml::Value::Array(items) => JsonValue::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => {
+            JsonValue::Object(table.into_iter().map(|(k, v)| (k, toml_to_json(v))).collect())
+        }
+    }
+}
+
+/// 1回分のクエリ（子プロセスで実行する）
+#[derive(Serialize, Deserialize)]
+struct QueryJob {
+    path: PathBuf,
+    format: DataFormat,
+    csv: CsvOptions,
+    query: String,
+    max_results: usize,
+    raw: bool,
+    compact: bool,
+}
+
+/// クエリの実行結果
+#[derive(Debug, Serialize, Deserialize)]
+struct QueryOutput {
+    output: String,
+    count: usize,
+    /// `max_results` で打ち切ったかどうか
+    truncated: bool,
+}
+
+impl QueryJob {
+    fn run(&self) -> Result<QueryOutput, String> {
+        let input = DataQueryTool::load(&self.path, self.format, &self.csv)?;
+        let (results, truncated) = DataQueryTool::run_query(&self.query, input, self.max_results)?;
+        let mut output = DataQueryTool::format_results(&results, self.raw, self.compact);
+        if output.chars().count() > MAX_OUTPUT_CHARS {
+            output = output.chars().take(MAX_OUTPUT_CHARS).collect();
+            output.push_str(&format!(
+                "\n... (output truncated at {} characters; narrow the query to see more)",
+                MAX_OUTPUT_CHARS
+            ));
+        }
+        Ok(QueryOutput {
+            output,
+            count: results.len(),
+            truncated,
+        })
+    }
+}
+
+/// 子プロセスとして起動するための隠しサブコマンド
+#[cfg(target_os = "linux")]
+pub const DATA_QUERY_WORKER_ARG: &str = "__data-query-worker";
+/// 子プロセスが結果を書き込むファイルディスクリプタ
+#[cfg(target_os = "linux")]
+const OUTPUT_FD: i32 = 3;
+
+/// 子プロセスのエントリポイント（標準入力のクエリを実行し、結果を `OUTPUT_FD` に書き込む）
+///
+/// 終了コードは 0 が成功、1 が入出力の失敗
+#[cfg(target_os = "linux")]
+pub fn run_data_query_worker() -> i32 {
+    use std::io::{Read, Write};
+    use std::os::fd::FromRawFd;
+
+    let mut input = Vec::new();
+    if std::io::stdin().read_to_end(&mut input).is_err() {
+        return 1;
+    }
+    let Ok(job) = serde_json::from_slice::<QueryJob>(&input) else {
+        return 1;
+    };
+    let result = serde_json::to_vec(&job.run()).unwrap_or_default();
+    // SAFETY: OUTPUT_FD は親プロセスが結果用のパイプとして渡したもので、ここでだけ使う
+    let mut output = unsafe { std::fs::File::from_raw_fd(OUTPUT_FD) };
+    if output.write_all(&result).is_ok() {
+        0
+    } else {
+        1
+    }
+}
+
+/// 子プロセスの起動コマンド（自分自身の実行ファイルを隠しサブコマンドで起動）
+#[cfg(all(target_os = "linux", not(test)))]
+fn worker_command() -> std::io::Result<tokio::process::Command> {
+    let mut command = tokio::process::Command::new(std::env::current_exe()?);
+    command.arg(DATA_QUERY_WORKER_ARG);
+    Ok(command)
+}
+
+/// テストではテストバイナリの `tests::query_worker` を子プロセスとして起動する
+#[cfg(all(target_os = "linux", test))]
+fn worker_command() -> std::io::Result<tokio::process::Command> {
+    let mut command = tokio::process::Command::new(std::env::current_exe()?);
+    command
+        .args(["--exact", "tools::data_query::tests::query_worker", "--test-threads=1"])
+        .env(tests::WORKER_ENV, "1");
+    Ok(command)
+}
+
+/// クエリを子プロセスで実行（CPU 時間・メモリを制限し、時間切れの場合は強制終了）
+///
+/// 外側の `Err` は子プロセスを起動できなかった場合、内側の `Err` はクエリのエラーや制限超過です。
+#[cfg(target_os = "linux")]
+async fn run_isolated(job: QueryJob, timeout: Duration) -> Result<Result<QueryOutput, String>, ToolError> {
+    use std::io::Read;
+    use std::os::fd::AsRawFd;
+    use std::process::Stdio;
+    use tokio::io::AsyncWriteExt;
+
+    let input = serde_json::to_vec(&job)?;
+    let memory_limit = current_data_size().saturating_add(QUERY_MEMORY_LIMIT);
+    let (mut reader, writer) = std::io::pipe()
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to create pipe: {}", e)))?;
+    let output_fd = writer.as_raw_fd();
+
+    let mut command = worker_command()
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to locate the bot executable: {}", e)))?;
+    command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+    // SAFETY: fork 後・exec 前の子プロセスでは、非同期シグナル安全な dup2 と setrlimit だけを呼ぶ
+    unsafe {
+        command.pre_exec(move || limit_child_process(output_fd, timeout, memory_limit));
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to start query process: {}", e)))?;
+    drop(writer);
+
+    let mut stdin = child.stdin.take();
+    let run = async {
+        if let Some(stdin) = stdin.as_mut() {
+            // 子プロセスが先に終了した場合の書き込みエラーは終了状態で判断する
+            let _ = stdin.write_all(&input).await;
+        }
+        drop(stdin);
+        let read = tokio::task::spawn_blocking(move || {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).map(|_| bytes)
+        });
+        let bytes = read.await.unwrap_or_else(|e| Err(std::io::Error::other(e)));
+        (bytes, child.wait().await)
+    };
+    // 時間切れの場合は `child` を破棄し、kill_on_drop で強制終了する
+    let Ok((bytes, status)) = tokio::time::timeout(timeout, run).await else {
+        return Ok(Err(format!("Query exceeded the time limit ({}s)", timeout.as_secs())));
+    };
+
+    let bytes = bytes.map_err(|e| ToolError::ExecutionFailed(format!("Failed to read query result: {}", e)))?;
+    if !bytes.is_empty() {
+        return serde_json::from_slice(&bytes)
+            .map_err(|e| ToolError::ExecutionFailed(format!("Invalid query result: {}", e)));
+    }
+
+    let status = status.map_err(|e| ToolError::ExecutionFailed(format!("Failed to wait for query process: {}", e)))?;
+    debug!("data_query process exited without a result: {}", status);
+    Ok(Err(describe_failure(status, timeout)))
+}
+
+/// 結果を返さずに終了した子プロセスの終了状態をエラーメッセージにする
+#[cfg(target_os = "linux")]
+fn describe_failure(status: std::process::ExitStatus, timeout: Duration) -> String {
+    use std::os::unix::process::ExitStatusExt;
+
+    match (status.signal(), status.code()) {
+        (Some(libc::SIGXCPU), _) => format!("Query exceeded the time limit ({}s)", timeout.as_secs()),
+        // メモリ確保に失敗すると abort する
+        (Some(libc::SIGABRT), _) => format!("Query exceeded the memory limit ({} MB)", QUERY_MEMORY_LIMIT / 1024 / 1024),
+        (Some(signal), _) => format!("Query process was killed by signal {}", signal),
+        (None, Some(101)) => "Query process panicked".to_string(),
+        (None, Some(code)) => format!("Query process exited without a result (exit code {})", code),
+        (None, None) => "Query process exited without a result".to_string(),
+    }
+}
+
+/// jaq を中断できない環境では、時間切れの場合は結果を待たずに返す（評価は止まらない）
+#[cfg(not(target_os = "linux"))]
+async fn run_isolated(job: QueryJob, timeout: Duration) -> Result<Result<QueryOutput, String>, ToolError> {
+    match tokio::time::timeout(timeout, tokio::task::spawn_blocking(move || job.run())).await {
+        Ok(Ok(result)) => Ok(result),
+        Ok(Err(e)) => Err(ToolError::ExecutionFailed(format!("Query task failed: {}", e))),
+        Err(_) => Ok(Err(format!("Query exceeded the time limit ({}s)", timeout.as_secs()))),
+    }
+}
+
+/// 子プロセスの制限を設定し、結果用のパイプを `OUTPUT_FD` に割り当てる（exec 前に呼ばれる）
+#[cfg(target_os = "linux")]
+fn limit_child_process(output_fd: i32, timeout: Duration, memory_limit: u64) -> std::io::Result<()> {
+    // SAFETY: 非同期シグナル安全なシステムコールだけを呼ぶ
+    unsafe {
+        // dup2 した側は close-on-exec が外れ、子プロセスに引き継がれる
+        let result = if output_fd == OUTPUT_FD {
+            libc::fcntl(OUTPUT_FD, libc::F_SETFD, 0)
+        } else {
+            libc::dup2(output_fd, OUTPUT_FD)
+        };
+        if result < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let cpu_seconds = timeout.as_secs() + 1;
+        let cpu = libc::rlimit {
+            rlim_cur: cpu_seconds,
+            rlim_max: cpu_seconds + 1,
+        };
+        let memory = libc::rlimit {
+            rlim_cur: memory_limit,
+            rlim_max: memory_limit,
+        };
+        if libc::setrlimit(libc::RLIMIT_CPU, &cpu) < 0 || libc::setrlimit(libc::RLIMIT_DATA, &memory) < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// 現在のデータ領域のサイズ（`RLIMIT_DATA` の対象、取得できなければ 0）
+#[cfg(target_os = "linux")]
+fn current_data_size() -> u64 {
+    std::fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix("VmData:"))
+                .and_then(|value| value.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+        })
+        .map_or(0, |kb| kb * 1024)
+}
+
+/// 構造化データクエリツール
+pub struct DataQueryTool;
+
+impl DataQueryTool {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// ファイルを読み込み、jq 式の入力となる JSON 値に変換
+    fn load(path: &Path, format: DataFormat, csv: &CsvOptions) -> Result<JsonValue, String> {
+        if format == DataFormat::Csv {
+            let (headers, rows) = SqlQueryTool::load_csv(path)?;
+            return csv.apply(&headers, &rows);
+        }
+
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+        match format {
+            DataFormat::Json => serde_json::from_str(&text).map_err(|e| format!("Invalid JSON: {}", e)),
+            DataFormat::JsonLines => text
+                .lines()
+                .filter(|l| !l.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<Result<Vec<JsonValue>, _>>()
+                .map(JsonValue::Array)
+                .map_err(|e| format!("Invalid JSON Lines: {}", e)),
+            DataFormat::Yaml => {
+                // 複数ドキュメントの場合は配列にまとめる
+                let mut documents = serde_yaml::Deserializer::from_str(&text)
+                    .map(|doc| JsonValue::deserialize(doc).map_err(|e| format!("Invalid YAML: {}", e)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(match documents.len() {
+                    0 => JsonValue::Null,
+                    1 => documents.remove(0),
+                    _ => JsonValue::Array(documents),
+                })
+            }
+            DataFormat::Toml => text
+                .parse::<toml::Table>()
+                .map(|table| toml_to_json(toml::Value::Table(table)))
+                .map_err(|e| format!("Invalid TOML: {}", e)),
+            DataFormat::Csv => unreachable!("handled above"),
+        }
+    }
+
+    /// jq 式を実行し、最大 `max_results` 件の結果を返す（2つ目は打ち切ったかどうか）
+    fn run_query(query: &str, input: JsonValue, max_results: usize) -> Result<(Vec<Val>, bool), String> {
+        let defs = jaq_std::defs()
+            .chain(jaq_json::defs())
+            .filter(|def| !BLOCKED_FILTERS.contains(&def.name));
+        let funs = jaq_std::funs()
+            .chain(jaq_json::funs())
+            .filter(|(name, _, _)| !BLOCKED_FILTERS.contains(name));
+
+        let arena = Arena::default();
+        let program = JaqFile { code: query, path: () };
+        let modules = Loader::new(defs).load(&arena, program).map_err(|errors| {
+            let details: Vec<String> = errors
+                .into_iter()
+                .flat_map(|(_, error)| match error {
+                    jaq_core::load::Error::Io(errs) => errs.into_iter().map(|(_, e)| e).collect::<Vec<_>>(),
+                    jaq_core::load::Error::Lex(errs) => errs
+                        .into_iter()
+                        .map(|(expect, got)| format!("expected {} near '{}'", expect.as_str(), snippet(got)))
+                        .collect(),
+                    jaq_core::load::Error::Parse(errs) => errs
+                        .into_iter()
+                        .map(|(expect, got)| format!("expected {} near '{}'", expect.as_str(), snippet(got)))
+                        .collect(),
+                })
+                .collect();
+            format!("Invalid query: {}", details.join("; "))
+        })?;
+        let filter = Compiler::default().with_funs(funs).compile(modules).map_err(|errors| {
+            let details: Vec<String> = errors
+                .into_iter()
+                .flat_map(|(_, errs)| errs)
+                .map(|(name, undefined)| format!("undefined {} '{}'", undefined.as_str(), name))
+                .collect();
+            format!("Invalid query: {}", details.join("; "))
+        })?;
+
+        let inputs = RcIter::new(core::iter::empty());
+        let mut outputs = filter.run((Ctx::new([], &inputs), Val::from(input)));
+        let mut results = Vec::new();
+        for output in outputs.by_ref() {
+            if results.len() >= max_results {
+                return Ok((results, true));
+            }
+            results.push(output.map_err(|e| format!("Query error: {}", e))?);
+        }
+        Ok((results, false))
+    }
+
+    /// 結果を整形（`raw` の場合、文字列はそのまま出力）
+    fn format_results(results: &[Val], raw: bool, compact: bool) -> String {
+        results
+            .iter()
+            .map(|value| match value {
+                Val::Str(s) if raw => s.to_string(),
+                _ if compact => value.to_string(),
+                _ => serde_json::from_str::<JsonValue>(&value.to_string())
+                    .ok()
+                    .and_then(|v| serde_json::to_string_pretty(&v).ok())
+                    .unwrap_or_else(|| value.to_string()),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Default for DataQueryTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// エラーメッセージ用にクエリの一部を切り出す
+fn snippet(text: &str) -> String {
+    text.chars().take(20).collect()
+}
+
+#[async_trait]
+impl Tool for DataQueryTool {
+    fn name(&self) -> &str {
+        "data_query"
+    }
+
+    fn description(&self) -> &str {
+        "Run a jq expression over a JSON, JSON Lines, YAML, TOML or CSV/TSV file in your workspace and return only the result. \
+         Prefer this over read_file for large structured files. CSV rows become objects (numbers are converted) and can be \
+         pre-filtered with 'where', reduced with 'columns', or summarized with 'group_by' and 'aggregate' before the query runs."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "File path relative to your workspace"
+                },
+                "query": {
+                    "type": "string",
+                    "description": "jq expression (default: '.'), e.g. '.items[] | select(.price > 100) | .name'"
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["json", "jsonl", "yaml", "toml", "csv", "tsv"],
+                    "description": "File format (default: detected from the extension)"
+                },
+                "where": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "CSV only: row conditions combined with AND, e.g. [\"amount >= 100\", \"status == done\", \"name contains foo\"]"
+                },
+                "columns": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "CSV only: columns to keep"
+                },
+                "group_by": {
+                    "type": "string",
+                    "description": "CSV only: column to group rows by"
+                },
+                "aggregate": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "CSV only: aggregates per group, e.g. [\"count\", \"sum:amount\", \"avg:amount\", \"min:date\", \"max:date\"]"
+                },
+                "raw": {
+                    "type": "boolean",
+                    "description": "Print string results without quotes like jq -r (default: false)"
+                },
+                "compact": {
+                    "type": "boolean",
+                    "description": "One result per line without indentation (default: false)"
+                },
+                "max_results": {
+                    "type": "integer",
+                    "description": "Maximum number of results (default: 100, max: 1000)"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let path = params["path"]
+            .as_str()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .ok_or_else(|| ToolError::InvalidParams("Missing 'path' parameter".to_string()))?;
+        let query = params["query"]
+            .as_str()
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .unwrap_or(".")
+            .to_string();
+        let format = DataFormat::detect(path, params["format"].as_str())?;
+        let csv = CsvOptions::from_params(&params)?;
+        if format != DataFormat::Csv && !csv.is_empty() {
+            return Err(ToolError::InvalidParams(
+                "'where', 'columns', 'group_by' and 'aggregate' are only supported for CSV files; use the query instead"
+                    .to_string(),
+            ));
+        }
+        let max_results = params["max_results"]
+            .as_u64()
+            .map(|n| n as usize)
+            .unwrap_or(DEFAULT_MAX_RESULTS)
+            .clamp(1, MAX_RESULTS);
+        let raw = params["raw"].as_bool().unwrap_or(false);
+        let compact = params["compact"].as_bool().unwrap_or(false);
+
+        let full_path = ApplyPatchTool::resolve_path(path, context)?;
+        let metadata = std::fs::metadata(&full_path)
+            .map_err(|_| ToolError::InvalidParams(format!("File not found: {}", path)))?;
+        if !metadata.is_file() {
+            return Err(ToolError::InvalidParams(format!("Not a file: {}", path)));
+        }
+        if metadata.len() > MAX_FILE_BYTES {
+            return Err(ToolError::InvalidParams(format!(
+                "File is too large ({} bytes, max {} bytes)",
+                metadata.len(),
+                MAX_FILE_BYTES
+            )));
+        }
+
+        info!("data_query on {} for user {}", path, context.user_id);
+
+        let job = QueryJob {
+            path: full_path,
+            format,
+            csv,
+            query,
+            max_results,
+            raw,
+            compact,
+        };
+        let QueryOutput {
+            mut output,
+            count,
+            truncated,
+        } = match run_isolated(job, QUERY_TIMEOUT).await? {
+            Ok(result) => result,
+            Err(message) => {
+                debug!("data_query failed: {}", message);
+                return Ok(ToolResult::error(message));
+            }
+        };
+
+        if count == 0 {
+            return Ok(ToolResult::success("Query returned no results."));
+        }
+        if truncated {
+            output.push_str(&format!("\n... (limited to {} results)", max_results));
+        }
+        Ok(ToolResult::success(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::test_workspace;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, ToolContext) {
+        let (dir, ctx, root) = test_workspace();
+        std::fs::write(
+            root.join("data.json"),
+            r#"{"items": [{"name": "a", "price": 50}, {"name": "b", "price": 150}, {"name": "c", "price": 300}]}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("sales.csv"),
+            "region,product,amount\n東京,pen,100\n大阪,pen,50\n東京,book,300\n大阪,book,\n",
+        )
+        .unwrap();
+        (dir, ctx)
+    }
+
+    async fn query(ctx: &ToolContext, params: JsonValue) -> ToolResult {
+        DataQueryTool::new().execute(params, ctx).await.unwrap()
+    }
+
+    #[test]
+    fn test_parse_condition_and_aggregate() {
+        let c = Condition::parse("amount >= 100").unwrap();
+        assert_eq!((c.column.as_str(), c.op, c.value.as_str()), ("amount", CompareOp::Ge, "100"));
+        assert!(c.matches("100") && c.matches("250.5") && !c.matches("99"));
+
+        let c = Condition::parse("name contains \"Foo\"").unwrap();
+        assert_eq!(c.op, CompareOp::Contains);
+        assert!(c.matches("my foo bar"));
+
+        let c = Condition::parse("status=done").unwrap();
+        assert!(c.matches("done") && !c.matches("open"));
+        assert!(Condition::parse("no operator").is_err());
+
+        assert_eq!(Aggregate::parse("sum:amount").unwrap().key(), "sum_amount");
+        assert!(Aggregate::parse("sum").is_err());
+        assert!(Aggregate::parse("median:x").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_json_query() {
+        let (_dir, ctx) = setup();
+        let result = query(
+            &ctx,
+            json!({"path": "data.json", "query": ".items[] | select(.price > 100) | .name", "raw": true}),
+        )
+        .await;
+        assert!(!result.is_error, "{}", result.output);
+        assert_eq!(result.output, "b\nc");
+
+        let result = query(&ctx, json!({"path": "data.json", "query": "[.items[].price] | add"})).await;
+        assert_eq!(result.output, "500");
+
+        let result = query(&ctx, json!({"path": "data.json", "query": ".items[] |"})).await;
+        assert!(result.is_error);
+        assert!(result.output.contains("Invalid query"));
+    }
+
+    #[tokio::test]
+    async fn test_yaml_and_toml() {
+        let (_dir, ctx) = setup();
+        let root = ctx.get_user_output_dir();
+        std::fs::write(format!("{}/config.yaml", root), "server:\n  port: 8080\n  hosts: [a, b]\n").unwrap();
+        std::fs::write(format!("{}/Cargo.toml", root), "[package]\nname = \"demo\"\n[dependencies]\nserde = \"1\"\n").unwrap();
+
+        let result = query(&ctx, json!({"path": "config.yaml", "query": ".server.port"})).await;
+        assert_eq!(result.output, "8080");
+
+        let result = query(
+            &ctx,
+            json!({"path": "Cargo.toml", "query": ".dependencies | keys", "compact": true}),
+        )
+        .await;
+        assert_eq!(result.output, "[\"serde\"]");
+    }
+
+    #[tokio::test]
+    async fn test_csv_filter_and_aggregate() {
+        let (_dir, ctx) = setup();
+        let result = query(
+            &ctx,
+            json!({"path": "sales.csv", "where": ["amount >= 100"], "columns": ["product"], "compact": true}),
+        )
+        .await;
+        assert_eq!(result.output, "[{\"product\":\"pen\"},{\"product\":\"book\"}]");
+
+        let result = query(
+            &ctx,
+            json!({
+                "path": "sales.csv",
+                "group_by": "region",
+                "aggregate": ["count", "sum:amount", "avg:amount"],
+                "query": ".[] | select(.region == \"大阪\")",
+                "compact": true
+            }),
+        )
+        .await;
+        assert!(!result.is_error, "{}", result.output);
+        let value: JsonValue = serde_json::from_str(&result.output).unwrap();
+        assert_eq!(value, json!({"region": "大阪", "count": 2, "sum_amount": 50, "avg_amount": 50}));
+
+        let result = query(&ctx, json!({"path": "sales.csv", "where": ["price > 1"]})).await;
+        assert!(result.is_error);
+        assert!(result.output.contains("Unknown column"));
+    }
+
+    #[tokio::test]
+    async fn test_output_is_capped_and_env_is_blocked() {
+        let (_dir, ctx) = setup();
+        let result = query(&ctx, json!({"path": "data.json", "query": "range(5000)", "max_results": 10})).await;
+        assert_eq!(result.output.lines().filter(|l| !l.starts_with("...")).count(), 10);
+        assert!(result.output.contains("limited to 10 results"));
+
+        let result = query(
+            &ctx,
+            json!({"path": "data.json", "query": "[range(100000)] | tostring"}),
+        )
+        .await;
+        assert!(result.output.contains("output truncated"));
+
+        let result = query(&ctx, json!({"path": "data.json", "query": "env"})).await;
+        assert!(result.is_error);
+        assert!(result.output.contains("undefined"));
+
+        let result = DataQueryTool::new()
+            .execute(json!({"path": "../secret.json"}), &ctx)
+            .await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+    }
+
+    /// 子プロセスとして起動されたときだけクエリを実行する（`worker_command` を参照）
+    #[cfg(target_os = "linux")]
+    pub(super) const WORKER_ENV: &str = "CC_BOT_DATA_QUERY_WORKER";
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn query_worker() {
+        if std::env::var_os(WORKER_ENV).is_some() {
+            std::process::exit(run_data_query_worker());
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_runaway_query_is_stopped() {
+        let (_dir, ctx) = setup();
+        let job = |query: &str| QueryJob {
+            path: PathBuf::from(ctx.get_user_output_dir()).join("data.json"),
+            format: DataFormat::Json,
+            csv: CsvOptions::default(),
+            query: query.to_string(),
+            max_results: 10,
+            raw: false,
+            compact: true,
+        };
+
+        let output = run_isolated(job(".items | length"), QUERY_TIMEOUT).await.unwrap().unwrap();
+        assert_eq!((output.output.as_str(), output.count), ("3", 1));
+
+        // 終わらないクエリは時間切れで子プロセスごと止める
+        let started = std::time::Instant::now();
+        let error = run_isolated(job("last(range(1e18))"), Duration::from_secs(1)).await.unwrap().unwrap_err();
+        assert!(error.contains("time limit"), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // 際限なくメモリを確保するクエリはメモリ上限で止まる
+        let error = run_isolated(job("[range(infinite)]"), QUERY_TIMEOUT).await.unwrap().unwrap_err();
+        assert!(error.contains("memory limit"), "{}", error);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_describe_failure() {
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::ExitStatus;
+
+        let timeout = Duration::from_secs(3);
+        assert!(describe_failure(ExitStatus::from_raw(libc::SIGXCPU), timeout).contains("time limit (3s)"));
+        assert!(describe_failure(ExitStatus::from_raw(libc::SIGABRT), timeout).contains("memory limit"));
+        assert!(describe_failure(ExitStatus::from_raw(libc::SIGSEGV), timeout).contains("signal 11"));
+        assert_eq!(describe_failure(ExitStatus::from_raw(101 << 8), timeout), "Query process panicked");
+        assert!(describe_failure(ExitStatus::from_raw(1 << 8), timeout).contains("exit code 1"));
+    }
+}
@@ -0,0 +1,1034 @@
+//! Discordネイティブツール
+//!
+//! serenity の `Http` を使って、サーバー内のメッセージ読み取り・検索、
+//! スレッド作成、リアクション追加、ピン留めを行います。
+//! 対象チャンネルでの呼び出し元ユーザーの Discord 権限（チャンネルの
+//! 権限上書きを含む）を毎回評価し、見えないチャンネルには触れません。
+//! 書き込み系のツールは、さらにボットの `Permission::DiscordWrite` を要求します。
+
+use crate::permission::{Permission, PermissionManager};
+use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveTime, TimeZone, Utc};
+use serde_json::{json, Value as JsonValue};
+use serenity::all::{
+    AutoArchiveDuration, Channel, ChannelId, ChannelType, CreateThread, GetMessages, GuildChannel, Message,
+    MessageId, Permissions, ReactionType,
+};
+use serenity::http::Http;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+/// 1回の読み取りで返す最大メッセージ数
+const MAX_READ_MESSAGES: usize = 100;
+/// デフォルトの読み取りメッセージ数
+const DEFAULT_READ_MESSAGES: usize = 50;
+/// 時間範囲指定・検索で走査する最大メッセージ数
+const MAX_SCAN_MESSAGES: usize = 1000;
+/// デフォルトの検索結果数
+const DEFAULT_SEARCH_RESULTS: usize = 20;
+/// 結果に含める最大文字数
+const MAX_OUTPUT_CHARS: usize = 15000;
+/// 1メッセージあたりの最大文字数
+const MAX_MESSAGE_CHARS: usize = 1000;
+/// Discordのエポック（2015-01-01T00:00:00Z）のミリ秒
+const DISCORD_EPOCH_MS: i64 = 1_420_070_400_000;
+
+/// チャンネル参照（未指定・ID/メンション・名前）
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ChannelRef {
+    Current,
+    Id(u64),
+    Name(String),
+}
+
+/// チャンネル指定をパース（`<#123>`、`123`、`#dev`、`dev`）
+fn parse_channel_ref(value: Option<&str>) -> ChannelRef {
+    let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+        return ChannelRef::Current;
+    };
+    let id_part = value
+        .strip_prefix("<#")
+        .and_then(|v| v.strip_suffix('>'))
+        .unwrap_or(value);
+    match id_part.parse::<u64>() {
+        Ok(id) if id > 0 => ChannelRef::Id(id),
+        _ => ChannelRef::Name(value.trim_start_matches('#').to_lowercase()),
+    }
+}
+
+/// メッセージ指定をパース（メッセージID、またはメッセージリンク）
+///
+/// リンクの場合はチャンネルIDも返す
+fn parse_message_ref(value: &str) -> Option<(Option<u64>, u64)> {
+    let value = value.trim();
+    if let Ok(id) = value.parse::<u64>() {
+        return (id > 0).then_some((None, id));
+    }
+    // https://discord.com/channels/{guild}/{channel}/{message}
+    let rest = value.split("/channels/").nth(1)?;
+    let parts: Vec<&str> = rest.trim_end_matches('/').split('/').collect();
+    if parts.len() != 3 {
+        return None;
+    }
+    let channel_id = parts[1].parse::<u64>().ok()?;
+    let message_id = parts[2].parse::<u64>().ok()?;
+    Some((Some(channel_id), message_id))
+}
+
+/// 時刻指定をパース（RFC3339、`today`、`yesterday`、`30m`/`6h`/`2d` 前）
+fn parse_time(value: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&Utc));
+    }
+
+    let start_of_local_day = |days_ago: i64| {
+        let date = now.with_timezone(&Local).date_naive() - ChronoDuration::days(days_ago);
+        Local
+            .from_local_datetime(&date.and_time(NaiveTime::MIN))
+            .earliest()
+            .map(|dt| dt.with_timezone(&Utc))
+    };
+    match value.to_lowercase().as_str() {
+        "today" => return start_of_local_day(0),
+        "yesterday" => return start_of_local_day(1),
+        _ => {}
+    }
+
+    if value.len() < 2 || !value.is_char_boundary(value.len() - 1) {
+        return None;
+    }
+    let (amount, unit) = value.split_at(value.len() - 1);
+    let amount: i64 = amount.parse().ok().filter(|n| *n >= 0)?;
+    let duration = match unit {
+        "m" => ChronoDuration::minutes(amount),
+        "h" => ChronoDuration::hours(amount),
+        "d" => ChronoDuration::days(amount),
+        _ => return None,
+    };
+    Some(now - duration)
+}
+
+/// 時刻からメッセージIDの境界値を計算
+fn snowflake_at(time: DateTime<Utc>) -> u64 {
+    let ms = (time.timestamp_millis() - DISCORD_EPOCH_MS).max(0) as u64;
+    (ms << 22).max(1)
+}
+
+/// 文字数で切り詰め
+fn truncate_chars(text: &str, max: usize) -> String {
+    if text.chars().count() > max {
+        let truncated: String = text.chars().take(max).collect();
+        format!("{}…", truncated)
+    } else {
+        text.to_string()
+    }
+}
+
+/// メッセージの投稿時刻
+fn message_time(msg: &Message) -> DateTime<Utc> {
+    DateTime::from_timestamp(msg.timestamp.unix_timestamp(), 0).unwrap_or_default()
+}
+
+/// メッセージを1行（＋添付情報）に整形
+fn format_message(msg: &Message) -> String {
+    let time = message_time(msg).with_timezone(&Local).format("%Y-%m-%d %H:%M");
+    let author = msg.author.global_name.as_deref().unwrap_or(&msg.author.name);
+    let mut line = format!(
+        "[{}] {} (message {}): {}",
+        time,
+        author,
+        msg.id,
+        truncate_chars(&msg.content, MAX_MESSAGE_CHARS)
+    );
+    for attachment in &msg.attachments {
+        line.push_str(&format!(" [attachment: {}]", attachment.filename));
+    }
+    if msg.content.is_empty() && !msg.embeds.is_empty() {
+        line.push_str(&format!(" [{} embed(s)]", msg.embeds.len()));
+    }
+    line
+}
+
+/// メッセージ一覧を古い順に整形（文字数上限あり）
+fn format_messages(header: &str, messages: &[Message]) -> String {
+    let mut output = format!("{}\n\n", header);
+    let mut omitted = 0;
+    // 新しいメッセージを優先して残す
+    let mut lines: Vec<String> = Vec::new();
+    let mut total = output.len();
+    for msg in messages.iter().rev() {
+        let line = format_message(msg);
+        if total + line.len() + 1 > MAX_OUTPUT_CHARS {
+            omitted += 1;
+            continue;
+        }
+        total += line.len() + 1;
+        lines.push(line);
+    }
+    lines.reverse();
+    if omitted > 0 {
+        output.push_str(&format!("({} older message(s) omitted to fit the output limit)\n", omitted));
+    }
+    output.push_str(&lines.join("\n"));
+    output
+}
+
+/// メッセージが検索条件に一致するか
+fn matches_query(msg: &Message, query: &str, author: Option<&str>) -> bool {
+    if let Some(author) = author {
+        let author = author.to_lowercase();
+        let matches_author = msg.author.name.to_lowercase() == author
+            || msg.author.id.to_string() == author
+            || msg
+                .author
+                .global_name
+                .as_deref()
+                .is_some_and(|n| n.to_lowercase() == author);
+        if !matches_author {
+            return false;
+        }
+    }
+    query.is_empty() || msg.content.to_lowercase().contains(&query.to_lowercase())
+}
+
+/// 権限チェック済みのチャンネル
+struct AuthorizedChannel {
+    id: ChannelId,
+    name: String,
+}
+
+/// Discord APIへのアクセスと権限チェック
+pub struct DiscordAccess {
+    http: Arc<Http>,
+    permissions: Arc<RwLock<PermissionManager>>,
+}
+
+impl DiscordAccess {
+    pub fn new(http: Arc<Http>, permissions: Arc<RwLock<PermissionManager>>) -> Self {
+        Self { http, permissions }
+    }
+
+    /// 書き込み系ツールの DiscordWrite 権限を確認（管理者は常に許可）
+    async fn check_write_permission(&self, context: &ToolContext) -> Result<(), ToolError> {
+        // ユーザー不明のシステム実行からは書き込ませない
+        if context.user_id == 0 {
+            return Err(ToolError::PermissionDenied(
+                "Discord actions can only be performed on behalf of a user".to_string(),
+            ));
+        }
+        let permissions = self.permissions.read().await;
+        if !permissions.is_admin(context.user_id)
+            && !permissions.has_permission(context.user_id, &Permission::DiscordWrite)
+        {
+            info!("Discord write tool denied for user {}: missing DiscordWrite", context.user_id);
+            return Err(ToolError::PermissionDenied(
+                "You do not have the DiscordWrite permission".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// チャンネル指定を解決し、呼び出し元ユーザーが `required` 権限を持つか確認
+    async fn authorize(
+        &self,
+        context: &ToolContext,
+        channel_ref: ChannelRef,
+        required: Permissions,
+    ) -> Result<AuthorizedChannel, ToolError> {
+        let channel_id = match channel_ref {
+            ChannelRef::Current => ChannelId::new(context.channel_id.max(1)),
+            ChannelRef::Id(id) => ChannelId::new(id),
+            ChannelRef::Name(name) => self.find_channel_by_name(context, &name).await?,
+        };
+        let is_current = channel_id.get() == context.channel_id;
+
+        let channel = channel_id.to_channel(&*self.http).await.map_err(|e| {
+            debug!("Failed to fetch channel {}: {}", channel_id, e);
+            ToolError::ExecutionFailed(format!("Channel {} not found or not accessible", channel_id))
+        })?;
+
+        let guild_channel = match channel {
+            Channel::Guild(channel) => channel,
+            // DMは呼び出し元のチャンネルのみ
+            _ if is_current => {
+                return Ok(AuthorizedChannel {
+                    id: channel_id,
+                    name: "direct message".to_string(),
+                });
+            }
+            _ => {
+                return Err(ToolError::PermissionDenied(
+                    "Only the current direct message channel can be accessed".to_string(),
+                ));
+            }
+        };
+
+        // 他のサーバーのチャンネルには触れない
+        if context.guild_id != Some(guild_channel.guild_id.get()) && !is_current {
+            return Err(ToolError::PermissionDenied(
+                "Channels in other servers cannot be accessed".to_string(),
+            ));
+        }
+        if guild_channel.kind == ChannelType::PrivateThread && !is_current {
+            return Err(ToolError::PermissionDenied(
+                "Private threads can only be accessed from inside the thread".to_string(),
+            ));
+        }
+
+        let permissions = self.member_permissions(context, &guild_channel).await?;
+        let needed = required | Permissions::VIEW_CHANNEL;
+        if !permissions.contains(needed) {
+            let missing = needed - permissions;
+            info!(
+                "Discord tool denied for user {} in channel {}: missing {}",
+                context.user_id, channel_id, missing
+            );
+            return Err(ToolError::PermissionDenied(format!(
+                "You don't have the required permissions in #{} ({})",
+                guild_channel.name, missing
+            )));
+        }
+
+        Ok(AuthorizedChannel {
+            id: channel_id,
+            name: format!("#{}", guild_channel.name),
+        })
+    }
+
+    /// 呼び出し元ユーザーのチャンネル内での権限を計算
+    ///
+    /// スレッドは親チャンネルの権限上書きで評価する
+    async fn member_permissions(&self, context: &ToolContext, channel: &GuildChannel) -> Result<Permissions, ToolError> {
+        let guild_id = channel.guild_id;
+        let permission_channel = match (channel.thread_metadata.is_some(), channel.parent_id) {
+            (true, Some(parent_id)) => match parent_id.to_channel(&*self.http).await {
+                Ok(Channel::Guild(parent)) => parent,
+                _ => {
+                    return Err(ToolError::ExecutionFailed(
+                        "Failed to fetch the parent channel of the thread".to_string(),
+                    ))
+                }
+            },
+            _ => channel.clone(),
+        };
+
+        let guild = self.http.get_guild(guild_id).await.map_err(|e| {
+            warn!("Failed to fetch guild {}: {}", guild_id, e);
+            ToolError::ExecutionFailed("Failed to fetch server information".to_string())
+        })?;
+        let member = self
+            .http
+            .get_member(guild_id, context.user_id.max(1).into())
+            .await
+            .map_err(|e| {
+                debug!("Failed to fetch member {} in {}: {}", context.user_id, guild_id, e);
+                ToolError::PermissionDenied("You are not a member of this server".to_string())
+            })?;
+
+        Ok(guild.user_permissions_in(&permission_channel, &member))
+    }
+
+    /// 現在のサーバーからチャンネル名で検索
+    async fn find_channel_by_name(&self, context: &ToolContext, name: &str) -> Result<ChannelId, ToolError> {
+        let guild_id = context.guild_id.ok_or_else(|| {
+            ToolError::InvalidParams("Channel names can only be used inside a server; pass a channel ID".to_string())
+        })?;
+        let channels = serenity::all::GuildId::new(guild_id)
+            .channels(&*self.http)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to list channels: {}", e)))?;
+
+        channels
+            .values()
+            .filter(|c| matches!(c.kind, ChannelType::Text | ChannelType::News | ChannelType::Voice))
+            .find(|c| c.name.to_lowercase() == name)
+            .map(|c| c.id)
+            .ok_or_else(|| ToolError::InvalidParams(format!("Channel '{}' not found in this server", name)))
+    }
+
+    /// 新しい順にメッセージを取得（`before` より前、`after` 以降、最大 `limit` 件）
+    async fn fetch_messages(
+        &self,
+        channel_id: ChannelId,
+        before: Option<MessageId>,
+        after: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<Message>, String> {
+        let mut messages: Vec<Message> = Vec::new();
+        let mut cursor = before;
+        while messages.len() < limit {
+            let page_size = (limit - messages.len()).min(100) as u8;
+            let mut request = GetMessages::new().limit(page_size);
+            if let Some(cursor) = cursor {
+                request = request.before(cursor);
+            }
+            let page = channel_id
+                .messages(&*self.http, request)
+                .await
+                .map_err(|e| format!("Failed to read messages: {}", e))?;
+            let page_len = page.len();
+
+            let mut reached_start = false;
+            for msg in page {
+                if after.is_some_and(|after| message_time(&msg) < after) {
+                    reached_start = true;
+                    break;
+                }
+                cursor = Some(msg.id);
+                messages.push(msg);
+            }
+            if reached_start || page_len < page_size as usize {
+                break;
+            }
+        }
+        Ok(messages)
+    }
+}
+
+/// 期間（開始, 終了）
+type TimeRange = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+/// 期間指定パラメータ（`since`/`until`）をパース
+fn parse_range(params: &JsonValue, now: DateTime<Utc>) -> Result<TimeRange, ToolError> {
+    let parse = |key: &str| -> Result<Option<DateTime<Utc>>, ToolError> {
+        match params[key].as_str() {
+            Some(value) => parse_time(value, now).map(Some).ok_or_else(|| {
+                ToolError::InvalidParams(format!(
+                    "Invalid '{}': use RFC3339, 'today', 'yesterday' or a relative time like '6h'",
+                    key
+                ))
+            }),
+            None => Ok(None),
+        }
+    };
+    Ok((parse("since")?, parse("until")?))
+}
+
+/// メッセージ指定を解決（リンクならそのチャンネルを優先）
+fn resolve_message(params: &JsonValue) -> Result<(ChannelRef, MessageId), ToolError> {
+    let message = params["message_id"].as_str().ok_or_else(|| {
+        ToolError::InvalidParams("Missing 'message_id' parameter".to_string())
+    })?;
+    let (link_channel, message_id) = parse_message_ref(message).ok_or_else(|| {
+        ToolError::InvalidParams("'message_id' must be a message ID or a message link".to_string())
+    })?;
+    let channel = match link_channel {
+        Some(id) => ChannelRef::Id(id),
+        None => parse_channel_ref(params["channel"].as_str()),
+    };
+    Ok((channel, MessageId::new(message_id)))
+}
+
+const CHANNEL_PARAM_DESCRIPTION: &str =
+    "Channel mention (<#id>), ID or name like #dev (default: current channel)";
+
+/// メッセージ読み取りツール
+pub struct DiscordReadMessagesTool {
+    access: Arc<DiscordAccess>,
+}
+
+impl DiscordReadMessagesTool {
+    pub fn new(access: Arc<DiscordAccess>) -> Self {
+        Self { access }
+    }
+}
+
+#[async_trait]
+impl Tool for DiscordReadMessagesTool {
+    fn name(&self) -> &str {
+        "discord_read_messages"
+    }
+
+    fn description(&self) -> &str {
+        "Read recent messages from the current or another channel in this Discord server, either the last N messages or a time range (e.g. since 'today'). Use this to summarize or answer questions about conversations."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "channel": {
+                    "type": "string",
+                    "description": CHANNEL_PARAM_DESCRIPTION
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Number of messages to read (default: 50, max: 100; with 'since', up to 1000)"
+                },
+                "since": {
+                    "type": "string",
+                    "description": "Start of the time range: RFC3339, 'today', 'yesterday' or relative like '6h', '2d'"
+                },
+                "until": {
+                    "type": "string",
+                    "description": "End of the time range (same formats as 'since', default: now)"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let (since, until) = parse_range(&params, Utc::now())?;
+        let max = if since.is_some() { MAX_SCAN_MESSAGES } else { MAX_READ_MESSAGES };
+        let limit = params["limit"]
+            .as_u64()
+            .map(|l| l as usize)
+            .unwrap_or(if since.is_some() { max } else { DEFAULT_READ_MESSAGES })
+            .clamp(1, max);
+
+        let channel = self
+            .access
+            .authorize(
+                context,
+                parse_channel_ref(params["channel"].as_str()),
+                Permissions::READ_MESSAGE_HISTORY,
+            )
+            .await?;
+
+        let before = until.map(|t| MessageId::new(snowflake_at(t)));
+        let mut messages = match self.access.fetch_messages(channel.id, before, since, limit).await {
+            Ok(messages) => messages,
+            Err(e) => return Ok(ToolResult::error(e)),
+        };
+        messages.reverse();
+
+        info!(
+            "discord_read_messages: {} message(s) from {} for user {}",
+            messages.len(),
+            channel.id,
+            context.user_id
+        );
+        if messages.is_empty() {
+            return Ok(ToolResult::success(format!("No messages found in {}", channel.name)));
+        }
+        let header = format!("{} message(s) from {} (oldest first):", messages.len(), channel.name);
+        Ok(ToolResult::success(format_messages(&header, &messages)))
+    }
+}
+
+/// メッセージ検索ツール
+pub struct DiscordSearchMessagesTool {
+    access: Arc<DiscordAccess>,
+}
+
+impl DiscordSearchMessagesTool {
+    pub fn new(access: Arc<DiscordAccess>) -> Self {
+        Self { access }
+    }
+}
+
+#[async_trait]
+impl Tool for DiscordSearchMessagesTool {
+    fn name(&self) -> &str {
+        "discord_search_messages"
+    }
+
+    fn description(&self) -> &str {
+        "Search recent messages in a channel of this Discord server by text and/or author. Scans up to the last 1000 messages (optionally limited by a time range)."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "Text to search for (case-insensitive)"
+                },
+                "author": {
+                    "type": "string",
+                    "description": "Only messages from this user (username, display name or ID)"
+                },
+                "channel": {
+                    "type": "string",
+                    "description": CHANNEL_PARAM_DESCRIPTION
+                },
+                "since": {
+                    "type": "string",
+                    "description": "Only messages after this time: RFC3339, 'today', 'yesterday' or relative like '6h'"
+                },
+                "max_results": {
+                    "type": "integer",
+                    "description": "Maximum number of matches (default: 20, max: 100)"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let query = params["query"].as_str().unwrap_or("").trim().to_string();
+        let author = params["author"].as_str().map(str::trim).filter(|a| !a.is_empty());
+        if query.is_empty() && author.is_none() {
+            return Err(ToolError::InvalidParams("Specify 'query' and/or 'author'".to_string()));
+        }
+        let (since, _) = parse_range(&params, Utc::now())?;
+        let max_results = params["max_results"]
+            .as_u64()
+            .map(|n| n as usize)
+            .unwrap_or(DEFAULT_SEARCH_RESULTS)
+            .clamp(1, MAX_READ_MESSAGES);
+
+        let channel = self
+            .access
+            .authorize(
+                context,
+                parse_channel_ref(params["channel"].as_str()),
+                Permissions::READ_MESSAGE_HISTORY,
+            )
+            .await?;
+
+        let scanned = match self.access.fetch_messages(channel.id, None, since, MAX_SCAN_MESSAGES).await {
+            Ok(messages) => messages,
+            Err(e) => return Ok(ToolResult::error(e)),
+        };
+        let mut matches: Vec<Message> = scanned
+            .iter()
+            .filter(|m| matches_query(m, &query, author))
+            .take(max_results)
+            .cloned()
+            .collect();
+        matches.reverse();
+
+        info!(
+            "discord_search_messages: {} match(es) in {} scanned message(s) of {}",
+            matches.len(),
+            scanned.len(),
+            channel.id
+        );
+        if matches.is_empty() {
+            return Ok(ToolResult::success(format!(
+                "No matching messages in the last {} message(s) of {}",
+                scanned.len(),
+                channel.name
+            )));
+        }
+        let header = format!(
+            "{} match(es) in {} (searched {} message(s), oldest first):",
+            matches.len(),
+            channel.name,
+            scanned.len()
+        );
+        Ok(ToolResult::success(format_messages(&header, &matches)))
+    }
+}
+
+/// スレッド作成ツール
+pub struct DiscordCreateThreadTool {
+    access: Arc<DiscordAccess>,
+}
+
+impl DiscordCreateThreadTool {
+    pub fn new(access: Arc<DiscordAccess>) -> Self {
+        Self { access }
+    }
+}
+
+#[async_trait]
+impl Tool for DiscordCreateThreadTool {
+    fn name(&self) -> &str {
+        "discord_create_thread"
+    }
+
+    fn description(&self) -> &str {
+        "Create a public thread in a channel of this Discord server, optionally starting from an existing message."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": "Thread name (max 100 characters)"
+                },
+                "message_id": {
+                    "type": "string",
+                    "description": "Message ID or link to start the thread from (optional)"
+                },
+                "channel": {
+                    "type": "string",
+                    "description": CHANNEL_PARAM_DESCRIPTION
+                },
+                "auto_archive_minutes": {
+                    "type": "integer",
+                    "enum": [60, 1440, 4320, 10080],
+                    "description": "Archive the thread after this many minutes of inactivity (default: 1440)"
+                }
+            },
+            "required": ["name"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        self.access.check_write_permission(context).await?;
+        let name = params["name"].as_str().map(str::trim).filter(|n| !n.is_empty()).ok_or_else(|| {
+            ToolError::InvalidParams("Missing 'name' parameter".to_string())
+        })?;
+        let name: String = name.chars().take(100).collect();
+        let archive = match params["auto_archive_minutes"].as_u64().unwrap_or(1440) {
+            60 => AutoArchiveDuration::OneHour,
+            1440 => AutoArchiveDuration::OneDay,
+            4320 => AutoArchiveDuration::ThreeDays,
+            10080 => AutoArchiveDuration::OneWeek,
+            other => {
+                return Err(ToolError::InvalidParams(format!(
+                    "Invalid auto_archive_minutes: {} (use 60, 1440, 4320 or 10080)",
+                    other
+                )))
+            }
+        };
+
+        let (channel_ref, message_id) = if params["message_id"].is_string() {
+            let (channel, message_id) = resolve_message(&params)?;
+            (channel, Some(message_id))
+        } else {
+            (parse_channel_ref(params["channel"].as_str()), None)
+        };
+
+        let channel = self
+            .access
+            .authorize(
+                context,
+                channel_ref,
+                Permissions::CREATE_PUBLIC_THREADS | Permissions::SEND_MESSAGES_IN_THREADS,
+            )
+            .await?;
+
+        let builder = CreateThread::new(name.clone()).auto_archive_duration(archive);
+        let result = match message_id {
+            Some(message_id) => {
+                channel
+                    .id
+                    .create_thread_from_message(&*self.access.http, message_id, builder)
+                    .await
+            }
+            None => {
+                channel
+                    .id
+                    .create_thread(&*self.access.http, builder.kind(ChannelType::PublicThread))
+                    .await
+            }
+        };
+
+        match result {
+            Ok(thread) => {
+                info!("Created thread {} in {} for user {}", thread.id, channel.id, context.user_id);
+                Ok(ToolResult::success(format!(
+                    "Created thread '{}' (<#{}>) in {}",
+                    name, thread.id, channel.name
+                )))
+            }
+            Err(e) => Ok(ToolResult::error(format!("Failed to create thread: {}", e))),
+        }
+    }
+}
+
+/// リアクション追加ツール
+pub struct DiscordAddReactionTool {
+    access: Arc<DiscordAccess>,
+}
+
+impl DiscordAddReactionTool {
+    pub fn new(access: Arc<DiscordAccess>) -> Self {
+        Self { access }
+    }
+}
+
+#[async_trait]
+impl Tool for DiscordAddReactionTool {
+    fn name(&self) -> &str {
+        "discord_add_reaction"
+    }
+
+    fn description(&self) -> &str {
+        "Add an emoji reaction to a message in this Discord server."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "message_id": {
+                    "type": "string",
+                    "description": "Message ID or message link"
+                },
+                "emoji": {
+                    "type": "string",
+                    "description": "Unicode emoji (e.g. 👍) or custom emoji (<:name:id>)"
+                },
+                "channel": {
+                    "type": "string",
+                    "description": CHANNEL_PARAM_DESCRIPTION
+                }
+            },
+            "required": ["message_id", "emoji"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        self.access.check_write_permission(context).await?;
+        let emoji = params["emoji"].as_str().map(str::trim).filter(|e| !e.is_empty()).ok_or_else(|| {
+            ToolError::InvalidParams("Missing 'emoji' parameter".to_string())
+        })?;
+        let reaction = ReactionType::try_from(emoji)
+            .map_err(|_| ToolError::InvalidParams(format!("Invalid emoji: {}", emoji)))?;
+        let (channel_ref, message_id) = resolve_message(&params)?;
+
+        let channel = self
+            .access
+            .authorize(
+                context,
+                channel_ref,
+                Permissions::ADD_REACTIONS | Permissions::READ_MESSAGE_HISTORY,
+            )
+            .await?;
+
+        match channel.id.create_reaction(&*self.access.http, message_id, reaction).await {
+            Ok(()) => Ok(ToolResult::success(format!(
+                "Reacted with {} to message {} in {}",
+                emoji, message_id, channel.name
+            ))),
+            Err(e) => Ok(ToolResult::error(format!("Failed to add reaction: {}", e))),
+        }
+    }
+}
+
+/// ピン留めツール
+pub struct DiscordPinTool {
+    access: Arc<DiscordAccess>,
+}
+
+impl DiscordPinTool {
+    pub fn new(access: Arc<DiscordAccess>) -> Self {
+        Self { access }
+    }
+}
+
+#[async_trait]
+impl Tool for DiscordPinTool {
+    fn name(&self) -> &str {
+        "discord_pin"
+    }
+
+    fn description(&self) -> &str {
+        "Pin a message in a channel of this Discord server. Requires the Manage Messages permission."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "message_id": {
+                    "type": "string",
+                    "description": "Message ID or message link"
+                },
+                "channel": {
+                    "type": "string",
+                    "description": CHANNEL_PARAM_DESCRIPTION
+                }
+            },
+            "required": ["message_id"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        self.access.check_write_permission(context).await?;
+        let (channel_ref, message_id) = resolve_message(&params)?;
+
+        let channel = self
+            .access
+            .authorize(
+                context,
+                channel_ref,
+                Permissions::MANAGE_MESSAGES | Permissions::READ_MESSAGE_HISTORY,
+            )
+            .await?;
+
+        match channel.id.pin(&*self.access.http, message_id).await {
+            Ok(()) => {
+                info!("Pinned message {} in {} for user {}", message_id, channel.id, context.user_id);
+                Ok(ToolResult::success(format!("Pinned message {} in {}", message_id, channel.name)))
+            }
+            Err(e) => Ok(ToolResult::error(format!("Failed to pin message: {}", e))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Timelike;
+    use tempfile::TempDir;
+
+    /// Discord API を呼ばない権限チェック用のアクセス（`permissions_json` は permissions.json の内容）
+    async fn test_access(dir: &TempDir, permissions_json: Option<&str>) -> Arc<DiscordAccess> {
+        if let Some(json) = permissions_json {
+            std::fs::write(dir.path().join("permissions.json"), json).unwrap();
+        }
+        let permissions = PermissionManager::load(&dir.path().to_string_lossy()).await.unwrap();
+        Arc::new(DiscordAccess::new(
+            Arc::new(Http::new("")),
+            Arc::new(RwLock::new(permissions)),
+        ))
+    }
+
+    fn context(user_id: u64) -> ToolContext {
+        ToolContext::new(user_id, "test_user".to_string(), 456, "output".to_string()).with_guild_id(Some(1))
+    }
+
+    fn test_message(id: u64, author: &str, content: &str) -> Message {
+        serde_json::from_value(json!({
+            "id": id.to_string(),
+            "channel_id": "1",
+            "author": {"id": "42", "username": author, "discriminator": "0", "global_name": null, "avatar": null},
+            "content": content,
+            "timestamp": "2026-10-18T01:02:03Z",
+            "edited_timestamp": null,
+            "tts": false,
+            "mention_everyone": false,
+            "mentions": [],
+            "mention_roles": [],
+            "attachments": [],
+            "embeds": [],
+            "pinned": false,
+            "type": 0
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_channel_ref() {
+        assert_eq!(parse_channel_ref(None), ChannelRef::Current);
+        assert_eq!(parse_channel_ref(Some("  ")), ChannelRef::Current);
+        assert_eq!(parse_channel_ref(Some("<#123456>")), ChannelRef::Id(123456));
+        assert_eq!(parse_channel_ref(Some("123456")), ChannelRef::Id(123456));
+        assert_eq!(parse_channel_ref(Some("#Dev")), ChannelRef::Name("dev".to_string()));
+        assert_eq!(parse_channel_ref(Some("general")), ChannelRef::Name("general".to_string()));
+    }
+
+    #[test]
+    fn test_parse_message_ref() {
+        assert_eq!(parse_message_ref("987"), Some((None, 987)));
+        assert_eq!(
+            parse_message_ref("https://discord.com/channels/1/22/333"),
+            Some((Some(22), 333))
+        );
+        assert_eq!(parse_message_ref("https://discord.com/channels/1/22"), None);
+        assert_eq!(parse_message_ref("not a message"), None);
+        assert_eq!(parse_message_ref("0"), None);
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let now = DateTime::parse_from_rfc3339("2026-10-18T12:00:00Z").unwrap().with_timezone(&Utc);
+
+        assert_eq!(parse_time("6h", now), Some(now - ChronoDuration::hours(6)));
+        assert_eq!(parse_time("30m", now), Some(now - ChronoDuration::minutes(30)));
+        assert_eq!(parse_time("2d", now), Some(now - ChronoDuration::days(2)));
+        assert_eq!(
+            parse_time("2026-10-17T00:00:00+09:00", now),
+            DateTime::parse_from_rfc3339("2026-10-16T15:00:00Z").ok().map(|d| d.with_timezone(&Utc))
+        );
+
+        let today = parse_time("today", now).unwrap().with_timezone(&Local);
+        assert_eq!((today.hour(), today.minute()), (0, 0));
+        assert!(parse_time("today", now).unwrap() <= now);
+        assert!(parse_time("yesterday", now).unwrap() < parse_time("today", now).unwrap());
+
+        assert_eq!(parse_time("soon", now), None);
+        assert_eq!(parse_time("5w", now), None);
+        assert_eq!(parse_time("-1h", now), None);
+    }
+
+    #[test]
+    fn test_snowflake_at() {
+        let epoch = DateTime::from_timestamp_millis(DISCORD_EPOCH_MS).unwrap();
+        assert_eq!(snowflake_at(epoch), 1);
+        let later = epoch + ChronoDuration::milliseconds(1000);
+        assert_eq!(snowflake_at(later), 1000 << 22);
+        // メッセージIDのタイムスタンプ部分と一致する
+        let id = MessageId::new(snowflake_at(later));
+        assert_eq!(id.created_at().unix_timestamp() * 1000, later.timestamp_millis());
+    }
+
+    #[test]
+    fn test_matches_query_and_format() {
+        let msg = test_message(100, "alice", "Deploy finished on staging");
+
+        assert!(matches_query(&msg, "deploy", None));
+        assert!(matches_query(&msg, "", Some("Alice")));
+        assert!(matches_query(&msg, "staging", Some("42")));
+        assert!(!matches_query(&msg, "deploy", Some("bob")));
+        assert!(!matches_query(&msg, "rollback", None));
+
+        let formatted = format_messages("header:", &[msg, test_message(101, "bob", "ok")]);
+        assert!(formatted.starts_with("header:\n\n"));
+        assert!(formatted.contains("alice (message 100): Deploy finished on staging"));
+        let alice = formatted.find("alice").unwrap();
+        let bob = formatted.find("bob").unwrap();
+        assert!(alice < bob);
+    }
+
+    #[test]
+    fn test_format_messages_truncates_oldest() {
+        let long = "x".repeat(MAX_MESSAGE_CHARS);
+        let messages: Vec<Message> = (1..=40).map(|i| test_message(i, "user", &long)).collect();
+
+        let formatted = format_messages("header:", &messages);
+
+        assert!(formatted.len() <= MAX_OUTPUT_CHARS + 200);
+        assert!(formatted.contains("older message(s) omitted"));
+        assert!(formatted.contains("(message 40)"));
+        assert!(!formatted.contains("(message 1)"));
+    }
+
+    #[tokio::test]
+    async fn test_write_tools_require_discord_write_permission() {
+        let dir = TempDir::new().unwrap();
+        let access = test_access(&dir, None).await;
+        let ctx = context(42);
+
+        let result = DiscordCreateThreadTool::new(access.clone())
+            .execute(json!({"name": "topic"}), &ctx)
+            .await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+        let result = DiscordAddReactionTool::new(access.clone())
+            .execute(json!({"message_id": "123", "emoji": "👍"}), &ctx)
+            .await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+        let result = DiscordPinTool::new(access.clone())
+            .execute(json!({"message_id": "123"}), &ctx)
+            .await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+    }
+
+    #[tokio::test]
+    async fn test_write_permission_denied_without_user() {
+        let dir = TempDir::new().unwrap();
+        let access = test_access(
+            &dir,
+            Some(r#"{"custom_permissions": {}, "admins": [0], "super_users": [], "version": 1}"#),
+        )
+        .await;
+
+        let result = access.check_write_permission(&context(0)).await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+    }
+
+    #[tokio::test]
+    async fn test_write_permission_granted_or_admin() {
+        let dir = TempDir::new().unwrap();
+        let access = test_access(
+            &dir,
+            Some(r#"{"custom_permissions": {"42": ["DiscordWrite"]}, "admins": [7], "super_users": [], "version": 1}"#),
+        )
+        .await;
+
+        assert!(access.check_write_permission(&context(42)).await.is_ok());
+        assert!(access.check_write_permission(&context(7)).await.is_ok());
+        assert!(access.check_write_permission(&context(99)).await.is_err());
+    }
+}
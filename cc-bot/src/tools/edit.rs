@@ -1,3 +1,4 @@
+use crate::file_history;
 use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
 use async_trait::async_trait;
 use serde_json::{json, Value as JsonValue};
@@ -14,14 +15,14 @@ impl EditTool {
     }
 
     /// パスがシンボリックリンクかチェック
-    fn is_symlink(path: &Path) -> bool {
+    pub(crate) fn is_symlink(path: &Path) -> bool {
         path.symlink_metadata()
             .map(|m| m.file_type().is_symlink())
             .unwrap_or(false)
     }
 
     /// パスのバリデーション
-    fn validate_path(path: &str) -> Result<(), ToolError> {
+    pub(crate) fn validate_path(path: &str) -> Result<(), ToolError> {
         // 絶対パスは禁止
         if path.starts_with('/') {
             return Err(ToolError::PermissionDenied(
@@ -168,6 +169,9 @@ impl Tool for EditTool {
             content.replacen(old_string, new_string, 1)
         };
 
+        // 変更前の内容を履歴に保存
+        file_history::snapshot_before_write(context, path, path_obj, self.name()).await;
+
         // ファイル書き込み
         match fs::write(&user_path, &new_content).await {
             Ok(_) => {
@@ -0,0 +1,220 @@
+use super::apply_patch::ApplyPatchTool;
+use crate::file_history::{FileHistory, FileHistoryError, FileVersion};
+use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
+use async_trait::async_trait;
+use serde_json::{json, Value as JsonValue};
+use tracing::{debug, warn};
+
+/// 差分出力の最大文字数
+const MAX_DIFF_CHARS: usize = 8000;
+
+/// ファイル履歴ツール（一覧・差分・復元）
+pub struct FileHistoryTool;
+
+impl FileHistoryTool {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// バージョン一覧の1行表示
+    pub fn format_version(version: &FileVersion) -> String {
+        let state = match &version.hash {
+            Some(hash) => format!("{} bytes, {}", version.size, &hash[..8.min(hash.len())]),
+            None => "did not exist".to_string(),
+        };
+        format!(
+            "v{} {} before {} ({})",
+            version.id,
+            version.created_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            version.source,
+            state
+        )
+    }
+
+    fn to_result(error: FileHistoryError) -> Result<ToolResult, ToolError> {
+        match error {
+            FileHistoryError::NoHistory(_) | FileHistoryError::VersionNotFound(_) => {
+                Ok(ToolResult::error(error.to_string()))
+            }
+            other => {
+                warn!("File history error: {}", other);
+                Err(ToolError::ExecutionFailed(
+                    "Failed to access file history".to_string(),
+                ))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for FileHistoryTool {
+    fn name(&self) -> &str {
+        "file_history"
+    }
+
+    fn description(&self) -> &str {
+        "Inspect and restore previous versions of files changed by write_file, edit_file and apply_patch. Actions: list (versions of a file), diff (a version against the current file or another version), restore (roll a file back; defaults to the state before the last change)."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["list", "diff", "restore"],
+                    "description": "Action to perform"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Relative path of the file"
+                },
+                "version": {
+                    "type": "integer",
+                    "description": "Version id (default: latest snapshot)"
+                },
+                "compare_to": {
+                    "type": "integer",
+                    "description": "For diff: another version id to compare with (default: current file)"
+                }
+            },
+            "required": ["action", "path"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let action = params["action"].as_str().ok_or_else(|| {
+            ToolError::InvalidParams("Missing 'action' parameter".to_string())
+        })?;
+        let path = params["path"].as_str().ok_or_else(|| {
+            ToolError::InvalidParams("Missing 'path' parameter".to_string())
+        })?;
+        let version = params["version"].as_u64();
+        let compare_to = params["compare_to"].as_u64();
+
+        debug!("file_history {} {} for user {}", action, path, context.user_id);
+        let history = FileHistory::for_context(context);
+
+        match action {
+            "list" => match history.list(path).await {
+                Ok(versions) if versions.is_empty() => {
+                    Ok(ToolResult::success(format!("No history for {}", path)))
+                }
+                Ok(versions) => {
+                    let lines: Vec<String> = versions.iter().map(Self::format_version).collect();
+                    Ok(ToolResult::success(format!(
+                        "History of {} (newest first):\n{}",
+                        path,
+                        lines.join("\n")
+                    )))
+                }
+                Err(e) => Self::to_result(e),
+            },
+            "diff" => match history
+                .diff(path, &ApplyPatchTool::resolve_path(path, context)?, version, compare_to)
+                .await
+            {
+                Ok(mut diff) => {
+                    if diff.chars().count() > MAX_DIFF_CHARS {
+                        diff = diff.chars().take(MAX_DIFF_CHARS).collect();
+                        diff.push_str("\n...(truncated)");
+                    }
+                    Ok(ToolResult::success(diff))
+                }
+                Err(e) => Self::to_result(e),
+            },
+            // 履歴に残る実ファイルのパスではなく、write_file と同じく現在のコンテキストで解決し直す
+            "restore" => match history
+                .restore(path, &ApplyPatchTool::resolve_path(path, context)?, version)
+                .await
+            {
+                Ok(restored) => Ok(ToolResult::success(format!(
+                    "Restored {} to v{}. The previous content was saved, so this can be undone.",
+                    path, restored.id
+                ))),
+                Err(e) => Self::to_result(e),
+            },
+            other => Err(ToolError::InvalidParams(format!(
+                "Unknown action: {} (expected list, diff or restore)",
+                other
+            ))),
+        }
+    }
+}
+
+impl Default for FileHistoryTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::{test_workspace, WriteFileTool};
+
+    #[tokio::test]
+    async fn test_write_then_restore() {
+        let (_dir, ctx, _root) = test_workspace();
+        let writer = WriteFileTool::new();
+        writer.execute(json!({"path": "a.txt", "content": "one"}), &ctx).await.unwrap();
+        writer.execute(json!({"path": "a.txt", "content": "two"}), &ctx).await.unwrap();
+
+        let tool = FileHistoryTool::new();
+        let list = tool.execute(json!({"action": "list", "path": "a.txt"}), &ctx).await.unwrap();
+        assert!(list.output.contains("before write_file"));
+
+        let result = tool.execute(json!({"action": "restore", "path": "a.txt"}), &ctx).await.unwrap();
+        assert!(!result.is_error);
+        let content = std::fs::read_to_string(format!("{}/a.txt", ctx.get_user_output_dir())).unwrap();
+        assert_eq!(content, "one");
+    }
+
+    #[tokio::test]
+    async fn test_diff_output() {
+        let (_dir, ctx, _root) = test_workspace();
+        let writer = WriteFileTool::new();
+        writer.execute(json!({"path": "a.txt", "content": "one\n"}), &ctx).await.unwrap();
+        writer.execute(json!({"path": "a.txt", "content": "two\n"}), &ctx).await.unwrap();
+
+        let result = FileHistoryTool::new()
+            .execute(json!({"action": "diff", "path": "a.txt"}), &ctx)
+            .await
+            .unwrap();
+        assert!(result.output.contains("-one"));
+        assert!(result.output.contains("+two"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_restore_rejects_symlinked_target() {
+        let (dir, ctx, _root) = test_workspace();
+        let writer = WriteFileTool::new();
+        writer.execute(json!({"path": "a.txt", "content": "one"}), &ctx).await.unwrap();
+        writer.execute(json!({"path": "a.txt", "content": "two"}), &ctx).await.unwrap();
+
+        // 履歴作成後に対象がワークスペース外へのシンボリックリンクに差し替えられた場合
+        let outside = dir.path().join("outside.txt");
+        std::fs::write(&outside, "secret").unwrap();
+        let target = format!("{}/a.txt", ctx.get_user_output_dir());
+        std::fs::remove_file(&target).unwrap();
+        std::os::unix::fs::symlink(&outside, &target).unwrap();
+
+        let tool = FileHistoryTool::new();
+        for action in ["restore", "diff"] {
+            let result = tool.execute(json!({"action": action, "path": "a.txt"}), &ctx).await;
+            assert!(matches!(result, Err(ToolError::PermissionDenied(_))), "{}", action);
+        }
+        assert_eq!(std::fs::read_to_string(&outside).unwrap(), "secret");
+    }
+
+    #[tokio::test]
+    async fn test_restore_without_history() {
+        let (_dir, ctx, _root) = test_workspace();
+        let result = FileHistoryTool::new()
+            .execute(json!({"action": "restore", "path": "missing.txt"}), &ctx)
+            .await
+            .unwrap();
+        assert!(result.is_error);
+    }
+}
@@ -0,0 +1,894 @@
+//! Gitツール
+//!
+//! ユーザーの作業ディレクトリ内にあるリポジトリに対して、git2 で
+//! status / diff / log / show / blame / branch / add / commit / checkout を実行し、
+//! 結果を構造化JSONで返します。fetch / push はネットワークが許可されている場合のみ。
+
+use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
+use crate::tools::EditTool;
+use crate::validation::PathValidator;
+use async_trait::async_trait;
+use chrono::DateTime;
+use git2::{
+    BlameOptions, BranchType, Commit, Cred, Diff, DiffFormat, DiffOptions, ErrorCode, FetchOptions,
+    IndexAddOption, Patch, PushOptions, RemoteCallbacks, Repository, RepositoryOpenFlags, Signature, Sort,
+    Status, StatusOptions,
+};
+use serde_json::{json, Value as JsonValue};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// log のデフォルト件数
+const DEFAULT_LOG_COUNT: usize = 20;
+/// log の最大件数
+const MAX_LOG_COUNT: usize = 200;
+/// パッチ本文の最大文字数
+const MAX_PATCH_CHARS: usize = 12000;
+/// status / diff で返す最大ファイル数
+const MAX_FILES: usize = 500;
+/// blame で返す最大行数
+const MAX_BLAME_LINES: usize = 500;
+
+/// 読み取り・書き込みアクション
+const ACTIONS: &[&str] = &[
+    "status", "diff", "log", "show", "blame", "branch", "add", "commit", "checkout", "fetch", "push",
+];
+
+/// Gitツール
+pub struct GitTool {
+    /// fetch / push を許可するか
+    allow_network: bool,
+}
+
+impl GitTool {
+    pub fn new(allow_network: bool) -> Self {
+        Self { allow_network }
+    }
+
+    /// 環境変数 `GIT_TOOL_ALLOW_NETWORK` から作成
+    pub fn from_env() -> Self {
+        let allow_network = std::env::var("GIT_TOOL_ALLOW_NETWORK")
+            .map(|v| v.to_lowercase() == "true" || v == "1")
+            .unwrap_or(false);
+        Self::new(allow_network)
+    }
+
+    /// リポジトリのパスを検証し、作業ディレクトリ内のリポジトリを開く
+    fn open_repo(repo_path: &str, context: &ToolContext) -> Result<Repository, ToolError> {
+        let repo_path = repo_path.trim().trim_start_matches("./");
+        let repo_path = if repo_path.is_empty() { "." } else { repo_path };
+        if repo_path != "." {
+            EditTool::validate_path(repo_path)?;
+        }
+
+        let base = PathBuf::from(context.get_user_output_dir());
+        let full_path = PathValidator::new(&base)
+            .validate_path(repo_path)
+            .map_err(|e| ToolError::PermissionDenied(e.to_string()))?;
+
+        // 上位ディレクトリへの探索はしない（作業ディレクトリ外のリポジトリを開かない）
+        let repo = Repository::open_ext(&full_path, RepositoryOpenFlags::NO_SEARCH, std::iter::empty::<&str>())
+            .map_err(|e| {
+                debug!("Failed to open repository {:?}: {}", full_path, e);
+                ToolError::InvalidParams(format!("Not a git repository: {}", repo_path))
+            })?;
+
+        // .git ファイルやシンボリックリンクで外部を指していないか確認
+        let base = base
+            .canonicalize()
+            .map_err(|_| ToolError::ExecutionFailed("Workspace directory does not exist".to_string()))?;
+        let inside = |path: &Path| path.canonicalize().map(|p| p.starts_with(&base)).unwrap_or(false);
+        let workdir_ok = repo.workdir().is_some_and(inside);
+        if !workdir_ok || !inside(repo.path()) {
+            return Err(ToolError::PermissionDenied(
+                "Repository must be inside your workspace".to_string(),
+            ));
+        }
+
+        Ok(repo)
+    }
+
+    /// リポジトリ内の相対パスを検証
+    fn validate_repo_path(path: &str) -> Result<String, ToolError> {
+        let path = path.trim().trim_start_matches("./");
+        if path.is_empty() {
+            return Err(ToolError::InvalidParams("Path cannot be empty".to_string()));
+        }
+        if path != "." {
+            EditTool::validate_path(path)?;
+        }
+        Ok(path.to_string())
+    }
+
+    /// パラメータの文字列配列（または単一文字列）を取得
+    fn string_list(params: &JsonValue, key: &str) -> Result<Vec<String>, ToolError> {
+        let values = match &params[key] {
+            JsonValue::Null => Vec::new(),
+            JsonValue::String(s) => vec![s.clone()],
+            JsonValue::Array(items) => items.iter().filter_map(|v| v.as_str().map(str::to_string)).collect(),
+            _ => return Err(ToolError::InvalidParams(format!("'{}' must be a string or an array", key))),
+        };
+        values.iter().map(|p| Self::validate_repo_path(p)).collect()
+    }
+
+    fn required_str<'a>(params: &'a JsonValue, key: &str) -> Result<&'a str, ToolError> {
+        params[key]
+            .as_str()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .ok_or_else(|| ToolError::InvalidParams(format!("Missing '{}' parameter", key)))
+    }
+
+    /// アクションを実行（ブロッキング）
+    fn run(
+        &self,
+        action: &str,
+        repo: &Repository,
+        params: &JsonValue,
+        context: &ToolContext,
+    ) -> Result<JsonValue, ToolError> {
+        match action {
+            "status" => Ok(status(repo, &Self::string_list(params, "paths")?)?),
+            "diff" => Ok(diff(
+                repo,
+                params["rev"].as_str(),
+                params["staged"].as_bool().unwrap_or(false),
+                &Self::string_list(params, "paths")?,
+            )?),
+            "log" => {
+                let count = params["max_count"]
+                    .as_u64()
+                    .map(|n| n as usize)
+                    .unwrap_or(DEFAULT_LOG_COUNT)
+                    .clamp(1, MAX_LOG_COUNT);
+                let path = params["path"].as_str().map(Self::validate_repo_path).transpose()?;
+                Ok(log(repo, params["rev"].as_str(), count, path.as_deref())?)
+            }
+            "show" => Ok(show(repo, params["rev"].as_str().unwrap_or("HEAD"))?),
+            "blame" => {
+                let path = Self::validate_repo_path(Self::required_str(params, "path")?)?;
+                let start = params["start_line"].as_u64().unwrap_or(1).max(1) as usize;
+                let end = params["end_line"].as_u64().map(|n| n as usize);
+                Ok(blame(repo, &path, start, end)?)
+            }
+            "branch" => {
+                let delete = params["delete"].as_bool().unwrap_or(false);
+                Ok(branch(repo, params["name"].as_str(), delete, params["rev"].as_str())?)
+            }
+            "add" => {
+                let paths = Self::string_list(params, "paths")?;
+                if paths.is_empty() {
+                    return Err(ToolError::InvalidParams("'paths' is required for add (use \".\" for all)".to_string()));
+                }
+                Ok(add(repo, &paths)?)
+            }
+            "commit" => {
+                let message = Self::required_str(params, "message")?;
+                Ok(commit(repo, message, context)?)
+            }
+            "checkout" => {
+                let name = Self::required_str(params, "name")?;
+                Ok(checkout(repo, name, params["create"].as_bool().unwrap_or(false))?)
+            }
+            "fetch" | "push" => {
+                if !self.allow_network {
+                    return Err(ToolError::PermissionDenied(format!(
+                        "git {} is disabled: network access is not permitted for the git tool",
+                        action
+                    )));
+                }
+                let remote = params["remote"].as_str().unwrap_or("origin");
+                if action == "fetch" {
+                    Ok(fetch(repo, remote)?)
+                } else {
+                    Ok(push(repo, remote, params["name"].as_str())?)
+                }
+            }
+            other => Err(ToolError::InvalidParams(format!(
+                "Unknown action: {} (expected one of: {})",
+                other,
+                ACTIONS.join(", ")
+            ))),
+        }
+    }
+}
+
+impl Default for GitTool {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+/// git2 のエラーを結果用のエラーに変換
+#[derive(Debug)]
+struct GitFailure(String);
+
+impl From<git2::Error> for GitFailure {
+    fn from(e: git2::Error) -> Self {
+        Self(e.message().to_string())
+    }
+}
+
+impl From<GitFailure> for ToolError {
+    fn from(e: GitFailure) -> Self {
+        ToolError::ExecutionFailed(e.0)
+    }
+}
+
+type GitResult<T> = Result<T, GitFailure>;
+
+/// コミットを要約したJSON
+fn commit_summary(commit: &Commit) -> JsonValue {
+    let author = commit.author();
+    json!({
+        "id": commit.id().to_string(),
+        "short_id": short_id(commit.id()),
+        "author": author.name().unwrap_or(""),
+        "email": author.email().unwrap_or(""),
+        "time": DateTime::from_timestamp(author.when().seconds(), 0).map(|t| t.to_rfc3339()),
+        "summary": commit.summary().unwrap_or(""),
+    })
+}
+
+fn short_id(oid: git2::Oid) -> String {
+    oid.to_string().chars().take(8).collect()
+}
+
+/// 現在のブランチ名（デタッチ時は None）
+fn current_branch(repo: &Repository) -> Option<String> {
+    match repo.head() {
+        Ok(head) if head.is_branch() => head.shorthand().map(str::to_string),
+        Ok(_) => None,
+        // コミットがまだないブランチ
+        Err(e) if e.code() == ErrorCode::UnbornBranch => repo
+            .find_reference("HEAD")
+            .ok()
+            .and_then(|r| r.symbolic_target().map(|t| t.trim_start_matches("refs/heads/").to_string())),
+        Err(_) => None,
+    }
+}
+
+fn head_commit(repo: &Repository) -> Option<Commit<'_>> {
+    repo.head().ok().and_then(|h| h.peel_to_commit().ok())
+}
+
+fn resolve_commit<'r>(repo: &'r Repository, rev: &str) -> GitResult<Commit<'r>> {
+    Ok(repo.revparse_single(rev)?.peel_to_commit()?)
+}
+
+/// status の1エントリを (index, worktree) の状態文字列に変換
+fn status_labels(status: Status) -> (Option<&'static str>, Option<&'static str>) {
+    let index = if status.is_index_new() {
+        Some("added")
+    } else if status.is_index_modified() {
+        Some("modified")
+    } else if status.is_index_deleted() {
+        Some("deleted")
+    } else if status.is_index_renamed() {
+        Some("renamed")
+    } else if status.is_index_typechange() {
+        Some("typechange")
+    } else {
+        None
+    };
+    let worktree = if status.is_wt_new() {
+        Some("untracked")
+    } else if status.is_wt_modified() {
+        Some("modified")
+    } else if status.is_wt_deleted() {
+        Some("deleted")
+    } else if status.is_wt_renamed() {
+        Some("renamed")
+    } else if status.is_wt_typechange() {
+        Some("typechange")
+    } else if status.is_conflicted() {
+        Some("conflicted")
+    } else {
+        None
+    };
+    (index, worktree)
+}
+
+fn status(repo: &Repository, paths: &[String]) -> GitResult<JsonValue> {
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .renames_head_to_index(true);
+    for path in paths {
+        options.pathspec(path);
+    }
+
+    let statuses = repo.statuses(Some(&mut options))?;
+    let files: Vec<JsonValue> = statuses
+        .iter()
+        .take(MAX_FILES)
+        .map(|entry| {
+            let (index, worktree) = status_labels(entry.status());
+            json!({
+                "path": entry.path().unwrap_or(""),
+                "index": index,
+                "worktree": worktree,
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "branch": current_branch(repo),
+        "head": head_commit(repo).map(|c| short_id(c.id())),
+        "clean": statuses.is_empty(),
+        "files": files,
+        "truncated": statuses.len() > MAX_FILES,
+    }))
+}
+
+/// 差分のファイル一覧とパッチ本文
+fn describe_diff(diff: &Diff) -> GitResult<JsonValue> {
+    let mut files = Vec::new();
+    for idx in 0..diff.deltas().len().min(MAX_FILES) {
+        let delta = diff.get_delta(idx).expect("delta index in range");
+        let (additions, deletions) = match Patch::from_diff(diff, idx)? {
+            Some(patch) => {
+                let (_, additions, deletions) = patch.line_stats()?;
+                (additions, deletions)
+            }
+            None => (0, 0),
+        };
+        let path = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+        files.push(json!({
+            "path": path,
+            "status": format!("{:?}", delta.status()).to_lowercase(),
+            "additions": additions,
+            "deletions": deletions,
+            "binary": delta.flags().is_binary(),
+        }));
+    }
+
+    let mut patch = String::new();
+    let mut truncated = false;
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        if patch.len() >= MAX_PATCH_CHARS {
+            truncated = true;
+            return false;
+        }
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            patch.push(line.origin());
+        }
+        patch.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })
+    .or_else(|e| if truncated { Ok(()) } else { Err(e) })?;
+
+    Ok(json!({
+        "files": files,
+        "patch": patch,
+        "truncated": truncated || diff.deltas().len() > MAX_FILES,
+    }))
+}
+
+fn diff(repo: &Repository, rev: Option<&str>, staged: bool, paths: &[String]) -> GitResult<JsonValue> {
+    let mut options = DiffOptions::new();
+    options.include_untracked(!staged && rev.is_none()).recurse_untracked_dirs(true).show_untracked_content(true);
+    for path in paths {
+        options.pathspec(path);
+    }
+
+    let diff = match rev {
+        // 指定リビジョンと作業ツリー（インデックス込み）の差分
+        Some(rev) => {
+            let tree = resolve_commit(repo, rev)?.tree()?;
+            repo.diff_tree_to_workdir_with_index(Some(&tree), Some(&mut options))?
+        }
+        // ステージ済みの変更（HEAD とインデックス）
+        None if staged => {
+            let tree = head_commit(repo).map(|c| c.tree()).transpose()?;
+            repo.diff_tree_to_index(tree.as_ref(), None, Some(&mut options))?
+        }
+        // 未ステージの変更（インデックスと作業ツリー）
+        None => repo.diff_index_to_workdir(None, Some(&mut options))?,
+    };
+    describe_diff(&diff)
+}
+
+fn log(repo: &Repository, rev: Option<&str>, count: usize, path: Option<&str>) -> GitResult<JsonValue> {
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TIME)?;
+    match rev {
+        Some(rev) => walk.push(resolve_commit(repo, rev)?.id())?,
+        None => match repo.head() {
+            Ok(_) => walk.push_head()?,
+            Err(e) if e.code() == ErrorCode::UnbornBranch => {
+                return Ok(json!({"branch": current_branch(repo), "commits": []}));
+            }
+            Err(e) => return Err(e.into()),
+        },
+    }
+
+    let mut commits = Vec::new();
+    for oid in walk {
+        if commits.len() >= count {
+            break;
+        }
+        let commit = repo.find_commit(oid?)?;
+        if let Some(path) = path {
+            if !touches_path(repo, &commit, path)? {
+                continue;
+            }
+        }
+        commits.push(commit_summary(&commit));
+    }
+
+    Ok(json!({"branch": current_branch(repo), "commits": commits}))
+}
+
+/// コミットが指定パスを変更しているか（最初の親との比較）
+fn touches_path(repo: &Repository, commit: &Commit, path: &str) -> GitResult<bool> {
+    let tree = commit.tree()?;
+    let parent_tree = commit.parents().next().map(|p| p.tree()).transpose()?;
+    let mut options = DiffOptions::new();
+    options.pathspec(path);
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut options))?;
+    Ok(diff.deltas().len() > 0)
+}
+
+fn show(repo: &Repository, rev: &str) -> GitResult<JsonValue> {
+    let commit = resolve_commit(repo, rev)?;
+    let tree = commit.tree()?;
+    let parent_tree = commit.parents().next().map(|p| p.tree()).transpose()?;
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+
+    let mut result = commit_summary(&commit);
+    result["message"] = json!(commit.message().unwrap_or(""));
+    result["parents"] = json!(commit.parent_ids().map(|id| id.to_string()).collect::<Vec<_>>());
+    result["diff"] = describe_diff(&diff)?;
+    Ok(result)
+}
+
+fn blame(repo: &Repository, path: &str, start: usize, end: Option<usize>) -> GitResult<JsonValue> {
+    let head = head_commit(repo).ok_or_else(|| GitFailure("Repository has no commits".to_string()))?;
+    let blob = head
+        .tree()?
+        .get_path(Path::new(path))?
+        .to_object(repo)?
+        .peel_to_blob()
+        .map_err(|_| GitFailure(format!("Not a file: {}", path)))?;
+    if blob.is_binary() {
+        return Err(GitFailure(format!("Cannot blame binary file: {}", path)));
+    }
+    let content = String::from_utf8_lossy(blob.content()).to_string();
+    let total = content.lines().count();
+
+    let mut options = BlameOptions::new();
+    options.newest_commit(head.id());
+    let end = end.unwrap_or(total).min(total).min(start + MAX_BLAME_LINES - 1);
+    if start <= end {
+        options.min_line(start).max_line(end);
+    }
+    let blame = repo.blame_file(Path::new(path), Some(&mut options))?;
+
+    let lines: Vec<JsonValue> = content
+        .lines()
+        .enumerate()
+        .skip(start - 1)
+        .take(end.saturating_sub(start - 1))
+        .map(|(idx, text)| {
+            let line_no = idx + 1;
+            let hunk = blame.get_line(line_no);
+            let signature = hunk.as_ref().map(|h| h.final_signature());
+            json!({
+                "line": line_no,
+                "commit": hunk.as_ref().map(|h| short_id(h.final_commit_id())),
+                "author": signature.as_ref().and_then(|s| s.name().map(str::to_string)),
+                "time": signature
+                    .as_ref()
+                    .and_then(|s| DateTime::from_timestamp(s.when().seconds(), 0))
+                    .map(|t| t.to_rfc3339()),
+                "content": text,
+            })
+        })
+        .collect();
+
+    Ok(json!({"path": path, "total_lines": total, "lines": lines}))
+}
+
+fn branch(repo: &Repository, name: Option<&str>, delete: bool, rev: Option<&str>) -> GitResult<JsonValue> {
+    match name.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) if delete => {
+            let mut branch = repo.find_branch(name, BranchType::Local)?;
+            if branch.is_head() {
+                return Err(GitFailure(format!("Cannot delete the current branch: {}", name)));
+            }
+            branch.delete()?;
+            Ok(json!({"deleted": name}))
+        }
+        Some(name) => {
+            let target = match rev {
+                Some(rev) => resolve_commit(repo, rev)?,
+                None => head_commit(repo).ok_or_else(|| GitFailure("Repository has no commits".to_string()))?,
+            };
+            repo.branch(name, &target, false)?;
+            Ok(json!({"created": name, "commit": short_id(target.id())}))
+        }
+        None => {
+            let mut branches = Vec::new();
+            for entry in repo.branches(Some(BranchType::Local))? {
+                let (branch, _) = entry?;
+                let commit = branch.get().peel_to_commit().ok();
+                branches.push(json!({
+                    "name": branch.name()?.unwrap_or(""),
+                    "current": branch.is_head(),
+                    "commit": commit.as_ref().map(|c| short_id(c.id())),
+                    "summary": commit.as_ref().and_then(|c| c.summary().map(str::to_string)),
+                }));
+            }
+            Ok(json!({"current": current_branch(repo), "branches": branches}))
+        }
+    }
+}
+
+fn add(repo: &Repository, paths: &[String]) -> GitResult<JsonValue> {
+    let mut index = repo.index()?;
+    index.add_all(paths.iter(), IndexAddOption::DEFAULT, None)?;
+    // 削除されたファイルもステージする
+    index.update_all(paths.iter(), None)?;
+    index.write()?;
+
+    let staged = status(repo, &[])?["files"]
+        .as_array()
+        .map(|files| {
+            files
+                .iter()
+                .filter(|f| !f["index"].is_null())
+                .map(|f| f["path"].clone())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    Ok(json!({"staged": staged}))
+}
+
+fn commit(repo: &Repository, message: &str, context: &ToolContext) -> GitResult<JsonValue> {
+    let mut index = repo.index()?;
+    let tree_id = index.write_tree()?;
+    let tree = repo.find_tree(tree_id)?;
+    let parent = head_commit(repo);
+    if parent.as_ref().is_some_and(|p| p.tree_id() == tree_id) {
+        return Err(GitFailure("Nothing to commit (stage changes with add first)".to_string()));
+    }
+
+    // 作者は呼び出し元のDiscordユーザー
+    let signature = Signature::now(&context.user_name, &format!("{}@users.discord.invalid", context.user_id))?;
+    let parents: Vec<&Commit> = parent.iter().collect();
+    let oid = repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)?;
+    let commit = repo.find_commit(oid)?;
+
+    let mut result = commit_summary(&commit);
+    result["branch"] = json!(current_branch(repo));
+    Ok(result)
+}
+
+fn checkout(repo: &Repository, name: &str, create: bool) -> GitResult<JsonValue> {
+    if create {
+        let head = head_commit(repo).ok_or_else(|| GitFailure("Repository has no commits".to_string()))?;
+        repo.branch(name, &head, false)?;
+    }
+
+    let mut builder = git2::build::CheckoutBuilder::new();
+    // 未コミットの変更を上書きしない
+    builder.safe();
+
+    match repo.find_branch(name, BranchType::Local) {
+        Ok(branch) => {
+            let reference = branch.get();
+            let target = reference.peel_to_commit()?;
+            repo.checkout_tree(target.as_object(), Some(&mut builder))?;
+            repo.set_head(reference.name().ok_or_else(|| GitFailure("Invalid branch name".to_string()))?)?;
+            Ok(json!({"branch": name, "commit": short_id(target.id()), "created": create}))
+        }
+        Err(_) => {
+            // ブランチ以外のリビジョンはデタッチ状態でチェックアウト
+            let target = resolve_commit(repo, name)?;
+            repo.checkout_tree(target.as_object(), Some(&mut builder))?;
+            repo.set_head_detached(target.id())?;
+            Ok(json!({"detached": true, "commit": short_id(target.id())}))
+        }
+    }
+}
+
+/// リモートのURLを確認（https のみ許可）
+fn checked_remote<'r>(repo: &'r Repository, name: &str) -> GitResult<git2::Remote<'r>> {
+    let remote = repo.find_remote(name)?;
+    let url = remote.url().unwrap_or("");
+    if !url.starts_with("https://") {
+        return Err(GitFailure(format!("Only https:// remotes are allowed (remote '{}')", name)));
+    }
+    Ok(remote)
+}
+
+/// 認証コールバック（リポジトリ設定は使わず、ホストのグローバル設定の認証ヘルパーのみ）
+fn remote_callbacks<'a>() -> RemoteCallbacks<'a> {
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(|url, username, _allowed| {
+        let config = git2::Config::open_default()?;
+        Cred::credential_helper(&config, url, username)
+    });
+    callbacks
+}
+
+fn fetch(repo: &Repository, remote_name: &str) -> GitResult<JsonValue> {
+    let mut remote = checked_remote(repo, remote_name)?;
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(remote_callbacks());
+    remote.fetch(&[] as &[&str], Some(&mut options), None)?;
+    let stats = remote.stats();
+    Ok(json!({
+        "remote": remote_name,
+        "received_objects": stats.received_objects(),
+        "received_bytes": stats.received_bytes(),
+    }))
+}
+
+fn push(repo: &Repository, remote_name: &str, branch: Option<&str>) -> GitResult<JsonValue> {
+    let branch = match branch {
+        Some(branch) => branch.to_string(),
+        None => current_branch(repo).ok_or_else(|| GitFailure("HEAD is detached; specify 'name'".to_string()))?,
+    };
+    let mut remote = checked_remote(repo, remote_name)?;
+    let refspec = format!("refs/heads/{0}:refs/heads/{0}", branch);
+    let mut options = PushOptions::new();
+    options.remote_callbacks(remote_callbacks());
+    remote.push(&[refspec.as_str()], Some(&mut options))?;
+    Ok(json!({"remote": remote_name, "pushed": branch}))
+}
+
+#[async_trait]
+impl Tool for GitTool {
+    fn name(&self) -> &str {
+        "git"
+    }
+
+    fn description(&self) -> &str {
+        "Run git operations on a repository inside your workspace and get structured JSON results. Actions: status, diff, log, show, blame, branch, add, commit, checkout (fetch/push only when network access is enabled). Prefer this over bash for git."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ACTIONS,
+                    "description": "Git operation to run"
+                },
+                "repo": {
+                    "type": "string",
+                    "description": "Relative path to the repository in your workspace (default: workspace root)"
+                },
+                "paths": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Paths to limit status/diff to, or to stage with add"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "File path for blame, or to filter log"
+                },
+                "rev": {
+                    "type": "string",
+                    "description": "Revision (commit, branch, tag, HEAD~1...) for diff/log/show/branch"
+                },
+                "staged": {
+                    "type": "boolean",
+                    "description": "diff: show staged changes instead of unstaged ones"
+                },
+                "max_count": {
+                    "type": "integer",
+                    "description": "log: number of commits (default: 20, max: 200)"
+                },
+                "start_line": {
+                    "type": "integer",
+                    "description": "blame: first line (1-based)"
+                },
+                "end_line": {
+                    "type": "integer",
+                    "description": "blame: last line"
+                },
+                "name": {
+                    "type": "string",
+                    "description": "branch/checkout: branch name (checkout also accepts a revision); push: branch to push"
+                },
+                "create": {
+                    "type": "boolean",
+                    "description": "checkout: create the branch from HEAD first"
+                },
+                "delete": {
+                    "type": "boolean",
+                    "description": "branch: delete the named branch"
+                },
+                "message": {
+                    "type": "string",
+                    "description": "commit: commit message"
+                },
+                "remote": {
+                    "type": "string",
+                    "description": "fetch/push: remote name (default: origin)"
+                }
+            },
+            "required": ["action"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let action = Self::required_str(&params, "action")?.to_lowercase();
+        let repo_path = params["repo"].as_str().unwrap_or(".").to_string();
+
+        info!("git {} in {} for user {}", action, repo_path, context.user_id);
+
+        let tool = Self::new(self.allow_network);
+        let context = context.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let repo = Self::open_repo(&repo_path, &context)?;
+            tool.run(&action, &repo, &params, &context)
+        })
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(format!("git task failed: {}", e)))?;
+
+        match result {
+            Ok(value) => Ok(ToolResult::success(serde_json::to_string_pretty(&value)?)),
+            Err(ToolError::ExecutionFailed(message)) => Ok(ToolResult::error(format!("git error: {}", message))),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::test_workspace;
+
+    fn setup_repo() -> (tempfile::TempDir, ToolContext, PathBuf) {
+        let (dir, ctx, workspace) = test_workspace();
+        let root = workspace.join("project");
+        std::fs::create_dir_all(&root).unwrap();
+        Repository::init(&root).unwrap();
+        std::fs::write(root.join("a.txt"), "one\ntwo\n").unwrap();
+        (dir, ctx, root)
+    }
+
+    async fn git(tool: &GitTool, ctx: &ToolContext, params: JsonValue) -> JsonValue {
+        let result = tool.execute(params, ctx).await.unwrap();
+        assert!(!result.is_error, "{}", result.output);
+        serde_json::from_str(&result.output).unwrap()
+    }
+
+    async fn initial_commit(tool: &GitTool, ctx: &ToolContext) -> JsonValue {
+        git(tool, ctx, json!({"action": "add", "repo": "project", "paths": ["."]})).await;
+        git(tool, ctx, json!({"action": "commit", "repo": "project", "message": "Initial commit"})).await
+    }
+
+    #[tokio::test]
+    async fn test_add_commit_log_and_status() {
+        let (_dir, ctx, _root) = setup_repo();
+        let tool = GitTool::default();
+
+        let status = git(&tool, &ctx, json!({"action": "status", "repo": "project"})).await;
+        assert_eq!(status["clean"], false);
+        assert_eq!(status["files"][0]["path"], "a.txt");
+        assert_eq!(status["files"][0]["worktree"], "untracked");
+
+        let commit = initial_commit(&tool, &ctx).await;
+        assert_eq!(commit["author"], "test_user");
+        assert_eq!(commit["summary"], "Initial commit");
+
+        let log = git(&tool, &ctx, json!({"action": "log", "repo": "project"})).await;
+        assert_eq!(log["commits"].as_array().unwrap().len(), 1);
+        assert_eq!(log["commits"][0]["id"], commit["id"]);
+
+        let status = git(&tool, &ctx, json!({"action": "status", "repo": "project"})).await;
+        assert_eq!(status["clean"], true);
+
+        let result = tool
+            .execute(json!({"action": "commit", "repo": "project", "message": "again"}), &ctx)
+            .await
+            .unwrap();
+        assert!(result.is_error);
+        assert!(result.output.contains("Nothing to commit"));
+    }
+
+    #[tokio::test]
+    async fn test_diff_and_show() {
+        let (_dir, ctx, root) = setup_repo();
+        let tool = GitTool::default();
+        initial_commit(&tool, &ctx).await;
+
+        std::fs::write(root.join("a.txt"), "one\nTWO\nthree\n").unwrap();
+        let diff = git(&tool, &ctx, json!({"action": "diff", "repo": "project"})).await;
+        assert_eq!(diff["files"][0]["path"], "a.txt");
+        assert_eq!(diff["files"][0]["additions"], 2);
+        assert_eq!(diff["files"][0]["deletions"], 1);
+        assert!(diff["patch"].as_str().unwrap().contains("+TWO"));
+
+        let staged = git(&tool, &ctx, json!({"action": "diff", "repo": "project", "staged": true})).await;
+        assert!(staged["files"].as_array().unwrap().is_empty());
+
+        git(&tool, &ctx, json!({"action": "add", "repo": "project", "paths": "a.txt"})).await;
+        git(&tool, &ctx, json!({"action": "commit", "repo": "project", "message": "Update a"})).await;
+        let show = git(&tool, &ctx, json!({"action": "show", "repo": "project"})).await;
+        assert_eq!(show["message"], "Update a");
+        assert_eq!(show["parents"].as_array().unwrap().len(), 1);
+        assert!(show["diff"]["patch"].as_str().unwrap().contains("-two"));
+
+        let log = git(&tool, &ctx, json!({"action": "log", "repo": "project", "max_count": 1})).await;
+        assert_eq!(log["commits"].as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_branch_checkout_and_blame() {
+        let (_dir, ctx, root) = setup_repo();
+        let tool = GitTool::default();
+        initial_commit(&tool, &ctx).await;
+
+        let checkout = git(&tool, &ctx, json!({"action": "checkout", "repo": "project", "name": "feature", "create": true})).await;
+        assert_eq!(checkout["branch"], "feature");
+
+        let branches = git(&tool, &ctx, json!({"action": "branch", "repo": "project"})).await;
+        assert_eq!(branches["current"], "feature");
+        assert_eq!(branches["branches"].as_array().unwrap().len(), 2);
+
+        std::fs::write(root.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        git(&tool, &ctx, json!({"action": "add", "repo": "project", "paths": ["a.txt"]})).await;
+        let second = git(&tool, &ctx, json!({"action": "commit", "repo": "project", "message": "Add three"})).await;
+
+        let blame = git(&tool, &ctx, json!({"action": "blame", "repo": "project", "path": "a.txt", "start_line": 2})).await;
+        assert_eq!(blame["total_lines"], 3);
+        let lines = blame["lines"].as_array().unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["content"], "two");
+        assert_eq!(lines[1]["commit"], second["short_id"]);
+        assert_eq!(lines[1]["author"], "test_user");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_paths_outside_workspace() {
+        let (_dir, ctx, _root) = setup_repo();
+        let tool = GitTool::default();
+
+        let result = tool.execute(json!({"action": "status", "repo": "../.."}), &ctx).await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+
+        let result = tool.execute(json!({"action": "status", "repo": "/etc"}), &ctx).await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+
+        // 作業ディレクトリ自体はリポジトリではない（上位を探索しない）
+        let result = tool.execute(json!({"action": "status"}), &ctx).await;
+        assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+
+        let result = tool
+            .execute(json!({"action": "blame", "repo": "project", "path": "../secret"}), &ctx)
+            .await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+    }
+
+    #[tokio::test]
+    async fn test_network_actions_require_permission() {
+        let (_dir, ctx, root) = setup_repo();
+        let tool = GitTool::default();
+
+        for action in ["fetch", "push"] {
+            let result = tool.execute(json!({"action": action, "repo": "project"}), &ctx).await;
+            assert!(matches!(result, Err(ToolError::PermissionDenied(_))), "{}", action);
+        }
+
+        // 許可されていても https 以外のリモートは拒否
+        let repo = Repository::open(&root).unwrap();
+        repo.remote("origin", "/tmp/elsewhere").unwrap();
+        let tool = GitTool::new(true);
+        let result = tool.execute(json!({"action": "fetch", "repo": "project"}), &ctx).await.unwrap();
+        assert!(result.is_error);
+        assert!(result.output.contains("Only https:// remotes"));
+    }
+}
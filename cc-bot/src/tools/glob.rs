@@ -1,10 +1,36 @@
 use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
 use async_trait::async_trait;
+use chrono::{DateTime, Local};
+use globset::{GlobBuilder, GlobMatcher};
+use ignore::WalkBuilder;
 use serde_json::{json, Value as JsonValue};
-use std::path::Path;
-use tokio::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use tracing::{debug, warn};
 
+/// デフォルトの最大件数
+const DEFAULT_MAX_RESULTS: usize = 200;
+/// 最大件数の上限
+const MAX_RESULTS_LIMIT: usize = 2000;
+/// 1回の検索で走査するエントリ数の上限（超えたら打ち切る）
+const MAX_VISITED_ENTRIES: usize = 50_000;
+
+/// 並び順
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortOrder {
+    Name,
+    /// 更新日時の新しい順
+    Mtime,
+}
+
+/// マッチしたファイル
+#[derive(Debug, Clone)]
+struct GlobEntry {
+    path: String,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
 /// Globツール（ファイルパターン検索）
 pub struct GlobTool;
 
@@ -43,115 +69,93 @@ impl GlobTool {
         }
     }
 
-    /// globパターンをマッチング
-    fn matches_pattern(file_name: &str, pattern: &str) -> bool {
-        // シンプルなglobマッチング実装
-        // サポート: *, ?, **
-
-        let pattern_chars: Vec<char> = pattern.chars().collect();
-        let file_chars: Vec<char> = file_name.chars().collect();
-
-        Self::match_recursive(&pattern_chars, &file_chars, 0, 0)
+    /// globパターンをコンパイル（`*` はディレクトリ区切りを越えない）
+    fn compile_pattern(pattern: &str) -> Result<GlobMatcher, globset::Error> {
+        GlobBuilder::new(pattern.trim_start_matches("./"))
+            .literal_separator(true)
+            .backslash_escape(true)
+            .build()
+            .map(|glob| glob.compile_matcher())
     }
 
-    fn match_recursive(
-        pattern: &[char],
-        file: &[char],
-        p_idx: usize,
-        f_idx: usize,
-    ) -> bool {
-        // パターン終了
-        if p_idx >= pattern.len() && f_idx >= file.len() {
-            return true;
-        }
+    /// ファイルを検索（ブロッキング）
+    ///
+    /// 走査したエントリ数が `max_visited` に達したら打ち切り、`true` を返す。
+    fn find_files(
+        root: &Path,
+        matcher: &GlobMatcher,
+        include_hidden: bool,
+        respect_gitignore: bool,
+        max_visited: usize,
+    ) -> (Vec<GlobEntry>, bool) {
+        let walker = WalkBuilder::new(root)
+            .hidden(!include_hidden)
+            .git_ignore(respect_gitignore)
+            .git_exclude(respect_gitignore)
+            .git_global(false)
+            .ignore(respect_gitignore)
+            .parents(false)
+            .require_git(false)
+            .follow_links(false)
+            .build();
 
-        // パターンだけ終了
-        if p_idx >= pattern.len() {
-            return false;
-        }
-
-        // ** の処理（任意のディレクトリ）
-        if p_idx + 1 < pattern.len() && pattern[p_idx] == '*' && pattern[p_idx + 1] == '*' {
-            // **/ をスキップ
-            let next_p_idx = if p_idx + 2 < pattern.len() && pattern[p_idx + 2] == '/' {
-                p_idx + 3
-            } else {
-                p_idx + 2
-            };
-
-            // 任意の位置でマッチを試行
-            for i in f_idx..=file.len() {
-                if Self::match_recursive(pattern, file, next_p_idx, i) {
-                    return true;
-                }
-            }
-            return false;
-        }
-
-        // ファイル終了
-        if f_idx >= file.len() {
-            // 残りが * だけならマッチ
-            return pattern[p_idx..].iter().all(|&c| c == '*');
-        }
-
-        match pattern[p_idx] {
-            '*' => {
-                // * は0文字以上にマッチ
-                // 0文字マッチ
-                if Self::match_recursive(pattern, file, p_idx + 1, f_idx) {
-                    return true;
-                }
-                // 1文字以上マッチ
-                Self::match_recursive(pattern, file, p_idx, f_idx + 1)
-            }
-            '?' => {
-                // ? は1文字にマッチ
-                Self::match_recursive(pattern, file, p_idx + 1, f_idx + 1)
+        let mut results = Vec::new();
+        for (visited, entry) in walker.enumerate() {
+            if visited >= max_visited {
+                debug!("Glob walk stopped after {} entries", visited);
+                return (results, true);
             }
-            c => {
-                // 文字が一致すれば次へ
-                if c == file[f_idx] {
-                    Self::match_recursive(pattern, file, p_idx + 1, f_idx + 1)
-                } else {
-                    false
+            let entry = match entry {
+                Ok(e) => e,
+                Err(e) => {
+                    debug!("Skipping entry: {}", e);
+                    continue;
                 }
+            };
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
             }
-        }
-    }
 
-    /// 再帰的にファイルを検索
-    async fn find_files(
-        base_path: &Path,
-        current_path: &Path,
-        pattern: &str,
-        results: &mut Vec<String>,
-    ) -> Result<(), std::io::Error> {
-        let mut entries = fs::read_dir(current_path).await?;
-
-        while let Some(entry) = entries.next_entry().await? {
-            let path = entry.path();
-            let file_name = entry.file_name().to_string_lossy().to_string();
-
-            // 隠しファイルはスキップ
-            if file_name.starts_with('.') {
+            let relative = match entry.path().strip_prefix(root) {
+                Ok(rel) => rel.to_string_lossy().replace('\\', "/"),
+                Err(_) => continue,
+            };
+            if !matcher.is_match(&relative) {
                 continue;
             }
 
-            let relative_path = path
-                .strip_prefix(base_path)
-                .unwrap_or(&path)
-                .to_string_lossy()
-                .to_string();
-
-            if path.is_dir() {
-                // サブディレクトリを検索
-                Box::pin(Self::find_files(base_path, &path, pattern, results)).await?;
-            } else if Self::matches_pattern(&relative_path, pattern) {
-                results.push(relative_path);
-            }
+            let metadata = entry.metadata().ok();
+            results.push(GlobEntry {
+                path: relative,
+                size: metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+                modified: metadata.and_then(|m| m.modified().ok()),
+            });
         }
+        (results, false)
+    }
 
-        Ok(())
+    /// ファイルサイズを読みやすい形式に
+    fn format_size(size: u64) -> String {
+        const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+        let mut value = size as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        if unit == 0 {
+            format!("{} B", size)
+        } else {
+            format!("{:.1} {}", value, UNITS[unit])
+        }
+    }
+
+    fn format_entry(entry: &GlobEntry) -> String {
+        let modified = entry
+            .modified
+            .map(|t| DateTime::<Local>::from(t).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        format!("{}  ({}, {})", entry.path, Self::format_size(entry.size), modified)
     }
 }
 
@@ -162,7 +166,7 @@ impl Tool for GlobTool {
     }
 
     fn description(&self) -> &str {
-        "Find files matching a glob pattern. Supports *, ?, and ** patterns. Only searches within the allowed directory."
+        "Find files matching a glob pattern. Supports *, ?, **, [abc] character classes and {a,b} alternatives. Respects .gitignore and skips hidden files by default. Returns size and modification time for each file. Only searches within the allowed directory."
     }
 
     fn parameters_schema(&self) -> JsonValue {
@@ -171,11 +175,28 @@ impl Tool for GlobTool {
             "properties": {
                 "pattern": {
                     "type": "string",
-                    "description": "Glob pattern (e.g., '**/*.rs', 'src/*.txt', 'data/**/*.json')"
+                    "description": "Glob pattern relative to path (e.g., '**/*.rs', 'src/*.{txt,md}', 'data/**/[0-9]*.json')"
                 },
                 "path": {
                     "type": "string",
                     "description": "Base directory to search from (default: current directory)"
+                },
+                "include_hidden": {
+                    "type": "boolean",
+                    "description": "Include hidden files and directories (default: false)"
+                },
+                "respect_gitignore": {
+                    "type": "boolean",
+                    "description": "Skip files ignored by .gitignore / .ignore (default: true)"
+                },
+                "max_results": {
+                    "type": "integer",
+                    "description": "Maximum number of files to return (default: 200, max: 2000)"
+                },
+                "sort": {
+                    "type": "string",
+                    "enum": ["name", "mtime"],
+                    "description": "Sort by path name or by modification time, newest first (default: name)"
                 }
             },
             "required": ["pattern"]
@@ -188,24 +209,45 @@ impl Tool for GlobTool {
         })?;
 
         let base_path = params["path"].as_str().unwrap_or(".");
+        let include_hidden = params["include_hidden"].as_bool().unwrap_or(false);
+        let respect_gitignore = params["respect_gitignore"].as_bool().unwrap_or(true);
+        let max_results = params["max_results"]
+            .as_u64()
+            .map(|n| n as usize)
+            .unwrap_or(DEFAULT_MAX_RESULTS)
+            .clamp(1, MAX_RESULTS_LIMIT);
+        let sort = match params["sort"].as_str() {
+            None | Some("name") => SortOrder::Name,
+            Some("mtime") => SortOrder::Mtime,
+            Some(other) => {
+                return Err(ToolError::InvalidParams(format!(
+                    "Invalid sort: {} (expected name or mtime)",
+                    other
+                )))
+            }
+        };
 
         // パスのバリデーション
         Self::validate_path(base_path)?;
+        Self::validate_path(pattern)?;
+
+        let matcher = Self::compile_pattern(pattern)
+            .map_err(|e| ToolError::InvalidParams(format!("Invalid glob pattern: {}", e)))?;
 
         // ユーザー固有のパスに変換
         let user_path = Self::get_user_path(base_path, context);
         debug!("Glob search: pattern='{}' in '{}'", pattern, user_path);
 
         // ベースディレクトリ存在確認
-        let base_path_obj = Path::new(&user_path);
-        if !base_path_obj.exists() {
+        let root = PathBuf::from(&user_path);
+        if !root.exists() {
             return Err(ToolError::ExecutionFailed(format!(
                 "Directory not found: {}",
                 base_path
             )));
         }
 
-        if !base_path_obj.is_dir() {
+        if !root.is_dir() {
             return Err(ToolError::ExecutionFailed(format!(
                 "Not a directory: {}",
                 base_path
@@ -213,30 +255,58 @@ impl Tool for GlobTool {
         }
 
         // ファイル検索
-        let mut results = Vec::new();
-        if let Err(e) = Self::find_files(base_path_obj, base_path_obj, pattern, &mut results).await {
+        let (mut results, truncated) = tokio::task::spawn_blocking(move || {
+            Self::find_files(
+                &root,
+                &matcher,
+                include_hidden,
+                respect_gitignore,
+                MAX_VISITED_ENTRIES,
+            )
+        })
+        .await
+        .map_err(|e| {
             warn!("Failed to search files: {}", e);
-            return Err(ToolError::ExecutionFailed(format!(
-                "Failed to search files: {}",
-                e
-            )));
-        }
+            ToolError::ExecutionFailed(format!("Failed to search files: {}", e))
+        })?;
 
         // ソート
-        results.sort();
+        match sort {
+            SortOrder::Name => results.sort_by(|a, b| a.path.cmp(&b.path)),
+            SortOrder::Mtime => results.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| a.path.cmp(&b.path))),
+        }
 
         debug!("Found {} files matching pattern", results.len());
 
+        let truncated_note = if truncated {
+            format!(
+                "\n(Search stopped after {} entries; results are incomplete. Narrow the path or pattern.)",
+                MAX_VISITED_ENTRIES
+            )
+        } else {
+            String::new()
+        };
+
         if results.is_empty() {
             Ok(ToolResult::success(format!(
-                "No files found matching pattern: {}",
-                pattern
+                "No files found matching pattern: {}{}",
+                pattern, truncated_note
             )))
         } else {
+            let total = results.len();
+            let mut lines: Vec<String> = results
+                .iter()
+                .take(max_results)
+                .map(Self::format_entry)
+                .collect();
+            if total > max_results {
+                lines.push(format!("... ({} more files)", total - max_results));
+            }
             Ok(ToolResult::success(format!(
-                "Found {} files:\n{}",
-                results.len(),
-                results.join("\n")
+                "Found {} files:\n{}{}",
+                total,
+                lines.join("\n"),
+                truncated_note
             )))
         }
     }
@@ -251,6 +321,7 @@ impl Default for GlobTool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tools::test_workspace;
 
     fn create_test_context() -> ToolContext {
         ToolContext::new(123, "test_user".to_string(), 456, "output".to_string())
@@ -280,18 +351,34 @@ mod tests {
         assert_eq!(tool.name(), "glob");
     }
 
+    fn matcher(pattern: &str) -> GlobMatcher {
+        GlobTool::compile_pattern(pattern).unwrap()
+    }
+
+    #[test]
+    fn test_compile_pattern_simple() {
+        assert!(matcher("*.txt").is_match("test.txt"));
+        assert!(!matcher("*.txt").is_match("test.rs"));
+        assert!(matcher("*.?s").is_match("file.rs"));
+        assert!(matcher("./*.txt").is_match("test.txt"));
+    }
+
     #[test]
-    fn test_matches_pattern_simple() {
-        assert!(GlobTool::matches_pattern("test.txt", "*.txt"));
-        assert!(!GlobTool::matches_pattern("test.rs", "*.txt"));
-        assert!(GlobTool::matches_pattern("file.rs", "*.?s"));
+    fn test_compile_pattern_with_path() {
+        assert!(matcher("src/*.rs").is_match("src/main.rs"));
+        assert!(matcher("**/*.rs").is_match("src/main.rs"));
+        assert!(matcher("**/*.rs").is_match("lib/test/mod.rs"));
     }
 
     #[test]
-    fn test_matches_pattern_with_path() {
-        assert!(GlobTool::matches_pattern("src/main.rs", "src/*.rs"));
-        assert!(GlobTool::matches_pattern("src/main.rs", "**/*.rs"));
-        assert!(GlobTool::matches_pattern("lib/test/mod.rs", "**/*.rs"));
+    fn test_compile_pattern_full_syntax() {
+        assert!(matcher("*.{txt,md}").is_match("a.md"));
+        assert!(matcher("data/[0-9].json").is_match("data/7.json"));
+        assert!(!matcher("data/[0-9].json").is_match("data/x.json"));
+        assert!(matcher("**/*.rs").is_match("main.rs"));
+        // * はディレクトリ区切りを越えない
+        assert!(!matcher("*.rs").is_match("src/main.rs"));
+        assert!(GlobTool::compile_pattern("[unclosed").is_err());
     }
 
     #[tokio::test]
@@ -328,4 +415,70 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    fn setup_workspace() -> (tempfile::TempDir, ToolContext) {
+        let (dir, ctx, root) = test_workspace();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("build")).unwrap();
+        std::fs::write(root.join("src/a.rs"), "a").unwrap();
+        std::fs::write(root.join("src/b.txt"), "bb").unwrap();
+        std::fs::write(root.join("build/c.rs"), "c").unwrap();
+        std::fs::write(root.join(".hidden.rs"), "h").unwrap();
+        std::fs::write(root.join(".gitignore"), "build/\n").unwrap();
+        (dir, ctx)
+    }
+
+    #[tokio::test]
+    async fn test_glob_gitignore_and_hidden() {
+        let (_dir, ctx) = setup_workspace();
+        let tool = GlobTool::new();
+
+        let result = tool.execute(json!({"pattern": "**/*.rs"}), &ctx).await.unwrap();
+        assert!(result.output.starts_with("Found 1 files"));
+        assert!(result.output.contains("src/a.rs  (1 B,"));
+
+        let result = tool
+            .execute(
+                json!({"pattern": "**/*.rs", "include_hidden": true, "respect_gitignore": false}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert!(result.output.starts_with("Found 3 files"));
+        assert!(result.output.contains("build/c.rs"));
+        assert!(result.output.contains(".hidden.rs"));
+    }
+
+    #[test]
+    fn test_find_files_stops_at_visit_limit() {
+        let (_dir, ctx) = setup_workspace();
+        let root = PathBuf::from(ctx.get_user_output_dir());
+        let matcher = matcher("**/*");
+
+        let (all, truncated) =
+            GlobTool::find_files(&root, &matcher, true, false, MAX_VISITED_ENTRIES);
+        assert!(!truncated);
+        assert_eq!(all.len(), 5);
+
+        let (partial, truncated) = GlobTool::find_files(&root, &matcher, true, false, 3);
+        assert!(truncated);
+        assert!(partial.len() < all.len());
+    }
+
+    #[tokio::test]
+    async fn test_glob_max_results_and_sort() {
+        let (_dir, ctx) = setup_workspace();
+        let result = GlobTool::new()
+            .execute(json!({"pattern": "src/*.{rs,txt}", "max_results": 1}), &ctx)
+            .await
+            .unwrap();
+        assert!(result.output.starts_with("Found 2 files"));
+        assert!(result.output.contains("src/a.rs"));
+        assert!(result.output.contains("... (1 more files)"));
+
+        let result = GlobTool::new()
+            .execute(json!({"pattern": "*", "sort": "size"}), &ctx)
+            .await;
+        assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+    }
 }
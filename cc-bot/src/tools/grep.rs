@@ -1,11 +1,133 @@
 use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
 use async_trait::async_trait;
-use regex::Regex;
+use grep_regex::{RegexMatcher, RegexMatcherBuilder};
+use grep_searcher::{BinaryDetection, Searcher, SearcherBuilder, Sink, SinkContext, SinkMatch};
+use ignore::overrides::OverrideBuilder;
+use ignore::{WalkBuilder, WalkState};
 use serde_json::{json, Value as JsonValue};
-use std::path::Path;
-use tokio::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use tracing::{debug, warn};
 
+/// デフォルトの最大マッチ数
+const DEFAULT_MAX_MATCHES: usize = 100;
+/// 最大マッチ数の上限
+const MAX_MATCHES_LIMIT: usize = 1000;
+/// デフォルトの最大出力バイト数
+const DEFAULT_MAX_BYTES: usize = 20_000;
+/// 最大出力バイト数の上限
+const MAX_BYTES_LIMIT: usize = 100_000;
+/// コンテキスト行数の上限
+const MAX_CONTEXT_LINES: usize = 10;
+/// 1行あたりの最大表示文字数
+const MAX_LINE_CHARS: usize = 500;
+/// 検索対象とする最大ファイルサイズ
+const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// 大文字小文字の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CaseMode {
+    Sensitive,
+    Insensitive,
+    /// パターンに大文字が含まれる場合のみ区別する
+    Smart,
+}
+
+/// 出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputMode {
+    Content,
+    FilesWithMatches,
+    Count,
+}
+
+/// 検索オプション
+#[derive(Debug, Clone)]
+struct GrepOptions {
+    before_context: usize,
+    after_context: usize,
+    case_mode: CaseMode,
+    output_mode: OutputMode,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    max_matches: usize,
+    max_bytes: usize,
+}
+
+/// 検索結果の1行（マッチ行またはコンテキスト行）
+#[derive(Debug, Clone)]
+struct GrepLine {
+    line_number: u64,
+    text: String,
+    is_match: bool,
+    /// 直前の行と連続していない（`--` 区切りを出力する）
+    break_before: bool,
+}
+
+/// 1ファイル分の検索結果
+#[derive(Debug, Clone)]
+struct FileMatches {
+    path: String,
+    lines: Vec<GrepLine>,
+    match_count: usize,
+}
+
+/// 検索結果を収集する Sink
+struct CollectSink {
+    lines: Vec<GrepLine>,
+    match_count: usize,
+    limit: usize,
+    pending_break: bool,
+}
+
+impl CollectSink {
+    fn new(limit: usize) -> Self {
+        Self {
+            lines: Vec::new(),
+            match_count: 0,
+            limit,
+            pending_break: false,
+        }
+    }
+
+    fn push(&mut self, line_number: Option<u64>, bytes: &[u8], is_match: bool) {
+        let start = line_number.unwrap_or(0);
+        for (offset, line) in bytes.split(|b| *b == b'\n').enumerate() {
+            if offset > 0 && line.is_empty() {
+                continue;
+            }
+            let text = String::from_utf8_lossy(line).trim_end_matches('\r').to_string();
+            self.lines.push(GrepLine {
+                line_number: start + offset as u64,
+                text,
+                is_match,
+                break_before: std::mem::take(&mut self.pending_break),
+            });
+        }
+    }
+}
+
+impl Sink for CollectSink {
+    type Error = std::io::Error;
+
+    fn matched(&mut self, _searcher: &Searcher, mat: &SinkMatch<'_>) -> Result<bool, Self::Error> {
+        self.push(mat.line_number(), mat.bytes(), true);
+        self.match_count += 1;
+        Ok(self.match_count < self.limit)
+    }
+
+    fn context(&mut self, _searcher: &Searcher, ctx: &SinkContext<'_>) -> Result<bool, Self::Error> {
+        self.push(ctx.line_number(), ctx.bytes(), false);
+        Ok(true)
+    }
+
+    fn context_break(&mut self, _searcher: &Searcher) -> Result<bool, Self::Error> {
+        self.pending_break = true;
+        Ok(true)
+    }
+}
+
 /// Grepツール（ファイル内容検索）
 pub struct GrepTool;
 
@@ -44,107 +166,297 @@ impl GrepTool {
         }
     }
 
-    /// ファイル内で正規表現検索
-    async fn search_in_file(
-        path: &str,
-        pattern: &Regex,
-        case_insensitive: bool,
-    ) -> Result<Vec<(usize, String)>, std::io::Error> {
-        let content = fs::read_to_string(path).await?;
-        let mut matches = Vec::new();
-
-        for (line_num, line) in content.lines().enumerate() {
-            let search_line = if case_insensitive {
-                line.to_lowercase()
-            } else {
-                line.to_string()
-            };
-
-            if pattern.is_match(&search_line) {
-                matches.push((line_num + 1, line.to_string()));
+    /// パラメータから検索オプションを作成
+    fn parse_options(params: &JsonValue) -> Result<GrepOptions, ToolError> {
+        let context = params["context"].as_u64().map(|n| n as usize);
+        let before_context = params["before_context"]
+            .as_u64()
+            .map(|n| n as usize)
+            .or(context)
+            .unwrap_or(0)
+            .min(MAX_CONTEXT_LINES);
+        let after_context = params["after_context"]
+            .as_u64()
+            .map(|n| n as usize)
+            .or(context)
+            .unwrap_or(0)
+            .min(MAX_CONTEXT_LINES);
+
+        // case_insensitive は後方互換のため残す
+        let case_mode = match params["case_mode"].as_str() {
+            Some("sensitive") => CaseMode::Sensitive,
+            Some("insensitive") => CaseMode::Insensitive,
+            Some("smart") => CaseMode::Smart,
+            Some(other) => {
+                return Err(ToolError::InvalidParams(format!(
+                    "Invalid case_mode: {} (expected sensitive, insensitive or smart)",
+                    other
+                )))
+            }
+            None if params["case_insensitive"].as_bool().unwrap_or(false) => CaseMode::Insensitive,
+            None => CaseMode::Sensitive,
+        };
+
+        let output_mode = match params["output_mode"].as_str() {
+            None | Some("content") => OutputMode::Content,
+            Some("files_with_matches") => OutputMode::FilesWithMatches,
+            Some("count") => OutputMode::Count,
+            Some(other) => {
+                return Err(ToolError::InvalidParams(format!(
+                    "Invalid output_mode: {} (expected content, files_with_matches or count)",
+                    other
+                )))
+            }
+        };
+
+        let mut include = Self::string_list(&params["include"]);
+        if let Some(ext) = params["file_pattern"].as_str() {
+            let ext = ext.trim().trim_start_matches("*.").trim_start_matches('.');
+            if !ext.is_empty() {
+                include.push(format!("*.{}", ext));
             }
         }
+        let exclude = Self::string_list(&params["exclude"]);
 
-        Ok(matches)
-    }
+        for glob in include.iter().chain(&exclude) {
+            Self::validate_path(glob.trim_start_matches('!'))?;
+        }
 
-    /// 再帰的にファイルを検索
-    async fn search_directory(
-        base_path: &Path,
-        current_path: &Path,
-        pattern: &Regex,
-        case_insensitive: bool,
-        results: &mut Vec<(String, usize, String)>,
-        file_pattern: Option<&str>,
-    ) -> Result<(), std::io::Error> {
-        let mut entries = fs::read_dir(current_path).await?;
+        let max_matches = params["max_matches"]
+            .as_u64()
+            .map(|n| n as usize)
+            .unwrap_or(DEFAULT_MAX_MATCHES)
+            .clamp(1, MAX_MATCHES_LIMIT);
+        let max_bytes = params["max_bytes"]
+            .as_u64()
+            .map(|n| n as usize)
+            .unwrap_or(DEFAULT_MAX_BYTES)
+            .clamp(1, MAX_BYTES_LIMIT);
+
+        Ok(GrepOptions {
+            before_context,
+            after_context,
+            case_mode,
+            output_mode,
+            include,
+            exclude,
+            max_matches,
+            max_bytes,
+        })
+    }
 
-        while let Some(entry) = entries.next_entry().await? {
-            let path = entry.path();
-            let file_name = entry.file_name().to_string_lossy().to_string();
+    /// 文字列または文字列配列を受け取る
+    fn string_list(value: &JsonValue) -> Vec<String> {
+        match value {
+            JsonValue::String(s) => vec![s.clone()],
+            JsonValue::Array(items) => items
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
 
-            // 隠しファイルはスキップ
-            if file_name.starts_with('.') {
-                continue;
+    /// 正規表現マッチャーを作成
+    fn build_matcher(pattern: &str, case_mode: CaseMode) -> Result<RegexMatcher, ToolError> {
+        let mut builder = RegexMatcherBuilder::new();
+        match case_mode {
+            CaseMode::Sensitive => {}
+            CaseMode::Insensitive => {
+                builder.case_insensitive(true);
             }
+            CaseMode::Smart => {
+                builder.case_smart(true);
+            }
+        }
+        builder
+            .build(pattern)
+            .map_err(|e| ToolError::InvalidParams(format!("Invalid regex pattern: {}", e)))
+    }
+
+    /// ベースからの相対パス表示
+    fn display_path(root: &Path, path: &Path, fallback: &str) -> String {
+        match path.strip_prefix(root) {
+            Ok(rel) if !rel.as_os_str().is_empty() => rel.to_string_lossy().to_string(),
+            _ => fallback.to_string(),
+        }
+    }
 
-            if path.is_dir() {
-                // サブディレクトリを検索
-                Box::pin(Self::search_directory(
-                    base_path,
-                    &path,
-                    pattern,
-                    case_insensitive,
-                    results,
-                    file_pattern,
-                ))
-                .await?;
-            } else {
-                // ファイルパターンチェック
-                if let Some(fp) = file_pattern {
-                    let ext = path.extension().map(|e| e.to_string_lossy().to_string());
-                    let matches_ext = match ext {
-                        Some(e) => fp.trim_start_matches('.').eq_ignore_ascii_case(&e),
-                        None => false,
-                    };
-                    if !matches_ext {
-                        continue;
+    /// ディレクトリ（またはファイル）を並列に検索する（ブロッキング）
+    fn search(
+        root: &Path,
+        display_root: &str,
+        matcher: RegexMatcher,
+        options: &GrepOptions,
+    ) -> Result<(Vec<FileMatches>, bool), String> {
+        let mut overrides = OverrideBuilder::new(root);
+        for glob in &options.include {
+            overrides.add(glob).map_err(|e| format!("Invalid include glob '{}': {}", glob, e))?;
+        }
+        for glob in &options.exclude {
+            let glob = format!("!{}", glob.trim_start_matches('!'));
+            overrides.add(&glob).map_err(|e| format!("Invalid exclude glob '{}': {}", glob, e))?;
+        }
+        let overrides = overrides.build().map_err(|e| format!("Invalid glob: {}", e))?;
+
+        let walker = WalkBuilder::new(root)
+            .overrides(overrides)
+            .hidden(true)
+            .follow_links(false)
+            .require_git(false)
+            .max_filesize(Some(MAX_FILE_SIZE))
+            .build_parallel();
+
+        // files_with_matches は1件見つかればそのファイルは十分
+        let per_file_limit = match options.output_mode {
+            OutputMode::Content => options.max_matches,
+            OutputMode::FilesWithMatches => 1,
+            OutputMode::Count => usize::MAX,
+        };
+
+        let results: Arc<Mutex<Vec<FileMatches>>> = Arc::new(Mutex::new(Vec::new()));
+        let total = Arc::new(AtomicUsize::new(0));
+        let truncated = Arc::new(AtomicUsize::new(0));
+
+        walker.run(|| {
+            let matcher = matcher.clone();
+            let results = results.clone();
+            let total = total.clone();
+            let truncated = truncated.clone();
+            let mut searcher = SearcherBuilder::new()
+                .line_number(true)
+                .before_context(options.before_context)
+                .after_context(options.after_context)
+                .binary_detection(BinaryDetection::quit(b'\x00'))
+                .build();
+
+            Box::new(move |entry| {
+                let entry = match entry {
+                    Ok(e) => e,
+                    Err(e) => {
+                        debug!("Skipping entry: {}", e);
+                        return WalkState::Continue;
                     }
+                };
+                if !entry.file_type().is_some_and(|t| t.is_file()) {
+                    return WalkState::Continue;
                 }
 
-                // バイナリファイルをスキップ（簡易チェック）
-                let file_type = entry.file_type().await;
-                if let Ok(ft) = file_type {
-                    if ft.is_symlink() {
-                        continue;
-                    }
+                let remaining = match options.output_mode {
+                    OutputMode::Content => options.max_matches.saturating_sub(total.load(Ordering::Relaxed)),
+                    _ => per_file_limit,
+                };
+                if remaining == 0 {
+                    truncated.store(1, Ordering::Relaxed);
+                    return WalkState::Quit;
+                }
+
+                let mut sink = CollectSink::new(remaining.min(per_file_limit));
+                if let Err(e) = searcher.search_path(&matcher, entry.path(), &mut sink) {
+                    debug!("Skipping file {}: {}", entry.path().display(), e);
+                    return WalkState::Continue;
+                }
+
+                if sink.match_count > 0 {
+                    total.fetch_add(sink.match_count, Ordering::Relaxed);
+                    let path = Self::display_path(root, entry.path(), display_root);
+                    results.lock().unwrap().push(FileMatches {
+                        path,
+                        lines: sink.lines,
+                        match_count: sink.match_count,
+                    });
                 }
+                WalkState::Continue
+            })
+        });
+
+        let mut results = std::mem::take(&mut *results.lock().unwrap());
+        results.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok((results, truncated.load(Ordering::Relaxed) > 0))
+    }
+
+    /// 1行を表示用に切り詰める
+    fn clip_line(text: &str) -> String {
+        if text.chars().count() > MAX_LINE_CHARS {
+            let clipped: String = text.chars().take(MAX_LINE_CHARS).collect();
+            format!("{}...", clipped)
+        } else {
+            text.to_string()
+        }
+    }
 
-                let relative_path = path
-                    .strip_prefix(base_path)
-                    .unwrap_or(&path)
-                    .to_string_lossy()
-                    .to_string();
-
-                // ファイル内検索（パスがUTF-8でない場合はスキップ）
-                if let Some(path_str) = path.to_str() {
-                    match Self::search_in_file(path_str, pattern, case_insensitive).await {
-                        Ok(file_matches) => {
-                            for (line_num, line) in file_matches {
-                                results.push((relative_path.clone(), line_num, line));
-                            }
+    /// 結果を出力形式に整形
+    fn format_results(results: &[FileMatches], options: &GrepOptions, truncated: bool) -> String {
+        let total: usize = results.iter().map(|f| f.match_count).sum();
+        let mut lines: Vec<String> = Vec::new();
+        let mut shown = 0;
+
+        match options.output_mode {
+            OutputMode::Content => {
+                lines.push(format!(
+                    "Found {}{} matches:",
+                    total,
+                    if truncated { "+" } else { "" }
+                ));
+                for (index, file) in results.iter().enumerate() {
+                    if index > 0 && (options.before_context > 0 || options.after_context > 0) {
+                        lines.push("--".to_string());
+                    }
+                    for line in &file.lines {
+                        if line.is_match && shown >= options.max_matches {
+                            break;
                         }
-                        Err(e) => {
-                            debug!("Skipping file {} due to error: {}", path.display(), e);
+                        if line.break_before {
+                            lines.push("--".to_string());
+                        }
+                        let sep = if line.is_match { ':' } else { '-' };
+                        lines.push(format!(
+                            "{}{}{}{} {}",
+                            file.path,
+                            sep,
+                            line.line_number,
+                            sep,
+                            Self::clip_line(&line.text)
+                        ));
+                        if line.is_match {
+                            shown += 1;
                         }
                     }
-                } else {
-                    debug!("Skipping file with non-UTF-8 path: {}", path.display());
                 }
             }
+            OutputMode::FilesWithMatches => {
+                lines.push(format!("Found {} files:", results.len()));
+                lines.extend(results.iter().map(|f| f.path.clone()));
+            }
+            OutputMode::Count => {
+                lines.push(format!("Found {} matches in {} files:", total, results.len()));
+                lines.extend(results.iter().map(|f| format!("{}: {}", f.path, f.match_count)));
+            }
         }
 
-        Ok(())
+        // バイト数上限で切り詰め
+        let mut output = String::new();
+        for (index, line) in lines.iter().enumerate() {
+            if output.len() + line.len() + 1 > options.max_bytes {
+                output.push_str(&format!(
+                    "... (output truncated at {} bytes, {} more lines)",
+                    options.max_bytes,
+                    lines.len() - index
+                ));
+                return output;
+            }
+            output.push_str(line);
+            output.push('\n');
+        }
+
+        if truncated {
+            output.push_str(&format!(
+                "... (stopped after {} matches; narrow the search or raise max_matches)",
+                options.max_matches
+            ));
+        }
+
+        output.trim_end().to_string()
     }
 }
 
@@ -155,7 +467,7 @@ impl Tool for GrepTool {
     }
 
     fn description(&self) -> &str {
-        "Search for text patterns in files using regular expressions. Searches recursively through directories."
+        "Search for text patterns in files using regular expressions. Searches directories recursively in parallel, respects .gitignore and skips hidden and binary files. Supports include/exclude globs, context lines, case modes and content/files_with_matches/count output."
     }
 
     fn parameters_schema(&self) -> JsonValue {
@@ -170,13 +482,53 @@ impl Tool for GrepTool {
                     "type": "string",
                     "description": "Base directory or file to search (default: current directory)"
                 },
+                "include": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Glob patterns of files to search (e.g., ['*.rs', 'src/**/*.toml'])"
+                },
+                "exclude": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Glob patterns of files or directories to skip (e.g., ['target', '*.min.js'])"
+                },
                 "file_pattern": {
                     "type": "string",
                     "description": "File extension to filter (e.g., 'rs', 'txt')"
                 },
+                "before_context": {
+                    "type": "integer",
+                    "description": "Lines of context before each match (like grep -B, max 10)"
+                },
+                "after_context": {
+                    "type": "integer",
+                    "description": "Lines of context after each match (like grep -A, max 10)"
+                },
+                "context": {
+                    "type": "integer",
+                    "description": "Lines of context before and after each match (like grep -C, max 10)"
+                },
+                "case_mode": {
+                    "type": "string",
+                    "enum": ["sensitive", "insensitive", "smart"],
+                    "description": "Case handling; smart is case-insensitive unless the pattern has uppercase (default: sensitive)"
+                },
                 "case_insensitive": {
                     "type": "boolean",
-                    "description": "Case insensitive search (default: false)"
+                    "description": "Case insensitive search (default: false, same as case_mode=insensitive)"
+                },
+                "output_mode": {
+                    "type": "string",
+                    "enum": ["content", "files_with_matches", "count"],
+                    "description": "Output matching lines, only file paths, or match counts per file (default: content)"
+                },
+                "max_matches": {
+                    "type": "integer",
+                    "description": "Stop after this many matches (default: 100, max: 1000)"
+                },
+                "max_bytes": {
+                    "type": "integer",
+                    "description": "Maximum output size in bytes (default: 20000, max: 100000)"
                 }
             },
             "required": ["pattern"]
@@ -189,78 +541,49 @@ impl Tool for GrepTool {
         })?;
 
         let base_path = params["path"].as_str().unwrap_or(".");
-        let file_pattern = params["file_pattern"].as_str();
-        let case_insensitive = params["case_insensitive"].as_bool().unwrap_or(false);
 
         // パスのバリデーション
         Self::validate_path(base_path)?;
 
+        let options = Self::parse_options(&params)?;
+
         // ユーザー固有のパスに変換
         let user_path = Self::get_user_path(base_path, context);
         debug!(
-            "Grep search: pattern='{}' in '{}' (case_insensitive={})",
-            pattern_str, user_path, case_insensitive
+            "Grep search: pattern='{}' in '{}' ({:?})",
+            pattern_str, user_path, options
         );
 
         // 正規表現コンパイル
-        let pattern = if case_insensitive {
-            Regex::new(&format!("(?i){}", pattern_str))
-        } else {
-            Regex::new(pattern_str)
-        }
-        .map_err(|e| {
-            ToolError::InvalidParams(format!("Invalid regex pattern: {}", e))
-        })?;
+        let matcher = Self::build_matcher(pattern_str, options.case_mode)?;
 
-        let base_path_obj = Path::new(&user_path);
+        let root = PathBuf::from(&user_path);
 
         // ベースパス存在確認
-        if !base_path_obj.exists() {
+        if !root.exists() {
             return Err(ToolError::ExecutionFailed(format!(
                 "Path not found: {}",
                 base_path
             )));
         }
 
-        let mut results = Vec::new();
-
-        if base_path_obj.is_file() {
-            // 単一ファイル検索
-            match Self::search_in_file(&user_path, &pattern, case_insensitive).await {
-                Ok(file_matches) => {
-                    for (line_num, line) in file_matches {
-                        results.push((base_path.to_string(), line_num, line));
-                    }
-                }
-                Err(e) => {
-                    warn!("Failed to search file {}: {}", user_path, e);
-                    return Err(ToolError::ExecutionFailed(format!(
-                        "Failed to search file: {}",
-                        e
-                    )));
-                }
-            }
-        } else {
-            // ディレクトリ検索
-            if let Err(e) = Self::search_directory(
-                base_path_obj,
-                base_path_obj,
-                &pattern,
-                case_insensitive,
-                &mut results,
-                file_pattern,
-            )
-            .await
-            {
-                warn!("Failed to search directory: {}", e);
-                return Err(ToolError::ExecutionFailed(format!(
-                    "Failed to search directory: {}",
-                    e
-                )));
+        let display_root = base_path.to_string();
+        let search_options = options.clone();
+        let search = tokio::task::spawn_blocking(move || {
+            Self::search(&root, &display_root, matcher, &search_options)
+        })
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(format!("Search task failed: {}", e)))?;
+
+        let (results, truncated) = match search {
+            Ok(r) => r,
+            Err(e) => {
+                warn!("Grep failed: {}", e);
+                return Err(ToolError::InvalidParams(e));
             }
-        }
+        };
 
-        debug!("Found {} matches", results.len());
+        debug!("Found matches in {} files", results.len());
 
         if results.is_empty() {
             Ok(ToolResult::success(format!(
@@ -268,26 +591,7 @@ impl Tool for GrepTool {
                 pattern_str
             )))
         } else {
-            // 結果をフォーマット
-            let output: Vec<String> = results
-                .iter()
-                .map(|(path, line_num, line)| format!("{}:{}: {}", path, line_num, line))
-                .collect();
-
-            // 最大100件に制限
-            let limited = if output.len() > 100 {
-                let mut truncated = output[..100].to_vec();
-                truncated.push(format!("... ({} more results)", output.len() - 100));
-                truncated
-            } else {
-                output
-            };
-
-            Ok(ToolResult::success(format!(
-                "Found {} matches:\n{}",
-                results.len(),
-                limited.join("\n")
-            )))
+            Ok(ToolResult::success(Self::format_results(&results, &options, truncated)))
         }
     }
 }
@@ -301,6 +605,7 @@ impl Default for GrepTool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tools::test_workspace;
 
     fn create_test_context() -> ToolContext {
         ToolContext::new(123, "test_user".to_string(), 456, "output".to_string())
@@ -382,4 +687,83 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    fn setup_workspace() -> (tempfile::TempDir, ToolContext) {
+        let (dir, ctx, root) = test_workspace();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {\n    let Needle = 1;\n    println!(\"{}\", Needle);\n}\n").unwrap();
+        std::fs::write(root.join("src/lib.rs"), "// needle here\npub fn a() {}\n").unwrap();
+        std::fs::write(root.join("notes.txt"), "a needle\nb\nc\nd needle\n").unwrap();
+        std::fs::write(root.join("target/out.rs"), "needle in build output\n").unwrap();
+        std::fs::write(root.join("data.bin"), b"needle\x00\x01\x02").unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        (dir, ctx)
+    }
+
+    #[tokio::test]
+    async fn test_grep_respects_gitignore_and_binary() {
+        let (_dir, ctx) = setup_workspace();
+        let result = GrepTool::new()
+            .execute(json!({"pattern": "needle", "case_mode": "insensitive", "output_mode": "files_with_matches"}), &ctx)
+            .await
+            .unwrap();
+
+        assert!(result.output.contains("src/main.rs"));
+        assert!(result.output.contains("notes.txt"));
+        assert!(!result.output.contains("target/out.rs"));
+        assert!(!result.output.contains("data.bin"));
+    }
+
+    #[tokio::test]
+    async fn test_grep_include_exclude_and_count() {
+        let (_dir, ctx) = setup_workspace();
+        let result = GrepTool::new()
+            .execute(
+                json!({
+                    "pattern": "needle",
+                    "case_insensitive": true,
+                    "include": ["*.rs"],
+                    "exclude": ["lib.rs"],
+                    "output_mode": "count"
+                }),
+                &ctx,
+            )
+            .await
+            .unwrap();
+
+        assert!(result.output.contains("src/main.rs: 2"));
+        assert!(!result.output.contains("lib.rs"));
+        assert!(!result.output.contains("notes.txt"));
+    }
+
+    #[tokio::test]
+    async fn test_grep_context_lines() {
+        let (_dir, ctx) = setup_workspace();
+        let result = GrepTool::new()
+            .execute(json!({"pattern": "needle", "path": "notes.txt", "after_context": 1}), &ctx)
+            .await
+            .unwrap();
+
+        assert!(result.output.contains("notes.txt:1: a needle"));
+        assert!(result.output.contains("notes.txt-2- b"));
+        assert!(result.output.contains("--\nnotes.txt:4: d needle"));
+    }
+
+    #[tokio::test]
+    async fn test_grep_smart_case_and_max_matches() {
+        let (_dir, ctx) = setup_workspace();
+        let result = GrepTool::new()
+            .execute(json!({"pattern": "Needle", "case_mode": "smart", "output_mode": "count"}), &ctx)
+            .await
+            .unwrap();
+        assert!(result.output.contains("Found 2 matches in 1 files"));
+
+        let result = GrepTool::new()
+            .execute(json!({"pattern": "needle", "path": "notes.txt", "max_matches": 1}), &ctx)
+            .await
+            .unwrap();
+        assert!(result.output.contains("a needle"));
+        assert!(!result.output.contains("d needle"));
+    }
 }
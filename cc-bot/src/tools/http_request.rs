@@ -0,0 +1,549 @@
+//! 設定済みREST APIへのHTTPリクエストツール
+//!
+//! `http-apis.json` に宣言されたベースURL配下のみにリクエストできます。
+//! 認証ヘッダーはサーバー側で `${ENV}` を展開して付与し、LLMには公開しません。
+
+use crate::security::mask_secrets;
+use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::{redirect, Client, Method, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// レスポンス本文の最大バイト数（設定の上限）
+const MAX_RESPONSE_BYTES: usize = 1024 * 1024;
+/// LLMが指定できないヘッダー
+const FORBIDDEN_HEADERS: &[&str] = &[
+    "host",
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "content-length",
+    "transfer-encoding",
+    "connection",
+];
+
+/// `${VAR}` 形式のテンプレート
+static ENV_TEMPLATE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap());
+
+/// API定義
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpApiEntry {
+    /// API名（ツール呼び出し時に指定）
+    pub name: String,
+    /// ベースURL（この配下のみリクエスト可能）
+    pub base_url: String,
+    /// 説明（LLMに表示）
+    #[serde(default)]
+    pub description: String,
+    /// 付与するヘッダー（値に `${ENV}` を使用可能、LLMには非公開）
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// 許可するHTTPメソッド
+    #[serde(default = "default_allowed_methods")]
+    pub allowed_methods: Vec<String>,
+    /// タイムアウト（秒）
+    #[serde(default = "default_timeout")]
+    pub timeout_seconds: u64,
+}
+
+/// HTTP API設定全体
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HttpApiConfig {
+    /// APIリスト
+    pub apis: Vec<HttpApiEntry>,
+    /// グローバル設定
+    #[serde(default)]
+    pub settings: HttpApiSettings,
+}
+
+/// HTTP APIグローバル設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpApiSettings {
+    /// レスポンス本文の最大バイト数（デフォルト）
+    #[serde(default = "default_max_response_bytes")]
+    pub max_response_bytes: usize,
+}
+
+fn default_allowed_methods() -> Vec<String> { vec!["GET".to_string()] }
+fn default_timeout() -> u64 { 30 }
+fn default_max_response_bytes() -> usize { 50_000 }
+
+impl Default for HttpApiSettings {
+    fn default() -> Self {
+        Self {
+            max_response_bytes: default_max_response_bytes(),
+        }
+    }
+}
+
+/// 検証済みのAPI定義
+struct HttpApi {
+    entry: HttpApiEntry,
+    base_url: Url,
+    methods: Vec<Method>,
+}
+
+/// HTTPリクエストツール
+pub struct HttpRequestTool {
+    client: Client,
+    apis: HashMap<String, HttpApi>,
+    max_response_bytes: usize,
+    description: String,
+}
+
+impl HttpRequestTool {
+    /// 設定からツールを作成
+    pub fn new(config: HttpApiConfig) -> Result<Self, String> {
+        let mut apis = HashMap::new();
+        for entry in config.apis {
+            let base_url = Url::parse(&entry.base_url)
+                .map_err(|e| format!("Invalid base_url for API '{}': {}", entry.name, e))?;
+            if !matches!(base_url.scheme(), "http" | "https") {
+                return Err(format!("API '{}' must use http or https", entry.name));
+            }
+            let methods = entry
+                .allowed_methods
+                .iter()
+                .map(|m| {
+                    Method::from_bytes(m.to_ascii_uppercase().as_bytes())
+                        .map_err(|_| format!("Invalid method '{}' for API '{}'", m, entry.name))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if apis.contains_key(&entry.name) {
+                return Err(format!("Duplicate API name: {}", entry.name));
+            }
+            apis.insert(entry.name.clone(), HttpApi { entry, base_url, methods });
+        }
+
+        let client = Client::builder()
+            .user_agent("cc-discord-bot/1.0")
+            // 認証ヘッダーを他ホストへ送らないためリダイレクトは追跡しない
+            .redirect(redirect::Policy::none())
+            .build()
+            .unwrap_or_else(|_| Client::new());
+
+        let description = Self::build_description(&apis);
+        Ok(Self {
+            client,
+            apis,
+            max_response_bytes: config.settings.max_response_bytes.min(MAX_RESPONSE_BYTES),
+            description,
+        })
+    }
+
+    /// 設定ファイルから読み込み
+    pub fn load(config_path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(config_path)
+            .map_err(|e| format!("Failed to read {}: {}", config_path, e))?;
+        let config: HttpApiConfig = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", config_path, e))?;
+        Self::new(config)
+    }
+
+    /// API一覧を含む説明文を生成（ヘッダーは含めない）
+    fn build_description(apis: &HashMap<String, HttpApi>) -> String {
+        let mut names: Vec<&String> = apis.keys().collect();
+        names.sort();
+        let mut description = "Call a configured REST API. Only paths under the declared base URLs are allowed and authentication is added automatically. Available APIs:".to_string();
+        for name in names {
+            let api = &apis[name];
+            let methods: Vec<&str> = api.methods.iter().map(|m| m.as_str()).collect();
+            description.push_str(&format!(
+                "\n- {} ({}) [{}] {}",
+                name,
+                api.base_url,
+                methods.join(", "),
+                api.entry.description
+            ));
+        }
+        description
+    }
+
+    /// `${VAR}` を環境変数で展開し、展開した値を `secrets` に追加（未設定なら None）
+    fn expand_template(template: &str, secrets: &mut Vec<String>) -> Option<String> {
+        let mut missing = false;
+        let expanded = ENV_TEMPLATE.replace_all(template, |caps: &regex::Captures| {
+            match std::env::var(&caps[1]) {
+                Ok(value) => {
+                    secrets.push(value.clone());
+                    value
+                }
+                Err(_) => {
+                    warn!("Environment variable {} is not set", &caps[1]);
+                    missing = true;
+                    String::new()
+                }
+            }
+        });
+        (!missing).then(|| expanded.into_owned())
+    }
+
+    /// ベースURLとパスを結合し、ベースURL配下であることを検証
+    fn build_url(base: &Url, path: &str) -> Result<Url, ToolError> {
+        let denied = |reason: &str| ToolError::PermissionDenied(format!("Invalid path '{}': {}", path, reason));
+
+        if path.contains("://") || path.starts_with("//") || path.contains('\\') || path.contains('#') {
+            return Err(denied("must be a path relative to the API base URL"));
+        }
+        let path_only = path.split('?').next().unwrap_or_default();
+        let lowered = path_only.to_ascii_lowercase().replace("%2e", ".");
+        if lowered.split('/').any(|segment| segment == ".." || segment == ".") || lowered.contains("%2f") {
+            return Err(denied("dot segments are not allowed"));
+        }
+
+        let joined = format!(
+            "{}/{}",
+            base.as_str().trim_end_matches('/'),
+            path.trim_start_matches('/')
+        );
+        let url = Url::parse(&joined).map_err(|_| denied("not a valid URL path"))?;
+
+        let base_path = base.path().trim_end_matches('/');
+        let within_base = url.scheme() == base.scheme()
+            && url.host_str() == base.host_str()
+            && url.port_or_known_default() == base.port_or_known_default()
+            && (url.path() == base_path || url.path().starts_with(&format!("{}/", base_path)));
+        if !within_base {
+            return Err(denied("outside of the API base URL"));
+        }
+        Ok(url)
+    }
+
+    /// レスポンスから秘密情報を除去
+    fn redact(text: &str, secrets: &[String]) -> String {
+        let mut result = text.to_string();
+        for secret in secrets.iter().filter(|s| s.len() >= 4) {
+            result = result.replace(secret.as_str(), "***MASKED***");
+        }
+        mask_secrets(&result)
+    }
+
+    /// 本文を表示用に整形（JSONは整形、バイナリは省略）
+    fn format_body(bytes: &[u8], content_type: &str) -> String {
+        if bytes.is_empty() {
+            return "(empty body)".to_string();
+        }
+        if bytes.contains(&0) {
+            return format!("(binary body, {} bytes, {})", bytes.len(), content_type);
+        }
+        let text = String::from_utf8_lossy(bytes);
+        if content_type.contains("json") {
+            if let Ok(value) = serde_json::from_str::<JsonValue>(&text) {
+                return serde_json::to_string_pretty(&value).unwrap_or_else(|_| text.into_owned());
+            }
+        }
+        text.into_owned()
+    }
+}
+
+#[async_trait]
+impl Tool for HttpRequestTool {
+    fn name(&self) -> &str {
+        "http_request"
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        let mut names: Vec<&String> = self.apis.keys().collect();
+        names.sort();
+        json!({
+            "type": "object",
+            "properties": {
+                "api": {
+                    "type": "string",
+                    "enum": names,
+                    "description": "Name of the configured API"
+                },
+                "method": {
+                    "type": "string",
+                    "enum": ["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD"],
+                    "description": "HTTP method (default: GET)"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Path relative to the API base URL (e.g., '/issues/42')"
+                },
+                "query": {
+                    "type": "object",
+                    "description": "Query parameters as key/value pairs"
+                },
+                "headers": {
+                    "type": "object",
+                    "description": "Additional request headers (authentication headers cannot be set)"
+                },
+                "body": {
+                    "description": "JSON request body"
+                },
+                "max_bytes": {
+                    "type": "integer",
+                    "description": format!("Maximum response body bytes to return (default: {})", self.max_response_bytes)
+                }
+            },
+            "required": ["api", "path"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let api_name = params["api"].as_str().ok_or_else(|| {
+            ToolError::InvalidParams("Missing 'api' parameter".to_string())
+        })?;
+        let path = params["path"].as_str().ok_or_else(|| {
+            ToolError::InvalidParams("Missing 'path' parameter".to_string())
+        })?;
+        let api = self.apis.get(api_name).ok_or_else(|| {
+            ToolError::InvalidParams(format!("Unknown API: {}", api_name))
+        })?;
+
+        let method_name = params["method"].as_str().unwrap_or("GET").to_ascii_uppercase();
+        let method = Method::from_bytes(method_name.as_bytes())
+            .map_err(|_| ToolError::InvalidParams(format!("Invalid method: {}", method_name)))?;
+        if !api.methods.contains(&method) {
+            return Err(ToolError::PermissionDenied(format!(
+                "Method {} is not allowed for API '{}'",
+                method, api_name
+            )));
+        }
+
+        let mut url = Self::build_url(&api.base_url, path)?;
+        if let Some(query) = params["query"].as_object() {
+            let mut pairs = url.query_pairs_mut();
+            for (key, value) in query {
+                let value = value.as_str().map(|s| s.to_string()).unwrap_or_else(|| value.to_string());
+                pairs.append_pair(key, &value);
+            }
+        }
+
+        let max_bytes = params["max_bytes"]
+            .as_u64()
+            .map(|n| n as usize)
+            .unwrap_or(self.max_response_bytes)
+            .min(self.max_response_bytes);
+
+        info!("http_request {} {} for user {}", method, url, context.user_id);
+
+        let mut request = self
+            .client
+            .request(method.clone(), url.clone())
+            .timeout(Duration::from_secs(api.entry.timeout_seconds));
+
+        // LLM指定のヘッダー（認証系・設定済みヘッダーは上書き不可）
+        if let Some(headers) = params["headers"].as_object() {
+            for (name, value) in headers {
+                let lowered = name.to_ascii_lowercase();
+                if FORBIDDEN_HEADERS.contains(&lowered.as_str())
+                    || api.entry.headers.keys().any(|k| k.eq_ignore_ascii_case(name))
+                {
+                    return Err(ToolError::PermissionDenied(format!("Header '{}' cannot be set", name)));
+                }
+                let value = value.as_str().map(|s| s.to_string()).unwrap_or_else(|| value.to_string());
+                request = request.header(name.as_str(), value);
+            }
+        }
+
+        // 設定済みヘッダー（秘密情報はサーバー側で展開）
+        let mut secrets = Vec::new();
+        for (name, template) in &api.entry.headers {
+            let value = Self::expand_template(template, &mut secrets).ok_or_else(|| {
+                ToolError::ExecutionFailed(format!("API '{}' is not fully configured on the server", api_name))
+            })?;
+            request = request.header(name.as_str(), value);
+        }
+
+        if !params["body"].is_null() {
+            request = request.json(&params["body"]);
+        }
+
+        let mut response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("http_request to {} failed: {}", api_name, e);
+                let message = if e.is_timeout() { "Request timed out" } else { "Request failed" };
+                return Err(ToolError::ExecutionFailed(format!("{}: {} {}", message, method, url)));
+            }
+        };
+
+        let status = response.status();
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string())
+        };
+        let content_type = header("content-type").unwrap_or_default();
+        let location = header("location");
+
+        // 上限まで本文を読み込む
+        let mut body = Vec::new();
+        let mut truncated = false;
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    let remaining = max_bytes.saturating_sub(body.len());
+                    if chunk.len() > remaining {
+                        body.extend_from_slice(&chunk[..remaining]);
+                        truncated = true;
+                        break;
+                    }
+                    body.extend_from_slice(&chunk);
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("Failed to read http_request response: {}", e);
+                    return Err(ToolError::ExecutionFailed("Failed to read response body".to_string()));
+                }
+            }
+        }
+
+        debug!("http_request {} -> {} ({} bytes)", url, status, body.len());
+
+        let mut output = format!("HTTP {}\n", status);
+        if !content_type.is_empty() {
+            output.push_str(&format!("Content-Type: {}\n", content_type));
+        }
+        if let Some(location) = location {
+            output.push_str(&format!("Location: {}\n", location));
+        }
+        output.push('\n');
+        output.push_str(&Self::format_body(&body, &content_type));
+        if truncated {
+            output.push_str(&format!("\n\n[Response truncated at {} bytes]", max_bytes));
+        }
+
+        let output = Self::redact(&output, &secrets);
+        if status.is_success() || status.is_redirection() {
+            Ok(ToolResult::success(output))
+        } else {
+            Ok(ToolResult::error(output))
+        }
+    }
+}
+
+/// HTTP API設定ファイルからツールを作成（ファイルがない・APIが空の場合は None）
+pub fn load_http_request_tool(config_path: &str) -> Result<Option<HttpRequestTool>, String> {
+    if !std::path::Path::new(config_path).exists() {
+        return Ok(None);
+    }
+    let tool = HttpRequestTool::load(config_path)?;
+    if tool.apis.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(tool))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn create_test_context() -> ToolContext {
+        ToolContext::new(123, "test_user".to_string(), 456, "output".to_string())
+    }
+
+    /// リクエスト内容をそのまま本文として返すローカルHTTPサーバー
+    async fn echo_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 8192];
+            let n = socket.read(&mut buf).await.unwrap();
+            let body = String::from_utf8_lossy(&buf[..n]).to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    fn tool_for(base_url: &str, methods: &[&str]) -> HttpRequestTool {
+        let config: HttpApiConfig = serde_json::from_value(json!({
+            "apis": [{
+                "name": "tracker",
+                "base_url": base_url,
+                "description": "Issue tracker",
+                "headers": {"Authorization": "Bearer ${HTTP_REQUEST_TEST_TOKEN}"},
+                "allowed_methods": methods
+            }]
+        }))
+        .unwrap();
+        HttpRequestTool::new(config).unwrap()
+    }
+
+    #[test]
+    fn test_build_url_stays_under_base() {
+        let base = Url::parse("https://api.example.com/v1/").unwrap();
+        assert_eq!(
+            HttpRequestTool::build_url(&base, "/issues/42").unwrap().as_str(),
+            "https://api.example.com/v1/issues/42"
+        );
+        for path in ["../admin", "/v1/%2e%2e/admin", "//evil.example/x", "https://evil.example/", "a\\b"] {
+            assert!(
+                matches!(HttpRequestTool::build_url(&base, path), Err(ToolError::PermissionDenied(_))),
+                "{} should be rejected",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_description_hides_headers() {
+        let tool = tool_for("https://api.example.com/v1", &["GET"]);
+        assert!(tool.description().contains("tracker (https://api.example.com/v1) [GET] Issue tracker"));
+        assert!(!tool.description().contains("HTTP_REQUEST_TEST_TOKEN"));
+        assert!(!tool.parameters_schema().to_string().contains("Authorization"));
+    }
+
+    #[tokio::test]
+    async fn test_injects_auth_and_masks_secret() {
+        std::env::set_var("HTTP_REQUEST_TEST_TOKEN", "s3cr3t-token-value");
+        let base = echo_server().await;
+        let tool = tool_for(&format!("{}/api", base), &["GET", "POST"]);
+
+        let result = tool
+            .execute(
+                json!({"api": "tracker", "method": "POST", "path": "/issues", "query": {"q": "open"}, "body": {"title": "bug"}}),
+                &create_test_context(),
+            )
+            .await
+            .unwrap();
+
+        assert!(!result.is_error);
+        assert!(result.output.starts_with("HTTP 200 OK"));
+        assert!(result.output.contains("POST /api/issues?q=open"));
+        assert!(result.output.contains("{\"title\":\"bug\"}"));
+        // サーバーには送られているが、出力には含まれない
+        assert!(result.output.to_lowercase().contains("authorization: bearer"));
+        assert!(!result.output.contains("s3cr3t-token-value"));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_disallowed_requests() {
+        let tool = tool_for("https://api.example.com/v1", &["GET"]);
+        let ctx = create_test_context();
+
+        let result = tool.execute(json!({"api": "other", "path": "/"}), &ctx).await;
+        assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+
+        let result = tool.execute(json!({"api": "tracker", "method": "DELETE", "path": "/issues/1"}), &ctx).await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+
+        let result = tool
+            .execute(json!({"api": "tracker", "path": "/issues", "headers": {"authorization": "x"}}), &ctx)
+            .await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+    }
+}
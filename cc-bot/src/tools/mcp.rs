@@ -1,55 +1,60 @@
 //! MCPツールアダプター
 //!
 //! MCPサーバーから提供されるツールをTool traitに適合させます。
+//! ツールは `mcp__<server>__<tool>` の名前で登録し、実行時は提供元サーバーに直接振り分けます。
+//! リソースは `mcp_read_resource` ツールで一覧・読み取りできます。
 
-use crate::mcp_client::MCPClient;
+use crate::llm::LLMClient;
+use crate::mcp_client::{MCPClient, MCPToolDefinition};
+use crate::mcp_sampling::SamplingApprovals;
 use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
 use async_trait::async_trait;
+use rmcp::model::ResourceContents;
 use serde_json::{json, Value as JsonValue};
 use std::sync::Arc;
 
+/// リソース読み取り結果の最大文字数
+const MAX_OUTPUT_CHARS: usize = 15000;
+
 /// MCPツールアダプター
 pub struct MCPToolAdapter {
     client: Arc<MCPClient>,
-    tool_name: String,
-    description: String,
-    input_schema: JsonValue,
+    definition: MCPToolDefinition,
 }
 
 impl MCPToolAdapter {
-    pub fn new(
-        client: Arc<MCPClient>,
-        tool_name: String,
-        description: String,
-        input_schema: JsonValue,
-    ) -> Self {
-        Self {
-            client,
-            tool_name,
-            description,
-            input_schema,
-        }
+    pub fn new(client: Arc<MCPClient>, definition: MCPToolDefinition) -> Self {
+        Self { client, definition }
+    }
+
+    /// ツール定義を取得
+    pub fn definition(&self) -> &MCPToolDefinition {
+        &self.definition
     }
 }
 
 #[async_trait]
 impl Tool for MCPToolAdapter {
     fn name(&self) -> &str {
-        &self.tool_name
+        &self.definition.name
     }
 
     fn description(&self) -> &str {
-        &self.description
+        &self.definition.description
     }
 
     fn parameters_schema(&self) -> JsonValue {
-        self.input_schema.clone()
+        self.definition.input_schema.clone()
     }
 
     async fn execute(&self, params: JsonValue, _context: &ToolContext) -> Result<ToolResult, ToolError> {
         let arguments = params.as_object().cloned();
 
-        match self.client.execute_tool(&self.tool_name, arguments).await {
+        match self
+            .client
+            .execute_tool(&self.definition.server_name, &self.definition.tool_name, arguments)
+            .await
+        {
             Ok(result) => {
                 let output = serde_json::to_string_pretty(&result)
                     .unwrap_or_else(|_| result.to_string());
@@ -62,12 +67,149 @@ impl Tool for MCPToolAdapter {
     }
 }
 
-/// MCP設定ファイルからツールを読み込み
-pub async fn load_mcp_tools(config_path: &str) -> Result<Vec<MCPToolAdapter>, String> {
+/// MCPリソース読み取りツール
+pub struct MCPReadResourceTool {
+    client: Arc<MCPClient>,
+}
+
+impl MCPReadResourceTool {
+    pub fn new(client: Arc<MCPClient>) -> Self {
+        Self { client }
+    }
+
+    /// リソース一覧を整形
+    async fn list(&self, server: &str) -> Result<ToolResult, ToolError> {
+        let resources = self
+            .client
+            .list_resources(server)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("MCP resource error: {}", e)))?;
+
+        if resources.is_empty() {
+            return Ok(ToolResult::success(format!("No resources available on MCP server '{}'", server)));
+        }
+
+        let lines: Vec<String> = resources
+            .iter()
+            .map(|r| {
+                let mut line = format!("- {} ({})", r.uri, r.name);
+                if let Some(mime_type) = &r.mime_type {
+                    line.push_str(&format!(" [{}]", mime_type));
+                }
+                if !r.description.is_empty() {
+                    line.push_str(&format!(": {}", r.description));
+                }
+                line
+            })
+            .collect();
+        Ok(ToolResult::success(format!(
+            "Resources on MCP server '{}' ({}):\n{}",
+            server,
+            resources.len(),
+            lines.join("\n")
+        )))
+    }
+
+    /// リソースを読み取って整形
+    async fn read(&self, server: &str, uri: &str) -> Result<ToolResult, ToolError> {
+        let result = self
+            .client
+            .read_resource(server, uri)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("MCP resource error: {}", e)))?;
+
+        let mut output = format_resource_contents(&result.contents);
+        if output.is_empty() {
+            output = format!("Resource {} is empty", uri);
+        }
+        if output.chars().count() > MAX_OUTPUT_CHARS {
+            output = output.chars().take(MAX_OUTPUT_CHARS).collect();
+            output.push_str(&format!("\n... (output truncated at {} characters)", MAX_OUTPUT_CHARS));
+        }
+        Ok(ToolResult::success(output))
+    }
+}
+
+/// リソースの内容をテキストに整形（バイナリはサイズのみ表示）
+fn format_resource_contents(contents: &[ResourceContents]) -> String {
+    contents
+        .iter()
+        .map(|content| match content {
+            ResourceContents::TextResourceContents { uri, mime_type, text, .. } => {
+                format!("--- {} ({}) ---\n{}", uri, mime_type.as_deref().unwrap_or("text/plain"), text)
+            }
+            ResourceContents::BlobResourceContents { uri, mime_type, blob, .. } => format!(
+                "--- {} ({}) ---\n[binary content, {} bytes base64-encoded]",
+                uri,
+                mime_type.as_deref().unwrap_or("application/octet-stream"),
+                blob.len()
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[async_trait]
+impl Tool for MCPReadResourceTool {
+    fn name(&self) -> &str {
+        "mcp_read_resource"
+    }
+
+    fn description(&self) -> &str {
+        "List or read resources (files, documents, records, ...) exposed by a connected MCP server. \
+         Call without 'uri' to list the server's resources, then pass one of the listed URIs to read its contents."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        let servers: Vec<String> = self
+            .client
+            .list_enabled_servers()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        json!({
+            "type": "object",
+            "properties": {
+                "server": {
+                    "type": "string",
+                    "enum": servers,
+                    "description": "MCP server name"
+                },
+                "uri": {
+                    "type": "string",
+                    "description": "Resource URI to read (omit to list available resources)"
+                }
+            },
+            "required": ["server"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, _context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let server = params["server"]
+            .as_str()
+            .filter(|s| !s.trim().is_empty())
+            .ok_or_else(|| ToolError::InvalidParams("'server' is required".to_string()))?;
+
+        match params["uri"].as_str().map(str::trim).filter(|u| !u.is_empty()) {
+            Some(uri) => self.read(server, uri).await,
+            None => self.list(server).await,
+        }
+    }
+}
+
+/// MCP設定ファイルからクライアントとツールを読み込み
+///
+/// サーバーからのサンプリング要求は `llm` で処理します（接続前に設定）。
+pub async fn load_mcp_tools(
+    config_path: &str,
+    llm: Arc<dyn LLMClient>,
+    approvals: Arc<SamplingApprovals>,
+) -> Result<(Arc<MCPClient>, Vec<MCPToolAdapter>), String> {
     let client = MCPClient::load(config_path)
         .map_err(|e| format!("Failed to load MCP config: {}", e))?;
 
     let client = Arc::new(client);
+    client.enable_sampling(llm, approvals);
 
     // ツール一覧を更新
     client.refresh_all_tools().await
@@ -77,17 +219,10 @@ pub async fn load_mcp_tools(config_path: &str) -> Result<Vec<MCPToolAdapter>, St
 
     let adapters: Vec<MCPToolAdapter> = tools
         .into_iter()
-        .map(|tool| {
-            MCPToolAdapter::new(
-                client.clone(),
-                tool.name,
-                tool.description,
-                tool.input_schema,
-            )
-        })
+        .map(|tool| MCPToolAdapter::new(client.clone(), tool))
         .collect();
 
-    Ok(adapters)
+    Ok((client, adapters))
 }
 
 #[cfg(test)]
@@ -99,12 +234,54 @@ mod tests {
         let client = Arc::new(MCPClient::new());
         let adapter = MCPToolAdapter::new(
             client,
-            "mcp_exa_web_search".to_string(),
-            "Search the web".to_string(),
-            json!({"type": "object"}),
+            MCPToolDefinition {
+                name: "mcp__exa__web_search".to_string(),
+                tool_name: "web_search".to_string(),
+                description: "Search the web".to_string(),
+                input_schema: json!({"type": "object"}),
+                server_name: "exa".to_string(),
+            },
         );
 
-        assert_eq!(adapter.name(), "mcp_exa_web_search");
+        assert_eq!(adapter.name(), "mcp__exa__web_search");
         assert_eq!(adapter.description(), "Search the web");
     }
+
+    #[tokio::test]
+    async fn test_mcp_read_resource_requires_server() {
+        let tool = MCPReadResourceTool::new(Arc::new(MCPClient::new()));
+        let ctx = ToolContext::new(1, "user".to_string(), 1, "/tmp".to_string());
+
+        assert!(matches!(
+            tool.execute(json!({"uri": "file:///a"}), &ctx).await,
+            Err(ToolError::InvalidParams(_))
+        ));
+        assert!(matches!(
+            tool.execute(json!({"server": "missing"}), &ctx).await,
+            Err(ToolError::ExecutionFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_format_resource_contents() {
+        let contents = vec![
+            ResourceContents::TextResourceContents {
+                uri: "note://a".to_string(),
+                mime_type: Some("text/markdown".to_string()),
+                text: "# A".to_string(),
+                meta: None,
+            },
+            ResourceContents::BlobResourceContents {
+                uri: "note://b".to_string(),
+                mime_type: None,
+                blob: "AAAA".to_string(),
+                meta: None,
+            },
+        ];
+
+        let output = format_resource_contents(&contents);
+        assert!(output.contains("--- note://a (text/markdown) ---\n# A"));
+        assert!(output.contains("note://b (application/octet-stream)"));
+        assert!(output.contains("4 bytes base64-encoded"));
+    }
 }
@@ -1,29 +1,77 @@
+mod apply_patch;
+mod archive;
 mod bash;
+mod bash_jobs;
+mod data_query;
+mod discord;
 mod edit;
+mod file_history;
+mod git;
 mod glob;
 mod grep;
+mod http_request;
 mod list_files;
 mod mcp;
 mod read_file;
 mod remember;
+mod render_chart;
+mod run_code;
+mod schedule;
+mod shell_session;
+mod sql_query;
+mod todo;
 mod web_fetch;
+mod web_search;
 mod write_file;
 
+pub use apply_patch::ApplyPatchTool;
+pub use archive::ArchiveTool;
 pub use bash::BashTool;
+pub use bash_jobs::BashJobsTool;
+pub use data_query::DataQueryTool;
+#[cfg(target_os = "linux")]
+pub use data_query::{run_data_query_worker, DATA_QUERY_WORKER_ARG};
+pub use discord::DiscordAccess;
 pub use edit::EditTool;
+pub use file_history::FileHistoryTool;
+pub use git::GitTool;
 pub use glob::GlobTool;
 pub use grep::GrepTool;
 pub use list_files::ListFilesTool;
-pub use mcp::{load_mcp_tools, MCPToolAdapter};
+pub use mcp::{load_mcp_tools, MCPReadResourceTool, MCPToolAdapter};
 pub use read_file::ReadFileTool;
+pub use render_chart::RenderChartTool;
+pub use run_code::RunCodeTool;
+pub use schedule::{ScheduleAccess, ScheduleCreateTool, ScheduleListTool};
+pub use shell_session::ShellSessionManager;
+pub use sql_query::SqlQueryTool;
+pub use todo::{format_todo, parse_due, ReminderOutcome, TodoReminders};
 pub use web_fetch::WebFetchTool;
+pub use web_search::{SearxngBackend, WebSearchTool};
 pub use write_file::WriteFileTool;
 
+use crate::llm::LLMClient;
+use crate::mcp_client::{MCPClient, MCPToolDefinition};
+use crate::mcp_sampling::SamplingApprovals;
 use crate::memory_store::MemoryStore;
+use crate::permission::PermissionManager;
+use crate::schedule_store::ScheduleStore;
+use crate::scheduler::Scheduler;
+use crate::security::WebPolicy;
+use crate::todo_store::TodoStore;
 use crate::tool::{Tool, ToolManager};
+use crate::web_cache::WebCache;
+use discord::{
+    DiscordAddReactionTool, DiscordCreateThreadTool, DiscordPinTool, DiscordReadMessagesTool,
+    DiscordSearchMessagesTool,
+};
 use remember::{RecallTool, RememberTool};
+use schedule::ScheduleCancelTool;
+use todo::{TodoAccess, TodoAddTool, TodoCompleteTool, TodoListTool, TodoUpdateTool};
+use serenity::http::Http;
 use std::sync::Arc;
-use tracing::info;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
 
 /// デフォルトツールを登録
 pub fn register_default_tools(manager: &mut ToolManager) {
@@ -32,11 +80,43 @@ pub fn register_default_tools(manager: &mut ToolManager) {
     manager.register(ListFilesTool::new());
     // 新しいツール
     manager.register(EditTool::new());
+    manager.register(ApplyPatchTool::new());
+    manager.register(FileHistoryTool::new());
     manager.register(GlobTool::new());
     manager.register(GrepTool::new());
-    manager.register(BashTool::new());
-    // Web ツール
-    manager.register(WebFetchTool::new());
+    // fetch / push は GIT_TOOL_ALLOW_NETWORK=true の場合のみ
+    manager.register(GitTool::from_env());
+    manager.register(SqlQueryTool::new());
+    manager.register(DataQueryTool::new());
+    manager.register(RenderChartTool::new());
+    manager.register(ArchiveTool::from_env());
+    // bash と bash_jobs は永続シェル・ジョブ管理を共有
+    let shell_sessions = Arc::new(ShellSessionManager::default());
+    manager.register(BashTool::with_sessions(shell_sessions.clone()));
+    manager.register(BashJobsTool::new(shell_sessions));
+    // WASMサンドボックスでのコード実行（ランタイムが設定されている場合のみ）
+    match RunCodeTool::from_env() {
+        Some(Ok(tool)) => {
+            manager.register(tool);
+        }
+        Some(Err(e)) => warn!("Failed to initialize run_code: {}", e),
+        None => info!("RUN_CODE_PYTHON_WASM / RUN_CODE_JS_WASM not set, skipping run_code tool"),
+    }
+}
+
+/// Webツールを登録（data/web_policy.json のアクセスポリシーを適用）
+pub fn register_web_tools(manager: &mut ToolManager, web_cache: Arc<WebCache>) {
+    let web_policy = Arc::new(WebPolicy::load_or_default("data"));
+    manager.register(WebFetchTool::with_policy(web_policy.clone()).with_cache(web_cache.clone()));
+
+    // Web検索（SEARXNG_URL が設定されている場合のみ）
+    match SearxngBackend::from_env() {
+        Some(backend) => {
+            let fetcher = Arc::new(WebFetchTool::with_policy(web_policy).with_cache(web_cache));
+            manager.register(WebSearchTool::new(Arc::new(backend)).with_fetcher(fetcher));
+        }
+        None => info!("SEARXNG_URL not set, skipping web_search tool"),
+    }
 }
 
 /// メモリツールを登録
@@ -45,27 +125,112 @@ pub fn register_memory_tools(manager: &mut ToolManager, memory_store: Arc<Memory
     manager.register(RecallTool::new(memory_store));
 }
 
-/// MCPツールを登録（非同期）
-pub async fn register_mcp_tools(manager: &mut ToolManager, config_path: &str) -> Result<(), String> {
-    match load_mcp_tools(config_path).await {
-        Ok(tools) => {
-            let count = tools.len();
+/// Discordツールを登録（呼び出し元ユーザーのチャンネル権限で実行）
+pub fn register_discord_tools(
+    manager: &mut ToolManager,
+    http: Arc<Http>,
+    permission_manager: Arc<RwLock<PermissionManager>>,
+) {
+    let access = Arc::new(DiscordAccess::new(http, permission_manager));
+    manager.register(DiscordReadMessagesTool::new(access.clone()));
+    manager.register(DiscordSearchMessagesTool::new(access.clone()));
+    manager.register(DiscordCreateThreadTool::new(access.clone()));
+    manager.register(DiscordAddReactionTool::new(access.clone()));
+    manager.register(DiscordPinTool::new(access));
+}
+
+/// スケジュールツールを登録（呼び出し元のチャンネル・ユーザーに紐付け）
+///
+/// 共通の状態を返すので、MCPサーバーモードも同じ検証・上限でスケジュールを扱える
+pub fn register_schedule_tools(
+    manager: &mut ToolManager,
+    scheduler: Arc<Scheduler>,
+    schedule_store: Arc<RwLock<ScheduleStore>>,
+    permission_manager: Arc<RwLock<PermissionManager>>,
+) -> Arc<ScheduleAccess> {
+    let access = Arc::new(ScheduleAccess::new(scheduler, schedule_store, permission_manager).with_env_limit());
+    manager.register(ScheduleCreateTool::new(access.clone()));
+    manager.register(ScheduleListTool::new(access.clone()));
+    manager.register(ScheduleCancelTool::new(access.clone()));
+    access
+}
+
+/// TODOツールを登録（期限のリマインダーはスケジューラーに登録）
+pub fn register_todo_tools(
+    manager: &mut ToolManager,
+    todo_store: Arc<TodoStore>,
+    scheduler: Arc<Scheduler>,
+    schedule_store: Arc<RwLock<ScheduleStore>>,
+) {
+    let access = Arc::new(TodoAccess::new(todo_store, TodoReminders::new(scheduler, schedule_store)));
+    manager.register(TodoAddTool::new(access.clone()));
+    manager.register(TodoListTool::new(access.clone()));
+    manager.register(TodoUpdateTool::new(access.clone()));
+    manager.register(TodoCompleteTool::new(access));
+}
+
+/// HTTP APIツールを登録（設定ファイルがあれば）
+pub fn register_http_api_tools(manager: &mut ToolManager, config_path: &str) -> Result<(), String> {
+    match http_request::load_http_request_tool(config_path)? {
+        Some(tool) => {
+            info!("Registering http_request tool from {}", config_path);
+            manager.register(tool);
+        }
+        None => info!("HTTP API config not found at {}, skipping http_request tool", config_path),
+    }
+    Ok(())
+}
+
+/// MCPツールを登録（非同期、読み込んだクライアントと登録できたツールを返す）
+///
+/// 既存のツールと名前が衝突したMCPツールは登録しません。
+/// サーバーからのサンプリング要求は `llm` で処理し、`untrusted` のサーバーは `approvals` で承認を待ちます。
+pub async fn register_mcp_tools(
+    manager: &mut ToolManager,
+    config_path: &str,
+    llm: Arc<dyn LLMClient>,
+    approvals: Arc<SamplingApprovals>,
+) -> Result<Option<(Arc<MCPClient>, Vec<MCPToolDefinition>)>, String> {
+    match load_mcp_tools(config_path, llm, approvals).await {
+        Ok((client, tools)) => {
+            let mut registered = Vec::new();
             for tool in tools {
                 let name = tool.name().to_string();
+                let definition = tool.definition().clone();
                 info!("Registering MCP tool: {}", name);
-                manager.register(tool);
+                if manager.register(tool) {
+                    registered.push(definition);
+                } else {
+                    warn!("MCP tool {} was not registered: name collides with an existing tool", name);
+                }
+            }
+            if !client.list_enabled_servers().is_empty() {
+                manager.register(MCPReadResourceTool::new(client.clone()));
             }
-            info!("Registered {} MCP tools", count);
-            Ok(())
+            info!("Registered {} MCP tools", registered.len());
+            Ok(Some((client, registered)))
         }
         Err(e) => {
             // MCP設定がなくてもエラーにせず、警告のみ
             if e.contains("No such file") || e.contains("not found") {
                 info!("MCP config not found at {}, skipping MCP tools", config_path);
-                Ok(())
+                Ok(None)
             } else {
                 Err(e)
             }
         }
     }
 }
+
+/// ファイル系ツールのテスト用ワークスペースを作成
+///
+/// 一時ディレクトリ・コンテキスト・作成済みのユーザー作業ディレクトリを返す。
+#[cfg(test)]
+pub(crate) fn test_workspace() -> (tempfile::TempDir, crate::tool::ToolContext, std::path::PathBuf) {
+    let dir = tempfile::TempDir::new().unwrap();
+    let base = dir.path().to_string_lossy().to_string();
+    let ctx = crate::tool::ToolContext::new(123, "test_user".to_string(), 456, base).with_custom_subdir("work");
+    let root = std::path::PathBuf::from(ctx.get_user_output_dir());
+    std::fs::create_dir_all(&root).unwrap();
+    (dir, ctx, root)
+}
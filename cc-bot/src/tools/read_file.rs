@@ -1,10 +1,36 @@
 use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
 use async_trait::async_trait;
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
 use serde_json::{json, Value as JsonValue};
 use std::path::Path;
 use tokio::fs;
 use tracing::{debug, warn};
 
+/// 範囲指定なしで全体を返す最大行数
+const MAX_UNRANGED_LINES: usize = 2000;
+/// 範囲指定なしで全体を返す最大バイト数
+const MAX_UNRANGED_BYTES: u64 = 256 * 1024;
+/// 1回で返す最大行数
+const MAX_LINES_PER_READ: usize = 2000;
+/// 1行の最大表示文字数
+const MAX_LINE_CHARS: usize = 2000;
+/// 読み込み可能な最大ファイルサイズ
+const MAX_FILE_BYTES: u64 = 20 * 1024 * 1024;
+/// 添付できる画像の最大サイズ（Discordの制限）
+const MAX_IMAGE_BYTES: u64 = 8 * 1024 * 1024;
+/// バイナリ判定に使う先頭バイト数
+const BINARY_SNIFF_BYTES: usize = 8192;
+/// 16進ダンプで表示するバイト数
+const HEX_DUMP_BYTES: usize = 256;
+
+/// デコード結果
+struct DecodedText {
+    text: String,
+    encoding: &'static str,
+    had_errors: bool,
+}
+
 /// ファイル読み込みツール
 pub struct ReadFileTool;
 
@@ -45,6 +71,88 @@ impl ReadFileTool {
 
         Ok(())
     }
+
+    /// 画像形式をマジックバイトから判定し、拡張子を返す
+    fn detect_image(bytes: &[u8]) -> Option<&'static str> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some("png")
+        } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some("jpg")
+        } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+            Some("gif")
+        } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+            Some("webp")
+        } else {
+            None
+        }
+    }
+
+    /// UTF-16 の BOM を判定
+    fn utf16_bom(bytes: &[u8]) -> Option<&'static Encoding> {
+        if bytes.starts_with(&[0xFF, 0xFE]) {
+            Some(UTF_16LE)
+        } else if bytes.starts_with(&[0xFE, 0xFF]) {
+            Some(UTF_16BE)
+        } else {
+            None
+        }
+    }
+
+    /// バイナリファイルか判定（先頭にNULバイトを含む）
+    fn is_binary(bytes: &[u8]) -> bool {
+        if Self::utf16_bom(bytes).is_some() {
+            return false;
+        }
+        let sniff = &bytes[..bytes.len().min(BINARY_SNIFF_BYTES)];
+        sniff.contains(&0)
+    }
+
+    /// 文字コードを判定してデコード（日本語の Shift_JIS / EUC-JP を優先）
+    fn decode(bytes: &[u8]) -> DecodedText {
+        let encoding = if let Some(encoding) = Encoding::for_bom(bytes).map(|(e, _)| e) {
+            encoding
+        } else if std::str::from_utf8(bytes).is_ok() {
+            UTF_8
+        } else {
+            let mut detector = EncodingDetector::new();
+            detector.feed(bytes, true);
+            detector.guess(Some(b"jp"), true)
+        };
+
+        let (text, actual, had_errors) = encoding.decode(bytes);
+        DecodedText {
+            text: text.into_owned(),
+            encoding: actual.name(),
+            had_errors,
+        }
+    }
+
+    /// xxd 形式の16進ダンプ
+    fn hex_dump(bytes: &[u8]) -> String {
+        bytes
+            .chunks(16)
+            .enumerate()
+            .map(|(row, chunk)| {
+                let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+                let ascii: String = chunk
+                    .iter()
+                    .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                    .collect();
+                format!("{:08x}: {:<47}  {}", row * 16, hex.join(" "), ascii)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// 表示用に1行を切り詰める
+    fn clip_line(line: &str) -> String {
+        if line.chars().count() > MAX_LINE_CHARS {
+            let clipped: String = line.chars().take(MAX_LINE_CHARS).collect();
+            format!("{}... (line truncated)", clipped)
+        } else {
+            line.to_string()
+        }
+    }
 }
 
 #[async_trait]
@@ -54,7 +162,7 @@ impl Tool for ReadFileTool {
     }
 
     fn description(&self) -> &str {
-        "Read the contents of a file. Only relative paths within the allowed directory are permitted."
+        "Read the contents of a file with line numbers. Use offset and limit to read a range of lines from large files. Detects text encodings such as Shift_JIS and EUC-JP, shows a hex dump for binary files, and attaches image files to the reply. Only relative paths within the allowed directory are permitted."
     }
 
     fn parameters_schema(&self) -> JsonValue {
@@ -64,18 +172,33 @@ impl Tool for ReadFileTool {
                 "path": {
                     "type": "string",
                     "description": "Relative path to the file to read"
+                },
+                "offset": {
+                    "type": "integer",
+                    "description": "Line number to start reading from (1-based, default: 1)"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Number of lines to read (default: to end of file, max: 2000)"
+                },
+                "line_numbers": {
+                    "type": "boolean",
+                    "description": "Prefix each line with its line number (default: true)"
                 }
             },
             "required": ["path"]
         })
     }
 
-    async fn execute(&self, params: JsonValue, _context: &ToolContext) -> Result<ToolResult, ToolError> {
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
         let path = params["path"].as_str().ok_or_else(|| {
             ToolError::InvalidParams("Missing 'path' parameter".to_string())
         })?;
+        let offset = params["offset"].as_u64().map(|n| n.max(1) as usize);
+        let limit = params["limit"].as_u64().map(|n| n as usize);
+        let line_numbers = params["line_numbers"].as_bool().unwrap_or(true);
 
-        debug!("Reading file: {}", path);
+        debug!("Reading file: {} (offset: {:?}, limit: {:?})", path, offset, limit);
 
         // パスのバリデーション
         Self::validate_path(path)?;
@@ -88,20 +211,114 @@ impl Tool for ReadFileTool {
             ));
         }
 
+        let size = fs::metadata(path_obj).await.map(|m| m.len()).unwrap_or(0);
+        if size > MAX_FILE_BYTES {
+            return Ok(ToolResult::error(format!(
+                "File is too large to read ({} bytes, max {} bytes). Use grep to search it instead.",
+                size, MAX_FILE_BYTES
+            )));
+        }
+
         // ファイル読み込み
-        match fs::read_to_string(path).await {
-            Ok(content) => {
-                debug!("Successfully read {} bytes from {}", content.len(), path);
-                Ok(ToolResult::success(content))
-            }
+        let bytes = match fs::read(path).await {
+            Ok(bytes) => bytes,
             Err(e) => {
                 warn!("Failed to read file {}: {}", path, e);
                 // ユーザーには一般的なエラーメッセージを返す
-                Err(ToolError::ExecutionFailed(
+                return Err(ToolError::ExecutionFailed(
                     "Failed to read file. Please check the path and permissions.".to_string()
-                ))
+                ));
+            }
+        };
+
+        // 画像は添付ファイルとして返す
+        if let Some(ext) = Self::detect_image(&bytes) {
+            if size > MAX_IMAGE_BYTES {
+                return Ok(ToolResult::error(format!(
+                    "Image is too large to attach ({} bytes, max {} bytes)",
+                    size, MAX_IMAGE_BYTES
+                )));
+            }
+            let filename = path_obj
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| format!("image.{}", ext));
+            if !context.add_attachment(filename.clone(), bytes) {
+                return Ok(ToolResult::error("Attachment limit (count or total size) reached for this reply".to_string()));
             }
+            debug!("Attached image {} ({} bytes)", path, size);
+            return Ok(ToolResult::success(format!(
+                "{} is a {} image ({} bytes). It has been attached to the reply as {}.",
+                path,
+                ext.to_uppercase(),
+                size,
+                filename
+            )));
+        }
+
+        // バイナリは16進ダンプのプレビューを返す
+        if Self::is_binary(&bytes) {
+            let preview = &bytes[..bytes.len().min(HEX_DUMP_BYTES)];
+            return Ok(ToolResult::success(format!(
+                "{} is a binary file ({} bytes). First {} bytes:\n{}",
+                path,
+                size,
+                preview.len(),
+                Self::hex_dump(preview)
+            )));
+        }
+
+        let decoded = Self::decode(&bytes);
+        let lines: Vec<&str> = decoded.text.lines().collect();
+        let total_lines = lines.len();
+
+        // 範囲指定なしで大きすぎる場合は範囲指定を促す
+        if offset.is_none() && limit.is_none() && (total_lines > MAX_UNRANGED_LINES || size > MAX_UNRANGED_BYTES) {
+            return Ok(ToolResult::error(format!(
+                "File is too large to read at once ({} lines, {} bytes). Use offset and limit to read a range (max {} lines per call).",
+                total_lines, size, MAX_LINES_PER_READ
+            )));
+        }
+
+        let start = offset.unwrap_or(1);
+        if total_lines > 0 && start > total_lines {
+            return Ok(ToolResult::error(format!(
+                "Offset {} is beyond the end of the file ({} lines)",
+                start, total_lines
+            )));
+        }
+        let count = limit.unwrap_or(MAX_LINES_PER_READ).clamp(1, MAX_LINES_PER_READ);
+        let end = (start - 1 + count).min(total_lines);
+
+        let mut header = format!("File: {} ({} lines", path, total_lines);
+        if decoded.encoding != "UTF-8" {
+            header.push_str(&format!(", encoding: {}", decoded.encoding));
+        }
+        if decoded.had_errors {
+            header.push_str(", some bytes could not be decoded");
+        }
+        header.push(')');
+        if start > 1 || end < total_lines {
+            header.push_str(&format!("\nShowing lines {}-{} of {}", start, end, total_lines));
         }
+
+        let width = end.max(1).to_string().len();
+        let body: Vec<String> = lines
+            .iter()
+            .enumerate()
+            .skip(start - 1)
+            .take(end.saturating_sub(start - 1))
+            .map(|(index, line)| {
+                if line_numbers {
+                    format!("{:>width$}\t{}", index + 1, Self::clip_line(line), width = width)
+                } else {
+                    Self::clip_line(line)
+                }
+            })
+            .collect();
+
+        debug!("Successfully read lines {}-{} of {} from {}", start, end, total_lines, path);
+        Ok(ToolResult::success(format!("{}\n{}", header, body.join("\n"))))
     }
 }
 
@@ -142,4 +359,101 @@ mod tests {
         let tool = ReadFileTool::new();
         assert_eq!(tool.name(), "read_file");
     }
+
+    /// カレントディレクトリ配下に一時ディレクトリを作成（相対パスのみ許可のため）
+    fn local_tempdir() -> tempfile::TempDir {
+        tempfile::Builder::new().prefix("read_file_test").tempdir_in(".").unwrap()
+    }
+
+    fn relative(dir: &tempfile::TempDir, name: &str) -> String {
+        format!("{}/{}", dir.path().file_name().unwrap().to_string_lossy(), name)
+    }
+
+    #[tokio::test]
+    async fn test_read_range_with_line_numbers() {
+        let dir = local_tempdir();
+        let content: String = (1..=10).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(dir.path().join("a.txt"), content).unwrap();
+
+        let result = ReadFileTool::new()
+            .execute(json!({"path": relative(&dir, "a.txt"), "offset": 3, "limit": 2}), &create_test_context())
+            .await
+            .unwrap();
+
+        assert!(result.output.contains("(10 lines)"));
+        assert!(result.output.contains("Showing lines 3-4 of 10"));
+        assert!(result.output.contains("3\tline 3\n4\tline 4"));
+        assert!(!result.output.contains("line 5"));
+    }
+
+    #[tokio::test]
+    async fn test_read_large_file_requires_range() {
+        let dir = local_tempdir();
+        let content = "x\n".repeat(MAX_UNRANGED_LINES + 1);
+        std::fs::write(dir.path().join("big.log"), content).unwrap();
+
+        let tool = ReadFileTool::new();
+        let result = tool
+            .execute(json!({"path": relative(&dir, "big.log")}), &create_test_context())
+            .await
+            .unwrap();
+        assert!(result.is_error);
+        assert!(result.output.contains("Use offset and limit"));
+
+        let result = tool
+            .execute(json!({"path": relative(&dir, "big.log"), "offset": 2000}), &create_test_context())
+            .await
+            .unwrap();
+        assert!(!result.is_error);
+        assert!(result.output.contains("Showing lines 2000-2001 of 2001"));
+    }
+
+    #[tokio::test]
+    async fn test_read_shift_jis() {
+        let dir = local_tempdir();
+        let (encoded, _, _) = encoding_rs::SHIFT_JIS.encode("こんにちは、世界。日本語のテキストファイルです。\n");
+        std::fs::write(dir.path().join("sjis.txt"), encoded).unwrap();
+
+        let result = ReadFileTool::new()
+            .execute(json!({"path": relative(&dir, "sjis.txt")}), &create_test_context())
+            .await
+            .unwrap();
+
+        assert!(result.output.contains("encoding: Shift_JIS"));
+        assert!(result.output.contains("こんにちは、世界。"));
+    }
+
+    #[tokio::test]
+    async fn test_read_binary_hex_dump() {
+        let dir = local_tempdir();
+        std::fs::write(dir.path().join("data.bin"), [0x00u8, 0x01, 0x41, 0x42]).unwrap();
+
+        let result = ReadFileTool::new()
+            .execute(json!({"path": relative(&dir, "data.bin")}), &create_test_context())
+            .await
+            .unwrap();
+
+        assert!(result.output.contains("binary file (4 bytes)"));
+        assert!(result.output.contains("00000000: 00 01 41 42"));
+        assert!(result.output.contains("..AB"));
+    }
+
+    #[tokio::test]
+    async fn test_read_image_becomes_attachment() {
+        let dir = local_tempdir();
+        let png = b"\x89PNG\r\n\x1a\nrest-of-image".to_vec();
+        std::fs::write(dir.path().join("pic.png"), &png).unwrap();
+        let ctx = create_test_context();
+
+        let result = ReadFileTool::new()
+            .execute(json!({"path": relative(&dir, "pic.png")}), &ctx)
+            .await
+            .unwrap();
+
+        assert!(result.output.contains("PNG image"));
+        let attachments = ctx.take_attachments();
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(attachments[0].filename, "pic.png");
+        assert_eq!(attachments[0].data, png);
+    }
 }
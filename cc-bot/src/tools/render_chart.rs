@@ -0,0 +1,713 @@
+//! チャート描画ツール
+//!
+//! 宣言的なチャート定義（折れ線・棒・散布図・円グラフ）から plotters で PNG を描画し、
+//! 添付ファイルとして Discord に送信します。描画は完全にオフラインで行います。
+//! 日本語を表示できるよう、フォントは `CHART_FONT_PATH` または既知のCJKフォントの
+//! パスから読み込みます。見つからない場合は同梱の DejaVu Sans を使用し、
+//! フォントにない文字（日本語など）を含むチャートは描画を拒否します。
+
+use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
+use ab_glyph::Font;
+use async_trait::async_trait;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use plotters::style::{register_font, FontStyle, Palette99};
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::{debug, info, warn};
+
+/// デフォルトの画像サイズ
+const DEFAULT_WIDTH: u32 = 800;
+const DEFAULT_HEIGHT: u32 = 500;
+/// 画像サイズの範囲
+const MIN_SIZE: u32 = 200;
+const MAX_SIZE: u32 = 2000;
+/// 最大系列数
+const MAX_SERIES: usize = 10;
+/// 1系列あたりの最大データ数
+const MAX_POINTS: usize = 10_000;
+/// plotters に登録するフォントファミリー名
+const FONT_FAMILY: &str = "sans-serif";
+/// デフォルトの出力ファイル名
+const DEFAULT_FILENAME: &str = "chart.png";
+
+/// フォントの探索候補（日本語対応フォントを優先）
+const FONT_CANDIDATES: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/opentype/noto/NotoSansCJKjp-Regular.otf",
+    "/usr/share/fonts/opentype/ipaexfont-gothic/ipaexg.ttf",
+    "/usr/share/fonts/truetype/takao-gothic/TakaoPGothic.ttf",
+    "/usr/share/fonts/truetype/vlgothic/VL-PGothic-Regular.ttf",
+    "/System/Library/Fonts/ヒラギノ角ゴシック W3.ttc",
+    "C:\\Windows\\Fonts\\YuGothM.ttc",
+    "C:\\Windows\\Fonts\\meiryo.ttc",
+];
+
+/// 同梱フォールバックフォント（日本語非対応）
+const BUNDLED_FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+const BUNDLED_FONT_NAME: &str = "bundled DejaVu Sans";
+
+/// 読み込み済みのチャート用フォント
+struct ChartFont {
+    name: String,
+    font: ab_glyph::FontRef<'static>,
+}
+
+static CHART_FONT: OnceLock<Result<ChartFont, String>> = OnceLock::new();
+
+/// チャート用フォントのデータを読み込む（見つからなければ同梱フォント）
+fn load_font_bytes() -> Result<(String, &'static [u8]), String> {
+    let path = match std::env::var("CHART_FONT_PATH") {
+        Ok(path) if !path.trim().is_empty() => Some(PathBuf::from(path.trim())),
+        _ => FONT_CANDIDATES.iter().map(PathBuf::from).find(|p| p.is_file()),
+    };
+    let Some(path) = path else {
+        return Ok((BUNDLED_FONT_NAME.to_string(), BUNDLED_FONT));
+    };
+    let bytes = std::fs::read(&path)
+        .map_err(|e| format!("Failed to read chart font {}: {}", path.display(), e))?;
+    // plotters のフォント登録は 'static を要求するため、プロセス終了まで保持する
+    Ok((path.display().to_string(), Box::leak(bytes.into_boxed_slice())))
+}
+
+/// チャート用フォントを読み込み、plotters に登録（初回のみ）
+fn chart_font() -> Result<&'static ChartFont, String> {
+    CHART_FONT
+        .get_or_init(|| {
+            let (name, bytes) = load_font_bytes()?;
+            let font = ab_glyph::FontRef::try_from_slice(bytes)
+                .map_err(|e| format!("Invalid chart font {}: {}", name, e))?;
+            register_font(FONT_FAMILY, FontStyle::Normal, bytes)
+                .map_err(|_| format!("Invalid chart font {}", name))?;
+            info!("Loaded chart font: {}", name);
+            Ok(ChartFont { name, font })
+        })
+        .as_ref()
+        .map_err(|e| e.clone())
+}
+
+/// フォントに含まれない文字を列挙
+fn missing_glyphs<'a>(font: &impl Font, texts: impl IntoIterator<Item = &'a str>) -> Vec<char> {
+    let mut missing = Vec::new();
+    for c in texts.into_iter().flat_map(str::chars) {
+        if !c.is_whitespace() && font.glyph_id(c).0 == 0 && !missing.contains(&c) {
+            missing.push(c);
+        }
+    }
+    missing
+}
+
+/// フォントで表示できない文字があれば、描画を拒否するエラーメッセージを返す
+fn check_glyphs<'a>(font: &ChartFont, texts: impl IntoIterator<Item = &'a str>) -> Result<(), String> {
+    let missing = missing_glyphs(&font.font, texts);
+    if missing.is_empty() {
+        return Ok(());
+    }
+    let chars: String = missing.iter().take(20).collect();
+    warn!("Chart font {} lacks glyphs: {}", font.name, chars);
+    Err(format!(
+        "The chart font ({}) has no glyphs for \"{}\", so the chart was not rendered. \
+         Use labels without these characters, or set CHART_FONT_PATH to a Japanese-capable font \
+         such as Noto Sans CJK.",
+        font.name, chars
+    ))
+}
+
+/// チャートの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ChartKind {
+    Line,
+    Bar,
+    Scatter,
+    Pie,
+}
+
+impl ChartKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Line => "line",
+            Self::Bar => "bar",
+            Self::Scatter => "scatter",
+            Self::Pie => "pie",
+        }
+    }
+}
+
+/// 系列の定義
+#[derive(Debug, Clone, Deserialize)]
+struct SeriesSpec {
+    #[serde(default)]
+    name: Option<String>,
+    /// line / bar / pie の値（`labels` と対応）
+    #[serde(default)]
+    values: Vec<f64>,
+    /// scatter の座標
+    #[serde(default)]
+    points: Vec<[f64; 2]>,
+}
+
+/// チャートの定義
+#[derive(Debug, Clone, Deserialize)]
+struct ChartSpec {
+    #[serde(rename = "type")]
+    kind: ChartKind,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    x_label: Option<String>,
+    #[serde(default)]
+    y_label: Option<String>,
+    #[serde(default)]
+    labels: Vec<String>,
+    series: Vec<SeriesSpec>,
+    #[serde(default)]
+    width: Option<u32>,
+    #[serde(default)]
+    height: Option<u32>,
+}
+
+impl ChartSpec {
+    /// 定義を検証
+    fn validate(&self) -> Result<(), String> {
+        if self.series.is_empty() {
+            return Err("At least one series is required".to_string());
+        }
+        if self.series.len() > MAX_SERIES {
+            return Err(format!("Too many series (max {})", MAX_SERIES));
+        }
+        for (i, series) in self.series.iter().enumerate() {
+            let len = match self.kind {
+                ChartKind::Scatter => series.points.len(),
+                _ => series.values.len(),
+            };
+            if len == 0 {
+                let field = if self.kind == ChartKind::Scatter { "points" } else { "values" };
+                return Err(format!("Series {} has no {}", i + 1, field));
+            }
+            if len > MAX_POINTS {
+                return Err(format!("Series {} has too many data points (max {})", i + 1, MAX_POINTS));
+            }
+            let finite = series.values.iter().all(|v| v.is_finite())
+                && series.points.iter().all(|p| p[0].is_finite() && p[1].is_finite());
+            if !finite {
+                return Err(format!("Series {} contains non-finite values", i + 1));
+            }
+        }
+        if self.kind == ChartKind::Pie {
+            let values = &self.series[0].values;
+            if values.iter().any(|v| *v < 0.0) || values.iter().sum::<f64>() <= 0.0 {
+                return Err("Pie chart values must be non-negative with a positive total".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// 画像サイズ
+    fn size(&self) -> (u32, u32) {
+        (
+            self.width.unwrap_or(DEFAULT_WIDTH).clamp(MIN_SIZE, MAX_SIZE),
+            self.height.unwrap_or(DEFAULT_HEIGHT).clamp(MIN_SIZE, MAX_SIZE),
+        )
+    }
+
+    /// カテゴリ数（line / bar / pie）
+    fn category_count(&self) -> usize {
+        self.series.iter().map(|s| s.values.len()).max().unwrap_or(0)
+    }
+
+    /// カテゴリのラベル（未指定なら 1, 2, 3...）
+    fn category_label(&self, index: usize) -> String {
+        self.labels
+            .get(index)
+            .cloned()
+            .unwrap_or_else(|| (index + 1).to_string())
+    }
+
+    /// 描画される文字列
+    fn texts(&self) -> impl Iterator<Item = &str> {
+        [&self.title, &self.x_label, &self.y_label]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .chain(self.labels.iter().map(String::as_str))
+            .chain(self.series.iter().filter_map(|s| s.name.as_deref()))
+    }
+}
+
+/// 系列の色
+fn series_color(index: usize) -> RGBColor {
+    let (r, g, b) = Palette99::COLORS[index % Palette99::COLORS.len()];
+    RGBColor(r, g, b)
+}
+
+/// 値の範囲に余白を付ける
+fn padded_range(min: f64, max: f64) -> (f64, f64) {
+    if (max - min).abs() < f64::EPSILON {
+        let pad = if min == 0.0 { 1.0 } else { min.abs() * 0.1 };
+        return (min - pad, max + pad);
+    }
+    let pad = (max - min) * 0.05;
+    (min - pad, max + pad)
+}
+
+/// 出力ファイル名を整形（パスを除き、拡張子を .png にする）
+fn sanitize_filename(name: Option<&str>) -> String {
+    let name = name
+        .and_then(|n| Path::new(n.trim()).file_stem())
+        .map(|s| s.to_string_lossy().to_string())
+        .filter(|s| !s.is_empty() && !s.starts_with('.'));
+    match name {
+        Some(stem) => format!("{}.png", stem),
+        None => DEFAULT_FILENAME.to_string(),
+    }
+}
+
+/// plotters のエラーを文字列に変換
+fn draw_error(e: impl std::fmt::Display) -> String {
+    format!("Failed to draw chart: {}", e)
+}
+
+/// 折れ線・棒グラフを描画
+fn draw_category_chart<DB: DrawingBackend>(root: &DrawingArea<DB, Shift>, spec: &ChartSpec) -> Result<(), String> {
+    let count = spec.category_count();
+    let values = spec.series.iter().flat_map(|s| s.values.iter().copied());
+    let (mut min, mut max) = values.fold((f64::MAX, f64::MIN), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    if spec.kind == ChartKind::Bar {
+        // 棒グラフは0を基準にする
+        min = min.min(0.0);
+        max = max.max(0.0);
+    }
+    let (y_min, y_max) = padded_range(min, max);
+
+    let mut builder = ChartBuilder::on(root);
+    builder.margin(16).x_label_area_size(48).y_label_area_size(64);
+    if let Some(title) = &spec.title {
+        builder.caption(title, (FONT_FAMILY, 24));
+    }
+    let mut chart = builder
+        .build_cartesian_2d(-0.5f64..(count as f64 - 0.5), y_min..y_max)
+        .map_err(draw_error)?;
+
+    let label_formatter = |x: &f64| {
+        let index = x.round();
+        if (x - index).abs() < 1e-6 && index >= 0.0 && (index as usize) < count {
+            spec.category_label(index as usize)
+        } else {
+            String::new()
+        }
+    };
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .x_labels(count.min(20) + 1)
+        .x_label_formatter(&label_formatter)
+        .x_desc(spec.x_label.as_deref().unwrap_or(""))
+        .y_desc(spec.y_label.as_deref().unwrap_or(""))
+        .light_line_style(RGBColor(240, 240, 240))
+        .label_style((FONT_FAMILY, 14))
+        .axis_desc_style((FONT_FAMILY, 16))
+        .draw()
+        .map_err(draw_error)?;
+
+    let series_count = spec.series.len();
+    let bar_width = 0.8 / series_count as f64;
+    let base = 0.0f64.clamp(y_min, y_max);
+    for (k, series) in spec.series.iter().enumerate() {
+        let color = series_color(k);
+        let drawn = match spec.kind {
+            ChartKind::Bar => chart
+                .draw_series(series.values.iter().enumerate().map(|(i, v)| {
+                    let left = i as f64 - 0.4 + k as f64 * bar_width;
+                    Rectangle::new([(left, base), (left + bar_width, *v)], color.filled())
+                }))
+                .map_err(draw_error)?,
+            _ => {
+                let points: Vec<(f64, f64)> = series.values.iter().enumerate().map(|(i, v)| (i as f64, *v)).collect();
+                if points.len() <= 100 {
+                    chart
+                        .draw_series(points.iter().map(|p| Circle::new(*p, 3, color.filled())))
+                        .map_err(draw_error)?;
+                }
+                chart
+                    .draw_series(LineSeries::new(points, color.stroke_width(2)))
+                    .map_err(draw_error)?
+            }
+        };
+        if let Some(name) = &series.name {
+            drawn
+                .label(name.as_str())
+                .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled()));
+        }
+    }
+
+    if spec.series.iter().any(|s| s.name.is_some()) {
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .label_font((FONT_FAMILY, 14))
+            .draw()
+            .map_err(draw_error)?;
+    }
+    Ok(())
+}
+
+/// 散布図を描画
+fn draw_scatter_chart<DB: DrawingBackend>(root: &DrawingArea<DB, Shift>, spec: &ChartSpec) -> Result<(), String> {
+    let points = spec.series.iter().flat_map(|s| s.points.iter());
+    let (x_min, x_max, y_min, y_max) = points.fold(
+        (f64::MAX, f64::MIN, f64::MAX, f64::MIN),
+        |(x0, x1, y0, y1), p| (x0.min(p[0]), x1.max(p[0]), y0.min(p[1]), y1.max(p[1])),
+    );
+    let (x_min, x_max) = padded_range(x_min, x_max);
+    let (y_min, y_max) = padded_range(y_min, y_max);
+
+    let mut builder = ChartBuilder::on(root);
+    builder.margin(16).x_label_area_size(48).y_label_area_size(64);
+    if let Some(title) = &spec.title {
+        builder.caption(title, (FONT_FAMILY, 24));
+    }
+    let mut chart = builder
+        .build_cartesian_2d(x_min..x_max, y_min..y_max)
+        .map_err(draw_error)?;
+    chart
+        .configure_mesh()
+        .x_desc(spec.x_label.as_deref().unwrap_or(""))
+        .y_desc(spec.y_label.as_deref().unwrap_or(""))
+        .light_line_style(RGBColor(240, 240, 240))
+        .label_style((FONT_FAMILY, 14))
+        .axis_desc_style((FONT_FAMILY, 16))
+        .draw()
+        .map_err(draw_error)?;
+
+    for (k, series) in spec.series.iter().enumerate() {
+        let color = series_color(k);
+        let drawn = chart
+            .draw_series(series.points.iter().map(|p| Circle::new((p[0], p[1]), 4, color.filled())))
+            .map_err(draw_error)?;
+        if let Some(name) = &series.name {
+            drawn
+                .label(name.as_str())
+                .legend(move |(x, y)| Circle::new((x + 5, y), 4, color.filled()));
+        }
+    }
+
+    if spec.series.iter().any(|s| s.name.is_some()) {
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .label_font((FONT_FAMILY, 14))
+            .draw()
+            .map_err(draw_error)?;
+    }
+    Ok(())
+}
+
+/// 円グラフを描画（最初の系列のみ使用）
+fn draw_pie_chart<DB: DrawingBackend>(root: &DrawingArea<DB, Shift>, spec: &ChartSpec) -> Result<(), String> {
+    let area = match &spec.title {
+        Some(title) => root.titled(title, (FONT_FAMILY, 24)).map_err(draw_error)?,
+        None => root.clone(),
+    };
+    let (width, height) = area.dim_in_pixel();
+    let center = (width as i32 / 2, height as i32 / 2);
+    let radius = (width.min(height) as f64) * 0.35;
+
+    let sizes = &spec.series[0].values;
+    let colors: Vec<RGBColor> = (0..sizes.len()).map(series_color).collect();
+    let labels: Vec<String> = (0..sizes.len()).map(|i| spec.category_label(i)).collect();
+
+    let mut pie = Pie::new(&center, &radius, sizes, &colors, &labels);
+    pie.start_angle(-90.0);
+    pie.label_style((FONT_FAMILY, 16).into_font().color(&BLACK));
+    pie.percentages((FONT_FAMILY, (radius * 0.08).max(10.0)).into_font().color(&WHITE));
+    area.draw(&pie).map_err(draw_error)?;
+    Ok(())
+}
+
+/// RGBバッファをPNGにエンコード
+fn encode_png(buffer: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let mut png_data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png_data, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder
+        .write_header()
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    writer
+        .write_image_data(buffer)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    writer.finish().map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    Ok(png_data)
+}
+
+/// チャートをPNGに描画
+fn render_png(spec: &ChartSpec) -> Result<Vec<u8>, String> {
+    let (width, height) = spec.size();
+    let mut buffer = vec![255u8; width as usize * height as usize * 3];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (width, height)).into_drawing_area();
+        root.fill(&WHITE).map_err(draw_error)?;
+        match spec.kind {
+            ChartKind::Line | ChartKind::Bar => draw_category_chart(&root, spec)?,
+            ChartKind::Scatter => draw_scatter_chart(&root, spec)?,
+            ChartKind::Pie => draw_pie_chart(&root, spec)?,
+        }
+        root.present().map_err(draw_error)?;
+    }
+    encode_png(&buffer, width, height)
+}
+
+/// チャート描画ツール
+pub struct RenderChartTool {
+    /// 文字の検査に使うフォント（None なら読み込んだチャート用フォント）
+    font: Option<&'static ChartFont>,
+}
+
+impl RenderChartTool {
+    pub fn new() -> Self {
+        Self { font: None }
+    }
+
+    /// 文字の検査に使うフォントを固定（テスト用）
+    #[cfg(test)]
+    fn with_font(mut self, font: &'static ChartFont) -> Self {
+        self.font = Some(font);
+        self
+    }
+}
+
+impl Default for RenderChartTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for RenderChartTool {
+    fn name(&self) -> &str {
+        "render_chart"
+    }
+
+    fn description(&self) -> &str {
+        "Render a chart as a PNG image and attach it to the reply. Supports line, bar, scatter and pie charts. \
+         For line/bar/pie, give category labels and one or more series of values; for scatter, give series of \
+         [x, y] points. Japanese text is supported when a CJK font is installed."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "type": {
+                    "type": "string",
+                    "enum": ["line", "bar", "scatter", "pie"],
+                    "description": "Chart type"
+                },
+                "title": {
+                    "type": "string",
+                    "description": "Chart title"
+                },
+                "x_label": {
+                    "type": "string",
+                    "description": "X axis description (line, bar, scatter)"
+                },
+                "y_label": {
+                    "type": "string",
+                    "description": "Y axis description (line, bar, scatter)"
+                },
+                "labels": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Category labels for line/bar x axis or pie slices (e.g. ['Mon', 'Tue'])"
+                },
+                "series": {
+                    "type": "array",
+                    "description": "Data series (pie uses only the first one)",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "name": {"type": "string", "description": "Series name shown in the legend"},
+                            "values": {"type": "array", "items": {"type": "number"}, "description": "Values per label (line, bar, pie)"},
+                            "points": {
+                                "type": "array",
+                                "items": {"type": "array", "items": {"type": "number"}, "minItems": 2, "maxItems": 2},
+                                "description": "[x, y] points (scatter)"
+                            }
+                        }
+                    }
+                },
+                "width": {
+                    "type": "integer",
+                    "description": "Image width in pixels (default: 800, 200-2000)"
+                },
+                "height": {
+                    "type": "integer",
+                    "description": "Image height in pixels (default: 500, 200-2000)"
+                },
+                "filename": {
+                    "type": "string",
+                    "description": "Attachment file name (default: chart.png)"
+                }
+            },
+            "required": ["type", "series"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let filename = sanitize_filename(params["filename"].as_str());
+        let spec: ChartSpec = serde_json::from_value(params)
+            .map_err(|e| ToolError::InvalidParams(format!("Invalid chart spec: {}", e)))?;
+        spec.validate().map_err(ToolError::InvalidParams)?;
+
+        let font = match self.font.map_or_else(chart_font, Ok) {
+            Ok(font) => font,
+            Err(e) => return Ok(ToolResult::error(e)),
+        };
+        if let Err(e) = check_glyphs(font, spec.texts()) {
+            return Ok(ToolResult::error(e));
+        }
+
+        debug!("Rendering {} chart for user {}", spec.kind.as_str(), context.user_id);
+        let kind = spec.kind;
+        let (width, height) = spec.size();
+        let png_data = tokio::task::spawn_blocking(move || render_png(&spec))
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("Chart task failed: {}", e)))?;
+        let png_data = match png_data {
+            Ok(data) => data,
+            Err(e) => return Ok(ToolResult::error(e)),
+        };
+
+        let size = png_data.len();
+        if !context.add_attachment(filename.clone(), png_data) {
+            return Ok(ToolResult::error("Attachment limit (count or total size) reached for this reply"));
+        }
+        info!("Rendered {} chart {} ({} bytes)", kind.as_str(), filename, size);
+
+        Ok(ToolResult::success(format!(
+            "Rendered {} chart ({}x{}) and attached it as {}",
+            kind.as_str(),
+            width,
+            height,
+            filename
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn test_context(dir: &TempDir) -> ToolContext {
+        ToolContext::new(123, "test_user".to_string(), 456, dir.path().to_string_lossy().to_string())
+    }
+
+    fn parse(spec: JsonValue) -> ChartSpec {
+        serde_json::from_value(spec).unwrap()
+    }
+
+    #[test]
+    fn test_validate_spec() {
+        let spec = parse(json!({"type": "line", "series": []}));
+        assert!(spec.validate().is_err());
+
+        let spec = parse(json!({"type": "scatter", "series": [{"values": [1, 2]}]}));
+        assert!(spec.validate().unwrap_err().contains("points"));
+
+        let spec = parse(json!({"type": "pie", "labels": ["a", "b"], "series": [{"values": [1, -2]}]}));
+        assert!(spec.validate().is_err());
+
+        let spec = parse(json!({"type": "bar", "labels": ["a"], "series": [{"values": [3]}], "width": 99999}));
+        assert!(spec.validate().is_ok());
+        assert_eq!(spec.size(), (MAX_SIZE, DEFAULT_HEIGHT));
+
+        let result: Result<ChartSpec, _> = serde_json::from_value(json!({"type": "radar", "series": []}));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_sanitize_filename() {
+        assert_eq!(sanitize_filename(None), "chart.png");
+        assert_eq!(sanitize_filename(Some("errors")), "errors.png");
+        assert_eq!(sanitize_filename(Some("../../etc/weekly.svg")), "weekly.png");
+        assert_eq!(sanitize_filename(Some(".hidden")), "chart.png");
+        assert_eq!(sanitize_filename(Some("週次エラー.png")), "週次エラー.png");
+    }
+
+    #[test]
+    fn test_padded_range() {
+        assert_eq!(padded_range(0.0, 0.0), (-1.0, 1.0));
+        let (lo, hi) = padded_range(10.0, 20.0);
+        assert!(lo < 10.0 && hi > 20.0);
+    }
+
+    fn bundled_font() -> ChartFont {
+        ChartFont {
+            name: BUNDLED_FONT_NAME.to_string(),
+            font: ab_glyph::FontRef::try_from_slice(BUNDLED_FONT).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_render_all_chart_types() {
+        let dir = TempDir::new().unwrap();
+        let context = test_context(&dir);
+        let tool = RenderChartTool::new();
+
+        let specs = [
+            json!({"type": "line", "title": "Weekly errors", "labels": ["Mon", "Tue", "Wed"],
+                   "series": [{"name": "api", "values": [3, 5, 2]}, {"name": "bot", "values": [1, 0, 4]}]}),
+            json!({"type": "bar", "labels": ["a", "b"], "series": [{"values": [-1.5, 2]}], "filename": "bars"}),
+            json!({"type": "scatter", "x_label": "x", "y_label": "y",
+                   "series": [{"name": "s", "points": [[0, 1], [2.5, 3], [4, -1]]}]}),
+            json!({"type": "pie", "title": "Share", "labels": ["A", "B", "C"], "series": [{"values": [50, 30, 20]}]}),
+        ];
+        for spec in specs {
+            let result = tool.execute(spec, &context).await.unwrap();
+            assert!(!result.is_error, "{}", result.output);
+        }
+
+        let attachments = context.take_attachments();
+        assert_eq!(attachments.len(), 4);
+        assert_eq!(attachments[1].filename, "bars.png");
+        for attachment in &attachments {
+            assert!(attachment.data.starts_with(b"\x89PNG\r\n\x1a\n"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_japanese_chart_requires_cjk_font() {
+        let dir = TempDir::new().unwrap();
+        let context = test_context(&dir);
+        let spec = json!({"type": "bar", "title": "週次エラー数", "labels": ["月", "火"],
+                          "series": [{"values": [3, 5]}]});
+        let font: &'static ChartFont = Box::leak(Box::new(bundled_font()));
+        let result = RenderChartTool::new().with_font(font).execute(spec, &context).await.unwrap();
+
+        // 日本語のない同梱フォントでは、文字化けした画像を送らずに拒否する
+        assert!(result.is_error);
+        assert!(result.output.contains("週次エラー数月火"));
+        assert!(result.output.contains("CHART_FONT_PATH"));
+        assert!(context.take_attachments().is_empty());
+    }
+
+    #[test]
+    fn test_bundled_font_glyphs() {
+        let font = bundled_font();
+        assert!(missing_glyphs(&font.font, ["Weekly errors 0123456789 -.,%", "  "]).is_empty());
+        assert_eq!(missing_glyphs(&font.font, ["エラー", "エ"]), vec!['エ', 'ラ', 'ー']);
+
+        assert!(check_glyphs(&font, ["Share", "A", "B"]).is_ok());
+        let err = check_glyphs(&font, ["週次"]).unwrap_err();
+        assert!(err.contains("週次"));
+        assert!(err.contains(BUNDLED_FONT_NAME));
+    }
+}
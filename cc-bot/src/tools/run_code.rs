@@ -0,0 +1,597 @@
+//! WASMサンドボックスでのコード実行ツール
+//!
+//! Python（WASIビルド）や JavaScript（WASM版JSエンジン）を wasmtime 上で実行します。
+//! 燃料（命令数）・メモリ・実行時間を制限し、ホストのファイルシステムは
+//! 作業用の一時ディレクトリ以外公開せず、ネットワークも使用できません。
+
+use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
+use async_trait::async_trait;
+use serde_json::{json, Value as JsonValue};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use wasmtime::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+use wasmtime_wasi::pipe::MemoryOutputPipe;
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::{DirPerms, FilePerms, I32Exit, WasiCtxBuilder};
+
+/// ゲストから見た作業ディレクトリ
+const GUEST_WORK_DIR: &str = "/work";
+/// エポックを進める間隔
+const EPOCH_TICK: Duration = Duration::from_millis(100);
+/// デフォルトの燃料（おおよその命令数）
+const DEFAULT_FUEL: u64 = 5_000_000_000;
+/// デフォルトのメモリ上限（MB）
+const DEFAULT_MEMORY_MB: usize = 256;
+/// デフォルトの実行時間上限（秒）
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+/// 実行時間上限の最大値（秒）
+const MAX_TIMEOUT_SECS: u64 = 30;
+/// 標準出力・標準エラーのバッファ上限
+const OUTPUT_CAPACITY: usize = 1024 * 1024;
+/// 結果に含める出力の最大文字数
+const MAX_OUTPUT_CHARS: usize = 8000;
+/// 添付する生成ファイルの最大サイズ
+const MAX_ARTIFACT_BYTES: u64 = 8 * 1024 * 1024;
+
+/// 実行言語
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    Python,
+    JavaScript,
+}
+
+impl Language {
+    fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "python" | "py" | "python3" => Some(Self::Python),
+            "javascript" | "js" | "node" => Some(Self::JavaScript),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Python => "python",
+            Self::JavaScript => "javascript",
+        }
+    }
+
+    /// 実行ファイル名（argv[0]）とスクリプトの拡張子
+    fn program(&self) -> (&'static str, &'static str) {
+        match self {
+            Self::Python => ("python", "py"),
+            Self::JavaScript => ("qjs", "js"),
+        }
+    }
+}
+
+/// 言語ランタイムの設定
+#[derive(Debug, Clone)]
+pub struct RuntimeSpec {
+    pub language: Language,
+    /// WASIモジュール（.wasm）のパス
+    pub module_path: PathBuf,
+    /// 読み取り専用で公開する標準ライブラリ（ホストパス, ゲストパス）
+    pub lib_dir: Option<(PathBuf, String)>,
+}
+
+/// 実行制限
+#[derive(Debug, Clone, Copy)]
+pub struct RunLimits {
+    pub fuel: u64,
+    pub memory_bytes: usize,
+}
+
+impl Default for RunLimits {
+    fn default() -> Self {
+        Self {
+            fuel: DEFAULT_FUEL,
+            memory_bytes: DEFAULT_MEMORY_MB * 1024 * 1024,
+        }
+    }
+}
+
+/// ストアに保持する状態
+struct RunState {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+}
+
+/// 実行結果
+struct RunOutput {
+    exit_code: i32,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    /// 異常終了の理由（燃料切れ・タイムアウトなど）
+    failure: Option<String>,
+}
+
+/// コード実行ツール
+pub struct RunCodeTool {
+    engine: Engine,
+    runtimes: HashMap<Language, RuntimeSpec>,
+    limits: RunLimits,
+    /// コンパイル済みモジュールのキャッシュ
+    modules: Arc<Mutex<HashMap<Language, Module>>>,
+}
+
+impl RunCodeTool {
+    pub fn new(runtimes: Vec<RuntimeSpec>, limits: RunLimits) -> Result<Self, String> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        config.epoch_interruption(true);
+        let engine = Engine::new(&config).map_err(|e| format!("Failed to create wasm engine: {}", e))?;
+
+        // 実行時間制限のためにエポックを定期的に進める
+        let ticker = engine.weak();
+        std::thread::Builder::new()
+            .name("run-code-epoch".to_string())
+            .spawn(move || {
+                while let Some(engine) = ticker.upgrade() {
+                    engine.increment_epoch();
+                    drop(engine);
+                    std::thread::sleep(EPOCH_TICK);
+                }
+            })
+            .map_err(|e| format!("Failed to start epoch thread: {}", e))?;
+
+        Ok(Self {
+            engine,
+            runtimes: runtimes.into_iter().map(|r| (r.language, r)).collect(),
+            limits,
+            modules: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// 環境変数から作成（ランタイムが1つも設定されていなければ None）
+    ///
+    /// - `RUN_CODE_PYTHON_WASM` / `RUN_CODE_PYTHON_LIB`
+    /// - `RUN_CODE_JS_WASM`
+    /// - `RUN_CODE_FUEL` / `RUN_CODE_MEMORY_MB`
+    pub fn from_env() -> Option<Result<Self, String>> {
+        let env_path = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+                .map(PathBuf::from)
+        };
+
+        let mut runtimes = Vec::new();
+        if let Some(module_path) = env_path("RUN_CODE_PYTHON_WASM") {
+            runtimes.push(RuntimeSpec {
+                language: Language::Python,
+                module_path,
+                lib_dir: env_path("RUN_CODE_PYTHON_LIB").map(|p| (p, "/usr/local/lib".to_string())),
+            });
+        }
+        if let Some(module_path) = env_path("RUN_CODE_JS_WASM") {
+            runtimes.push(RuntimeSpec {
+                language: Language::JavaScript,
+                module_path,
+                lib_dir: None,
+            });
+        }
+        if runtimes.is_empty() {
+            return None;
+        }
+
+        let limits = RunLimits {
+            fuel: std::env::var("RUN_CODE_FUEL")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_FUEL),
+            memory_bytes: std::env::var("RUN_CODE_MEMORY_MB")
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(DEFAULT_MEMORY_MB)
+                * 1024
+                * 1024,
+        };
+        Some(Self::new(runtimes, limits))
+    }
+
+    /// モジュールを取得（初回はコンパイルしてキャッシュ）
+    fn module(engine: &Engine, modules: &Mutex<HashMap<Language, Module>>, spec: &RuntimeSpec) -> Result<Module, String> {
+        if let Some(module) = modules.lock().unwrap().get(&spec.language) {
+            return Ok(module.clone());
+        }
+        info!("Compiling {} runtime from {:?}", spec.language.as_str(), spec.module_path);
+        let module = Module::from_file(engine, &spec.module_path).map_err(|e| {
+            warn!("Failed to compile {:?}: {:#}", spec.module_path, e);
+            format!("The {} runtime is not available", spec.language.as_str())
+        })?;
+        modules.lock().unwrap().insert(spec.language, module.clone());
+        Ok(module)
+    }
+
+    /// サンドボックス内でスクリプトを実行（ブロッキング）
+    fn run_blocking(
+        engine: &Engine,
+        module: &Module,
+        spec: &RuntimeSpec,
+        limits: RunLimits,
+        work_dir: &Path,
+        script_name: &str,
+        timeout_secs: u64,
+    ) -> Result<RunOutput, String> {
+        let stdout = MemoryOutputPipe::new(OUTPUT_CAPACITY);
+        let stderr = MemoryOutputPipe::new(OUTPUT_CAPACITY);
+
+        let (argv0, _) = spec.language.program();
+        let script = format!("{}/{}", GUEST_WORK_DIR, script_name);
+
+        let mut builder = WasiCtxBuilder::new();
+        builder
+            .args(&[argv0, script.as_str()])
+            .stdout(stdout.clone())
+            .stderr(stderr.clone())
+            .env("HOME", GUEST_WORK_DIR)
+            .env("PYTHONDONTWRITEBYTECODE", "1")
+            .allow_tcp(false)
+            .allow_udp(false)
+            .allow_ip_name_lookup(false)
+            .preopened_dir(work_dir, GUEST_WORK_DIR, DirPerms::all(), FilePerms::all())
+            .map_err(|e| format!("Failed to prepare sandbox: {}", e))?;
+        if let Some((host, guest)) = &spec.lib_dir {
+            builder
+                .preopened_dir(host, guest, DirPerms::READ, FilePerms::READ)
+                .map_err(|e| format!("Failed to prepare runtime library: {}", e))?;
+        }
+
+        let state = RunState {
+            wasi: builder.build_p1(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(limits.memory_bytes)
+                .instances(1)
+                .build(),
+        };
+        let mut store = Store::new(engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(limits.fuel).map_err(|e| e.to_string())?;
+        store.set_epoch_deadline(timeout_secs * 1000 / EPOCH_TICK.as_millis() as u64);
+
+        let mut linker: Linker<RunState> = Linker::new(engine);
+        preview1::add_to_linker_sync(&mut linker, |state: &mut RunState| &mut state.wasi)
+            .map_err(|e| format!("Failed to link WASI: {}", e))?;
+
+        let result = linker
+            .instantiate(&mut store, module)
+            .and_then(|instance| instance.get_typed_func::<(), ()>(&mut store, "_start"))
+            .and_then(|start| start.call(&mut store, ()));
+
+        let (exit_code, failure) = match result {
+            Ok(()) => (0, None),
+            Err(e) => {
+                if let Some(exit) = e.downcast_ref::<I32Exit>() {
+                    (exit.0, None)
+                } else if let Some(trap) = e.downcast_ref::<Trap>() {
+                    let reason = match trap {
+                        Trap::OutOfFuel => "CPU limit exceeded (out of fuel)".to_string(),
+                        Trap::Interrupt => format!("Time limit exceeded ({}s)", timeout_secs),
+                        Trap::StackOverflow => "Stack overflow".to_string(),
+                        other => format!("Runtime trap: {}", other),
+                    };
+                    (-1, Some(reason))
+                } else {
+                    debug!("run_code failed: {:#}", e);
+                    (-1, Some(format!("Execution failed: {}", e)))
+                }
+            }
+        };
+
+        Ok(RunOutput {
+            exit_code,
+            stdout: stdout.contents().to_vec(),
+            stderr: stderr.contents().to_vec(),
+            failure,
+        })
+    }
+
+    /// 出力を表示用に切り詰め
+    fn clip(bytes: &[u8]) -> String {
+        let text = String::from_utf8_lossy(bytes);
+        let text = text.trim_end();
+        if text.chars().count() > MAX_OUTPUT_CHARS {
+            let clipped: String = text.chars().take(MAX_OUTPUT_CHARS).collect();
+            format!("{}\n...(truncated)", clipped)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// 作業ディレクトリに生成されたファイルを収集（スクリプト自体は除く）
+    fn collect_artifacts(work_dir: &Path, script_name: &str) -> Vec<(String, Vec<u8>)> {
+        let mut artifacts = Vec::new();
+        let walker = ignore::WalkBuilder::new(work_dir)
+            .standard_filters(false)
+            .follow_links(false)
+            .max_depth(Some(4))
+            .build();
+        for entry in walker.flatten() {
+            let path = entry.path();
+            let Ok(relative) = path.strip_prefix(work_dir) else {
+                continue;
+            };
+            let name = relative.to_string_lossy().replace('\\', "/");
+            if name.is_empty() || name == script_name || !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            match std::fs::metadata(path) {
+                Ok(meta) if meta.len() <= MAX_ARTIFACT_BYTES => {
+                    if let Ok(data) = std::fs::read(path) {
+                        artifacts.push((name, data));
+                    }
+                }
+                Ok(meta) => warn!("Skipping large artifact {} ({} bytes)", name, meta.len()),
+                Err(_) => {}
+            }
+        }
+        artifacts.sort_by(|a, b| a.0.cmp(&b.0));
+        artifacts
+    }
+
+    /// 結果を整形し、生成ファイルを添付
+    fn format_output(output: &RunOutput, artifacts: Vec<(String, Vec<u8>)>, context: &ToolContext) -> String {
+        let mut text = match &output.failure {
+            Some(reason) => format!("Execution stopped: {}\n", reason),
+            None => format!("Exit code: {}\n", output.exit_code),
+        };
+
+        let stdout = Self::clip(&output.stdout);
+        let stderr = Self::clip(&output.stderr);
+        if !stdout.is_empty() {
+            text.push_str(&format!("\n--- stdout ---\n{}\n", stdout));
+        }
+        if !stderr.is_empty() {
+            text.push_str(&format!("\n--- stderr ---\n{}\n", stderr));
+        }
+        if stdout.is_empty() && stderr.is_empty() {
+            text.push_str("\n(no output)\n");
+        }
+
+        if !artifacts.is_empty() {
+            text.push_str("\nFiles:\n");
+            for (name, data) in artifacts {
+                let size = data.len();
+                let filename = name.rsplit('/').next().unwrap_or(&name).to_string();
+                let status = if context.add_attachment(filename, data) { "attached" } else { "not attached, attachment limit reached" };
+                text.push_str(&format!("- {} ({} bytes, {})\n", name, size, status));
+            }
+        }
+        text
+    }
+}
+
+#[async_trait]
+impl Tool for RunCodeTool {
+    fn name(&self) -> &str {
+        "run_code"
+    }
+
+    fn description(&self) -> &str {
+        "Run a Python or JavaScript snippet in an isolated WebAssembly sandbox and return stdout, stderr and any files it writes. No network access; the only writable directory is the current working directory /work. CPU, memory and time are limited. Use print()/console.log() to show results."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        let mut languages: Vec<&str> = self.runtimes.keys().map(|l| l.as_str()).collect();
+        languages.sort();
+        json!({
+            "type": "object",
+            "properties": {
+                "language": {
+                    "type": "string",
+                    "enum": languages,
+                    "description": "Programming language of the code"
+                },
+                "code": {
+                    "type": "string",
+                    "description": "Source code to run"
+                },
+                "timeout": {
+                    "type": "integer",
+                    "description": "Time limit in seconds (default: 10, max: 30)"
+                }
+            },
+            "required": ["language", "code"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let language_name = params["language"].as_str().ok_or_else(|| {
+            ToolError::InvalidParams("Missing 'language' parameter".to_string())
+        })?;
+        let code = params["code"].as_str().ok_or_else(|| {
+            ToolError::InvalidParams("Missing 'code' parameter".to_string())
+        })?;
+        let timeout_secs = params["timeout"].as_u64().unwrap_or(DEFAULT_TIMEOUT_SECS).clamp(1, MAX_TIMEOUT_SECS);
+
+        let language = Language::parse(language_name).ok_or_else(|| {
+            ToolError::InvalidParams(format!("Unsupported language: {}", language_name))
+        })?;
+        let Some(spec) = self.runtimes.get(&language).cloned() else {
+            return Ok(ToolResult::error(format!(
+                "The {} runtime is not configured on this bot",
+                language.as_str()
+            )));
+        };
+
+        // 実行ごとに空の作業ディレクトリを用意
+        let work_dir = std::env::temp_dir().join(format!("cc-bot-run-code-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&work_dir).map_err(|e| {
+            warn!("Failed to create work dir {:?}: {}", work_dir, e);
+            ToolError::ExecutionFailed("Failed to prepare sandbox".to_string())
+        })?;
+        let (_, extension) = language.program();
+        let script_name = format!("main.{}", extension);
+        if let Err(e) = std::fs::write(work_dir.join(&script_name), code) {
+            let _ = std::fs::remove_dir_all(&work_dir);
+            warn!("Failed to write script: {}", e);
+            return Err(ToolError::ExecutionFailed("Failed to prepare sandbox".to_string()));
+        }
+
+        info!("run_code ({}) for user {} ({} bytes)", language.as_str(), context.user_id, code.len());
+
+        let engine = self.engine.clone();
+        let modules = self.modules.clone();
+        let limits = self.limits;
+        let dir = work_dir.clone();
+        let script = script_name.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let module = Self::module(&engine, &modules, &spec)?;
+            let output = Self::run_blocking(&engine, &module, &spec, limits, &dir, &script, timeout_secs)?;
+            let artifacts = Self::collect_artifacts(&dir, &script);
+            Ok::<_, String>((output, artifacts))
+        })
+        .await;
+
+        if let Err(e) = std::fs::remove_dir_all(&work_dir) {
+            warn!("Failed to remove work dir {:?}: {}", work_dir, e);
+        }
+
+        match result {
+            Ok(Ok((output, artifacts))) => {
+                let text = Self::format_output(&output, artifacts, context);
+                if output.failure.is_none() && output.exit_code == 0 {
+                    Ok(ToolResult::success(text))
+                } else {
+                    Ok(ToolResult::error(text))
+                }
+            }
+            Ok(Err(message)) => Ok(ToolResult::error(message)),
+            Err(e) => Err(ToolError::ExecutionFailed(format!("Sandbox task failed: {}", e))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn create_test_context() -> ToolContext {
+        ToolContext::new(123, "test_user".to_string(), 456, "output".to_string())
+    }
+
+    /// 標準出力に書き込み、/work/out.txt を作成するWASIモジュール
+    const HELLO_WAT: &str = r#"
+(module
+  (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "path_open"
+    (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 16) "hello from wasm\n")
+  (data (i32.const 64) "out.txt")
+  (func (export "_start")
+    ;; iovec {ptr=16, len=16}
+    (i32.store (i32.const 0) (i32.const 16))
+    (i32.store (i32.const 4) (i32.const 16))
+    (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))
+    ;; /work（fd 3）に out.txt を作成して書き込む
+    (drop (call $path_open (i32.const 3) (i32.const 0) (i32.const 64) (i32.const 7)
+      (i32.const 1) (i64.const 64) (i64.const 0) (i32.const 0) (i32.const 12)))
+    (drop (call $fd_write (i32.load (i32.const 12)) (i32.const 0) (i32.const 1) (i32.const 8)))))
+"#;
+
+    /// 無限ループするモジュール
+    const LOOP_WAT: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "_start") (loop $l (br $l))))
+"#;
+
+    /// 指定コードで終了するモジュール
+    const EXIT_WAT: &str = r#"
+(module
+  (import "wasi_snapshot_preview1" "proc_exit" (func $exit (param i32)))
+  (memory (export "memory") 1)
+  (func (export "_start") (call $exit (i32.const 3))))
+"#;
+
+    fn tool_with(wat: &str, limits: RunLimits) -> (TempDir, RunCodeTool) {
+        let dir = TempDir::new().unwrap();
+        let module_path = dir.path().join("runtime.wat");
+        std::fs::write(&module_path, wat).unwrap();
+        let tool = RunCodeTool::new(
+            vec![RuntimeSpec {
+                language: Language::Python,
+                module_path,
+                lib_dir: None,
+            }],
+            limits,
+        )
+        .unwrap();
+        (dir, tool)
+    }
+
+    #[tokio::test]
+    async fn test_run_captures_stdout_and_files() {
+        let (_dir, tool) = tool_with(HELLO_WAT, RunLimits::default());
+        let ctx = create_test_context();
+
+        let result = tool
+            .execute(json!({"language": "python", "code": "print('hello')"}), &ctx)
+            .await
+            .unwrap();
+
+        assert!(!result.is_error, "{}", result.output);
+        assert!(result.output.contains("Exit code: 0"));
+        assert!(result.output.contains("--- stdout ---\nhello from wasm"));
+        assert!(result.output.contains("- out.txt (16 bytes, attached)"));
+        let attachments = ctx.take_attachments();
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(attachments[0].data, b"hello from wasm\n");
+    }
+
+    #[tokio::test]
+    async fn test_fuel_limit_stops_infinite_loop() {
+        let limits = RunLimits {
+            fuel: 1_000_000,
+            ..RunLimits::default()
+        };
+        let (_dir, tool) = tool_with(LOOP_WAT, limits);
+
+        let result = tool
+            .execute(json!({"language": "python", "code": "while True: pass"}), &create_test_context())
+            .await
+            .unwrap();
+
+        assert!(result.is_error);
+        assert!(result.output.contains("out of fuel"), "{}", result.output);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_stops_execution() {
+        let limits = RunLimits {
+            fuel: u64::MAX,
+            ..RunLimits::default()
+        };
+        let (_dir, tool) = tool_with(LOOP_WAT, limits);
+
+        let result = tool
+            .execute(json!({"language": "python", "code": "", "timeout": 1}), &create_test_context())
+            .await
+            .unwrap();
+
+        assert!(result.output.contains("Time limit exceeded (1s)"), "{}", result.output);
+    }
+
+    #[tokio::test]
+    async fn test_exit_code_and_unconfigured_language() {
+        let (_dir, tool) = tool_with(EXIT_WAT, RunLimits::default());
+        let ctx = create_test_context();
+
+        let result = tool.execute(json!({"language": "py", "code": ""}), &ctx).await.unwrap();
+        assert!(result.is_error);
+        assert!(result.output.contains("Exit code: 3"));
+
+        let result = tool.execute(json!({"language": "javascript", "code": ""}), &ctx).await.unwrap();
+        assert!(result.is_error);
+        assert!(result.output.contains("not configured"));
+
+        let result = tool.execute(json!({"language": "ruby", "code": ""}), &ctx).await;
+        assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+    }
+}
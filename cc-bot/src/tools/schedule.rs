@@ -0,0 +1,584 @@
+//! スケジュールツール
+//!
+//! LLM が自分でリマインダーや定期実行を登録・確認・取り消しできるように、
+//! `Scheduler` と `ScheduleStore` を操作するツールを提供します。
+//! タスクは呼び出し元のチャンネルとユーザーに紐付けられ、
+//! `Permission::Schedule` とユーザーごとの上限数を適用します。
+//! TODO の期限リマインダーは TODO ツールが管理するため、一覧・取り消し・上限の対象外です。
+
+use crate::permission::{Permission, PermissionManager};
+use crate::schedule_store::ScheduleStore;
+use crate::scheduler::{ScheduleId, ScheduledTask, Scheduler, TaskKind};
+use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
+use async_trait::async_trait;
+use crate::datetime_utils::{parse_datetime, parse_delay};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value as JsonValue};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{error, info};
+
+/// ユーザーあたりのデフォルト最大スケジュール数
+const DEFAULT_MAX_SCHEDULES_PER_USER: usize = 10;
+/// プロンプトの最大文字数
+const MAX_PROMPT_CHARS: usize = 2000;
+/// 一覧で表示するプロンプトの最大文字数
+const MAX_LIST_PROMPT_CHARS: usize = 80;
+/// ID前方一致に必要な最小文字数
+const MIN_ID_PREFIX_LEN: usize = 4;
+
+/// スケジュールツール共通の状態
+pub struct ScheduleAccess {
+    scheduler: Arc<Scheduler>,
+    store: Arc<RwLock<ScheduleStore>>,
+    permissions: Arc<RwLock<PermissionManager>>,
+    data_dir: String,
+    max_per_user: usize,
+}
+
+impl ScheduleAccess {
+    pub fn new(
+        scheduler: Arc<Scheduler>,
+        store: Arc<RwLock<ScheduleStore>>,
+        permissions: Arc<RwLock<PermissionManager>>,
+    ) -> Self {
+        Self {
+            scheduler,
+            store,
+            permissions,
+            data_dir: "data".to_string(),
+            max_per_user: DEFAULT_MAX_SCHEDULES_PER_USER,
+        }
+    }
+
+    /// 環境変数 MAX_SCHEDULES_PER_USER から上限数を設定
+    pub fn with_env_limit(self) -> Self {
+        match std::env::var("MAX_SCHEDULES_PER_USER").ok().and_then(|v| v.parse().ok()) {
+            Some(max) => self.with_max_per_user(max),
+            None => self,
+        }
+    }
+
+    /// ユーザーあたりの最大スケジュール数を設定
+    pub fn with_max_per_user(mut self, max: usize) -> Self {
+        self.max_per_user = max;
+        self
+    }
+
+    /// 保存先ディレクトリを設定（テスト用）
+    #[cfg(test)]
+    pub(crate) fn with_data_dir(mut self, data_dir: impl Into<String>) -> Self {
+        self.data_dir = data_dir.into();
+        self
+    }
+
+    /// Schedule 権限を確認
+    async fn check_permission(&self, context: &ToolContext) -> Result<(), ToolError> {
+        // ユーザー不明のシステム実行からは登録させない
+        if context.user_id == 0 {
+            return Err(ToolError::PermissionDenied(
+                "Schedules can only be managed on behalf of a user".to_string(),
+            ));
+        }
+        if !self.permissions.read().await.has_permission(context.user_id, &Permission::Schedule) {
+            return Err(ToolError::PermissionDenied(
+                "You do not have the Schedule permission".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 他ユーザーのスケジュールも操作できるか（管理者・スーパーユーザー）
+    async fn can_manage_all(&self, user_id: u64) -> bool {
+        let permissions = self.permissions.read().await;
+        permissions.is_admin(user_id) || permissions.has_permission(user_id, &Permission::SuperUser)
+    }
+
+    /// ユーザーが作成したタスク一覧（TODOリマインダーを除く）
+    async fn user_tasks(&self, user_id: u64) -> Vec<ScheduledTask> {
+        self.scheduler
+            .list_tasks()
+            .await
+            .into_iter()
+            .filter(|t| t.user_id == Some(user_id) && t.kind == TaskKind::Schedule)
+            .collect()
+    }
+}
+
+/// cron式を正規化（標準の5フィールド形式なら秒フィールドを補う）
+fn normalize_cron(expr: &str) -> String {
+    let fields: Vec<&str> = expr.split_whitespace().collect();
+    if fields.len() == 5 {
+        format!("0 {}", fields.join(" "))
+    } else {
+        fields.join(" ")
+    }
+}
+
+/// 日時を表示用に整形
+fn format_time(time: Option<DateTime<Utc>>) -> String {
+    time.map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// タスクの種類を表示用に整形
+fn describe_kind(task: &ScheduledTask) -> String {
+    if task.is_one_shot() {
+        "once".to_string()
+    } else {
+        format!("cron `{}`", task.cron_expression)
+    }
+}
+
+/// 文字数で切り詰め
+fn truncate_chars(text: &str, max: usize) -> String {
+    if text.chars().count() > max {
+        let truncated: String = text.chars().take(max).collect();
+        format!("{}…", truncated)
+    } else {
+        text.to_string()
+    }
+}
+
+/// スケジュール作成ツール
+pub struct ScheduleCreateTool {
+    access: Arc<ScheduleAccess>,
+}
+
+impl ScheduleCreateTool {
+    pub fn new(access: Arc<ScheduleAccess>) -> Self {
+        Self { access }
+    }
+}
+
+#[async_trait]
+impl Tool for ScheduleCreateTool {
+    fn name(&self) -> &str {
+        "schedule_create"
+    }
+
+    fn description(&self) -> &str {
+        "Schedule a prompt to be run later in the current channel on behalf of the current user. \
+         Specify exactly one of: run_at (one-shot at a time), delay (one-shot after a delay) or \
+         cron (recurring, evaluated in UTC). When it fires, the prompt is sent to the assistant \
+         and the answer is posted to this channel."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "prompt": {
+                    "type": "string",
+                    "description": "Prompt to run when the schedule fires (e.g. 'Remind me to review the PR')"
+                },
+                "run_at": {
+                    "type": "string",
+                    "description": "One-shot time: RFC3339 (2026-10-20T09:00:00+09:00) or 'YYYY-MM-DD HH:MM' in server local time"
+                },
+                "delay": {
+                    "type": "string",
+                    "description": "One-shot delay from now: e.g. '30m', '2h', '1d'"
+                },
+                "cron": {
+                    "type": "string",
+                    "description": "Recurring cron expression in UTC: 5 fields (min hour day month weekday) or 6 fields with seconds; use names for weekdays (MON-FRI)"
+                }
+            },
+            "required": ["prompt"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let prompt = params["prompt"]
+            .as_str()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .ok_or_else(|| ToolError::InvalidParams("prompt is required".to_string()))?;
+        if prompt.chars().count() > MAX_PROMPT_CHARS {
+            return Err(ToolError::InvalidParams(format!(
+                "prompt is too long (max {} characters)",
+                MAX_PROMPT_CHARS
+            )));
+        }
+
+        let run_at = params["run_at"].as_str().filter(|v| !v.trim().is_empty());
+        let delay = params["delay"].as_str().filter(|v| !v.trim().is_empty());
+        let cron = params["cron"].as_str().filter(|v| !v.trim().is_empty());
+        if [run_at, delay, cron].iter().filter(|v| v.is_some()).count() != 1 {
+            return Err(ToolError::InvalidParams(
+                "Specify exactly one of run_at, delay or cron".to_string(),
+            ));
+        }
+
+        self.access.check_permission(context).await?;
+
+        let existing = self.access.user_tasks(context.user_id).await.len();
+        if existing >= self.access.max_per_user {
+            return Ok(ToolResult::error(format!(
+                "Schedule limit reached ({} of {}). Cancel an existing schedule first.",
+                existing, self.access.max_per_user
+            )));
+        }
+
+        let task = if let Some(cron) = cron {
+            ScheduledTask::new(normalize_cron(cron), prompt.to_string(), context.channel_id)
+        } else {
+            let time = match (run_at, delay) {
+                (Some(value), _) => parse_datetime(value).ok_or_else(|| {
+                    ToolError::InvalidParams(format!("Invalid run_at: {}", value))
+                })?,
+                (_, Some(value)) => {
+                    let duration = parse_delay(value)
+                        .ok_or_else(|| ToolError::InvalidParams(format!("Invalid delay: {}", value)))?;
+                    Utc::now() + duration
+                }
+                _ => unreachable!("one of run_at or delay is set"),
+            };
+            ScheduledTask::once(time, prompt.to_string(), context.channel_id)
+        };
+        let task = match task {
+            Ok(task) => task.with_user(context.user_id).with_guild(context.guild_id),
+            Err(e) => return Err(ToolError::InvalidParams(e.to_string())),
+        };
+
+        let id = task.id;
+        let summary = format!(
+            "Scheduled `{}` ({}), next run: {}",
+            id,
+            describe_kind(&task),
+            format_time(task.next_run())
+        );
+
+        self.access.scheduler.add_task(task.clone()).await;
+        {
+            let mut store = self.access.store.write().await;
+            store.add_task(task);
+            if let Err(e) = store.save(&self.access.data_dir).await {
+                error!("Failed to save schedule: {}", e);
+                return Ok(ToolResult::success(format!(
+                    "{}\nWarning: failed to persist the schedule, it will be lost on restart: {}",
+                    summary, e
+                )));
+            }
+        }
+
+        info!("User {} created schedule {} in channel {}", context.user_id, id, context.channel_id);
+        Ok(ToolResult::success(summary))
+    }
+}
+
+/// スケジュール一覧ツール
+pub struct ScheduleListTool {
+    access: Arc<ScheduleAccess>,
+}
+
+impl ScheduleListTool {
+    pub fn new(access: Arc<ScheduleAccess>) -> Self {
+        Self { access }
+    }
+}
+
+#[async_trait]
+impl Tool for ScheduleListTool {
+    fn name(&self) -> &str {
+        "schedule_list"
+    }
+
+    fn description(&self) -> &str {
+        "List the schedules created by the current user, with their IDs and next run times."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {}
+        })
+    }
+
+    async fn execute(&self, _params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        self.access.check_permission(context).await?;
+
+        let mut tasks = self.access.user_tasks(context.user_id).await;
+        if tasks.is_empty() {
+            return Ok(ToolResult::success("You have no schedules."));
+        }
+        tasks.sort_by_key(|t| t.next_run());
+
+        let lines: Vec<String> = tasks
+            .iter()
+            .map(|t| {
+                format!(
+                    "- `{}` {} | next: {} | channel: <#{}>{} | {}",
+                    t.id,
+                    describe_kind(t),
+                    format_time(t.next_run()),
+                    t.channel_id,
+                    if t.enabled { "" } else { " | disabled" },
+                    truncate_chars(&t.prompt, MAX_LIST_PROMPT_CHARS)
+                )
+            })
+            .collect();
+
+        Ok(ToolResult::success(format!(
+            "Your schedules ({} of {}):\n{}",
+            tasks.len(),
+            self.access.max_per_user,
+            lines.join("\n")
+        )))
+    }
+}
+
+/// スケジュール取り消しツール
+pub struct ScheduleCancelTool {
+    access: Arc<ScheduleAccess>,
+}
+
+impl ScheduleCancelTool {
+    pub fn new(access: Arc<ScheduleAccess>) -> Self {
+        Self { access }
+    }
+
+    /// ID（または一意な前方一致）からタスクを特定
+    async fn resolve(&self, id: &str) -> Result<ScheduledTask, String> {
+        let id = id.trim().trim_matches('`').to_lowercase();
+        if id.len() < MIN_ID_PREFIX_LEN {
+            return Err(format!("Schedule ID must be at least {} characters", MIN_ID_PREFIX_LEN));
+        }
+
+        let matches: Vec<ScheduledTask> = self
+            .access
+            .scheduler
+            .list_tasks()
+            .await
+            .into_iter()
+            .filter(|t| t.kind == TaskKind::Schedule && t.id.to_string().starts_with(&id))
+            .collect();
+        match matches.len() {
+            0 => Err(format!("Schedule not found: {}", id)),
+            1 => Ok(matches.into_iter().next().unwrap()),
+            n => Err(format!("{} schedules match '{}', use a longer ID", n, id)),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ScheduleCancelTool {
+    fn name(&self) -> &str {
+        "schedule_cancel"
+    }
+
+    fn description(&self) -> &str {
+        "Cancel one of the current user's schedules by ID (a unique prefix of the ID is enough)."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "id": {
+                    "type": "string",
+                    "description": "Schedule ID from schedule_list"
+                }
+            },
+            "required": ["id"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let id = params["id"]
+            .as_str()
+            .ok_or_else(|| ToolError::InvalidParams("id is required".to_string()))?;
+
+        self.access.check_permission(context).await?;
+
+        let task = match self.resolve(id).await {
+            Ok(task) => task,
+            Err(e) => return Ok(ToolResult::error(e)),
+        };
+
+        // 自分のスケジュールのみ（作成者不明のものは管理者のみ）
+        if task.user_id != Some(context.user_id) && !self.access.can_manage_all(context.user_id).await {
+            return Err(ToolError::PermissionDenied(
+                "You can only cancel your own schedules".to_string(),
+            ));
+        }
+
+        let id: ScheduleId = task.id;
+        if let Err(e) = self.access.scheduler.remove_task(id).await {
+            return Ok(ToolResult::error(e.to_string()));
+        }
+        {
+            let mut store = self.access.store.write().await;
+            store.remove_task(id);
+            if let Err(e) = store.save(&self.access.data_dir).await {
+                error!("Failed to save after cancel: {}", e);
+                return Ok(ToolResult::success(format!(
+                    "Cancelled `{}`\nWarning: failed to persist the change: {}",
+                    id, e
+                )));
+            }
+        }
+
+        info!("User {} cancelled schedule {}", context.user_id, id);
+        Ok(ToolResult::success(format!(
+            "Cancelled `{}` ({}): {}",
+            id,
+            describe_kind(&task),
+            truncate_chars(&task.prompt, MAX_LIST_PROMPT_CHARS)
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn test_access(dir: &TempDir, max_per_user: usize) -> Arc<ScheduleAccess> {
+        Arc::new(
+            ScheduleAccess::new(
+                Arc::new(Scheduler::new()),
+                Arc::new(RwLock::new(ScheduleStore::new())),
+                Arc::new(RwLock::new(PermissionManager::new())),
+            )
+            .with_data_dir(dir.path().to_string_lossy().to_string())
+            .with_max_per_user(max_per_user),
+        )
+    }
+
+    fn context(user_id: u64) -> ToolContext {
+        ToolContext::new(user_id, "test_user".to_string(), 456, "output".to_string())
+    }
+
+    #[test]
+    fn test_normalize_cron() {
+        assert_eq!(normalize_cron("30 9 * * MON-FRI"), "0 30 9 * * MON-FRI");
+        assert_eq!(normalize_cron("0 0  9 * * *"), "0 0 9 * * *");
+    }
+
+    #[tokio::test]
+    async fn test_create_list_cancel() {
+        let dir = TempDir::new().unwrap();
+        let access = test_access(&dir, 10);
+        let create = ScheduleCreateTool::new(access.clone());
+        let list = ScheduleListTool::new(access.clone());
+        let cancel = ScheduleCancelTool::new(access.clone());
+        let ctx = context(42).with_guild_id(Some(789));
+
+        let result = create
+            .execute(json!({"prompt": "Stand-up reminder", "cron": "0 9 * * MON-FRI"}), &ctx)
+            .await
+            .unwrap();
+        assert!(!result.is_error, "{}", result.output);
+        let result = create
+            .execute(json!({"prompt": "Check the deploy", "delay": "2h"}), &ctx)
+            .await
+            .unwrap();
+        assert!(!result.is_error, "{}", result.output);
+
+        let tasks = access.user_tasks(42).await;
+        assert_eq!(tasks.len(), 2);
+        assert!(tasks.iter().all(|t| t.channel_id == 456 && t.guild_id == Some(789)));
+        let one_shot = tasks.iter().find(|t| t.is_one_shot()).unwrap().clone();
+
+        // 永続化されている
+        let stored = ScheduleStore::load(&dir.path().to_string_lossy()).await.unwrap();
+        assert_eq!(stored.tasks.len(), 2);
+
+        let result = list.execute(json!({}), &ctx).await.unwrap();
+        assert!(result.output.contains("Stand-up reminder"));
+        assert!(result.output.contains("cron `0 0 9 * * MON-FRI`"));
+
+        let prefix = &one_shot.id.to_string()[..8];
+        let result = cancel.execute(json!({"id": prefix}), &ctx).await.unwrap();
+        assert!(!result.is_error, "{}", result.output);
+        assert_eq!(access.user_tasks(42).await.len(), 1);
+        let stored = ScheduleStore::load(&dir.path().to_string_lossy()).await.unwrap();
+        assert_eq!(stored.tasks.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_create_validation_and_limit() {
+        let dir = TempDir::new().unwrap();
+        let access = test_access(&dir, 1);
+        let create = ScheduleCreateTool::new(access.clone());
+        let ctx = context(42);
+
+        // 指定は1つだけ
+        let result = create
+            .execute(json!({"prompt": "x", "delay": "1h", "cron": "0 9 * * *"}), &ctx)
+            .await;
+        assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+        // 過去の時刻
+        let result = create
+            .execute(json!({"prompt": "x", "run_at": "2000-01-01T00:00:00Z"}), &ctx)
+            .await;
+        assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+        // システム実行（ユーザー不明）
+        let result = create.execute(json!({"prompt": "x", "delay": "1h"}), &context(0)).await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+
+        let result = create.execute(json!({"prompt": "x", "delay": "1h"}), &ctx).await.unwrap();
+        assert!(!result.is_error);
+        let result = create.execute(json!({"prompt": "y", "delay": "1h"}), &ctx).await.unwrap();
+        assert!(result.is_error);
+        assert!(result.output.contains("limit"));
+
+        // 上限はユーザーごと
+        let result = create.execute(json!({"prompt": "z", "delay": "1h"}), &context(7)).await.unwrap();
+        assert!(!result.is_error);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_other_users_schedule_denied() {
+        let dir = TempDir::new().unwrap();
+        let access = test_access(&dir, 10);
+        let create = ScheduleCreateTool::new(access.clone());
+        let cancel = ScheduleCancelTool::new(access.clone());
+
+        create
+            .execute(json!({"prompt": "mine", "delay": "1h"}), &context(42))
+            .await
+            .unwrap();
+        let id = access.user_tasks(42).await[0].id.to_string();
+
+        let result = cancel.execute(json!({"id": id}), &context(7)).await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+        assert_eq!(access.user_tasks(42).await.len(), 1);
+
+        // 他人の一覧には出ない
+        let list = ScheduleListTool::new(access.clone());
+        let result = list.execute(json!({}), &context(7)).await.unwrap();
+        assert!(result.output.contains("no schedules"));
+    }
+
+    #[tokio::test]
+    async fn test_todo_reminders_are_excluded() {
+        let dir = TempDir::new().unwrap();
+        let access = test_access(&dir, 1);
+        let reminder = ScheduledTask::once(Utc::now() + chrono::Duration::hours(1), "todo".to_string(), 456)
+            .unwrap()
+            .with_user(42)
+            .with_kind(TaskKind::TodoReminder);
+        access.scheduler.add_task(reminder.clone()).await;
+        let ctx = context(42);
+
+        // 上限に数えず、一覧にも出ない
+        let result = ScheduleListTool::new(access.clone()).execute(json!({}), &ctx).await.unwrap();
+        assert!(result.output.contains("no schedules"));
+        let result = ScheduleCreateTool::new(access.clone())
+            .execute(json!({"prompt": "x", "delay": "1h"}), &ctx)
+            .await
+            .unwrap();
+        assert!(!result.is_error, "{}", result.output);
+
+        // 取り消しもできない
+        let result = ScheduleCancelTool::new(access.clone())
+            .execute(json!({"id": reminder.id.to_string()}), &ctx)
+            .await
+            .unwrap();
+        assert!(result.is_error);
+        assert_eq!(access.scheduler.list_tasks().await.len(), 2);
+    }
+}
@@ -0,0 +1,690 @@
+//! 永続シェルセッションとバックグラウンドジョブ管理
+//!
+//! `bash` ツールの呼び出し間で `cd` や環境変数を保持するための
+//! セッションごとの常駐シェルと、バックグラウンド実行ジョブを管理します。
+
+use crate::session::SessionKey;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command as TokioCommand};
+use tokio::sync::{oneshot, Mutex};
+use tokio::time::timeout;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// 永続シェルのデフォルトアイドルタイムアウト（10分）
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// ユーザーあたりの同時実行バックグラウンドジョブ上限
+const MAX_RUNNING_JOBS_PER_USER: usize = 5;
+
+/// ユーザーあたりの保持ジョブ数上限（完了済みを含む）
+const MAX_RETAINED_JOBS_PER_USER: usize = 20;
+
+/// ジョブ出力バッファの上限（バイト、末尾を保持）
+const MAX_JOB_OUTPUT_BYTES: usize = 64 * 1024;
+
+/// シェルコマンド実行結果
+#[derive(Debug, Clone)]
+pub struct ShellOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: i32,
+}
+
+impl ShellOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == 0
+    }
+}
+
+/// ワンショット実行用のシェルコマンドを構築
+pub fn build_shell_command(command: &str, working_dir: &str) -> TokioCommand {
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut cmd = TokioCommand::new("powershell");
+        cmd.args(["-Command", command]);
+        cmd
+    };
+
+    #[cfg(not(windows))]
+    let mut cmd = {
+        let mut cmd = TokioCommand::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    };
+
+    cmd.current_dir(working_dir);
+    cmd
+}
+
+/// プロセスグループごと強制終了（シェルが起動した子プロセスも止める）
+fn kill_process_group(child: &mut Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // SAFETY: 自分が起動したプロセスグループにシグナルを送るだけ
+        unsafe {
+            libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+        }
+    }
+    let _ = child.start_kill();
+}
+
+/// 常駐シェルプロセス
+struct PersistentShell {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    stderr: BufReader<ChildStderr>,
+    last_used: Instant,
+}
+
+impl PersistentShell {
+    /// 作業ディレクトリを指定してシェルを起動
+    fn spawn(working_dir: &str) -> Result<Self, String> {
+        let mut cmd = TokioCommand::new("sh");
+        // 独立したプロセスグループで起動し、終了時にグループごと止められるようにする
+        #[cfg(unix)]
+        cmd.process_group(0);
+        let mut child = cmd
+            .current_dir(working_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to start shell session: {}", e))?;
+
+        let stdin = child.stdin.take().ok_or("Failed to open shell stdin")?;
+        let stdout = child.stdout.take().ok_or("Failed to open shell stdout")?;
+        let stderr = child.stderr.take().ok_or("Failed to open shell stderr")?;
+
+        Ok(Self {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+            stderr: BufReader::new(stderr),
+            last_used: Instant::now(),
+        })
+    }
+
+    /// センチネル付きでコマンドを送信し、出力境界まで読み取る
+    async fn run(&mut self, command: &str) -> Result<ShellOutput, String> {
+        let sentinel = format!("__CC_BOT_DONE_{}__", Uuid::new_v4().simple());
+
+        // 標準入力は /dev/null に向け、シェルの入力ストリームを消費させない
+        let script = format!(
+            "{{\n{}\n}} < /dev/null\nprintf '\\n{}:%d\\n' \"$?\"\nprintf '\\n{}\\n' >&2\n",
+            command, sentinel, sentinel
+        );
+
+        self.stdin
+            .write_all(script.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write to shell session: {}", e))?;
+        self.stdin
+            .flush()
+            .await
+            .map_err(|e| format!("Failed to write to shell session: {}", e))?;
+
+        let (stdout_result, stderr_result) = tokio::join!(
+            read_until_sentinel(&mut self.stdout, &sentinel),
+            read_until_sentinel(&mut self.stderr, &sentinel),
+        );
+
+        let (stdout, status_line) = stdout_result?;
+        let (stderr, _) = stderr_result?;
+
+        let exit_code = status_line
+            .rsplit(':')
+            .next()
+            .and_then(|code| code.trim().parse::<i32>().ok())
+            .unwrap_or(-1);
+
+        self.last_used = Instant::now();
+
+        Ok(ShellOutput {
+            stdout,
+            stderr,
+            exit_code,
+        })
+    }
+}
+
+impl Drop for PersistentShell {
+    fn drop(&mut self) {
+        kill_process_group(&mut self.child);
+    }
+}
+
+/// センチネル行が現れるまで読み取り、(出力, センチネル行) を返す
+async fn read_until_sentinel<R>(reader: &mut BufReader<R>, sentinel: &str) -> Result<(String, String), String>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut output = String::new();
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        let read = reader
+            .read_line(&mut line)
+            .await
+            .map_err(|e| format!("Failed to read from shell session: {}", e))?;
+
+        if read == 0 {
+            return Err("Shell session exited".to_string());
+        }
+
+        if line.trim_end().starts_with(sentinel) {
+            // センチネル直前に挿入した改行と末尾の改行を取り除く
+            let trimmed_len = output.trim_end_matches('\n').len();
+            output.truncate(trimmed_len);
+            return Ok((output, line.trim_end().to_string()));
+        }
+
+        output.push_str(&line);
+    }
+}
+
+/// バックグラウンドジョブの状態
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    Running,
+    Exited(i32),
+    Killed,
+    Failed(String),
+}
+
+impl std::fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobStatus::Running => write!(f, "running"),
+            JobStatus::Exited(code) => write!(f, "exited ({})", code),
+            JobStatus::Killed => write!(f, "killed"),
+            JobStatus::Failed(e) => write!(f, "failed ({})", e),
+        }
+    }
+}
+
+/// バックグラウンドジョブのスナップショット
+#[derive(Debug, Clone)]
+pub struct JobSnapshot {
+    pub id: String,
+    pub command: String,
+    pub started_at: DateTime<Utc>,
+    pub status: JobStatus,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// バックグラウンドジョブ
+struct BackgroundJob {
+    id: String,
+    user_id: u64,
+    command: String,
+    started_at: DateTime<Utc>,
+    status: Arc<Mutex<JobStatus>>,
+    stdout: Arc<Mutex<String>>,
+    stderr: Arc<Mutex<String>>,
+    kill_tx: Option<oneshot::Sender<()>>,
+}
+
+impl BackgroundJob {
+    async fn snapshot(&self) -> JobSnapshot {
+        JobSnapshot {
+            id: self.id.clone(),
+            command: self.command.clone(),
+            started_at: self.started_at,
+            status: self.status.lock().await.clone(),
+            stdout: self.stdout.lock().await.clone(),
+            stderr: self.stderr.lock().await.clone(),
+        }
+    }
+}
+
+/// 出力をバッファへ転送（上限を超えた分は先頭から捨てる）
+async fn pump_output<R>(mut reader: R, buffer: Arc<Mutex<String>>)
+where
+    R: AsyncRead + Unpin,
+{
+    let mut chunk = [0u8; 4096];
+    loop {
+        match reader.read(&mut chunk).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                let mut buf = buffer.lock().await;
+                buf.push_str(&String::from_utf8_lossy(&chunk[..n]));
+                if buf.len() > MAX_JOB_OUTPUT_BYTES {
+                    let mut cut = buf.len() - MAX_JOB_OUTPUT_BYTES;
+                    while !buf.is_char_boundary(cut) {
+                        cut += 1;
+                    }
+                    buf.drain(..cut);
+                }
+            }
+        }
+    }
+}
+
+/// 永続シェルセッションとバックグラウンドジョブのマネージャー
+pub struct ShellSessionManager {
+    shells: Mutex<HashMap<SessionKey, Arc<Mutex<PersistentShell>>>>,
+    jobs: Mutex<Vec<BackgroundJob>>,
+    idle_timeout: Duration,
+}
+
+impl ShellSessionManager {
+    pub fn new(idle_timeout: Duration) -> Self {
+        Self {
+            shells: Mutex::new(HashMap::new()),
+            jobs: Mutex::new(Vec::new()),
+            idle_timeout,
+        }
+    }
+
+    /// アイドルタイムアウトを超えたシェルを終了
+    async fn cleanup_idle_shells(&self) {
+        let mut shells = self.shells.lock().await;
+        let idle_timeout = self.idle_timeout;
+        let before = shells.len();
+
+        // 実行中（ロック中）のシェルは対象外
+        shells.retain(|key, shell| match shell.try_lock() {
+            Ok(shell) => {
+                let keep = shell.last_used.elapsed() < idle_timeout;
+                if !keep {
+                    info!(
+                        "Closing idle shell session for user {} in channel {}",
+                        key.user_id, key.channel_id
+                    );
+                }
+                keep
+            }
+            Err(_) => true,
+        });
+
+        let removed = before - shells.len();
+        if removed > 0 {
+            debug!("Cleaned up {} idle shell sessions", removed);
+        }
+    }
+
+    /// 永続シェルでコマンドを実行
+    ///
+    /// タイムアウトやシェル終了時はセッションを破棄し、次回呼び出しで再作成する。
+    pub async fn run_persistent(
+        &self,
+        key: SessionKey,
+        command: &str,
+        timeout_secs: u64,
+        working_dir: &str,
+    ) -> Result<ShellOutput, String> {
+        if cfg!(windows) {
+            return Err("Persistent shell sessions are not supported on Windows".to_string());
+        }
+
+        self.cleanup_idle_shells().await;
+
+        let shell = {
+            let mut shells = self.shells.lock().await;
+            match shells.get(&key) {
+                Some(shell) => shell.clone(),
+                None => {
+                    debug!(
+                        "Starting persistent shell for user {} in channel {}",
+                        key.user_id, key.channel_id
+                    );
+                    let shell = Arc::new(Mutex::new(PersistentShell::spawn(working_dir)?));
+                    shells.insert(key.clone(), shell.clone());
+                    shell
+                }
+            }
+        };
+
+        let result = {
+            let mut shell = shell.lock().await;
+            timeout(Duration::from_secs(timeout_secs), shell.run(command)).await
+        };
+
+        match result {
+            Ok(Ok(output)) => Ok(output),
+            Ok(Err(e)) => {
+                self.close_session(&key).await;
+                Err(format!("{}; the shell session was reset", e))
+            }
+            Err(_) => {
+                self.close_session(&key).await;
+                Err(format!(
+                    "Command timed out after {} seconds; the shell session was reset",
+                    timeout_secs
+                ))
+            }
+        }
+    }
+
+    /// セッションの永続シェルを終了
+    pub async fn close_session(&self, key: &SessionKey) -> bool {
+        let removed = self.shells.lock().await.remove(key);
+        if let Some(shell) = removed {
+            // 実行中で取得できない場合は最後の参照が破棄された時点で Drop が止める
+            if let Ok(mut shell) = shell.try_lock() {
+                kill_process_group(&mut shell.child);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// バックグラウンドジョブを開始し、ジョブIDを返す
+    pub async fn spawn_job(&self, user_id: u64, command: &str, working_dir: &str) -> Result<String, String> {
+        let mut jobs = self.jobs.lock().await;
+
+        let mut running = 0;
+        for job in jobs.iter().filter(|j| j.user_id == user_id) {
+            if *job.status.lock().await == JobStatus::Running {
+                running += 1;
+            }
+        }
+        if running >= MAX_RUNNING_JOBS_PER_USER {
+            return Err(format!(
+                "Too many running background jobs (max {})",
+                MAX_RUNNING_JOBS_PER_USER
+            ));
+        }
+
+        let mut cmd = build_shell_command(command, working_dir);
+        #[cfg(unix)]
+        cmd.process_group(0);
+        let mut child = cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|_| "Failed to execute command. Please check the command syntax.".to_string())?;
+
+        let id = Uuid::new_v4().simple().to_string()[..8].to_string();
+        let status = Arc::new(Mutex::new(JobStatus::Running));
+        let stdout = Arc::new(Mutex::new(String::new()));
+        let stderr = Arc::new(Mutex::new(String::new()));
+
+        if let Some(out) = child.stdout.take() {
+            tokio::spawn(pump_output(out, stdout.clone()));
+        }
+        if let Some(err) = child.stderr.take() {
+            tokio::spawn(pump_output(err, stderr.clone()));
+        }
+
+        let (kill_tx, kill_rx) = oneshot::channel::<()>();
+        let job_status = status.clone();
+        let job_id = id.clone();
+        tokio::spawn(async move {
+            let final_status = tokio::select! {
+                result = child.wait() => match result {
+                    Ok(exit) => JobStatus::Exited(exit.code().unwrap_or(-1)),
+                    Err(e) => JobStatus::Failed(e.to_string()),
+                },
+                _ = kill_rx => {
+                    kill_process_group(&mut child);
+                    let _ = child.wait().await;
+                    JobStatus::Killed
+                }
+            };
+            debug!("Background job {} finished: {}", job_id, final_status);
+            *job_status.lock().await = final_status;
+        });
+
+        info!("Started background job {} for user {}", id, user_id);
+
+        jobs.push(BackgroundJob {
+            id: id.clone(),
+            user_id,
+            command: command.to_string(),
+            started_at: Utc::now(),
+            status,
+            stdout,
+            stderr,
+            kill_tx: Some(kill_tx),
+        });
+
+        // 古い完了済みジョブを削除
+        let mut user_jobs = jobs.iter().filter(|j| j.user_id == user_id).count();
+        let mut index = 0;
+        while user_jobs > MAX_RETAINED_JOBS_PER_USER && index < jobs.len() {
+            let finished = jobs[index].user_id == user_id
+                && *jobs[index].status.lock().await != JobStatus::Running;
+            if finished {
+                jobs.remove(index);
+                user_jobs -= 1;
+            } else {
+                index += 1;
+            }
+        }
+
+        Ok(id)
+    }
+
+    /// ユーザーのジョブ一覧を取得
+    pub async fn list_jobs(&self, user_id: u64) -> Vec<JobSnapshot> {
+        let jobs = self.jobs.lock().await;
+        let mut snapshots = Vec::new();
+        for job in jobs.iter().filter(|j| j.user_id == user_id) {
+            snapshots.push(job.snapshot().await);
+        }
+        snapshots
+    }
+
+    /// ジョブの状態と出力を取得（他ユーザーのジョブは見えない）
+    pub async fn poll_job(&self, user_id: u64, job_id: &str) -> Option<JobSnapshot> {
+        let jobs = self.jobs.lock().await;
+        match jobs.iter().find(|j| j.id == job_id && j.user_id == user_id) {
+            Some(job) => Some(job.snapshot().await),
+            None => None,
+        }
+    }
+
+    /// ジョブを強制終了
+    pub async fn kill_job(&self, user_id: u64, job_id: &str) -> Result<(), String> {
+        let mut jobs = self.jobs.lock().await;
+        let job = jobs
+            .iter_mut()
+            .find(|j| j.id == job_id && j.user_id == user_id)
+            .ok_or_else(|| format!("Job not found: {}", job_id))?;
+
+        match job.kill_tx.take() {
+            Some(tx) if *job.status.lock().await == JobStatus::Running => {
+                let _ = tx.send(());
+                info!("Killed background job {} for user {}", job_id, user_id);
+                Ok(())
+            }
+            _ => {
+                warn!("Job {} is not running", job_id);
+                Err(format!("Job is not running: {}", job_id))
+            }
+        }
+    }
+}
+
+impl Default for ShellSessionManager {
+    fn default() -> Self {
+        Self::new(DEFAULT_IDLE_TIMEOUT)
+    }
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn test_key() -> SessionKey {
+        SessionKey::new(1, 2)
+    }
+
+    #[tokio::test]
+    async fn test_persistent_shell_keeps_cwd_and_env() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        let working_dir = dir.path().to_str().unwrap();
+        let manager = ShellSessionManager::default();
+
+        manager
+            .run_persistent(test_key(), "cd sub && export FOO=bar", 5, working_dir)
+            .await
+            .unwrap();
+        let output = manager
+            .run_persistent(test_key(), "pwd; echo $FOO", 5, working_dir)
+            .await
+            .unwrap();
+
+        assert!(output.success());
+        assert!(output.stdout.contains("sub"));
+        assert!(output.stdout.ends_with("bar"));
+    }
+
+    #[tokio::test]
+    async fn test_persistent_shell_exit_code_and_stderr() {
+        let dir = tempdir().unwrap();
+        let working_dir = dir.path().to_str().unwrap();
+        let manager = ShellSessionManager::default();
+
+        let output = manager
+            .run_persistent(test_key(), "echo oops >&2; false", 5, working_dir)
+            .await
+            .unwrap();
+
+        assert_eq!(output.exit_code, 1);
+        assert_eq!(output.stderr, "oops");
+        assert_eq!(output.stdout, "");
+    }
+
+    #[tokio::test]
+    async fn test_persistent_shell_output_without_newline() {
+        let dir = tempdir().unwrap();
+        let working_dir = dir.path().to_str().unwrap();
+        let manager = ShellSessionManager::default();
+
+        let output = manager
+            .run_persistent(test_key(), "printf abc", 5, working_dir)
+            .await
+            .unwrap();
+
+        assert_eq!(output.stdout, "abc");
+    }
+
+    #[tokio::test]
+    async fn test_persistent_shell_timeout_resets_session() {
+        let dir = tempdir().unwrap();
+        let working_dir = dir.path().to_str().unwrap();
+        let manager = ShellSessionManager::default();
+
+        manager
+            .run_persistent(test_key(), "export FOO=bar", 5, working_dir)
+            .await
+            .unwrap();
+        let result = manager.run_persistent(test_key(), "sleep 10", 1, working_dir).await;
+        assert!(result.is_err());
+        assert_eq!(manager.shells.lock().await.len(), 0);
+
+        let output = manager
+            .run_persistent(test_key(), "echo \"[$FOO]\"", 5, working_dir)
+            .await
+            .unwrap();
+        assert_eq!(output.stdout, "[]");
+    }
+
+    /// プロセスが生存しているか（ゾンビは終了済みとみなす）
+    #[cfg(target_os = "linux")]
+    fn process_alive(pid: &str) -> bool {
+        std::fs::read_to_string(format!("/proc/{}/stat", pid))
+            .map(|stat| !stat.contains(") Z "))
+            .unwrap_or(false)
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_persistent_shell_timeout_kills_child_processes() {
+        let dir = tempdir().unwrap();
+        let working_dir = dir.path().to_str().unwrap();
+        let manager = ShellSessionManager::default();
+
+        let result = manager
+            .run_persistent(test_key(), "sleep 100 & echo $! > child.pid; wait", 1, working_dir)
+            .await;
+        assert!(result.is_err());
+
+        let pid = std::fs::read_to_string(dir.path().join("child.pid")).unwrap();
+        let pid = pid.trim();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while process_alive(pid) && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert!(!process_alive(pid), "child process {} survived the session reset", pid);
+    }
+
+    #[tokio::test]
+    async fn test_idle_sessions_are_cleaned_up() {
+        let dir = tempdir().unwrap();
+        let working_dir = dir.path().to_str().unwrap();
+        let manager = ShellSessionManager::new(Duration::from_millis(50));
+
+        manager
+            .run_persistent(test_key(), "true", 5, working_dir)
+            .await
+            .unwrap();
+        assert_eq!(manager.shells.lock().await.len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        manager.cleanup_idle_shells().await;
+        assert_eq!(manager.shells.lock().await.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_background_job_poll() {
+        let dir = tempdir().unwrap();
+        let working_dir = dir.path().to_str().unwrap();
+        let manager = ShellSessionManager::default();
+
+        let id = manager.spawn_job(1, "echo done", working_dir).await.unwrap();
+
+        let mut snapshot = manager.poll_job(1, &id).await.unwrap();
+        for _ in 0..50 {
+            if snapshot.status != JobStatus::Running {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            snapshot = manager.poll_job(1, &id).await.unwrap();
+        }
+
+        assert_eq!(snapshot.status, JobStatus::Exited(0));
+        assert!(snapshot.stdout.contains("done"));
+        // 他ユーザーからは見えない
+        assert!(manager.poll_job(2, &id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_background_job_kill() {
+        let dir = tempdir().unwrap();
+        let working_dir = dir.path().to_str().unwrap();
+        let manager = ShellSessionManager::default();
+
+        let id = manager.spawn_job(1, "sleep 30", working_dir).await.unwrap();
+        assert!(manager.kill_job(2, &id).await.is_err());
+        manager.kill_job(1, &id).await.unwrap();
+
+        let mut status = JobStatus::Running;
+        for _ in 0..50 {
+            status = manager.poll_job(1, &id).await.unwrap().status;
+            if status != JobStatus::Running {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(status, JobStatus::Killed);
+    }
+}
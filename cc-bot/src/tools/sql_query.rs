@@ -0,0 +1,765 @@
+//! 読み取り専用SQLクエリツール
+//!
+//! 作業ディレクトリ内の SQLite ファイルを読み取り専用で開き、CSV / JSON ファイルを
+//! 一時テーブルとして読み込んだうえで、SELECT 文を実行します。
+//! 実行時間と行数を制限し、結果を Markdown テーブルまたは CSV 添付で返します。
+
+use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
+use crate::tools::ApplyPatchTool;
+use async_trait::async_trait;
+use rusqlite::limits::Limit;
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+use serde_json::{json, Value as JsonValue};
+use std::path::Path;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// デフォルトの実行時間上限（秒）
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+/// 実行時間上限の最大値（秒）
+const MAX_TIMEOUT_SECS: u64 = 30;
+/// Markdown 表示のデフォルト行数
+const DEFAULT_MAX_ROWS: usize = 100;
+/// Markdown 表示の最大行数
+const MAX_MARKDOWN_ROWS: usize = 500;
+/// CSV 添付の最大行数
+const MAX_CSV_ROWS: usize = 100_000;
+/// 読み込むデータファイルの最大サイズ
+const MAX_DATA_FILE_BYTES: u64 = 100 * 1024 * 1024;
+/// 1セルの最大表示文字数
+const MAX_CELL_CHARS: usize = 200;
+/// 結果に含める最大文字数
+const MAX_OUTPUT_CHARS: usize = 15000;
+/// CSV 形式で結果と一緒に表示するプレビュー行数
+const CSV_PREVIEW_ROWS: usize = 10;
+
+/// 出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Markdown,
+    Csv,
+}
+
+/// 読み込むデータファイル（テーブル名, 実パス）
+#[derive(Debug, Clone)]
+struct DataTable {
+    name: String,
+    path: std::path::PathBuf,
+}
+
+/// クエリ結果
+#[derive(Debug, Default)]
+struct QueryOutput {
+    columns: Vec<String>,
+    rows: Vec<Vec<String>>,
+    /// 行数上限で打ち切ったか
+    truncated: bool,
+}
+
+/// SQLクエリツール
+pub struct SqlQueryTool;
+
+impl SqlQueryTool {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// ファイル名からテーブル名を作成（英数字とアンダースコアのみ）
+    fn table_name_from_path(path: &str) -> String {
+        let stem = Path::new(path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Self::sanitize_identifier(&stem)
+    }
+
+    fn sanitize_identifier(name: &str) -> String {
+        let mut ident: String = name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+            .collect();
+        if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+            ident.insert(0, 't');
+            ident.insert(1, '_');
+        }
+        ident
+    }
+
+    /// 識別子をクォート
+    fn quote_identifier(name: &str) -> String {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+
+    /// `tables` パラメータを解決（パスの配列、または {テーブル名: パス}）
+    fn resolve_tables(params: &JsonValue, context: &ToolContext) -> Result<Vec<DataTable>, ToolError> {
+        let entries: Vec<(String, String)> = match &params["tables"] {
+            JsonValue::Null => Vec::new(),
+            JsonValue::String(path) => vec![(Self::table_name_from_path(path), path.clone())],
+            JsonValue::Array(items) => items
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|path| (Self::table_name_from_path(path), path.to_string()))
+                .collect(),
+            JsonValue::Object(map) => map
+                .iter()
+                .filter_map(|(name, path)| path.as_str().map(|p| (Self::sanitize_identifier(name), p.to_string())))
+                .collect(),
+            _ => {
+                return Err(ToolError::InvalidParams(
+                    "'tables' must be an array of paths or an object of {table: path}".to_string(),
+                ))
+            }
+        };
+
+        let mut tables: Vec<DataTable> = Vec::new();
+        for (name, path) in entries {
+            if tables.iter().any(|t| t.name == name) {
+                return Err(ToolError::InvalidParams(format!("Duplicate table name: {}", name)));
+            }
+            let full_path = ApplyPatchTool::resolve_path(&path, context)?;
+            if !full_path.is_file() {
+                return Err(ToolError::InvalidParams(format!("File not found: {}", path)));
+            }
+            tables.push(DataTable { name, path: full_path });
+        }
+        Ok(tables)
+    }
+
+    /// 接続を作成（SQLite ファイルは読み取り専用）
+    fn open_connection(database: Option<&Path>) -> Result<Connection, String> {
+        let conn = match database {
+            Some(path) => Connection::open_with_flags(
+                path,
+                OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            )
+            .map_err(|e| format!("Failed to open database: {}", e))?,
+            None => Connection::open_in_memory().map_err(|e| format!("Failed to create database: {}", e))?,
+        };
+        // 他のファイルをATTACHさせない
+        conn.set_limit(Limit::SQLITE_LIMIT_ATTACHED, 0);
+        Ok(conn)
+    }
+
+    /// 文字列値から列の型を推定
+    fn infer_column_type<'a>(values: impl Iterator<Item = &'a str>) -> &'static str {
+        let mut is_integer = true;
+        let mut is_real = true;
+        let mut seen = false;
+        for value in values.filter(|v| !v.is_empty()) {
+            seen = true;
+            if value.parse::<i64>().is_err() {
+                is_integer = false;
+            }
+            if value.parse::<f64>().is_err() {
+                is_real = false;
+            }
+            if !is_integer && !is_real {
+                break;
+            }
+        }
+        match (seen, is_integer, is_real) {
+            (true, true, _) => "INTEGER",
+            (true, false, true) => "REAL",
+            _ => "TEXT",
+        }
+    }
+
+    /// 列名を重複・空文字のない識別子に整形
+    fn unique_columns(headers: &[String]) -> Vec<String> {
+        let mut columns: Vec<String> = Vec::new();
+        for (idx, header) in headers.iter().enumerate() {
+            let base = match header.trim() {
+                "" => format!("column{}", idx + 1),
+                name => name.to_string(),
+            };
+            let mut name = base.clone();
+            let mut n = 2;
+            while columns.iter().any(|c| c.eq_ignore_ascii_case(&name)) {
+                name = format!("{}_{}", base, n);
+                n += 1;
+            }
+            columns.push(name);
+        }
+        columns
+    }
+
+    /// 行データから一時テーブルを作成
+    fn create_table(conn: &Connection, name: &str, headers: &[String], rows: &[Vec<String>]) -> Result<(), String> {
+        let columns = Self::unique_columns(headers);
+        let definitions: Vec<String> = columns
+            .iter()
+            .enumerate()
+            .map(|(idx, column)| {
+                let kind = Self::infer_column_type(rows.iter().map(|r| r.get(idx).map(String::as_str).unwrap_or("")));
+                format!("{} {}", Self::quote_identifier(column), kind)
+            })
+            .collect();
+
+        conn.execute_batch(&format!(
+            "CREATE TEMP TABLE {} ({});",
+            Self::quote_identifier(name),
+            definitions.join(", ")
+        ))
+        .map_err(|e| format!("Failed to create table {}: {}", name, e))?;
+
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let sql = format!("INSERT INTO temp.{} VALUES ({})", Self::quote_identifier(name), placeholders);
+        let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        {
+            let mut stmt = tx.prepare(&sql).map_err(|e| e.to_string())?;
+            for row in rows {
+                let values = (0..columns.len()).map(|idx| match row.get(idx).map(String::as_str) {
+                    None | Some("") => None,
+                    Some(value) => Some(value),
+                });
+                stmt.execute(rusqlite::params_from_iter(values))
+                    .map_err(|e| format!("Failed to load table {}: {}", name, e))?;
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    /// CSV / TSV ファイルを読み込み
+    pub(crate) fn load_csv(path: &Path) -> Result<(Vec<String>, Vec<Vec<String>>), String> {
+        let delimiter = match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("tsv") => b'\t',
+            _ => b',',
+        };
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .flexible(true)
+            .from_path(path)
+            .map_err(|e| format!("Failed to read CSV: {}", e))?;
+        let headers: Vec<String> = reader
+            .headers()
+            .map_err(|e| format!("Failed to read CSV header: {}", e))?
+            .iter()
+            .map(|h| h.trim_start_matches('\u{feff}').to_string())
+            .collect();
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(|e| format!("Invalid CSV: {}", e))?;
+            rows.push(record.iter().map(str::to_string).collect());
+        }
+        Ok((headers, rows))
+    }
+
+    /// JSON（オブジェクトの配列）/ JSON Lines ファイルを読み込み
+    fn load_json(path: &Path) -> Result<(Vec<String>, Vec<Vec<String>>), String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read JSON: {}", e))?;
+        let is_lines = matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("jsonl") | Some("ndjson")
+        );
+        let records: Vec<JsonValue> = if is_lines {
+            text.lines()
+                .filter(|l| !l.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("Invalid JSON Lines: {}", e))?
+        } else {
+            match serde_json::from_str(&text).map_err(|e| format!("Invalid JSON: {}", e))? {
+                JsonValue::Array(items) => items,
+                _ => return Err("JSON file must contain an array of objects".to_string()),
+            }
+        };
+
+        let mut headers: Vec<String> = Vec::new();
+        for record in &records {
+            let object = record
+                .as_object()
+                .ok_or_else(|| "JSON file must contain an array of objects".to_string())?;
+            for key in object.keys() {
+                if !headers.contains(key) {
+                    headers.push(key.clone());
+                }
+            }
+        }
+        let rows = records
+            .iter()
+            .map(|record| {
+                headers
+                    .iter()
+                    .map(|key| match &record[key] {
+                        JsonValue::Null => String::new(),
+                        JsonValue::String(s) => s.clone(),
+                        JsonValue::Bool(b) => (*b as i64).to_string(),
+                        other => other.to_string(),
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok((headers, rows))
+    }
+
+    /// データファイルを一時テーブルとして読み込み
+    fn load_table(conn: &Connection, table: &DataTable) -> Result<usize, String> {
+        let size = std::fs::metadata(&table.path).map(|m| m.len()).unwrap_or(0);
+        if size > MAX_DATA_FILE_BYTES {
+            return Err(format!(
+                "{} is too large ({} bytes, max {} bytes)",
+                table.name, size, MAX_DATA_FILE_BYTES
+            ));
+        }
+        let extension = table
+            .path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+        let (headers, rows) = match extension.as_str() {
+            "csv" | "tsv" => Self::load_csv(&table.path)?,
+            "json" | "jsonl" | "ndjson" => Self::load_json(&table.path)?,
+            other => return Err(format!("Unsupported data file type: .{} (use csv, tsv, json or jsonl)", other)),
+        };
+        if headers.is_empty() {
+            return Err(format!("{} has no columns", table.name));
+        }
+        Self::create_table(conn, &table.name, &headers, &rows)?;
+        Ok(rows.len())
+    }
+
+    /// 値を表示用文字列に変換
+    fn render_value(value: ValueRef) -> String {
+        match value {
+            ValueRef::Null => "NULL".to_string(),
+            ValueRef::Integer(i) => i.to_string(),
+            ValueRef::Real(f) => f.to_string(),
+            ValueRef::Text(t) => String::from_utf8_lossy(t).to_string(),
+            ValueRef::Blob(b) => format!("<blob {} bytes>", b.len()),
+        }
+    }
+
+    /// クエリを実行（ブロッキング）
+    fn run_query(conn: &Connection, sql: &str, max_rows: usize) -> Result<QueryOutput, String> {
+        let mut batch = rusqlite::Batch::new(conn, sql);
+        let mut stmt = batch
+            .next()
+            .map_err(|e| format!("SQL error: {}", e))?
+            .ok_or_else(|| "Query is empty".to_string())?;
+        if batch.next().map_err(|e| format!("SQL error: {}", e))?.is_some() {
+            return Err("Only a single SQL statement is allowed".to_string());
+        }
+        if !stmt.readonly() {
+            return Err("Only read-only queries (SELECT, WITH, EXPLAIN, read-only PRAGMA) are allowed".to_string());
+        }
+
+        let columns: Vec<String> = stmt.column_names().into_iter().map(str::to_string).collect();
+        let column_count = columns.len();
+        let mut output = QueryOutput {
+            columns,
+            ..Default::default()
+        };
+        let mut rows = stmt.query([]).map_err(|e| format!("SQL error: {}", e))?;
+        while let Some(row) = rows.next().map_err(|e| format!("SQL error: {}", e))? {
+            if output.rows.len() >= max_rows {
+                output.truncated = true;
+                break;
+            }
+            let values = (0..column_count)
+                .map(|idx| row.get_ref(idx).map(Self::render_value).unwrap_or_default())
+                .collect();
+            output.rows.push(values);
+        }
+        Ok(output)
+    }
+
+    /// Markdown テーブルに整形
+    fn to_markdown(output: &QueryOutput, max_rows: usize) -> (String, usize) {
+        let escape = |value: &str| {
+            let value = value.replace('|', "\\|").replace(['\r', '\n'], " ");
+            if value.chars().count() > MAX_CELL_CHARS {
+                let truncated: String = value.chars().take(MAX_CELL_CHARS).collect();
+                format!("{}…", truncated)
+            } else {
+                value
+            }
+        };
+
+        let mut table = format!(
+            "| {} |\n|{}|\n",
+            output.columns.iter().map(|c| escape(c)).collect::<Vec<_>>().join(" | "),
+            vec!["---"; output.columns.len()].join("|")
+        );
+        let mut shown = 0;
+        for row in output.rows.iter().take(max_rows) {
+            let line = format!("| {} |\n", row.iter().map(|v| escape(v)).collect::<Vec<_>>().join(" | "));
+            if table.len() + line.len() > MAX_OUTPUT_CHARS {
+                break;
+            }
+            table.push_str(&line);
+            shown += 1;
+        }
+        (table, shown)
+    }
+
+    /// CSV に整形
+    fn to_csv(output: &QueryOutput) -> Result<Vec<u8>, String> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(&output.columns).map_err(|e| e.to_string())?;
+        for row in &output.rows {
+            writer.write_record(row).map_err(|e| e.to_string())?;
+        }
+        writer.into_inner().map_err(|e| e.to_string())
+    }
+}
+
+impl Default for SqlQueryTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for SqlQueryTool {
+    fn name(&self) -> &str {
+        "sql_query"
+    }
+
+    fn description(&self) -> &str {
+        "Run a read-only SQL (SQLite) query over data in your workspace: a SQLite database file opened read-only, and/or CSV/TSV/JSON files loaded as tables. Returns a Markdown table, or a CSV attachment for large results. Use 'SELECT name FROM sqlite_master' or 'PRAGMA table_info(t)' to inspect schemas."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "A single read-only SQL statement (SQLite dialect)"
+                },
+                "database": {
+                    "type": "string",
+                    "description": "Relative path to a SQLite database file (opened read-only)"
+                },
+                "tables": {
+                    "description": "CSV/TSV/JSON/JSONL files to load as tables: an array of paths (table name = file name) or an object {\"table_name\": \"path\"}",
+                    "oneOf": [
+                        {"type": "array", "items": {"type": "string"}},
+                        {"type": "object", "additionalProperties": {"type": "string"}}
+                    ]
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["markdown", "csv"],
+                    "description": "Result format (default: markdown). csv attaches all rows as a file"
+                },
+                "max_rows": {
+                    "type": "integer",
+                    "description": "Maximum rows to return (default: 100; markdown max 500, csv max 100000)"
+                },
+                "timeout": {
+                    "type": "integer",
+                    "description": "Time limit in seconds (default: 10, max: 30)"
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let query = params["query"]
+            .as_str()
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .ok_or_else(|| ToolError::InvalidParams("Missing 'query' parameter".to_string()))?
+            .to_string();
+        let format = match params["format"].as_str().unwrap_or("markdown") {
+            "markdown" | "md" => OutputFormat::Markdown,
+            "csv" => OutputFormat::Csv,
+            other => return Err(ToolError::InvalidParams(format!("Unknown format: {}", other))),
+        };
+        let row_cap = match format {
+            OutputFormat::Markdown => MAX_MARKDOWN_ROWS,
+            OutputFormat::Csv => MAX_CSV_ROWS,
+        };
+        let default_rows = match format {
+            OutputFormat::Markdown => DEFAULT_MAX_ROWS,
+            OutputFormat::Csv => MAX_CSV_ROWS,
+        };
+        let max_rows = params["max_rows"]
+            .as_u64()
+            .map(|n| n as usize)
+            .unwrap_or(default_rows)
+            .clamp(1, row_cap);
+        let timeout_secs = params["timeout"]
+            .as_u64()
+            .unwrap_or(DEFAULT_TIMEOUT_SECS)
+            .clamp(1, MAX_TIMEOUT_SECS);
+
+        let database = match params["database"].as_str() {
+            Some(path) => {
+                let full_path = ApplyPatchTool::resolve_path(path, context)?;
+                if !full_path.is_file() {
+                    return Err(ToolError::InvalidParams(format!("Database not found: {}", path)));
+                }
+                Some(full_path)
+            }
+            None => None,
+        };
+        let tables = Self::resolve_tables(&params, context)?;
+
+        info!(
+            "sql_query for user {} (database: {}, tables: {})",
+            context.user_id,
+            database.is_some(),
+            tables.len()
+        );
+
+        // 接続の作成とデータ読み込みもタイムアウトの対象にする
+        let (handle_tx, handle_rx) = tokio::sync::oneshot::channel();
+        let task = tokio::task::spawn_blocking(move || {
+            let conn = Self::open_connection(database.as_deref())?;
+            let _ = handle_tx.send(conn.get_interrupt_handle());
+            let mut loaded = Vec::new();
+            for table in &tables {
+                let rows = Self::load_table(&conn, table)?;
+                loaded.push(format!("{} ({} rows)", table.name, rows));
+            }
+            // 以降は一時テーブルも含めて書き込み禁止
+            conn.execute_batch("PRAGMA query_only = ON;").map_err(|e| e.to_string())?;
+            let output = Self::run_query(&conn, &query, max_rows)?;
+            Ok::<_, String>((output, loaded))
+        });
+
+        let interrupt = handle_rx.await.ok();
+        let timed_out = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let watchdog = interrupt.map(|handle| {
+            let timed_out = timed_out.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_secs(timeout_secs)).await;
+                timed_out.store(true, std::sync::atomic::Ordering::SeqCst);
+                handle.interrupt();
+            })
+        });
+        let result = task.await;
+        if let Some(watchdog) = watchdog {
+            watchdog.abort();
+        }
+
+        let (output, loaded) = match result {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) if timed_out.load(std::sync::atomic::Ordering::SeqCst) => {
+                return Ok(ToolResult::error(format!("Query exceeded the time limit ({}s)", timeout_secs)));
+            }
+            Ok(Err(message)) => {
+                debug!("sql_query failed: {}", message);
+                return Ok(ToolResult::error(message));
+            }
+            Err(e) => return Err(ToolError::ExecutionFailed(format!("Query task failed: {}", e))),
+        };
+
+        let mut text = String::new();
+        if !loaded.is_empty() {
+            text.push_str(&format!("Loaded tables: {}\n\n", loaded.join(", ")));
+        }
+        if output.columns.is_empty() {
+            text.push_str("Query returned no columns.");
+            return Ok(ToolResult::success(text));
+        }
+
+        let row_count = output.rows.len();
+        let limit_note = if output.truncated {
+            format!(" (limited to {} rows)", max_rows)
+        } else {
+            String::new()
+        };
+
+        match format {
+            OutputFormat::Markdown => {
+                let (table, shown) = Self::to_markdown(&output, max_rows);
+                text.push_str(&table);
+                text.push_str(&format!("\n{} row(s){}", row_count, limit_note));
+                if shown < row_count {
+                    text.push_str(&format!(
+                        "; showing the first {} to fit the output limit. Use format \"csv\" to get all rows.",
+                        shown
+                    ));
+                }
+            }
+            OutputFormat::Csv => {
+                let data = Self::to_csv(&output).map_err(ToolError::ExecutionFailed)?;
+                let attached = context.add_attachment("query_result.csv", data);
+                if !attached {
+                    warn!("sql_query: attachment limit reached");
+                }
+                let (preview, _) = Self::to_markdown(&output, CSV_PREVIEW_ROWS);
+                text.push_str(&format!(
+                    "{} row(s){}{}\n\nPreview:\n{}",
+                    row_count,
+                    limit_note,
+                    if attached {
+                        ", attached as query_result.csv"
+                    } else {
+                        " (CSV not attached: attachment limit reached)"
+                    },
+                    preview
+                ));
+            }
+        }
+
+        Ok(ToolResult::success(text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::test_workspace;
+    use std::path::PathBuf;
+
+    fn setup_workspace() -> (tempfile::TempDir, ToolContext, PathBuf) {
+        let (dir, ctx, root) = test_workspace();
+        std::fs::create_dir_all(root.join("data")).unwrap();
+        std::fs::write(
+            root.join("data/sales.csv"),
+            "region,amount,note\nnorth,100,first\nsouth,250,\"a|b\"\nnorth,50,\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("data/users.json"),
+            r#"[{"id": 1, "name": "alice", "tags": ["a"]}, {"id": 2, "name": "bob", "active": true}]"#,
+        )
+        .unwrap();
+        let conn = Connection::open(root.join("data/app.db")).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE items (id INTEGER, title TEXT); INSERT INTO items VALUES (1, 'x'), (2, 'y'), (3, 'z');",
+        )
+        .unwrap();
+        (dir, ctx, root)
+    }
+
+    #[tokio::test]
+    async fn test_query_csv_as_markdown() {
+        let (_dir, ctx, _root) = setup_workspace();
+        let tool = SqlQueryTool::new();
+
+        let result = tool
+            .execute(
+                json!({
+                    "query": "SELECT region, SUM(amount) AS total FROM sales GROUP BY region ORDER BY total DESC",
+                    "tables": ["data/sales.csv"]
+                }),
+                &ctx,
+            )
+            .await
+            .unwrap();
+
+        assert!(!result.is_error, "{}", result.output);
+        assert!(result.output.contains("Loaded tables: sales (3 rows)"));
+        assert!(result.output.contains("| region | total |\n|---|---|\n| south | 250 |\n| north | 150 |"));
+        assert!(result.output.contains("2 row(s)"));
+
+        // 区切り文字はエスケープされる
+        let result = tool
+            .execute(json!({"query": "SELECT note FROM s WHERE region = 'south'", "tables": {"s": "data/sales.csv"}}), &ctx)
+            .await
+            .unwrap();
+        assert!(result.output.contains("| a\\|b |"), "{}", result.output);
+    }
+
+    #[tokio::test]
+    async fn test_query_sqlite_and_json_join() {
+        let (_dir, ctx, _root) = setup_workspace();
+        let tool = SqlQueryTool::new();
+
+        let result = tool
+            .execute(
+                json!({
+                    "query": "SELECT u.name, i.title, u.active FROM users u JOIN items i ON i.id = u.id ORDER BY u.id",
+                    "database": "data/app.db",
+                    "tables": ["data/users.json"]
+                }),
+                &ctx,
+            )
+            .await
+            .unwrap();
+
+        assert!(!result.is_error, "{}", result.output);
+        assert!(result.output.contains("| alice | x | NULL |"));
+        assert!(result.output.contains("| bob | y | 1 |"));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_writes_and_attach() {
+        let (_dir, ctx, root) = setup_workspace();
+        let tool = SqlQueryTool::new();
+
+        for query in [
+            "DELETE FROM items",
+            "INSERT INTO items VALUES (4, 'w')",
+            "CREATE TABLE t (x)",
+            "ATTACH DATABASE '/etc/passwd' AS p",
+            "SELECT 1; DELETE FROM items",
+        ] {
+            let result = tool
+                .execute(json!({"query": query, "database": "data/app.db"}), &ctx)
+                .await
+                .unwrap();
+            assert!(result.is_error, "{} should fail", query);
+        }
+
+        let conn = Connection::open(root.join("data/app.db")).unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM items", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 3);
+
+        let result = tool
+            .execute(json!({"query": "SELECT 1", "database": "../../etc/app.db"}), &ctx)
+            .await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+    }
+
+    #[tokio::test]
+    async fn test_row_limit_and_csv_attachment() {
+        let (_dir, ctx, _root) = setup_workspace();
+        let tool = SqlQueryTool::new();
+        let query = "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n WHERE x < 1000) SELECT x FROM n";
+
+        let result = tool.execute(json!({"query": query, "max_rows": 5}), &ctx).await.unwrap();
+        assert!(result.output.contains("5 row(s) (limited to 5 rows)"), "{}", result.output);
+        assert!(!result.output.contains("| 6 |"));
+
+        let result = tool.execute(json!({"query": query, "format": "csv"}), &ctx).await.unwrap();
+        assert!(result.output.contains("1000 row(s), attached as query_result.csv"));
+        let attachments = ctx.take_attachments();
+        assert_eq!(attachments.len(), 1);
+        let csv = String::from_utf8(attachments[0].data.clone()).unwrap();
+        assert!(csv.starts_with("x\n1\n2\n"));
+        assert_eq!(csv.lines().count(), 1001);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_interrupts_query() {
+        let (_dir, ctx, _root) = setup_workspace();
+        let tool = SqlQueryTool::new();
+
+        let result = tool
+            .execute(
+                json!({
+                    "query": "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n) SELECT COUNT(*) FROM n",
+                    "timeout": 1
+                }),
+                &ctx,
+            )
+            .await
+            .unwrap();
+
+        assert!(result.is_error);
+        assert!(result.output.contains("time limit (1s)"), "{}", result.output);
+    }
+
+    #[test]
+    fn test_table_names_and_type_inference() {
+        assert_eq!(SqlQueryTool::table_name_from_path("data/Sales 2024.csv"), "sales_2024");
+        assert_eq!(SqlQueryTool::table_name_from_path("2024.csv"), "t_2024");
+        assert_eq!(SqlQueryTool::infer_column_type(["1", "2", ""].into_iter()), "INTEGER");
+        assert_eq!(SqlQueryTool::infer_column_type(["1", "2.5"].into_iter()), "REAL");
+        assert_eq!(SqlQueryTool::infer_column_type(["1", "x"].into_iter()), "TEXT");
+        assert_eq!(SqlQueryTool::infer_column_type(["", ""].into_iter()), "TEXT");
+        assert_eq!(
+            SqlQueryTool::unique_columns(&["a".to_string(), "".to_string(), "A".to_string()]),
+            vec!["a", "column2", "A_2"]
+        );
+    }
+}
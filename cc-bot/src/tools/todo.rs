@@ -0,0 +1,728 @@
+//! TODOツール
+//!
+//! ユーザーごと（任意でチャンネル限定）の TODO リストを `TodoStore` に保存し、
+//! 追加・一覧・更新・完了を LLM から操作できるようにします。
+//! 期限のある TODO はスケジューラーに1回だけのリマインダーを登録し、
+//! 完了・期限変更時に取り消し・再登録します。リマインダーは通常のスケジュールとは区別し
+//! （`TaskKind::TodoReminder`）、ユーザーごとに別の上限を適用します。
+//! リマインダーは LLM を通さず、登録時に作成したメッセージをそのまま投稿します。
+
+use crate::datetime_utils::{parse_datetime, parse_delay};
+use crate::schedule_store::ScheduleStore;
+use crate::scheduler::{ScheduledTask, Scheduler, TaskKind};
+use crate::todo_store::{
+    NewTodo, StatusFilter, Todo, TodoError, TodoFilter, TodoPriority, TodoStatus, TodoStore, TodoUpdate,
+};
+use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
+use async_trait::async_trait;
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use serde_json::{json, Value as JsonValue};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+
+/// 一覧のデフォルト件数
+const DEFAULT_LIST_LIMIT: usize = 20;
+/// 一覧の最大件数
+const MAX_LIST_LIMIT: usize = 100;
+/// 日付のみ指定された場合の時刻（ローカル時刻）
+const DATE_ONLY_HOUR: u32 = 9;
+/// ユーザーあたりのデフォルト最大リマインダー数
+const DEFAULT_MAX_REMINDERS_PER_USER: usize = 20;
+
+/// 期限をパース（RFC3339、`YYYY-MM-DD HH:MM`、`YYYY-MM-DD`（9:00）、`2h` / `3d` 後）
+pub fn parse_due(value: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Some(due) = parse_datetime(value) {
+        return Some(due);
+    }
+    if let Some(delay) = parse_delay(value) {
+        return Some(now + delay);
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    let time = NaiveTime::from_hms_opt(DATE_ONLY_HOUR, 0, 0)?;
+    Local
+        .from_local_datetime(&date.and_time(time))
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// TODOを1行に整形
+pub fn format_todo(todo: &Todo, now: DateTime<Utc>) -> String {
+    let mut line = format!("#{} [{}]", todo.id, todo.status.as_str());
+    if todo.priority != TodoPriority::Normal {
+        line.push_str(&format!(" ({})", todo.priority.as_str()));
+    }
+    line.push_str(&format!(" {}", todo.title));
+    if let Some(due) = todo.due_at {
+        line.push_str(&format!(" | due {}", due.with_timezone(&Local).format("%Y-%m-%d %H:%M %:z")));
+        if todo.is_overdue(now) {
+            line.push_str(" (overdue)");
+        }
+    }
+    if let Some(channel_id) = todo.channel_id {
+        line.push_str(&format!(" | <#{}>", channel_id));
+    }
+    if !todo.tags.is_empty() {
+        let tags: Vec<String> = todo.tags.iter().map(|t| format!("#{}", t)).collect();
+        line.push_str(&format!(" | {}", tags.join(" ")));
+    }
+    line
+}
+
+/// TODOエラーをツールエラーに変換
+fn to_tool_error(e: TodoError) -> ToolError {
+    match e {
+        TodoError::NotFound(id) => ToolError::InvalidParams(format!("Todo #{} not found", id)),
+        TodoError::PermissionDenied(msg) => ToolError::PermissionDenied(msg),
+        TodoError::InvalidInput(msg) => ToolError::InvalidParams(msg),
+        TodoError::DatabaseError(msg) => ToolError::ExecutionFailed(msg),
+    }
+}
+
+/// リマインダー登録の結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReminderOutcome {
+    /// 登録した
+    Scheduled,
+    /// 期限なし・完了済み・期限切れのため登録しなかった
+    Skipped,
+    /// ユーザーのリマインダー数が上限に達しているため登録しなかった
+    LimitReached(usize),
+}
+
+/// 期限リマインダー（スケジューラーの1回だけのタスク）の管理
+pub struct TodoReminders {
+    scheduler: Arc<Scheduler>,
+    schedule_store: Arc<RwLock<ScheduleStore>>,
+    data_dir: String,
+    max_per_user: usize,
+}
+
+impl TodoReminders {
+    pub fn new(scheduler: Arc<Scheduler>, schedule_store: Arc<RwLock<ScheduleStore>>) -> Self {
+        Self {
+            scheduler,
+            schedule_store,
+            data_dir: "data".to_string(),
+            max_per_user: DEFAULT_MAX_REMINDERS_PER_USER,
+        }
+    }
+
+    /// ユーザーあたりの最大リマインダー数を設定（テスト用）
+    #[cfg(test)]
+    fn with_max_per_user(mut self, max: usize) -> Self {
+        self.max_per_user = max;
+        self
+    }
+
+    /// ユーザーの登録済みリマインダー数
+    async fn count_for_user(&self, user_id: u64) -> usize {
+        self.scheduler
+            .list_tasks()
+            .await
+            .iter()
+            .filter(|t| t.kind == TaskKind::TodoReminder && t.user_id == Some(user_id))
+            .count()
+    }
+
+    /// 保存先ディレクトリを設定（テスト用）
+    #[cfg(test)]
+    fn with_data_dir(mut self, data_dir: impl Into<String>) -> Self {
+        self.data_dir = data_dir.into();
+        self
+    }
+
+    /// TODOの状態に合わせてリマインダーを再登録
+    ///
+    /// 未完了で期限が未来なら `channel_id` に通知するリマインダーを登録する
+    pub async fn sync(&self, store: &TodoStore, todo: &Todo, channel_id: u64) -> ReminderOutcome {
+        self.cancel(store, todo).await;
+
+        let Some(due_at) = todo.due_at.filter(|_| todo.status != TodoStatus::Done) else {
+            return ReminderOutcome::Skipped;
+        };
+        let message = format!(
+            "⏰ <@{}> TODO #{}「{}」の期限（{}）です。",
+            todo.user_id,
+            todo.id,
+            todo.title,
+            due_at.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+        );
+        let task = match ScheduledTask::once(due_at, message, channel_id) {
+            Ok(task) => task.with_user(todo.user_id).with_kind(TaskKind::TodoReminder),
+            // 期限が過去ならリマインダーは不要
+            Err(_) => return ReminderOutcome::Skipped,
+        };
+        if self.count_for_user(todo.user_id).await >= self.max_per_user {
+            warn!("User {} reached the todo reminder limit, skipping todo {}", todo.user_id, todo.id);
+            return ReminderOutcome::LimitReached(self.max_per_user);
+        }
+        let reminder_id = task.id.to_string();
+
+        self.scheduler.add_task(task.clone()).await;
+        {
+            let mut schedules = self.schedule_store.write().await;
+            schedules.add_task(task);
+            if let Err(e) = schedules.save(&self.data_dir).await {
+                error!("Failed to save schedule for todo {}: {}", todo.id, e);
+            }
+        }
+        if let Err(e) = store.set_reminder(todo.id, Some(&reminder_id)) {
+            warn!("Failed to record reminder for todo {}: {}", todo.id, e);
+        }
+        info!("Scheduled reminder {} for todo {}", reminder_id, todo.id);
+        ReminderOutcome::Scheduled
+    }
+
+    /// リマインダーを取り消し
+    pub async fn cancel(&self, store: &TodoStore, todo: &Todo) {
+        let Some(reminder_id) = todo.reminder_id.as_deref() else {
+            return;
+        };
+        if let Ok(id) = uuid::Uuid::parse_str(reminder_id) {
+            // 実行済みのリマインダーはスケジューラーから削除済み
+            let _ = self.scheduler.remove_task(id).await;
+            let mut schedules = self.schedule_store.write().await;
+            if schedules.remove_task(id).is_some() {
+                if let Err(e) = schedules.save(&self.data_dir).await {
+                    error!("Failed to save schedule store: {}", e);
+                }
+            }
+        }
+        if let Err(e) = store.set_reminder(todo.id, None) {
+            // 削除済みのTODOは記録不要
+            if !matches!(e, TodoError::NotFound(_)) {
+                warn!("Failed to clear reminder for todo {}: {}", todo.id, e);
+            }
+        }
+    }
+}
+
+/// TODOツール共通の状態
+pub struct TodoAccess {
+    store: Arc<TodoStore>,
+    reminders: TodoReminders,
+}
+
+impl TodoAccess {
+    pub fn new(store: Arc<TodoStore>, reminders: TodoReminders) -> Self {
+        Self { store, reminders }
+    }
+}
+
+/// パラメータからタグ一覧を取得
+fn tags_param(params: &JsonValue) -> Option<Vec<String>> {
+    match &params["tags"] {
+        JsonValue::Array(items) => Some(items.iter().filter_map(|t| t.as_str().map(String::from)).collect()),
+        JsonValue::String(s) => Some(s.split(',').map(String::from).collect()),
+        _ => None,
+    }
+}
+
+/// パラメータから優先度を取得
+fn priority_param(params: &JsonValue) -> Result<Option<TodoPriority>, ToolError> {
+    match params["priority"].as_str() {
+        Some(value) => TodoPriority::parse(value)
+            .map(Some)
+            .ok_or_else(|| ToolError::InvalidParams(format!("Invalid priority: {}", value))),
+        None => Ok(None),
+    }
+}
+
+/// パラメータからTODO IDを取得（`12` / `"#12"`）
+fn id_param(params: &JsonValue) -> Result<i64, ToolError> {
+    params["id"]
+        .as_i64()
+        .or_else(|| params["id"].as_str().and_then(|s| s.trim().trim_start_matches('#').parse().ok()))
+        .ok_or_else(|| ToolError::InvalidParams("id is required".to_string()))
+}
+
+/// 結果にリマインダーの状態を付ける
+fn with_reminder_note(line: String, todo: &Todo, outcome: ReminderOutcome) -> String {
+    match (todo.due_at, outcome) {
+        (Some(_), ReminderOutcome::Scheduled) => {
+            format!("{}\nA reminder will be posted in this channel when it is due.", line)
+        }
+        (Some(_), ReminderOutcome::LimitReached(max)) => format!(
+            "{}\nNo reminder was scheduled: you already have {} pending todo reminders.",
+            line, max
+        ),
+        (Some(due), ReminderOutcome::Skipped) if due <= Utc::now() && todo.status != TodoStatus::Done => {
+            format!("{}\nThe due date is already past, so no reminder was scheduled.", line)
+        }
+        _ => line,
+    }
+}
+
+/// TODO追加ツール
+pub struct TodoAddTool {
+    access: Arc<TodoAccess>,
+}
+
+impl TodoAddTool {
+    pub fn new(access: Arc<TodoAccess>) -> Self {
+        Self { access }
+    }
+}
+
+#[async_trait]
+impl Tool for TodoAddTool {
+    fn name(&self) -> &str {
+        "todo_add"
+    }
+
+    fn description(&self) -> &str {
+        "Add an item to the current user's todo list. Items with a due date get a reminder posted in this channel when due."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": {
+                    "type": "string",
+                    "description": "What needs to be done"
+                },
+                "due": {
+                    "type": "string",
+                    "description": "Due date: RFC3339, 'YYYY-MM-DD HH:MM' or 'YYYY-MM-DD' (09:00) in server local time, or relative like '2h' / '3d'"
+                },
+                "priority": {
+                    "type": "string",
+                    "enum": ["low", "normal", "high"],
+                    "description": "Priority (default: normal)"
+                },
+                "tags": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Tags (e.g. ['work', 'release'])"
+                },
+                "scope": {
+                    "type": "string",
+                    "enum": ["personal", "channel"],
+                    "description": "personal (default) or channel to tie the item to this channel"
+                }
+            },
+            "required": ["title"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let title = params["title"]
+            .as_str()
+            .ok_or_else(|| ToolError::InvalidParams("title is required".to_string()))?;
+        let now = Utc::now();
+        let due_at = match params["due"].as_str().filter(|v| !v.trim().is_empty()) {
+            Some(value) => Some(
+                parse_due(value, now).ok_or_else(|| ToolError::InvalidParams(format!("Invalid due date: {}", value)))?,
+            ),
+            None => None,
+        };
+        let channel_id = match params["scope"].as_str() {
+            Some("channel") => Some(context.channel_id),
+            Some("personal") | None => None,
+            Some(other) => return Err(ToolError::InvalidParams(format!("Invalid scope: {}", other))),
+        };
+
+        let todo = self
+            .access
+            .store
+            .add_todo(NewTodo {
+                user_id: context.user_id,
+                channel_id,
+                title: title.to_string(),
+                priority: priority_param(&params)?.unwrap_or(TodoPriority::Normal),
+                due_at,
+                tags: tags_param(&params).unwrap_or_default(),
+            })
+            .map_err(to_tool_error)?;
+        info!("User {} added todo {}", context.user_id, todo.id);
+
+        let outcome = self.access.reminders.sync(&self.access.store, &todo, context.channel_id).await;
+        let line = format!("Added {}", format_todo(&todo, now));
+        Ok(ToolResult::success(with_reminder_note(line, &todo, outcome)))
+    }
+}
+
+/// TODO一覧ツール
+pub struct TodoListTool {
+    access: Arc<TodoAccess>,
+}
+
+impl TodoListTool {
+    pub fn new(access: Arc<TodoAccess>) -> Self {
+        Self { access }
+    }
+}
+
+#[async_trait]
+impl Tool for TodoListTool {
+    fn name(&self) -> &str {
+        "todo_list"
+    }
+
+    fn description(&self) -> &str {
+        "List the current user's todo items, soonest due first. By default only open and in-progress items are shown."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "status": {
+                    "type": "string",
+                    "enum": ["active", "open", "in_progress", "done", "all"],
+                    "description": "Status filter (default: active = open and in_progress)"
+                },
+                "tag": {
+                    "type": "string",
+                    "description": "Only items with this tag"
+                },
+                "channel_only": {
+                    "type": "boolean",
+                    "description": "Only items tied to this channel (default: false)"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Maximum number of items (default: 20, max: 100)"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let status = match params["status"].as_str().unwrap_or("active") {
+            "active" => StatusFilter::Active,
+            "all" => StatusFilter::All,
+            other => StatusFilter::Only(
+                TodoStatus::parse(other).ok_or_else(|| ToolError::InvalidParams(format!("Invalid status: {}", other)))?,
+            ),
+        };
+        let filter = TodoFilter {
+            channel_id: params["channel_only"].as_bool().unwrap_or(false).then_some(context.channel_id),
+            status,
+            tag: params["tag"].as_str().filter(|t| !t.trim().is_empty()).map(String::from),
+            limit: Some(
+                params["limit"]
+                    .as_u64()
+                    .map(|n| n as usize)
+                    .unwrap_or(DEFAULT_LIST_LIMIT)
+                    .clamp(1, MAX_LIST_LIMIT),
+            ),
+        };
+
+        let todos = self
+            .access
+            .store
+            .list_todos(context.user_id, &filter)
+            .map_err(to_tool_error)?;
+        if todos.is_empty() {
+            return Ok(ToolResult::success("No todo items found."));
+        }
+
+        let now = Utc::now();
+        let lines: Vec<String> = todos.iter().map(|t| format!("- {}", format_todo(t, now))).collect();
+        Ok(ToolResult::success(format!("Todo items ({}):\n{}", todos.len(), lines.join("\n"))))
+    }
+}
+
+/// TODO更新ツール
+pub struct TodoUpdateTool {
+    access: Arc<TodoAccess>,
+}
+
+impl TodoUpdateTool {
+    pub fn new(access: Arc<TodoAccess>) -> Self {
+        Self { access }
+    }
+}
+
+#[async_trait]
+impl Tool for TodoUpdateTool {
+    fn name(&self) -> &str {
+        "todo_update"
+    }
+
+    fn description(&self) -> &str {
+        "Update a todo item of the current user: title, status, due date, priority or tags. Only given fields change."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "id": {
+                    "type": "integer",
+                    "description": "Todo ID"
+                },
+                "title": {
+                    "type": "string",
+                    "description": "New title"
+                },
+                "status": {
+                    "type": "string",
+                    "enum": ["open", "in_progress", "done"],
+                    "description": "New status"
+                },
+                "due": {
+                    "type": "string",
+                    "description": "New due date (same formats as todo_add), or 'none' to remove it"
+                },
+                "priority": {
+                    "type": "string",
+                    "enum": ["low", "normal", "high"],
+                    "description": "New priority"
+                },
+                "tags": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Replace tags"
+                }
+            },
+            "required": ["id"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let id = id_param(&params)?;
+        let now = Utc::now();
+
+        let status = match params["status"].as_str() {
+            Some(value) => Some(
+                TodoStatus::parse(value).ok_or_else(|| ToolError::InvalidParams(format!("Invalid status: {}", value)))?,
+            ),
+            None => None,
+        };
+        let due_at = match params["due"].as_str().map(str::trim) {
+            Some("" | "none" | "null") => Some(None),
+            Some(value) => Some(Some(
+                parse_due(value, now).ok_or_else(|| ToolError::InvalidParams(format!("Invalid due date: {}", value)))?,
+            )),
+            None => None,
+        };
+        let update = TodoUpdate {
+            title: params["title"].as_str().map(String::from),
+            status,
+            priority: priority_param(&params)?,
+            due_at,
+            tags: tags_param(&params),
+        };
+        let reschedule = update.status.is_some() || update.due_at.is_some();
+
+        let todo = self
+            .access
+            .store
+            .update_todo(context.user_id, id, update)
+            .map_err(to_tool_error)?;
+        info!("User {} updated todo {}", context.user_id, todo.id);
+
+        let line = format!("Updated {}", format_todo(&todo, now));
+        if reschedule {
+            let outcome = self.access.reminders.sync(&self.access.store, &todo, context.channel_id).await;
+            Ok(ToolResult::success(with_reminder_note(line, &todo, outcome)))
+        } else {
+            Ok(ToolResult::success(line))
+        }
+    }
+}
+
+/// TODO完了ツール
+pub struct TodoCompleteTool {
+    access: Arc<TodoAccess>,
+}
+
+impl TodoCompleteTool {
+    pub fn new(access: Arc<TodoAccess>) -> Self {
+        Self { access }
+    }
+}
+
+#[async_trait]
+impl Tool for TodoCompleteTool {
+    fn name(&self) -> &str {
+        "todo_complete"
+    }
+
+    fn description(&self) -> &str {
+        "Mark a todo item of the current user as done and cancel its reminder."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "id": {
+                    "type": "integer",
+                    "description": "Todo ID"
+                }
+            },
+            "required": ["id"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let id = id_param(&params)?;
+
+        let todo = self
+            .access
+            .store
+            .complete_todo(context.user_id, id)
+            .map_err(to_tool_error)?;
+        self.access.reminders.cancel(&self.access.store, &todo).await;
+        info!("User {} completed todo {}", context.user_id, todo.id);
+
+        Ok(ToolResult::success(format!("Completed {}", format_todo(&todo, Utc::now()))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Timelike};
+    use tempfile::TempDir;
+
+    struct Fixture {
+        _dir: TempDir,
+        scheduler: Arc<Scheduler>,
+        store: Arc<TodoStore>,
+        access: Arc<TodoAccess>,
+    }
+
+    fn fixture() -> Fixture {
+        let dir = TempDir::new().unwrap();
+        let scheduler = Arc::new(Scheduler::new());
+        let store = Arc::new(TodoStore::new().unwrap());
+        let reminders = TodoReminders::new(scheduler.clone(), Arc::new(RwLock::new(ScheduleStore::new())))
+            .with_data_dir(dir.path().to_string_lossy().to_string());
+        let access = Arc::new(TodoAccess::new(store.clone(), reminders));
+        Fixture {
+            _dir: dir,
+            scheduler,
+            store,
+            access,
+        }
+    }
+
+    fn context(user_id: u64) -> ToolContext {
+        ToolContext::new(user_id, "test_user".to_string(), 456, "output".to_string())
+    }
+
+    #[test]
+    fn test_parse_due() {
+        let now = Utc::now();
+        assert_eq!(parse_due("2h", now), Some(now + Duration::hours(2)));
+        let date = parse_due("2026-12-24", now).unwrap().with_timezone(&Local);
+        assert_eq!(date.hour(), DATE_ONLY_HOUR);
+        assert!(parse_due("2026-12-24 18:30", now).is_some());
+        assert!(parse_due("someday", now).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_add_with_due_schedules_reminder() {
+        let f = fixture();
+        let add = TodoAddTool::new(f.access.clone());
+        let ctx = context(42);
+
+        let result = add
+            .execute(json!({"title": "Submit report", "due": "3h", "priority": "high", "tags": ["work"]}), &ctx)
+            .await
+            .unwrap();
+        assert!(!result.is_error, "{}", result.output);
+        assert!(result.output.contains("(high) Submit report"));
+
+        let tasks = f.scheduler.list_tasks().await;
+        assert_eq!(tasks.len(), 1);
+        assert!(tasks[0].is_one_shot());
+        assert_eq!(tasks[0].kind, TaskKind::TodoReminder);
+        assert_eq!(tasks[0].user_id, Some(42));
+        assert_eq!(tasks[0].channel_id, 456);
+        assert!(tasks[0].prompt.starts_with("⏰ <@42> TODO #"));
+        assert!(tasks[0].prompt.contains("「Submit report」"));
+
+        let todo = &f.store.list_todos(42, &TodoFilter::default()).unwrap()[0];
+        assert_eq!(todo.reminder_id, Some(tasks[0].id.to_string()));
+
+        // 期限なしならリマインダーは登録しない
+        add.execute(json!({"title": "Someday"}), &ctx).await.unwrap();
+        assert_eq!(f.scheduler.list_tasks().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reminder_limit() {
+        let dir = TempDir::new().unwrap();
+        let scheduler = Arc::new(Scheduler::new());
+        let store = Arc::new(TodoStore::new().unwrap());
+        let reminders = TodoReminders::new(scheduler.clone(), Arc::new(RwLock::new(ScheduleStore::new())))
+            .with_data_dir(dir.path().to_string_lossy().to_string())
+            .with_max_per_user(1);
+        let add = TodoAddTool::new(Arc::new(TodoAccess::new(store, reminders)));
+
+        let result = add.execute(json!({"title": "First", "due": "1d"}), &context(42)).await.unwrap();
+        assert!(result.output.contains("A reminder will be posted"));
+        let result = add.execute(json!({"title": "Second", "due": "1d"}), &context(42)).await.unwrap();
+        assert!(!result.is_error);
+        assert!(result.output.contains("No reminder was scheduled"));
+        assert_eq!(scheduler.list_tasks().await.len(), 1);
+
+        // 上限はユーザーごと
+        let result = add.execute(json!({"title": "Other", "due": "1d"}), &context(7)).await.unwrap();
+        assert!(result.output.contains("A reminder will be posted"));
+    }
+
+    #[tokio::test]
+    async fn test_update_and_complete_manage_reminder() {
+        let f = fixture();
+        let ctx = context(42);
+        TodoAddTool::new(f.access.clone())
+            .execute(json!({"title": "Review", "due": "1d"}), &ctx)
+            .await
+            .unwrap();
+        let id = f.store.list_todos(42, &TodoFilter::default()).unwrap()[0].id;
+        let first_reminder = f.scheduler.list_tasks().await[0].id;
+
+        // 期限変更でリマインダーを差し替え
+        let update = TodoUpdateTool::new(f.access.clone());
+        update.execute(json!({"id": id, "due": "2d"}), &ctx).await.unwrap();
+        let tasks = f.scheduler.list_tasks().await;
+        assert_eq!(tasks.len(), 1);
+        assert_ne!(tasks[0].id, first_reminder);
+
+        // タイトルのみの変更ではリマインダーはそのまま
+        update.execute(json!({"id": format!("#{}", id), "title": "Review PR"}), &ctx).await.unwrap();
+        assert_eq!(f.scheduler.list_tasks().await[0].id, tasks[0].id);
+
+        let result = TodoCompleteTool::new(f.access.clone())
+            .execute(json!({"id": id}), &ctx)
+            .await
+            .unwrap();
+        assert!(result.output.contains("[done] Review PR"));
+        assert!(f.scheduler.list_tasks().await.is_empty());
+        assert!(f.store.get_todo(42, id).unwrap().reminder_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_and_ownership() {
+        let f = fixture();
+        let add = TodoAddTool::new(f.access.clone());
+        add.execute(json!({"title": "Mine", "scope": "channel"}), &context(42))
+            .await
+            .unwrap();
+        add.execute(json!({"title": "Theirs"}), &context(7)).await.unwrap();
+
+        let list = TodoListTool::new(f.access.clone());
+        let result = list.execute(json!({}), &context(42)).await.unwrap();
+        assert!(result.output.contains("Mine"));
+        assert!(result.output.contains("<#456>"));
+        assert!(!result.output.contains("Theirs"));
+
+        let result = list.execute(json!({"status": "done"}), &context(42)).await.unwrap();
+        assert!(result.output.contains("No todo items"));
+
+        let their_id = f.store.list_todos(7, &TodoFilter::default()).unwrap()[0].id;
+        let result = TodoCompleteTool::new(f.access.clone())
+            .execute(json!({"id": their_id}), &context(42))
+            .await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+    }
+}
@@ -1,9 +1,13 @@
+use crate::security::{GuardedResolver, WebPolicy, WebPolicyError};
 use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
+use crate::web_cache::{CachePolicy, CachedPage, WebCache, MAX_BODY_BYTES};
 use async_trait::async_trait;
+use chrono::Utc;
 use once_cell::sync::Lazy;
 use regex::Regex;
-use reqwest::Client;
+use reqwest::{redirect, Client, Url};
 use serde_json::{json, Value as JsonValue};
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, info, warn};
 
@@ -67,50 +71,220 @@ impl RegexPatterns {
 /// グローバルにキャッシュされた正規表現
 static REGEX: Lazy<RegexPatterns> = Lazy::new(RegexPatterns::new);
 
+/// リダイレクトの最大追跡回数
+const MAX_REDIRECTS: usize = 5;
+
+/// 取得処理のエラー
+enum FetchError {
+    /// ポリシー違反
+    Policy(WebPolicyError),
+    /// 通信・HTTPエラー
+    Http(String),
+}
+
+/// 取得結果
+enum FetchOutcome {
+    /// 304 Not Modified（キャッシュを再利用）
+    NotModified(CachePolicy),
+    /// 新しく取得した本文
+    Fetched(FetchedPage),
+}
+
+/// 取得した本文とキャッシュ用ヘッダー
+struct FetchedPage {
+    body: String,
+    content_type: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    cache_policy: CachePolicy,
+}
+
 /// Web取得ツール（HTTP取得 + Markdown変換）
 pub struct WebFetchTool {
     client: Client,
+    policy: Arc<WebPolicy>,
+    cache: Option<Arc<WebCache>>,
 }
 
 impl WebFetchTool {
     pub fn new() -> Self {
+        Self::with_policy(Arc::new(WebPolicy::default()))
+    }
+
+    /// アクセスポリシーを指定して作成
+    pub fn with_policy(policy: Arc<WebPolicy>) -> Self {
         let client = Client::builder()
             .timeout(Duration::from_secs(30))
             .user_agent("cc-discord-bot/1.0")
+            // リダイレクト先もポリシーで検証するため自前で追跡する
+            .redirect(redirect::Policy::none())
+            // 接続先アドレスを検証済みのものに限定する（プロキシ経由だと検証できないため無効化）
+            .dns_resolver(Arc::new(GuardedResolver))
+            .no_proxy()
             .build()
             .unwrap_or_else(|_| Client::new());
 
-        Self { client }
+        Self {
+            client,
+            policy,
+            cache: None,
+        }
     }
 
-    /// URLからコンテンツを取得
-    async fn fetch(&self, url: &str) -> Result<(String, String), String> {
-        debug!("Fetching URL: {}", url);
+    /// 取得結果をキャッシュするストアを設定
+    pub fn with_cache(mut self, cache: Arc<WebCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
 
-        let response = self
-            .client
-            .get(url)
-            .send()
-            .await
-            .map_err(|e| format!("HTTP request failed: {}", e))?;
+    /// URLからコンテンツを取得（リダイレクトごとにポリシーを再検証）
+    ///
+    /// `cached` を渡すと ETag / Last-Modified による条件付きGETを行う
+    async fn fetch(
+        &self,
+        url: Url,
+        guild_id: Option<u64>,
+        cached: Option<&CachedPage>,
+    ) -> Result<FetchOutcome, FetchError> {
+        let mut url = url;
+        let mut redirects = 0;
+
+        let response = loop {
+            debug!("Fetching URL: {}", url);
+            self.policy
+                .check_url(&url, guild_id)
+                .await
+                .map_err(FetchError::Policy)?;
+
+            let mut request = self.client.get(url.clone());
+            if let Some(cached) = cached {
+                if let Some(etag) = &cached.etag {
+                    request = request.header("if-none-match", etag);
+                }
+                if let Some(last_modified) = &cached.last_modified {
+                    request = request.header("if-modified-since", last_modified);
+                }
+            }
+
+            let response = request
+                .send()
+                .await
+                .map_err(|e| FetchError::Http(format!("HTTP request failed: {}", e)))?;
+
+            if !response.status().is_redirection() || response.status() == reqwest::StatusCode::NOT_MODIFIED {
+                break response;
+            }
 
+            redirects += 1;
+            if redirects > MAX_REDIRECTS {
+                return Err(FetchError::Http(format!("Too many redirects (max {})", MAX_REDIRECTS)));
+            }
+            let location = response
+                .headers()
+                .get("location")
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| FetchError::Http(format!("HTTP error: {} without Location", response.status())))?;
+            url = url
+                .join(location)
+                .map_err(|e| FetchError::Policy(WebPolicyError::InvalidUrl(e.to_string())))?;
+        };
+
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string())
+        };
+        let cache_policy = CachePolicy::from_header(header("cache-control").as_deref());
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED && cached.is_some() {
+            return Ok(FetchOutcome::NotModified(cache_policy));
+        }
         if !response.status().is_success() {
-            return Err(format!("HTTP error: {}", response.status()));
+            return Err(FetchError::Http(format!("HTTP error: {}", response.status())));
         }
 
-        let content_type = response
-            .headers()
-            .get("content-type")
-            .and_then(|v| v.to_str().ok())
-            .unwrap_or("text/html")
-            .to_string();
+        let content_type = header("content-type").unwrap_or_else(|| "text/html".to_string());
+        let etag = header("etag");
+        let last_modified = header("last-modified");
 
         let body = response
             .text()
             .await
-            .map_err(|e| format!("Failed to read response: {}", e))?;
+            .map_err(|e| FetchError::Http(format!("Failed to read response: {}", e)))?;
+
+        Ok(FetchOutcome::Fetched(FetchedPage {
+            body,
+            content_type,
+            etag,
+            last_modified,
+            cache_policy,
+        }))
+    }
+
+    /// 本文をContent-Typeに応じてMarkdownに変換
+    fn render(body: &str, content_type: &str, url: &str) -> String {
+        if content_type.contains("text/html") {
+            // readabilityで本文抽出を試みる
+            if let Some(extracted) = Self::extract_readable_content(body, url) {
+                // タイトル + 本文の形式で出力
+                let mut result = String::new();
+                if !extracted.title.is_empty() {
+                    result.push_str(&format!("# {}\n\n", extracted.title));
+                }
+                result.push_str(&format!("> URL: {}\n\n", url));
+                result.push_str(&extracted.content);
+                result
+            } else {
+                // フォールバック: 従来の正規表現処理
+                Self::html_to_markdown(body)
+            }
+        } else if content_type.contains("application/json") {
+            // JSONはそのままコードブロックで表示
+            format!("```json\n{}\n```", body)
+        } else if content_type.contains("text/plain") || content_type.contains("text/markdown") {
+            body.to_string()
+        } else {
+            // その他はHTMLとして処理を試みる
+            Self::html_to_markdown(body)
+        }
+    }
 
-        Ok((body, content_type))
+    /// キャッシュからエントリを取得（エラーはキャッシュなし扱い）
+    fn cached_page(&self, key: &str) -> Option<CachedPage> {
+        let cache = self.cache.as_ref()?;
+        cache.get(key).unwrap_or_else(|e| {
+            warn!("Failed to read web cache: {}", e);
+            None
+        })
+    }
+
+    /// 取得結果をキャッシュに保存
+    fn store_page(&self, key: &str, page: &FetchedPage, markdown: &str) {
+        let Some(cache) = &self.cache else {
+            return;
+        };
+        if page.cache_policy == CachePolicy::NoStore || page.body.len() > MAX_BODY_BYTES {
+            debug!("Not caching {}", key);
+            return;
+        }
+
+        let now = Utc::now();
+        let entry = CachedPage {
+            url: key.to_string(),
+            content_type: page.content_type.clone(),
+            body: page.body.clone(),
+            markdown: markdown.to_string(),
+            etag: page.etag.clone(),
+            last_modified: page.last_modified.clone(),
+            fetched_at: now,
+            expires_at: page.cache_policy.expires_at(now),
+            hits: 0,
+        };
+        if let Err(e) = cache.put(&entry) {
+            warn!("Failed to store web cache: {}", e);
+        }
     }
 
     /// HTMLをMarkdownに変換（キャッシュされたRegexを使用）
@@ -238,7 +412,7 @@ impl Tool for WebFetchTool {
     }
 
     fn description(&self) -> &str {
-        "Fetch content from a URL and convert it to Markdown format. Returns the content as clean, readable text."
+        "Fetch content from a URL and convert it to Markdown format. Returns the content as clean, readable text. Responses are cached; pass max_age to control how stale a cached copy may be."
     }
 
     fn parameters_schema(&self) -> JsonValue {
@@ -252,13 +426,17 @@ impl Tool for WebFetchTool {
                 "max_chars": {
                     "type": "integer",
                     "description": "Maximum characters to return (default: 10000, max: 50000)"
+                },
+                "max_age": {
+                    "type": "integer",
+                    "description": "Accept a cached copy up to this many seconds old (0 forces revalidation). Default: follow the server's Cache-Control"
                 }
             },
             "required": ["url"]
         })
     }
 
-    async fn execute(&self, params: JsonValue, _context: &ToolContext) -> Result<ToolResult, ToolError> {
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
         let url = params["url"].as_str().ok_or_else(|| {
             ToolError::InvalidParams("Missing 'url' parameter".to_string())
         })?;
@@ -266,45 +444,56 @@ impl Tool for WebFetchTool {
         let max_chars = params["max_chars"].as_u64().unwrap_or(10000).min(50000) as usize;
 
         // URLの基本的なバリデーション
-        if !url.starts_with("http://") && !url.starts_with("https://") {
-            return Err(ToolError::InvalidParams(
-                "URL must start with http:// or https://".to_string(),
-            ));
+        let parsed = Url::parse(url).map_err(|_| {
+            ToolError::InvalidParams("URL must be an absolute http:// or https:// URL".to_string())
+        })?;
+
+        let max_age = params["max_age"].as_u64();
+
+        debug!("Web fetch: {} (max_chars: {}, max_age: {:?})", url, max_chars, max_age);
+
+        // キャッシュを返す場合もドメインポリシーは適用する
+        if let Err(e) = self.policy.check_static(&parsed, context.guild_id) {
+            warn!("Web fetch blocked by policy: {} ({})", url, e);
+            return Err(ToolError::PermissionDenied(e.to_string()));
         }
 
-        debug!("Web fetch: {} (max_chars: {})", url, max_chars);
-
-        match self.fetch(url).await {
-            Ok((body, content_type)) => {
-                let markdown = if content_type.contains("text/html") {
-                    // readabilityで本文抽出を試みる
-                    if let Some(extracted) = Self::extract_readable_content(&body, url) {
-                        // タイトル + 本文の形式で出力
-                        let mut result = String::new();
-                        if !extracted.title.is_empty() {
-                            result.push_str(&format!("# {}\n\n", extracted.title));
-                        }
-                        result.push_str(&format!("> URL: {}\n\n", url));
-                        result.push_str(&extracted.content);
-                        result
-                    } else {
-                        // フォールバック: 従来の正規表現処理
-                        Self::html_to_markdown(&body)
+        let key = parsed.to_string();
+        let cached = self.cached_page(&key);
+        if let (Some(cache), Some(page)) = (&self.cache, &cached) {
+            if page.is_fresh(Utc::now(), max_age) {
+                debug!("Web cache hit: {}", key);
+                if let Err(e) = cache.record_hit(&key) {
+                    warn!("Failed to update web cache: {}", e);
+                }
+                let note = format!("> Cached copy fetched at {}\n\n", page.fetched_at.format("%Y-%m-%d %H:%M:%S UTC"));
+                return Ok(ToolResult::success(Self::truncate(&(note + &page.markdown), max_chars)));
+            }
+        }
+
+        let validators = cached.as_ref().filter(|page| page.has_validator());
+        match self.fetch(parsed, context.guild_id, validators).await {
+            Ok(FetchOutcome::NotModified(cache_policy)) => {
+                debug!("Web cache revalidated: {}", key);
+                let page = validators.expect("validators are sent only with a cached page");
+                if let Some(cache) = &self.cache {
+                    let now = Utc::now();
+                    if let Err(e) = cache.revalidated(&key, now, cache_policy.expires_at(now)) {
+                        warn!("Failed to update web cache: {}", e);
                     }
-                } else if content_type.contains("application/json") {
-                    // JSONはそのままコードブロックで表示
-                    format!("```json\n{}\n```", body)
-                } else if content_type.contains("text/plain") || content_type.contains("text/markdown") {
-                    body
-                } else {
-                    // その他はHTMLとして処理を試みる
-                    Self::html_to_markdown(&body)
-                };
-
-                let truncated = Self::truncate(&markdown, max_chars);
-                Ok(ToolResult::success(truncated))
+                }
+                Ok(ToolResult::success(Self::truncate(&page.markdown, max_chars)))
             }
-            Err(e) => {
+            Ok(FetchOutcome::Fetched(page)) => {
+                let markdown = Self::render(&page.body, &page.content_type, url);
+                self.store_page(&key, &page, &markdown);
+                Ok(ToolResult::success(Self::truncate(&markdown, max_chars)))
+            }
+            Err(FetchError::Policy(e)) => {
+                warn!("Web fetch blocked by policy: {} ({})", url, e);
+                Err(ToolError::PermissionDenied(e.to_string()))
+            }
+            Err(FetchError::Http(e)) => {
                 warn!("Web fetch failed: {}", e);
                 Err(ToolError::ExecutionFailed(e))
             }
@@ -410,6 +599,69 @@ mod tests {
         assert!(matches!(result, Err(ToolError::InvalidParams(_))));
     }
 
+    #[tokio::test]
+    async fn test_web_fetch_blocks_internal_addresses() {
+        let tool = WebFetchTool::new();
+        let ctx = create_test_context();
+
+        for url in ["http://127.0.0.1:3000/api/health", "http://169.254.169.254/", "file:///etc/passwd"] {
+            let result = tool.execute(json!({"url": url}), &ctx).await;
+            assert!(matches!(result, Err(ToolError::PermissionDenied(_))), "{} should be denied", url);
+        }
+    }
+
+    fn cached_page(url: &str, markdown: &str, age_secs: i64) -> CachedPage {
+        let fetched_at = Utc::now() - chrono::Duration::seconds(age_secs);
+        CachedPage {
+            url: url.to_string(),
+            content_type: "text/html".to_string(),
+            body: format!("<p>{}</p>", markdown),
+            markdown: markdown.to_string(),
+            etag: None,
+            last_modified: None,
+            fetched_at,
+            expires_at: fetched_at + chrono::Duration::seconds(3600),
+            hits: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_web_fetch_serves_fresh_cache() {
+        let cache = Arc::new(WebCache::new().unwrap());
+        cache.put(&cached_page("https://example.com/page", "cached body", 60)).unwrap();
+        let tool = WebFetchTool::new().with_cache(cache.clone());
+
+        let result = tool
+            .execute(json!({"url": "https://example.com/page", "max_age": 120}), &create_test_context())
+            .await
+            .unwrap();
+
+        assert!(result.output.contains("Cached copy"));
+        assert!(result.output.contains("cached body"));
+        assert_eq!(cache.get("https://example.com/page").unwrap().unwrap().hits, 1);
+    }
+
+    #[tokio::test]
+    async fn test_web_fetch_cache_respects_policy() {
+        let cache = Arc::new(WebCache::new().unwrap());
+        cache.put(&cached_page("https://example.com/", "cached body", 0)).unwrap();
+        let policy: WebPolicy = serde_json::from_str(r#"{"domains": {"deny": ["example.com"]}}"#).unwrap();
+        let tool = WebFetchTool::with_policy(Arc::new(policy)).with_cache(cache);
+
+        let result = tool.execute(json!({"url": "https://example.com/"}), &create_test_context()).await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(_))));
+    }
+
+    #[tokio::test]
+    async fn test_web_fetch_guild_domain_policy() {
+        let policy: WebPolicy = serde_json::from_str(r#"{"guilds": {"1": {"deny": ["example.com"]}}}"#).unwrap();
+        let tool = WebFetchTool::with_policy(Arc::new(policy));
+        let ctx = create_test_context().with_guild_id(Some(1));
+
+        let result = tool.execute(json!({"url": "https://www.example.com/"}), &ctx).await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied(msg)) if msg.contains("blocked")));
+    }
+
     #[test]
     fn test_regex_patterns_case_insensitive() {
         // 大文字小文字を区別しないことを確認
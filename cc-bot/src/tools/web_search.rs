@@ -0,0 +1,438 @@
+use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
+use crate::tools::WebFetchTool;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// 検索結果の最大件数
+const MAX_RESULTS: usize = 20;
+/// 自動取得する上位件数の上限
+const MAX_FETCH_TOP: usize = 3;
+/// スニペットの最大文字数
+const MAX_SNIPPET_CHARS: usize = 300;
+
+/// 検索クエリ
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pub query: String,
+    pub max_results: usize,
+    /// 言語コード（例: ja, en）
+    pub language: Option<String>,
+    /// 期間（day / week / month / year）
+    pub time_range: Option<String>,
+}
+
+/// 検索結果（ランク順）
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
+
+/// 検索バックエンド
+#[async_trait]
+pub trait SearchBackend: Send + Sync {
+    /// バックエンド名（表示用）
+    fn name(&self) -> &str;
+
+    /// 検索を実行し、ランク順の結果を返す
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>, String>;
+}
+
+/// SearxNG JSON API のレスポンス
+#[derive(Debug, Deserialize)]
+struct SearxngResponse {
+    #[serde(default)]
+    results: Vec<SearxngResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearxngResult {
+    #[serde(default)]
+    title: String,
+    url: String,
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    score: Option<f64>,
+}
+
+/// SearxNG バックエンド（`/search?format=json` を使用）
+///
+/// インスタンス側の `settings.yml` で `search.formats` に `json` を含める必要がある
+pub struct SearxngBackend {
+    client: Client,
+    base_url: String,
+}
+
+impl SearxngBackend {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(15))
+            .user_agent("cc-discord-bot/1.0")
+            .build()
+            .unwrap_or_else(|_| Client::new());
+
+        Self {
+            client,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// 環境変数 `SEARXNG_URL` から作成（未設定なら None）
+    pub fn from_env() -> Option<Self> {
+        std::env::var("SEARXNG_URL")
+            .ok()
+            .filter(|url| !url.trim().is_empty())
+            .map(|url| Self::new(url.trim()))
+    }
+}
+
+#[async_trait]
+impl SearchBackend for SearxngBackend {
+    fn name(&self) -> &str {
+        "searxng"
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>, String> {
+        let mut params = vec![
+            ("q", query.query.clone()),
+            ("format", "json".to_string()),
+            ("pageno", "1".to_string()),
+        ];
+        if let Some(language) = &query.language {
+            params.push(("language", language.clone()));
+        }
+        if let Some(time_range) = &query.time_range {
+            params.push(("time_range", time_range.clone()));
+        }
+
+        let response = self
+            .client
+            .get(format!("{}/search", self.base_url))
+            .query(&params)
+            .send()
+            .await
+            .map_err(|e| format!("Search request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Search backend returned HTTP {}", response.status()));
+        }
+
+        let body: SearxngResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid search response: {}", e))?;
+
+        // SearxNG はスコア順で返すが、念のため安定ソートで並べ直す
+        let mut results = body.results;
+        results.sort_by(|a, b| {
+            b.score
+                .unwrap_or(0.0)
+                .partial_cmp(&a.score.unwrap_or(0.0))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let mut seen = std::collections::HashSet::new();
+        Ok(results
+            .into_iter()
+            .filter(|r| seen.insert(r.url.clone()))
+            .take(query.max_results)
+            .map(|r| SearchResult {
+                title: r.title,
+                url: r.url,
+                snippet: r.content.unwrap_or_default(),
+            })
+            .collect())
+    }
+}
+
+/// Web検索ツール
+pub struct WebSearchTool {
+    backend: Arc<dyn SearchBackend>,
+    /// 上位結果の自動取得に使うツール
+    fetcher: Option<Arc<WebFetchTool>>,
+}
+
+impl WebSearchTool {
+    pub fn new(backend: Arc<dyn SearchBackend>) -> Self {
+        Self {
+            backend,
+            fetcher: None,
+        }
+    }
+
+    /// 上位結果の自動取得に使う WebFetchTool を設定
+    pub fn with_fetcher(mut self, fetcher: Arc<WebFetchTool>) -> Self {
+        self.fetcher = Some(fetcher);
+        self
+    }
+
+    /// スニペットを1行に整えて切り詰める
+    fn clean_snippet(snippet: &str) -> String {
+        let snippet = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+        if snippet.chars().count() > MAX_SNIPPET_CHARS {
+            let truncated: String = snippet.chars().take(MAX_SNIPPET_CHARS).collect();
+            format!("{}...", truncated)
+        } else {
+            snippet
+        }
+    }
+
+    /// 検索結果を番号付きリストに整形
+    fn format_results(query: &str, results: &[SearchResult]) -> String {
+        let mut output = format!("Search results for \"{}\":\n", query);
+        for (index, result) in results.iter().enumerate() {
+            let title = if result.title.trim().is_empty() { &result.url } else { &result.title };
+            output.push_str(&format!("\n{}. {}\n   {}\n", index + 1, title.trim(), result.url));
+            let snippet = Self::clean_snippet(&result.snippet);
+            if !snippet.is_empty() {
+                output.push_str(&format!("   {}\n", snippet));
+            }
+        }
+        output
+    }
+}
+
+#[async_trait]
+impl Tool for WebSearchTool {
+    fn name(&self) -> &str {
+        "web_search"
+    }
+
+    fn description(&self) -> &str {
+        "Search the web and return ranked results with title, URL and snippet. Use this to find pages instead of guessing URLs, then read them with web_fetch. Set fetch_top to also fetch the content of the top results."
+    }
+
+    fn parameters_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "Search query"
+                },
+                "max_results": {
+                    "type": "integer",
+                    "description": "Maximum number of results (default: 5, max: 20)"
+                },
+                "language": {
+                    "type": "string",
+                    "description": "Language code for results (e.g., 'ja', 'en')"
+                },
+                "time_range": {
+                    "type": "string",
+                    "enum": ["day", "week", "month", "year"],
+                    "description": "Only return results from this time range"
+                },
+                "fetch_top": {
+                    "type": "integer",
+                    "description": "Fetch and include the content of the top N results (default: 0, max: 3)"
+                },
+                "fetch_max_chars": {
+                    "type": "integer",
+                    "description": "Maximum characters per fetched page (default: 3000)"
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    async fn execute(&self, params: JsonValue, context: &ToolContext) -> Result<ToolResult, ToolError> {
+        let query = params["query"]
+            .as_str()
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .ok_or_else(|| ToolError::InvalidParams("Missing 'query' parameter".to_string()))?;
+
+        let max_results = params["max_results"].as_u64().unwrap_or(5).clamp(1, MAX_RESULTS as u64) as usize;
+        let fetch_top = params["fetch_top"].as_u64().unwrap_or(0).min(MAX_FETCH_TOP as u64) as usize;
+        let fetch_max_chars = params["fetch_max_chars"].as_u64().unwrap_or(3000).min(10000);
+        let time_range = match params["time_range"].as_str() {
+            None => None,
+            Some(range @ ("day" | "week" | "month" | "year")) => Some(range.to_string()),
+            Some(other) => {
+                return Err(ToolError::InvalidParams(format!(
+                    "Invalid time_range: {} (expected day, week, month or year)",
+                    other
+                )))
+            }
+        };
+
+        let search_query = SearchQuery {
+            query: query.to_string(),
+            max_results,
+            language: params["language"].as_str().map(|s| s.to_string()),
+            time_range,
+        };
+
+        debug!("Web search via {}: {:?}", self.backend.name(), search_query);
+
+        let results = match self.backend.search(&search_query).await {
+            Ok(results) => results,
+            Err(e) => {
+                warn!("Web search failed: {}", e);
+                return Err(ToolError::ExecutionFailed(e));
+            }
+        };
+
+        if results.is_empty() {
+            return Ok(ToolResult::success(format!("No results found for \"{}\"", query)));
+        }
+
+        let mut output = Self::format_results(query, &results);
+
+        if let Some(fetcher) = &self.fetcher {
+            for (index, result) in results.iter().take(fetch_top).enumerate() {
+                output.push_str(&format!("\n---\n\n## [{}] {}\n\n", index + 1, result.url));
+                match fetcher
+                    .execute(json!({"url": result.url, "max_chars": fetch_max_chars}), context)
+                    .await
+                {
+                    Ok(page) if !page.is_error => output.push_str(&page.output),
+                    Ok(page) => output.push_str(&format!("(Could not fetch: {})", page.output)),
+                    Err(e) => output.push_str(&format!("(Could not fetch: {})", e)),
+                }
+                output.push('\n');
+            }
+        }
+
+        Ok(ToolResult::success(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn create_test_context() -> ToolContext {
+        ToolContext::new(123, "test_user".to_string(), 456, "output".to_string())
+    }
+
+    /// 固定の結果を返すバックエンド
+    struct StubBackend(Vec<SearchResult>);
+
+    #[async_trait]
+    impl SearchBackend for StubBackend {
+        fn name(&self) -> &str {
+            "stub"
+        }
+
+        async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>, String> {
+            Ok(self.0.iter().take(query.max_results).cloned().collect())
+        }
+    }
+
+    fn result(title: &str, url: &str, snippet: &str) -> SearchResult {
+        SearchResult {
+            title: title.to_string(),
+            url: url.to_string(),
+            snippet: snippet.to_string(),
+        }
+    }
+
+    /// 1回だけ固定のJSONを返すローカルHTTPサーバー（リクエスト行を返す）
+    async fn serve_once(body: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).lines().next().unwrap_or_default().to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            request
+        });
+        (format!("http://{}", addr), handle)
+    }
+
+    #[tokio::test]
+    async fn test_searxng_backend_parses_and_ranks() {
+        let body = r#"{"results": [
+            {"title": "Second", "url": "https://b.example/", "content": "b", "score": 1.0},
+            {"title": "First", "url": "https://a.example/", "content": "a", "score": 3.5},
+            {"title": "Dup", "url": "https://a.example/", "content": "dup", "score": 0.5}
+        ]}"#;
+        let (base_url, server) = serve_once(body).await;
+
+        let backend = SearxngBackend::new(format!("{}/", base_url));
+        let results = backend
+            .search(&SearchQuery {
+                query: "rust async".to_string(),
+                max_results: 5,
+                language: Some("ja".to_string()),
+                time_range: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            results,
+            vec![result("First", "https://a.example/", "a"), result("Second", "https://b.example/", "b")]
+        );
+        let request_line = server.await.unwrap();
+        assert!(request_line.starts_with("GET /search?q=rust+async&format=json"));
+        assert!(request_line.contains("language=ja"));
+    }
+
+    #[tokio::test]
+    async fn test_web_search_formats_results() {
+        let backend = StubBackend(vec![
+            result("Rust", "https://www.rust-lang.org/", "A language   empowering\neveryone"),
+            result("", "https://docs.rs/", ""),
+            result("Extra", "https://extra.example/", "x"),
+        ]);
+        let tool = WebSearchTool::new(Arc::new(backend));
+
+        let output = tool
+            .execute(json!({"query": "rust", "max_results": 2}), &create_test_context())
+            .await
+            .unwrap()
+            .output;
+
+        assert!(output.contains("1. Rust\n   https://www.rust-lang.org/\n   A language empowering everyone"));
+        assert!(output.contains("2. https://docs.rs/\n   https://docs.rs/"));
+        assert!(!output.contains("extra.example"));
+    }
+
+    #[tokio::test]
+    async fn test_web_search_validation() {
+        let tool = WebSearchTool::new(Arc::new(StubBackend(vec![])));
+        let ctx = create_test_context();
+
+        assert!(matches!(tool.execute(json!({}), &ctx).await, Err(ToolError::InvalidParams(_))));
+        assert!(matches!(
+            tool.execute(json!({"query": "x", "time_range": "decade"}), &ctx).await,
+            Err(ToolError::InvalidParams(_))
+        ));
+        let empty = tool.execute(json!({"query": "nothing"}), &ctx).await.unwrap();
+        assert!(empty.output.contains("No results found"));
+    }
+
+    #[tokio::test]
+    async fn test_web_search_fetch_top_reports_blocked_pages() {
+        let backend = StubBackend(vec![result("Local", "http://127.0.0.1:3000/api/health", "")]);
+        let tool = WebSearchTool::new(Arc::new(backend)).with_fetcher(Arc::new(WebFetchTool::new()));
+
+        let output = tool
+            .execute(json!({"query": "local", "fetch_top": 1}), &create_test_context())
+            .await
+            .unwrap()
+            .output;
+
+        assert!(output.contains("## [1] http://127.0.0.1:3000/api/health"));
+        assert!(output.contains("Could not fetch"));
+    }
+}
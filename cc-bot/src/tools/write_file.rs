@@ -1,3 +1,4 @@
+use crate::file_history;
 use crate::tool::{Tool, ToolContext, ToolError, ToolResult};
 use async_trait::async_trait;
 use serde_json::{json, Value as JsonValue};
@@ -113,6 +114,9 @@ impl Tool for WriteFileTool {
             }
         }
 
+        // 変更前の内容を履歴に保存
+        file_history::snapshot_before_write(context, path, path_obj, self.name()).await;
+
         // ファイル書き込み
         match fs::write(&user_path, content).await {
             Ok(_) => {
@@ -0,0 +1,415 @@
+//! Web取得キャッシュ（SQLite永続化）
+//!
+//! `web_fetch` の取得結果をURLごとに保存します。ETag / Last-Modified による
+//! 条件付きGETと、`Cache-Control` から算出した有効期限で再取得を抑制します。
+
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use thiserror::Error;
+use tracing::{debug, error, info};
+
+/// `Cache-Control` がない場合の有効期限（秒）
+pub const DEFAULT_TTL_SECS: i64 = 300;
+/// 有効期限の上限（秒）
+const MAX_TTL_SECS: i64 = 7 * 24 * 3600;
+/// 保存する最大エントリ数（超えた分は古い順に削除）
+const MAX_ENTRIES: i64 = 1000;
+/// キャッシュする本文の最大サイズ
+pub const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// キャッシュエラー
+#[derive(Debug, Error)]
+pub enum WebCacheError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+}
+
+/// キャッシュエントリ
+#[derive(Debug, Clone)]
+pub struct CachedPage {
+    pub url: String,
+    pub content_type: String,
+    /// 取得した本文（生データ）
+    pub body: String,
+    /// 本文から抽出したMarkdown
+    pub markdown: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub fetched_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub hits: u64,
+}
+
+impl CachedPage {
+    /// キャッシュが新鮮か判定
+    ///
+    /// `max_age` 指定時は取得からの経過秒数で、未指定時は有効期限で判定
+    pub fn is_fresh(&self, now: DateTime<Utc>, max_age: Option<u64>) -> bool {
+        match max_age {
+            Some(max_age) => (now - self.fetched_at).num_seconds() < max_age as i64,
+            None => now < self.expires_at,
+        }
+    }
+
+    /// 条件付きGETに使える検証子があるか
+    pub fn has_validator(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+}
+
+/// キャッシュ統計
+#[derive(Debug, Clone, Default)]
+pub struct WebCacheStats {
+    pub entries: u64,
+    pub expired: u64,
+    pub total_bytes: u64,
+    pub total_hits: u64,
+    pub oldest: Option<DateTime<Utc>>,
+}
+
+/// `Cache-Control` の解釈結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// 保存しない
+    NoStore,
+    /// 指定秒数だけ新鮮とみなす（0なら毎回再検証）
+    Ttl(i64),
+}
+
+impl CachePolicy {
+    /// `Cache-Control` ヘッダーを解釈
+    pub fn from_header(value: Option<&str>) -> Self {
+        let Some(value) = value else {
+            return Self::Ttl(DEFAULT_TTL_SECS);
+        };
+
+        let mut max_age = None;
+        let mut shared_max_age = None;
+        let mut no_cache = false;
+        for directive in value.split(',').map(|d| d.trim().to_ascii_lowercase()) {
+            match directive.as_str() {
+                "no-store" | "private" => return Self::NoStore,
+                "no-cache" => no_cache = true,
+                _ => {
+                    if let Some(v) = directive.strip_prefix("s-maxage=") {
+                        shared_max_age = v.trim_matches('"').parse::<i64>().ok();
+                    } else if let Some(v) = directive.strip_prefix("max-age=") {
+                        max_age = v.trim_matches('"').parse::<i64>().ok();
+                    }
+                }
+            }
+        }
+
+        if no_cache {
+            return Self::Ttl(0);
+        }
+        let ttl = shared_max_age.or(max_age).unwrap_or(DEFAULT_TTL_SECS);
+        Self::Ttl(ttl.clamp(0, MAX_TTL_SECS))
+    }
+
+    /// 有効期限を算出
+    pub fn expires_at(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Self::NoStore => now,
+            Self::Ttl(secs) => now + chrono::Duration::seconds(*secs),
+        }
+    }
+}
+
+/// Web取得キャッシュ（SQLite永続化）
+pub struct WebCache {
+    conn: Mutex<Connection>,
+}
+
+impl WebCache {
+    /// Mutexロックを取得するヘルパー
+    fn lock_conn(&self) -> Result<std::sync::MutexGuard<'_, Connection>, WebCacheError> {
+        self.conn.lock().map_err(|e| {
+            WebCacheError::DatabaseError(format!("Failed to lock connection: {}", e))
+        })
+    }
+
+    /// 新しいWebCacheを作成（インメモリ）
+    pub fn new() -> Result<Self, WebCacheError> {
+        let conn = Connection::open_in_memory().map_err(|e| {
+            error!("Failed to create in-memory DB: {}", e);
+            WebCacheError::DatabaseError("Failed to create database".to_string())
+        })?;
+
+        let cache = Self {
+            conn: Mutex::new(conn),
+        };
+        cache.initialize()?;
+        Ok(cache)
+    }
+
+    /// ファイルパスから読み込み
+    pub fn load(base_dir: &str) -> Result<Self, WebCacheError> {
+        let path = Self::get_file_path(base_dir);
+        debug!("Loading web cache from {:?}", path);
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                error!("Failed to create directory: {}", e);
+                WebCacheError::DatabaseError("Failed to initialize storage".to_string())
+            })?;
+        }
+
+        let conn = Connection::open(&path).map_err(|e| {
+            error!("Failed to open database at {:?}: {}", path, e);
+            WebCacheError::DatabaseError("Failed to open database".to_string())
+        })?;
+
+        let cache = Self {
+            conn: Mutex::new(conn),
+        };
+        cache.initialize()?;
+        info!("Web cache loaded successfully");
+        Ok(cache)
+    }
+
+    /// ファイルパスを生成
+    fn get_file_path(base_dir: &str) -> PathBuf {
+        Path::new(base_dir).join("web_cache.db")
+    }
+
+    /// データベースを初期化
+    fn initialize(&self) -> Result<(), WebCacheError> {
+        let conn = self.lock_conn()?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS web_cache (
+                url TEXT PRIMARY KEY,
+                content_type TEXT NOT NULL,
+                body TEXT NOT NULL,
+                markdown TEXT NOT NULL,
+                etag TEXT,
+                last_modified TEXT,
+                fetched_at INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL,
+                hits INTEGER NOT NULL DEFAULT 0
+            )",
+            [],
+        ).map_err(|e| WebCacheError::DatabaseError(format!("Failed to create table: {}", e)))?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_web_cache_fetched_at ON web_cache(fetched_at)",
+            [],
+        ).map_err(|e| WebCacheError::DatabaseError(format!("Failed to create index: {}", e)))?;
+
+        debug!("Web cache initialized");
+        Ok(())
+    }
+
+    /// URLのキャッシュを取得
+    pub fn get(&self, url: &str) -> Result<Option<CachedPage>, WebCacheError> {
+        let conn = self.lock_conn()?;
+
+        conn.query_row(
+            "SELECT url, content_type, body, markdown, etag, last_modified, fetched_at, expires_at, hits
+             FROM web_cache WHERE url = ?1",
+            params![url],
+            |row| {
+                Ok(CachedPage {
+                    url: row.get(0)?,
+                    content_type: row.get(1)?,
+                    body: row.get(2)?,
+                    markdown: row.get(3)?,
+                    etag: row.get(4)?,
+                    last_modified: row.get(5)?,
+                    fetched_at: timestamp(row.get(6)?),
+                    expires_at: timestamp(row.get(7)?),
+                    hits: row.get::<_, i64>(8)? as u64,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| WebCacheError::DatabaseError(format!("Failed to query cache: {}", e)))
+    }
+
+    /// キャッシュを保存（既存エントリは置き換え）
+    pub fn put(&self, page: &CachedPage) -> Result<(), WebCacheError> {
+        let conn = self.lock_conn()?;
+
+        conn.execute(
+            "INSERT OR REPLACE INTO web_cache
+             (url, content_type, body, markdown, etag, last_modified, fetched_at, expires_at, hits)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                page.url,
+                page.content_type,
+                page.body,
+                page.markdown,
+                page.etag,
+                page.last_modified,
+                page.fetched_at.timestamp(),
+                page.expires_at.timestamp(),
+                page.hits as i64
+            ],
+        ).map_err(|e| WebCacheError::DatabaseError(format!("Failed to store cache: {}", e)))?;
+
+        // 上限を超えた古いエントリを削除
+        conn.execute(
+            "DELETE FROM web_cache WHERE url NOT IN
+             (SELECT url FROM web_cache ORDER BY fetched_at DESC LIMIT ?1)",
+            params![MAX_ENTRIES],
+        ).map_err(|e| WebCacheError::DatabaseError(format!("Failed to evict cache: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// 再検証（304）成功時に取得日時と有効期限を更新
+    pub fn revalidated(&self, url: &str, now: DateTime<Utc>, expires_at: DateTime<Utc>) -> Result<(), WebCacheError> {
+        let conn = self.lock_conn()?;
+        conn.execute(
+            "UPDATE web_cache SET fetched_at = ?2, expires_at = ?3, hits = hits + 1 WHERE url = ?1",
+            params![url, now.timestamp(), expires_at.timestamp()],
+        ).map_err(|e| WebCacheError::DatabaseError(format!("Failed to update cache: {}", e)))?;
+        Ok(())
+    }
+
+    /// ヒット数を加算
+    pub fn record_hit(&self, url: &str) -> Result<(), WebCacheError> {
+        let conn = self.lock_conn()?;
+        conn.execute("UPDATE web_cache SET hits = hits + 1 WHERE url = ?1", params![url])
+            .map_err(|e| WebCacheError::DatabaseError(format!("Failed to update cache: {}", e)))?;
+        Ok(())
+    }
+
+    /// キャッシュ統計を取得
+    pub fn stats(&self) -> Result<WebCacheStats, WebCacheError> {
+        let conn = self.lock_conn()?;
+        let now = Utc::now().timestamp();
+
+        conn.query_row(
+            "SELECT COUNT(*),
+                    COALESCE(SUM(CASE WHEN expires_at <= ?1 THEN 1 ELSE 0 END), 0),
+                    COALESCE(SUM(LENGTH(CAST(body AS BLOB)) + LENGTH(CAST(markdown AS BLOB))), 0),
+                    COALESCE(SUM(hits), 0),
+                    MIN(fetched_at)
+             FROM web_cache",
+            params![now],
+            |row| {
+                Ok(WebCacheStats {
+                    entries: row.get::<_, i64>(0)? as u64,
+                    expired: row.get::<_, i64>(1)? as u64,
+                    total_bytes: row.get::<_, i64>(2)? as u64,
+                    total_hits: row.get::<_, i64>(3)? as u64,
+                    oldest: row.get::<_, Option<i64>>(4)?.map(timestamp),
+                })
+            },
+        )
+        .map_err(|e| WebCacheError::DatabaseError(format!("Failed to query stats: {}", e)))
+    }
+
+    /// すべてのエントリを削除
+    pub fn purge_all(&self) -> Result<usize, WebCacheError> {
+        let conn = self.lock_conn()?;
+        conn.execute("DELETE FROM web_cache", [])
+            .map_err(|e| WebCacheError::DatabaseError(format!("Failed to purge cache: {}", e)))
+    }
+
+    /// 有効期限切れのエントリを削除
+    pub fn purge_expired(&self) -> Result<usize, WebCacheError> {
+        let conn = self.lock_conn()?;
+        conn.execute(
+            "DELETE FROM web_cache WHERE expires_at <= ?1",
+            params![Utc::now().timestamp()],
+        )
+        .map_err(|e| WebCacheError::DatabaseError(format!("Failed to purge cache: {}", e)))
+    }
+
+    /// URLに指定文字列を含むエントリを削除
+    pub fn purge_matching(&self, pattern: &str) -> Result<usize, WebCacheError> {
+        let conn = self.lock_conn()?;
+        conn.execute(
+            "DELETE FROM web_cache WHERE instr(url, ?1) > 0",
+            params![pattern],
+        )
+        .map_err(|e| WebCacheError::DatabaseError(format!("Failed to purge cache: {}", e)))
+    }
+}
+
+/// UNIX秒をDateTimeに変換
+fn timestamp(secs: i64) -> DateTime<Utc> {
+    Utc.timestamp_opt(secs, 0).single().unwrap_or_else(Utc::now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str, ttl: i64) -> CachedPage {
+        let now = Utc::now();
+        CachedPage {
+            url: url.to_string(),
+            content_type: "text/html".to_string(),
+            body: "<p>hello</p>".to_string(),
+            markdown: "hello".to_string(),
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+            fetched_at: now,
+            expires_at: now + chrono::Duration::seconds(ttl),
+            hits: 0,
+        }
+    }
+
+    #[test]
+    fn test_cache_control_parsing() {
+        assert_eq!(CachePolicy::from_header(None), CachePolicy::Ttl(DEFAULT_TTL_SECS));
+        assert_eq!(CachePolicy::from_header(Some("public, max-age=600")), CachePolicy::Ttl(600));
+        assert_eq!(CachePolicy::from_header(Some("max-age=60, s-maxage=120")), CachePolicy::Ttl(120));
+        assert_eq!(CachePolicy::from_header(Some("no-cache, max-age=600")), CachePolicy::Ttl(0));
+        assert_eq!(CachePolicy::from_header(Some("no-store")), CachePolicy::NoStore);
+        assert_eq!(CachePolicy::from_header(Some("max-age=99999999")), CachePolicy::Ttl(MAX_TTL_SECS));
+    }
+
+    #[test]
+    fn test_put_get_and_hits() {
+        let cache = WebCache::new().unwrap();
+        assert!(cache.get("https://example.com/").unwrap().is_none());
+
+        cache.put(&page("https://example.com/", 60)).unwrap();
+        cache.record_hit("https://example.com/").unwrap();
+
+        let cached = cache.get("https://example.com/").unwrap().unwrap();
+        assert_eq!(cached.markdown, "hello");
+        assert_eq!(cached.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(cached.hits, 1);
+        assert!(cached.is_fresh(Utc::now(), None));
+        assert!(!cached.is_fresh(Utc::now(), Some(0)));
+    }
+
+    #[test]
+    fn test_revalidated_extends_expiry() {
+        let cache = WebCache::new().unwrap();
+        cache.put(&page("https://example.com/", -10)).unwrap();
+        assert!(!cache.get("https://example.com/").unwrap().unwrap().is_fresh(Utc::now(), None));
+
+        let now = Utc::now();
+        cache.revalidated("https://example.com/", now, now + chrono::Duration::seconds(60)).unwrap();
+        let cached = cache.get("https://example.com/").unwrap().unwrap();
+        assert!(cached.is_fresh(Utc::now(), None));
+        assert_eq!(cached.hits, 1);
+    }
+
+    #[test]
+    fn test_stats_and_purge() {
+        let cache = WebCache::new().unwrap();
+        cache.put(&page("https://example.com/a", 60)).unwrap();
+        cache.put(&page("https://example.com/b", -10)).unwrap();
+        cache.put(&page("https://other.example/", 60)).unwrap();
+
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.entries, 3);
+        assert_eq!(stats.expired, 1);
+        assert!(stats.total_bytes > 0);
+
+        assert_eq!(cache.purge_expired().unwrap(), 1);
+        assert_eq!(cache.purge_matching("other.example").unwrap(), 1);
+        assert_eq!(cache.purge_all().unwrap(), 1);
+        assert_eq!(cache.stats().unwrap().entries, 0);
+    }
+}